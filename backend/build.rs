@@ -1,9 +1,23 @@
 // generated by `sqlx migrate build-script`
 fn main() {
     // trigger recompilation when a new migration is added
-    #[cfg(feature = "db-sqlite")]
     println!("cargo:rerun-if-changed=migrations/sqlite");
-
-    #[cfg(feature = "db-postgres")]
     println!("cargo:rerun-if-changed=migrations/postgres");
+    println!("cargo:rerun-if-changed=migrations/mysql");
+
+    // The query macros are checked against SQLite. With `DATABASE_URL` pointing at another
+    // database, e.g. to run the server on Postgres or MySQL, they would check against that
+    // instead, so they read the SQLite data in `.sqlx` then.
+    println!("cargo:rerun-if-env-changed=DATABASE_URL");
+    println!("cargo:rerun-if-env-changed=SQLX_OFFLINE");
+    let database_url = std::env::var("DATABASE_URL").ok().or_else(|| {
+        dotenvy::dotenv_iter()
+            .ok()?
+            .filter_map(Result::ok)
+            .find_map(|(key, value)| (key == "DATABASE_URL").then_some(value))
+    });
+    let checks_elsewhere = database_url.is_some_and(|url| !url.starts_with("sqlite:"));
+    if checks_elsewhere && std::env::var_os("SQLX_OFFLINE").is_none() {
+        println!("cargo:rustc-env=SQLX_OFFLINE=true");
+    }
 }
@@ -0,0 +1,4 @@
+DROP TABLE refresh_tokens;
+DROP TABLE memberships;
+DROP TABLE organizations;
+DROP TABLE users;
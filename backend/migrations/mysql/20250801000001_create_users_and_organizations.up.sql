@@ -0,0 +1,42 @@
+-- MySQL starts out with the current schema; the migrations of the other backends carry the
+-- history before it. Tables use a binary collation, so that text compares case-sensitively
+-- as it does on SQLite and Postgres. Text that is part of a key is a VARCHAR, since MySQL
+-- can't index TEXT as a whole.
+CREATE TABLE users (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    email VARCHAR(255) NOT NULL,
+    password_hash TEXT NOT NULL,
+    UNIQUE KEY email (email)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+-- Organizations own contacts, tags, custom fields and shares. Users work in them through
+-- memberships, whose role decides what they may change.
+CREATE TABLE organizations (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    name VARCHAR(255) NOT NULL,
+    created_at DATETIME(6) NOT NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+CREATE TABLE memberships (
+    org_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    role VARCHAR(16) NOT NULL CHECK (role IN ('owner', 'admin', 'member', 'viewer')),
+    created_at DATETIME(6) NOT NULL,
+    PRIMARY KEY (org_id, user_id),
+    KEY idx_memberships_user_id (user_id),
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+-- `org_id` is the organization the session is kept in.
+CREATE TABLE refresh_tokens (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    user_id BIGINT NOT NULL,
+    token_hash VARCHAR(255) NOT NULL,
+    expires_at DATETIME(6) NOT NULL,
+    org_id BIGINT,
+    UNIQUE KEY user_id (user_id),
+    KEY idx_refresh_tokens_token_hash (token_hash),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
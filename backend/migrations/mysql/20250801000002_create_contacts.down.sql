@@ -0,0 +1,8 @@
+DROP TABLE custom_fields;
+DROP TABLE contact_tags;
+DROP TABLE tags;
+DROP TABLE contact_addresses;
+DROP TABLE contact_phones;
+DROP TABLE contact_emails;
+DROP TABLE contacts;
+DROP TABLE contact_types;
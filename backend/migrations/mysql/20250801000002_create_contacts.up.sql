@@ -0,0 +1,113 @@
+-- The contact types this deployment offers. Every name must be a `ContactType` variant;
+-- disable a type or change its label or position to customize the set per deployment.
+CREATE TABLE contact_types (
+    name VARCHAR(16) PRIMARY KEY NOT NULL CHECK (name IN ('Personal', 'Work', 'Family', 'Friend', 'Customer', 'Lead', 'Partner', 'Other')),
+    label TEXT NOT NULL,
+    position BIGINT NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+INSERT INTO contact_types (name, label, position) VALUES
+    ('Personal', 'Personal', 1),
+    ('Work', 'Work', 2),
+    ('Family', 'Family', 3),
+    ('Friend', 'Friend', 4),
+    ('Customer', 'Customer', 5),
+    ('Lead', 'Lead', 6),
+    ('Partner', 'Partner', 7),
+    ('Other', 'Other', 8);
+
+-- `user_id` records who created a contact. Deleted contacts are kept in the trash, with a
+-- `deleted_at`, until they are purged. `custom_fields` holds the values of the
+-- organization's custom fields, as a JSON object keyed by field name. Newsletter consent
+-- records when and how the contact gave their current consent.
+--
+-- MySQL has no partial indexes, so the email only has to be unique among the contacts
+-- outside the trash through `active_email`, which is NULL in the trash.
+CREATE TABLE contacts (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    org_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    email VARCHAR(255) NOT NULL,
+    birthday DATE,
+    subscribed BOOLEAN NOT NULL,
+    subscribed_at DATETIME(6),
+    subscription_source VARCHAR(32),
+    contact_type VARCHAR(16) NOT NULL,
+    notes TEXT,
+    custom_fields JSON NOT NULL DEFAULT (JSON_OBJECT()),
+    avatar_key VARCHAR(255),
+    deleted_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    updated_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    active_email VARCHAR(255) AS (CASE WHEN deleted_at IS NULL THEN email END) VIRTUAL,
+    UNIQUE KEY email (org_id, active_email),
+    KEY idx_contacts_deleted_at (deleted_at),
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+CREATE TABLE contact_emails (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    contact_id BIGINT NOT NULL,
+    position BIGINT NOT NULL,
+    label TEXT NOT NULL,
+    email TEXT NOT NULL,
+    KEY idx_contact_emails_contact_id (contact_id),
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+CREATE TABLE contact_phones (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    contact_id BIGINT NOT NULL,
+    position BIGINT NOT NULL,
+    label TEXT NOT NULL,
+    number TEXT NOT NULL,
+    KEY idx_contact_phones_contact_id (contact_id),
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+CREATE TABLE contact_addresses (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    contact_id BIGINT NOT NULL,
+    position BIGINT NOT NULL,
+    label TEXT NOT NULL,
+    street TEXT NOT NULL,
+    city TEXT NOT NULL,
+    region TEXT NOT NULL,
+    postal_code TEXT NOT NULL,
+    country TEXT NOT NULL,
+    KEY idx_contact_addresses_contact_id (contact_id),
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+CREATE TABLE tags (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    org_id BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    UNIQUE KEY name (org_id, name),
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+CREATE TABLE contact_tags (
+    contact_id BIGINT NOT NULL,
+    tag_id BIGINT NOT NULL,
+    PRIMARY KEY (contact_id, tag_id),
+    KEY idx_contact_tags_tag_id (tag_id),
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+-- The organization's definitions of extra contact attributes. `options` holds the JSON array
+-- of choices of a `select` field and is empty for every other type.
+CREATE TABLE custom_fields (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    org_id BIGINT NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    field_type VARCHAR(16) NOT NULL CHECK (field_type IN ('text', 'number', 'date', 'bool', 'select')),
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    options JSON NOT NULL DEFAULT (JSON_ARRAY()),
+    UNIQUE KEY name (org_id, name),
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
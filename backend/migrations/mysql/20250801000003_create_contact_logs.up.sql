@@ -0,0 +1,34 @@
+-- A log of every change to a contact. Snapshots are the JSON-serialized contact before and
+-- after the change. A merge is logged on both contacts involved, each event pointing at the
+-- other contact through `related_contact_id`, a plain ID rather than a foreign key since the
+-- merged-away contact is purged eventually.
+CREATE TABLE contact_events (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    contact_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    action VARCHAR(32) NOT NULL,
+    request_id TEXT,
+    before_snapshot LONGTEXT,
+    after_snapshot LONGTEXT,
+    related_contact_id BIGINT,
+    created_at DATETIME(6) NOT NULL,
+    KEY idx_contact_events_contact_id (contact_id),
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+-- Every subscription change, as a record for GDPR and CAN-SPAM.
+CREATE TABLE subscription_events (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    contact_id BIGINT NOT NULL,
+    user_id BIGINT,
+    action VARCHAR(32) NOT NULL,
+    source VARCHAR(32) NOT NULL,
+    email TEXT NOT NULL,
+    request_id TEXT,
+    created_at DATETIME(6) NOT NULL,
+    KEY idx_subscription_events_contact_id (contact_id),
+    CONSTRAINT subscription_events_contact_id_fkey
+        FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
@@ -0,0 +1,35 @@
+-- A share grants a user of another organization access to a single contact, or to the
+-- organization's whole contact book when `contact_id` is NULL. MySQL has no partial indexes,
+-- so a book is shared with a user only once through `book_grantee_id`, which is NULL for
+-- shares of a single contact; those are unique anyway, as a unique key ignores NULLs.
+CREATE TABLE shares (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    org_id BIGINT NOT NULL,
+    grantee_id BIGINT NOT NULL,
+    contact_id BIGINT,
+    permission VARCHAR(8) NOT NULL CHECK (permission IN ('read', 'write')),
+    created_at DATETIME(6) NOT NULL,
+    book_grantee_id BIGINT AS (CASE WHEN contact_id IS NULL THEN grantee_id END) VIRTUAL,
+    UNIQUE KEY grantee_id (org_id, book_grantee_id),
+    UNIQUE KEY contact_id (grantee_id, contact_id),
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (grantee_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
+
+-- Files attached to contacts. The content lives in the configured storage under
+-- `storage_key`; the avatar of a contact is a thumbnail stored the same way.
+CREATE TABLE attachments (
+    id BIGINT PRIMARY KEY AUTO_INCREMENT,
+    contact_id BIGINT NOT NULL,
+    user_id BIGINT,
+    file_name TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size BIGINT NOT NULL,
+    storage_key VARCHAR(255) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    UNIQUE KEY storage_key (storage_key),
+    KEY idx_attachments_contact_id (contact_id),
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+) ENGINE = InnoDB DEFAULT CHARSET = utf8mb4 COLLATE = utf8mb4_bin;
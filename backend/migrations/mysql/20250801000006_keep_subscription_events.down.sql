@@ -0,0 +1,6 @@
+-- Events of contacts that no longer exist can't be kept under the old foreign key.
+DELETE FROM subscription_events WHERE contact_id IS NULL;
+ALTER TABLE subscription_events DROP FOREIGN KEY subscription_events_contact_id_fkey;
+ALTER TABLE subscription_events MODIFY contact_id BIGINT NOT NULL;
+ALTER TABLE subscription_events ADD CONSTRAINT subscription_events_contact_id_fkey
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE;
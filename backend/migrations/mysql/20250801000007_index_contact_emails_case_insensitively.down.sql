@@ -0,0 +1 @@
+ALTER TABLE contacts DROP KEY idx_contacts_org_id_lower_email, DROP COLUMN lower_email;
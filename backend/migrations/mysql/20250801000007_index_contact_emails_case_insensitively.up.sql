@@ -0,0 +1,7 @@
+-- Lets imports look up existing contacts by their lowercased email, the way they deduplicate,
+-- without scanning the organization's contacts. MySQL indexes expressions through generated
+-- columns and uses the index for queries on `LOWER(email)`, which matches the column's
+-- definition.
+ALTER TABLE contacts
+    ADD COLUMN lower_email VARCHAR(255) AS (LOWER(email)) VIRTUAL,
+    ADD KEY idx_contacts_org_id_lower_email (org_id, lower_email);
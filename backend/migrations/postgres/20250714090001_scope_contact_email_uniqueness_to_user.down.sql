@@ -0,0 +1,5 @@
+-- Contact emails are unique across all users again. This fails, and changes nothing, if two
+-- users have a contact with the same email.
+ALTER TABLE contacts DROP CONSTRAINT contacts_user_id_email_key;
+
+ALTER TABLE contacts ADD CONSTRAINT contacts_email_key UNIQUE (email);
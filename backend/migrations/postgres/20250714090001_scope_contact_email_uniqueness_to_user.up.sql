@@ -0,0 +1,4 @@
+-- Contact emails only need to be unique within a user's contact book.
+ALTER TABLE contacts DROP CONSTRAINT IF EXISTS contacts_email_key;
+
+ALTER TABLE contacts ADD CONSTRAINT contacts_user_id_email_key UNIQUE (user_id, email);
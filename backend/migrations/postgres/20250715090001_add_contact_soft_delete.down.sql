@@ -0,0 +1,10 @@
+-- Without a trash, the contacts in it are purged: their emails may clash with active contacts.
+DROP INDEX idx_contacts_deleted_at;
+
+DROP INDEX idx_contacts_user_id_email_active;
+
+DELETE FROM contacts WHERE deleted_at IS NOT NULL;
+
+ALTER TABLE contacts ADD CONSTRAINT contacts_user_id_email_key UNIQUE (user_id, email);
+
+ALTER TABLE contacts DROP COLUMN deleted_at;
@@ -0,0 +1,9 @@
+-- Deleted contacts are kept in the trash until they are purged.
+ALTER TABLE contacts ADD COLUMN deleted_at TIMESTAMP;
+
+-- The email only has to be unique among contacts that are not in the trash.
+ALTER TABLE contacts DROP CONSTRAINT IF EXISTS contacts_user_id_email_key;
+
+CREATE UNIQUE INDEX idx_contacts_user_id_email_active ON contacts(user_id, email) WHERE deleted_at IS NULL;
+
+CREATE INDEX idx_contacts_deleted_at ON contacts(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,4 @@
+DROP TABLE contact_events;
+
+ALTER TABLE contacts DROP COLUMN updated_at;
+ALTER TABLE contacts DROP COLUMN created_at;
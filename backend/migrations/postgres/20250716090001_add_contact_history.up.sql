@@ -0,0 +1,19 @@
+-- Track when contacts were created and last updated, and keep a log of every change.
+ALTER TABLE contacts ADD COLUMN created_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'UTC');
+ALTER TABLE contacts ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT (NOW() AT TIME ZONE 'UTC');
+
+-- Snapshots are the JSON-serialized contact before and after the change.
+CREATE TABLE contact_events (
+    id BIGSERIAL PRIMARY KEY,
+    contact_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    action TEXT NOT NULL,
+    request_id TEXT,
+    before_snapshot TEXT,
+    after_snapshot TEXT,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_events_contact_id ON contact_events(contact_id);
@@ -0,0 +1,2 @@
+-- The normalized contact types are kept; they are valid free-text types as well.
+DROP TABLE contact_types;
@@ -0,0 +1,9 @@
+-- Ages were dropped on the way up, so every contact comes back with an age of 0.
+DROP TABLE contact_emails;
+DROP TABLE contact_phones;
+DROP TABLE contact_addresses;
+
+ALTER TABLE contacts ADD COLUMN age BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE contacts ALTER COLUMN age DROP DEFAULT;
+ALTER TABLE contacts DROP COLUMN notes;
+ALTER TABLE contacts DROP COLUMN birthday;
@@ -0,0 +1,15 @@
+-- Per-user definitions of extra contact attributes. `options` holds the JSON array of
+-- choices of a `select` field and is empty for every other type.
+CREATE TABLE custom_fields (
+    id BIGSERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL CHECK (field_type IN ('text', 'number', 'date', 'bool', 'select')),
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    options JSONB NOT NULL DEFAULT '[]',
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE (user_id, name)
+);
+
+-- The values of a contact's custom fields, as a JSON object keyed by field name.
+ALTER TABLE contacts ADD COLUMN custom_fields JSONB NOT NULL DEFAULT '{}';
@@ -0,0 +1,59 @@
+-- Contacts, tags, custom fields and shares go back to the user who owns their organization.
+-- This fails, and changes nothing, if the data no longer fits one contact book per user: an
+-- organization without an owner, or one user owning two organizations with the same contact
+-- email, tag or custom field.
+CREATE TEMPORARY TABLE org_owners AS
+SELECT o.id AS org_id, (
+    SELECT m.user_id FROM memberships m
+    WHERE m.org_id = o.id AND m.role = 'owner'
+    ORDER BY m.created_at, m.user_id
+    LIMIT 1
+) AS user_id
+FROM organizations o;
+
+ALTER TABLE refresh_tokens DROP COLUMN org_id;
+
+-- A share opens its owner's contact book again.
+ALTER TABLE shares DROP CONSTRAINT shares_org_id_fkey;
+
+UPDATE shares SET org_id = (SELECT user_id FROM org_owners WHERE org_owners.org_id = shares.org_id);
+
+ALTER TABLE shares RENAME COLUMN org_id TO owner_id;
+
+ALTER TABLE shares ADD CONSTRAINT shares_owner_id_fkey
+    FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE;
+
+-- Dropping `org_id` also drops the constraints on it.
+ALTER TABLE custom_fields ADD COLUMN user_id BIGINT REFERENCES users(id) ON DELETE CASCADE;
+
+UPDATE custom_fields SET user_id = (SELECT user_id FROM org_owners WHERE org_owners.org_id = custom_fields.org_id);
+
+ALTER TABLE custom_fields ALTER COLUMN user_id SET NOT NULL;
+
+ALTER TABLE custom_fields DROP COLUMN org_id;
+
+ALTER TABLE custom_fields ADD CONSTRAINT custom_fields_user_id_name_key UNIQUE (user_id, name);
+
+ALTER TABLE tags ADD COLUMN user_id BIGINT REFERENCES users(id) ON DELETE CASCADE;
+
+UPDATE tags SET user_id = (SELECT user_id FROM org_owners WHERE org_owners.org_id = tags.org_id);
+
+ALTER TABLE tags ALTER COLUMN user_id SET NOT NULL;
+
+ALTER TABLE tags DROP COLUMN org_id;
+
+ALTER TABLE tags ADD CONSTRAINT tags_user_id_name_key UNIQUE (user_id, name);
+
+UPDATE contacts SET user_id = (SELECT user_id FROM org_owners WHERE org_owners.org_id = contacts.org_id);
+
+DROP INDEX idx_contacts_org_id_email_active;
+
+ALTER TABLE contacts DROP COLUMN org_id;
+
+CREATE UNIQUE INDEX idx_contacts_user_id_email_active ON contacts(user_id, email) WHERE deleted_at IS NULL;
+
+DROP TABLE org_owners;
+
+DROP TABLE memberships;
+
+DROP TABLE organizations;
@@ -0,0 +1,77 @@
+-- Organizations own contacts, tags, custom fields and shares. Users work in them through
+-- memberships, whose role decides what they may change.
+CREATE TABLE organizations (
+    id BIGSERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE memberships (
+    org_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('owner', 'admin', 'member', 'viewer')),
+    created_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (org_id, user_id),
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_memberships_user_id ON memberships(user_id);
+
+-- Every existing user gets a personal organization with the same ID, which takes over
+-- their contact book.
+INSERT INTO organizations (id, name, created_at)
+SELECT id, email, NOW() AT TIME ZONE 'UTC' FROM users;
+
+SELECT setval(
+    pg_get_serial_sequence('organizations', 'id'),
+    COALESCE((SELECT MAX(id) FROM organizations), 0) + 1,
+    false
+);
+
+INSERT INTO memberships (org_id, user_id, role, created_at)
+SELECT id, id, 'owner', NOW() AT TIME ZONE 'UTC' FROM users;
+
+-- Contacts belong to an organization; `user_id` now records who created them.
+ALTER TABLE contacts ADD COLUMN org_id BIGINT REFERENCES organizations(id) ON DELETE CASCADE;
+
+UPDATE contacts SET org_id = user_id;
+
+ALTER TABLE contacts ALTER COLUMN org_id SET NOT NULL;
+
+DROP INDEX idx_contacts_user_id_email_active;
+
+CREATE UNIQUE INDEX idx_contacts_org_id_email_active ON contacts(org_id, email) WHERE deleted_at IS NULL;
+
+-- Tags and custom fields are shared by the organization. Dropping `user_id` also drops the
+-- constraints on it.
+ALTER TABLE tags ADD COLUMN org_id BIGINT REFERENCES organizations(id) ON DELETE CASCADE;
+
+UPDATE tags SET org_id = user_id;
+
+ALTER TABLE tags ALTER COLUMN org_id SET NOT NULL;
+
+ALTER TABLE tags DROP COLUMN user_id;
+
+ALTER TABLE tags ADD CONSTRAINT tags_org_id_name_key UNIQUE (org_id, name);
+
+ALTER TABLE custom_fields ADD COLUMN org_id BIGINT REFERENCES organizations(id) ON DELETE CASCADE;
+
+UPDATE custom_fields SET org_id = user_id;
+
+ALTER TABLE custom_fields ALTER COLUMN org_id SET NOT NULL;
+
+ALTER TABLE custom_fields DROP COLUMN user_id;
+
+ALTER TABLE custom_fields ADD CONSTRAINT custom_fields_org_id_name_key UNIQUE (org_id, name);
+
+-- A share now opens an organization's contact book, or one of its contacts.
+ALTER TABLE shares DROP CONSTRAINT shares_owner_id_fkey;
+
+ALTER TABLE shares RENAME COLUMN owner_id TO org_id;
+
+ALTER TABLE shares ADD CONSTRAINT shares_org_id_fkey
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE;
+
+-- The organization a refresh token keeps the session in.
+ALTER TABLE refresh_tokens ADD COLUMN org_id BIGINT REFERENCES organizations(id) ON DELETE SET NULL;
@@ -0,0 +1,20 @@
+-- Newsletter consent: when and how each contact gave their current consent, and a log of
+-- every subscription change as a record for GDPR and CAN-SPAM. Subscriptions that existed
+-- before consent was tracked keep a NULL `subscribed_at`.
+ALTER TABLE contacts ADD COLUMN subscribed_at TIMESTAMP;
+ALTER TABLE contacts ADD COLUMN subscription_source TEXT;
+
+CREATE TABLE subscription_events (
+    id BIGSERIAL PRIMARY KEY,
+    contact_id BIGINT NOT NULL,
+    user_id BIGINT,
+    action TEXT NOT NULL,
+    source TEXT NOT NULL,
+    email TEXT NOT NULL,
+    request_id TEXT,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_subscription_events_contact_id ON subscription_events(contact_id);
@@ -0,0 +1,6 @@
+-- Events of contacts that no longer exist can't be kept under the old foreign key.
+DELETE FROM subscription_events WHERE contact_id IS NULL;
+ALTER TABLE subscription_events DROP CONSTRAINT subscription_events_contact_id_fkey;
+ALTER TABLE subscription_events ADD CONSTRAINT subscription_events_contact_id_fkey
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE;
+ALTER TABLE subscription_events ALTER COLUMN contact_id SET NOT NULL;
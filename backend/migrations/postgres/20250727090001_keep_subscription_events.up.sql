@@ -0,0 +1,7 @@
+-- The subscription log is the consent record, so it outlives the contacts it is about: a
+-- purged or deleted contact leaves its events behind with a NULL `contact_id`. The email of
+-- each event says who it was about.
+ALTER TABLE subscription_events ALTER COLUMN contact_id DROP NOT NULL;
+ALTER TABLE subscription_events DROP CONSTRAINT subscription_events_contact_id_fkey;
+ALTER TABLE subscription_events ADD CONSTRAINT subscription_events_contact_id_fkey
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE SET NULL;
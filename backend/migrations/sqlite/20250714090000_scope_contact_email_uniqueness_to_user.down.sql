@@ -0,0 +1,19 @@
+-- Contact emails are unique across all users again. This fails, and changes nothing, if two
+-- users have a contact with the same email.
+CREATE TABLE contacts_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL UNIQUE,
+    age INTEGER NOT NULL,
+    subscribed BOOLEAN NOT NULL,
+    contact_type TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+INSERT INTO contacts_new (id, user_id, name, email, age, subscribed, contact_type)
+SELECT id, user_id, name, email, age, subscribed, contact_type FROM contacts;
+
+DROP TABLE contacts;
+
+ALTER TABLE contacts_new RENAME TO contacts;
@@ -0,0 +1,20 @@
+-- Contact emails only need to be unique within a user's contact book.
+-- SQLite cannot drop a column constraint, so the table is rebuilt and the rows copied over.
+CREATE TABLE contacts_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    age INTEGER NOT NULL,
+    subscribed BOOLEAN NOT NULL,
+    contact_type TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id),
+    UNIQUE (user_id, email)
+);
+
+INSERT INTO contacts_new (id, user_id, name, email, age, subscribed, contact_type)
+SELECT id, user_id, name, email, age, subscribed, contact_type FROM contacts;
+
+DROP TABLE contacts;
+
+ALTER TABLE contacts_new RENAME TO contacts;
@@ -0,0 +1,20 @@
+-- Without a trash, the contacts in it are purged: their emails may clash with active contacts.
+CREATE TABLE contacts_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    age INTEGER NOT NULL,
+    subscribed BOOLEAN NOT NULL,
+    contact_type TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id),
+    UNIQUE (user_id, email)
+);
+
+INSERT INTO contacts_new (id, user_id, name, email, age, subscribed, contact_type)
+SELECT id, user_id, name, email, age, subscribed, contact_type FROM contacts
+WHERE deleted_at IS NULL;
+
+DROP TABLE contacts;
+
+ALTER TABLE contacts_new RENAME TO contacts;
@@ -0,0 +1,25 @@
+-- Deleted contacts are kept in the trash until they are purged.
+-- The email only has to be unique among contacts that are not in the trash, which needs a
+-- partial index instead of the table constraint, so the table is rebuilt once more.
+CREATE TABLE contacts_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    age INTEGER NOT NULL,
+    subscribed BOOLEAN NOT NULL,
+    contact_type TEXT NOT NULL,
+    deleted_at TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+INSERT INTO contacts_new (id, user_id, name, email, age, subscribed, contact_type)
+SELECT id, user_id, name, email, age, subscribed, contact_type FROM contacts;
+
+DROP TABLE contacts;
+
+ALTER TABLE contacts_new RENAME TO contacts;
+
+CREATE UNIQUE INDEX idx_contacts_user_id_email_active ON contacts(user_id, email) WHERE deleted_at IS NULL;
+
+CREATE INDEX idx_contacts_deleted_at ON contacts(deleted_at) WHERE deleted_at IS NOT NULL;
@@ -0,0 +1,24 @@
+DROP TABLE contact_events;
+
+CREATE TABLE contacts_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    age INTEGER NOT NULL,
+    subscribed BOOLEAN NOT NULL,
+    contact_type TEXT NOT NULL,
+    deleted_at TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+INSERT INTO contacts_new (id, user_id, name, email, age, subscribed, contact_type, deleted_at)
+SELECT id, user_id, name, email, age, subscribed, contact_type, deleted_at FROM contacts;
+
+DROP TABLE contacts;
+
+ALTER TABLE contacts_new RENAME TO contacts;
+
+CREATE UNIQUE INDEX idx_contacts_user_id_email_active ON contacts(user_id, email) WHERE deleted_at IS NULL;
+
+CREATE INDEX idx_contacts_deleted_at ON contacts(deleted_at) WHERE deleted_at IS NOT NULL;
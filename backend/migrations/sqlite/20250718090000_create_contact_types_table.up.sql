@@ -0,0 +1,76 @@
+-- The contact types this deployment offers. Every name must be a `ContactType` variant;
+-- disable a type or change its label or position to customize the set per deployment.
+CREATE TABLE contact_types (
+    name TEXT PRIMARY KEY NOT NULL CHECK (name IN ('Personal', 'Work', 'Family', 'Friend', 'Customer', 'Lead', 'Partner', 'Other')),
+    label TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+INSERT INTO contact_types (name, label, position) VALUES
+    ('Personal', 'Personal', 1),
+    ('Work', 'Work', 2),
+    ('Family', 'Family', 3),
+    ('Friend', 'Friend', 4),
+    ('Customer', 'Customer', 5),
+    ('Lead', 'Lead', 6),
+    ('Partner', 'Partner', 7),
+    ('Other', 'Other', 8);
+
+-- Normalize free-text types: case and whitespace variants map to the canonical name, a few
+-- common synonyms to their closest type, and anything else to 'Other'.
+UPDATE contacts SET contact_type = CASE LOWER(TRIM(contact_type))
+    WHEN 'personal' THEN 'Personal'
+    WHEN 'work' THEN 'Work'
+    WHEN 'family' THEN 'Family'
+    WHEN 'friend' THEN 'Friend'
+    WHEN 'customer' THEN 'Customer'
+    WHEN 'lead' THEN 'Lead'
+    WHEN 'partner' THEN 'Partner'
+    WHEN 'private' THEN 'Personal'
+    WHEN 'business' THEN 'Work'
+    WHEN 'relative' THEN 'Family'
+    WHEN 'friends' THEN 'Friend'
+    WHEN 'client' THEN 'Customer'
+    WHEN 'prospect' THEN 'Lead'
+    ELSE 'Other'
+END;
+
+-- Keep the history snapshots readable as contacts.
+UPDATE contact_events
+SET before_snapshot = json_set(before_snapshot, '$.contactType', CASE LOWER(TRIM(json_extract(before_snapshot, '$.contactType')))
+        WHEN 'personal' THEN 'Personal'
+        WHEN 'work' THEN 'Work'
+        WHEN 'family' THEN 'Family'
+        WHEN 'friend' THEN 'Friend'
+        WHEN 'customer' THEN 'Customer'
+        WHEN 'lead' THEN 'Lead'
+        WHEN 'partner' THEN 'Partner'
+        WHEN 'private' THEN 'Personal'
+        WHEN 'business' THEN 'Work'
+        WHEN 'relative' THEN 'Family'
+        WHEN 'friends' THEN 'Friend'
+        WHEN 'client' THEN 'Customer'
+        WHEN 'prospect' THEN 'Lead'
+        ELSE 'Other'
+    END)
+WHERE before_snapshot IS NOT NULL;
+
+UPDATE contact_events
+SET after_snapshot = json_set(after_snapshot, '$.contactType', CASE LOWER(TRIM(json_extract(after_snapshot, '$.contactType')))
+        WHEN 'personal' THEN 'Personal'
+        WHEN 'work' THEN 'Work'
+        WHEN 'family' THEN 'Family'
+        WHEN 'friend' THEN 'Friend'
+        WHEN 'customer' THEN 'Customer'
+        WHEN 'lead' THEN 'Lead'
+        WHEN 'partner' THEN 'Partner'
+        WHEN 'private' THEN 'Personal'
+        WHEN 'business' THEN 'Work'
+        WHEN 'relative' THEN 'Family'
+        WHEN 'friends' THEN 'Friend'
+        WHEN 'client' THEN 'Customer'
+        WHEN 'prospect' THEN 'Lead'
+        ELSE 'Other'
+    END)
+WHERE after_snapshot IS NOT NULL;
@@ -0,0 +1,40 @@
+-- A stored age goes stale, so contacts keep an optional birthday instead. Existing ages
+-- cannot be turned into a date and are dropped.
+ALTER TABLE contacts ADD COLUMN birthday DATE;
+ALTER TABLE contacts ADD COLUMN notes TEXT;
+ALTER TABLE contacts DROP COLUMN age;
+
+CREATE TABLE contact_emails (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    contact_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    email TEXT NOT NULL,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+);
+
+CREATE TABLE contact_phones (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    contact_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    number TEXT NOT NULL,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+);
+
+CREATE TABLE contact_addresses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    contact_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    street TEXT NOT NULL,
+    city TEXT NOT NULL,
+    region TEXT NOT NULL,
+    postal_code TEXT NOT NULL,
+    country TEXT NOT NULL,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_contact_emails_contact_id ON contact_emails(contact_id);
+CREATE INDEX IF NOT EXISTS idx_contact_phones_contact_id ON contact_phones(contact_id);
+CREATE INDEX IF NOT EXISTS idx_contact_addresses_contact_id ON contact_addresses(contact_id);
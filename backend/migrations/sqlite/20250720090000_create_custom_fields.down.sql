@@ -0,0 +1,3 @@
+ALTER TABLE contacts DROP COLUMN custom_fields;
+
+DROP TABLE custom_fields;
@@ -0,0 +1 @@
+ALTER TABLE contact_events DROP COLUMN related_contact_id;
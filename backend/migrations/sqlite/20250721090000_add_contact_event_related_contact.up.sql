@@ -0,0 +1,3 @@
+-- A merge is logged on both contacts involved; each event points at the other contact. This
+-- is a plain ID rather than a foreign key, since the merged-away contact is purged eventually.
+ALTER TABLE contact_events ADD COLUMN related_contact_id INTEGER;
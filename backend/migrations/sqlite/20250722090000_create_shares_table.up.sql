@@ -0,0 +1,18 @@
+-- A share grants another user access to a single contact, or to the owner's whole contact
+-- book when `contact_id` is NULL.
+CREATE TABLE shares (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    owner_id INTEGER NOT NULL,
+    grantee_id INTEGER NOT NULL,
+    contact_id INTEGER,
+    permission TEXT NOT NULL CHECK (permission IN ('read', 'write')),
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (grantee_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_shares_book
+    ON shares(owner_id, grantee_id) WHERE contact_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_shares_contact
+    ON shares(grantee_id, contact_id) WHERE contact_id IS NOT NULL;
@@ -0,0 +1,144 @@
+-- Contacts, tags, custom fields and shares go back to the user who owns their organization.
+-- This fails, and changes nothing, if the data no longer fits one contact book per user: an
+-- organization without an owner, or one user owning two organizations with the same contact
+-- email, tag or custom field.
+CREATE TABLE org_owners AS
+SELECT o.id AS org_id, (
+    SELECT m.user_id FROM memberships m
+    WHERE m.org_id = o.id AND m.role = 'owner'
+    ORDER BY m.created_at, m.user_id
+    LIMIT 1
+) AS user_id
+FROM organizations o;
+
+-- The contacts table has to be rebuilt to drop `org_id`, and dropping it cascades to its
+-- details, history, tag assignments and shares, which are set aside meanwhile.
+CREATE TABLE contact_events_saved AS SELECT * FROM contact_events;
+CREATE TABLE contact_emails_saved AS SELECT * FROM contact_emails;
+CREATE TABLE contact_phones_saved AS SELECT * FROM contact_phones;
+CREATE TABLE contact_addresses_saved AS SELECT * FROM contact_addresses;
+CREATE TABLE contact_tags_saved AS SELECT contact_id, tag_id FROM contact_tags;
+
+CREATE TABLE shares_saved AS
+SELECT s.id, o.user_id AS owner_id, s.grantee_id, s.contact_id, s.permission, s.created_at
+FROM shares s LEFT JOIN org_owners o ON o.org_id = s.org_id;
+
+DROP TABLE shares;
+
+CREATE TABLE contacts_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    email TEXT NOT NULL,
+    subscribed BOOLEAN NOT NULL,
+    contact_type TEXT NOT NULL,
+    deleted_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    birthday DATE,
+    notes TEXT,
+    custom_fields TEXT NOT NULL DEFAULT '{}' CHECK (json_valid(custom_fields)),
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+INSERT INTO contacts_new (id, user_id, name, email, subscribed, contact_type, deleted_at,
+    created_at, updated_at, birthday, notes, custom_fields)
+SELECT c.id, o.user_id, c.name, c.email, c.subscribed, c.contact_type, c.deleted_at,
+    c.created_at, c.updated_at, c.birthday, c.notes, c.custom_fields
+FROM contacts c LEFT JOIN org_owners o ON o.org_id = c.org_id;
+
+DROP TABLE contacts;
+
+ALTER TABLE contacts_new RENAME TO contacts;
+
+CREATE UNIQUE INDEX idx_contacts_user_id_email_active ON contacts(user_id, email) WHERE deleted_at IS NULL;
+
+CREATE INDEX idx_contacts_deleted_at ON contacts(deleted_at) WHERE deleted_at IS NOT NULL;
+
+CREATE TABLE tags_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE (user_id, name)
+);
+
+INSERT INTO tags_new (id, user_id, name)
+SELECT t.id, o.user_id, t.name FROM tags t LEFT JOIN org_owners o ON o.org_id = t.org_id;
+
+DROP TABLE tags;
+
+ALTER TABLE tags_new RENAME TO tags;
+
+CREATE TABLE custom_fields_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL CHECK (field_type IN ('text', 'number', 'date', 'bool', 'select')),
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    options TEXT NOT NULL DEFAULT '[]' CHECK (json_valid(options)),
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    UNIQUE (user_id, name)
+);
+
+INSERT INTO custom_fields_new (id, user_id, name, field_type, required, options)
+SELECT f.id, o.user_id, f.name, f.field_type, f.required, f.options
+FROM custom_fields f LEFT JOIN org_owners o ON o.org_id = f.org_id;
+
+DROP TABLE custom_fields;
+
+ALTER TABLE custom_fields_new RENAME TO custom_fields;
+
+CREATE TABLE shares (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    owner_id INTEGER NOT NULL,
+    grantee_id INTEGER NOT NULL,
+    contact_id INTEGER,
+    permission TEXT NOT NULL CHECK (permission IN ('read', 'write')),
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (owner_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (grantee_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_shares_book
+    ON shares(owner_id, grantee_id) WHERE contact_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_shares_contact
+    ON shares(grantee_id, contact_id) WHERE contact_id IS NOT NULL;
+
+INSERT INTO shares SELECT * FROM shares_saved;
+INSERT INTO contact_events SELECT * FROM contact_events_saved;
+INSERT INTO contact_emails SELECT * FROM contact_emails_saved;
+INSERT INTO contact_phones SELECT * FROM contact_phones_saved;
+INSERT INTO contact_addresses SELECT * FROM contact_addresses_saved;
+INSERT INTO contact_tags (contact_id, tag_id) SELECT contact_id, tag_id FROM contact_tags_saved;
+
+DROP TABLE shares_saved;
+DROP TABLE contact_events_saved;
+DROP TABLE contact_emails_saved;
+DROP TABLE contact_phones_saved;
+DROP TABLE contact_addresses_saved;
+DROP TABLE contact_tags_saved;
+
+CREATE TABLE refresh_tokens_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL UNIQUE,
+    token_hash TEXT NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+INSERT INTO refresh_tokens_new (id, user_id, token_hash, expires_at)
+SELECT id, user_id, token_hash, expires_at FROM refresh_tokens;
+
+DROP TABLE refresh_tokens;
+
+ALTER TABLE refresh_tokens_new RENAME TO refresh_tokens;
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_token_hash ON refresh_tokens(token_hash);
+
+DROP TABLE org_owners;
+
+DROP TABLE memberships;
+
+DROP TABLE organizations;
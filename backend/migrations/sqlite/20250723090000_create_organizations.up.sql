@@ -0,0 +1,105 @@
+-- Organizations own contacts, tags, custom fields and shares. Users work in them through
+-- memberships, whose role decides what they may change.
+CREATE TABLE organizations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE memberships (
+    org_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    role TEXT NOT NULL CHECK (role IN ('owner', 'admin', 'member', 'viewer')),
+    created_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (org_id, user_id),
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_memberships_user_id ON memberships(user_id);
+
+-- Every existing user gets a personal organization with the same ID, which takes over
+-- their contact book.
+INSERT INTO organizations (id, name, created_at)
+SELECT id, email, CURRENT_TIMESTAMP FROM users;
+
+INSERT INTO memberships (org_id, user_id, role, created_at)
+SELECT id, id, 'owner', CURRENT_TIMESTAMP FROM users;
+
+-- Contacts belong to an organization; `user_id` now records who created them. The contacts
+-- table is not rebuilt, since dropping it would cascade to its details and history.
+ALTER TABLE contacts ADD COLUMN org_id INTEGER REFERENCES organizations(id) ON DELETE CASCADE;
+
+UPDATE contacts SET org_id = user_id;
+
+DROP INDEX idx_contacts_user_id_email_active;
+
+CREATE UNIQUE INDEX idx_contacts_org_id_email_active ON contacts(org_id, email) WHERE deleted_at IS NULL;
+
+-- SQLite cannot change a table constraint in place, so tags, custom fields and shares are
+-- rebuilt. Dropping `tags` cascades to the tag assignments, which are set aside meanwhile.
+CREATE TABLE tags_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    org_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    UNIQUE (org_id, name)
+);
+
+INSERT INTO tags_new (id, org_id, name) SELECT id, user_id, name FROM tags;
+
+CREATE TABLE contact_tags_saved AS SELECT contact_id, tag_id FROM contact_tags;
+
+DROP TABLE tags;
+
+ALTER TABLE tags_new RENAME TO tags;
+
+INSERT INTO contact_tags (contact_id, tag_id) SELECT contact_id, tag_id FROM contact_tags_saved;
+
+DROP TABLE contact_tags_saved;
+
+CREATE TABLE custom_fields_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    org_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    field_type TEXT NOT NULL CHECK (field_type IN ('text', 'number', 'date', 'bool', 'select')),
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    options TEXT NOT NULL DEFAULT '[]' CHECK (json_valid(options)),
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    UNIQUE (org_id, name)
+);
+
+INSERT INTO custom_fields_new (id, org_id, name, field_type, required, options)
+SELECT id, user_id, name, field_type, required, options FROM custom_fields;
+
+DROP TABLE custom_fields;
+
+ALTER TABLE custom_fields_new RENAME TO custom_fields;
+
+-- A share now opens an organization's contact book, or one of its contacts.
+CREATE TABLE shares_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    org_id INTEGER NOT NULL,
+    grantee_id INTEGER NOT NULL,
+    contact_id INTEGER,
+    permission TEXT NOT NULL CHECK (permission IN ('read', 'write')),
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (org_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (grantee_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE
+);
+
+INSERT INTO shares_new (id, org_id, grantee_id, contact_id, permission, created_at)
+SELECT id, owner_id, grantee_id, contact_id, permission, created_at FROM shares;
+
+DROP TABLE shares;
+
+ALTER TABLE shares_new RENAME TO shares;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_shares_book
+    ON shares(org_id, grantee_id) WHERE contact_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_shares_contact
+    ON shares(grantee_id, contact_id) WHERE contact_id IS NOT NULL;
+
+-- The organization a refresh token keeps the session in.
+ALTER TABLE refresh_tokens ADD COLUMN org_id INTEGER REFERENCES organizations(id) ON DELETE SET NULL;
@@ -0,0 +1,4 @@
+-- The stored files are left in the storage.
+DROP TABLE attachments;
+
+ALTER TABLE contacts DROP COLUMN avatar_key;
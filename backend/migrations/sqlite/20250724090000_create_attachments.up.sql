@@ -0,0 +1,18 @@
+-- Files attached to contacts. The content lives in the configured storage under
+-- `storage_key`; the avatar of a contact is a thumbnail stored the same way.
+CREATE TABLE attachments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER NOT NULL,
+    user_id INTEGER,
+    file_name TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    storage_key TEXT NOT NULL UNIQUE,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_attachments_contact_id ON attachments(contact_id);
+
+ALTER TABLE contacts ADD COLUMN avatar_key TEXT;
@@ -0,0 +1,4 @@
+DROP TABLE subscription_events;
+
+ALTER TABLE contacts DROP COLUMN subscription_source;
+ALTER TABLE contacts DROP COLUMN subscribed_at;
@@ -0,0 +1,3 @@
+-- Server administrators run maintenance such as backups. They are made with the
+-- `backend user` commands, never through the API.
+ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
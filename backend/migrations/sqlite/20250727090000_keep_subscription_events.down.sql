@@ -0,0 +1,22 @@
+-- Events of contacts that no longer exist can't be kept under the old foreign key.
+CREATE TABLE subscription_events_old (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER NOT NULL,
+    user_id INTEGER,
+    action TEXT NOT NULL,
+    source TEXT NOT NULL,
+    email TEXT NOT NULL,
+    request_id TEXT,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+INSERT INTO subscription_events_old (id, contact_id, user_id, action, source, email, request_id, created_at)
+SELECT id, contact_id, user_id, action, source, email, request_id, created_at
+FROM subscription_events WHERE contact_id IS NOT NULL;
+
+DROP TABLE subscription_events;
+ALTER TABLE subscription_events_old RENAME TO subscription_events;
+
+CREATE INDEX IF NOT EXISTS idx_subscription_events_contact_id ON subscription_events(contact_id);
@@ -0,0 +1,24 @@
+-- The subscription log is the consent record, so it outlives the contacts it is about: a
+-- purged or deleted contact leaves its events behind with a NULL `contact_id`. The email of
+-- each event says who it was about. SQLite can't change a foreign key, so the table is
+-- rebuilt.
+CREATE TABLE subscription_events_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER,
+    user_id INTEGER,
+    action TEXT NOT NULL,
+    source TEXT NOT NULL,
+    email TEXT NOT NULL,
+    request_id TEXT,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (contact_id) REFERENCES contacts(id) ON DELETE SET NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+INSERT INTO subscription_events_new (id, contact_id, user_id, action, source, email, request_id, created_at)
+SELECT id, contact_id, user_id, action, source, email, request_id, created_at FROM subscription_events;
+
+DROP TABLE subscription_events;
+ALTER TABLE subscription_events_new RENAME TO subscription_events;
+
+CREATE INDEX IF NOT EXISTS idx_subscription_events_contact_id ON subscription_events(contact_id);
@@ -0,0 +1 @@
+DROP INDEX idx_contacts_org_id_lower_email_active;
@@ -0,0 +1,3 @@
+-- Lets imports look up existing contacts by their lowercased email, the way they deduplicate,
+-- without scanning the organization's contacts.
+CREATE INDEX idx_contacts_org_id_lower_email_active ON contacts(org_id, LOWER(email)) WHERE deleted_at IS NULL;
@@ -0,0 +1,248 @@
+//! The operator tasks behind the `backend user`, `backend tokens` and `backend config`
+//! subcommands. They work on the database directly, so the first administrator can be made
+//! and passwords rotated without going through the API.
+
+use bcrypt::{hash, DEFAULT_COST};
+use common::Credentials;
+use rand::Rng;
+use thiserror::Error;
+use validator::Validate;
+
+use crate::config::{AppConfig, DatabaseConfig};
+use crate::db::{DbPool, DbPools};
+use crate::migrations::MigrationState;
+use crate::repository::{Repositories, RepositoryError, UserDeletion};
+use crate::storage::Storage;
+
+#[derive(Debug, Error)]
+pub enum AdminError {
+    #[error("There is no user with the email {0}")]
+    UnknownUser(String),
+
+    #[error("A user with the email {0} already exists")]
+    UserExists(String),
+
+    #[error("Invalid credentials: {0}")]
+    Invalid(#[from] validator::ValidationErrors),
+
+    #[error(
+        "{email} is the last owner of the organization '{org}', which has other members; \
+         make one of them an owner first"
+    )]
+    LastOwner { email: String, org: String },
+
+    #[error("Failed to hash the password: {0}")]
+    Hash(#[from] bcrypt::BcryptError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct UserSummary {
+    pub id: i64,
+    pub email: String,
+    pub is_admin: bool,
+    /// How many organizations the user is a member of.
+    pub orgs: i64,
+}
+
+/// A random password for a user made without one, to be handed to them.
+pub fn generate_password() -> String {
+    rand::rng()
+        .sample_iter(&rand::distr::Alphanumeric)
+        .take(20)
+        .map(char::from)
+        .collect()
+}
+
+fn repositories(db_pool: &DbPool) -> Repositories {
+    Repositories::sql(DbPools::new(db_pool.clone()))
+}
+
+fn hash_password(email: &str, password: &str) -> Result<String, AdminError> {
+    Credentials {
+        email: email.to_string(),
+        password: password.to_string(),
+    }
+    .validate()?;
+    Ok(hash(password, DEFAULT_COST)?)
+}
+
+/// Registers a user with their personal organization, as signing up does.
+pub async fn create_user(
+    db_pool: &DbPool,
+    email: &str,
+    password: &str,
+    is_admin: bool,
+) -> Result<i64, AdminError> {
+    let password_hash = hash_password(email, password)?;
+    let result = repositories(db_pool)
+        .users
+        .create(email, &password_hash)
+        .await;
+    let user_id = match result {
+        Ok(user_id) => user_id,
+        Err(RepositoryError::Duplicate(_)) => {
+            return Err(AdminError::UserExists(email.to_string()))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if is_admin {
+        set_admin(db_pool, email, true).await?;
+    }
+    Ok(user_id)
+}
+
+pub async fn list_users(db_pool: &DbPool) -> Result<Vec<UserSummary>, AdminError> {
+    Ok(repositories(db_pool).users.summaries().await?)
+}
+
+/// Makes a user a server administrator, or takes it away.
+pub async fn set_admin(db_pool: &DbPool, email: &str, is_admin: bool) -> Result<(), AdminError> {
+    let updated = repositories(db_pool)
+        .users
+        .set_admin(email, is_admin)
+        .await?;
+    if !updated {
+        return Err(AdminError::UnknownUser(email.to_string()));
+    }
+    Ok(())
+}
+
+/// Sets a new password and ends the user's session, so it has to be used from now on.
+pub async fn reset_password(
+    db_pool: &DbPool,
+    email: &str,
+    password: &str,
+) -> Result<(), AdminError> {
+    let password_hash = hash_password(email, password)?;
+
+    let updated = repositories(db_pool)
+        .users
+        .reset_password(email, &password_hash)
+        .await?;
+    if !updated {
+        return Err(AdminError::UnknownUser(email.to_string()));
+    }
+    Ok(())
+}
+
+/// Deletes a user along with the organizations nobody else is a member of, and their files.
+/// What they created in the organizations that stay is credited to an owner there. A user
+/// who is the last owner of an organization with other members is kept. Returns the number
+/// of deleted organizations.
+pub async fn delete_user(
+    db_pool: &DbPool,
+    storage: &dyn Storage,
+    email: &str,
+) -> Result<u64, AdminError> {
+    let deletion = repositories(db_pool).users.delete(email).await?;
+    let (deleted_orgs, storage_keys) = match deletion {
+        UserDeletion::UnknownUser => return Err(AdminError::UnknownUser(email.to_string())),
+        UserDeletion::LastOwner(org) => {
+            return Err(AdminError::LastOwner {
+                email: email.to_string(),
+                org,
+            })
+        }
+        UserDeletion::Deleted { orgs, storage_keys } => (orgs, storage_keys),
+    };
+
+    for key in storage_keys {
+        if let Err(e) = storage.delete(&key).await {
+            tracing::warn!("Failed to delete stored object {}: {}", key, e);
+        }
+    }
+
+    Ok(deleted_orgs)
+}
+
+/// Deletes the expired refresh tokens, or every one of them with `all`, which signs everybody
+/// out. Returns the number of deleted tokens.
+pub async fn purge_tokens(db_pool: &DbPool, all: bool) -> Result<u64, AdminError> {
+    Ok(repositories(db_pool).tokens.purge(all).await?)
+}
+
+/// One line of `backend config check`: what was checked, and what was found or went wrong.
+#[derive(Debug)]
+pub struct ConfigCheck {
+    pub name: &'static str,
+    pub result: Result<String, String>,
+}
+
+/// Checks that the server could start with `config`: the JWT secret is strong enough, the
+/// database is reachable with migrations the build knows, and storage and mailer are set up.
+pub async fn check_config(config: &AppConfig) -> Vec<ConfigCheck> {
+    let jwt = match config.jwt.secret.len() {
+        len if len < 32 => Err(format!("the secret is {len} bytes long; use at least 32")),
+        _ => Ok("secret set".to_string()),
+    };
+    let storage = crate::storage::from_config(&config.storage)
+        .map(|_| format!("{:?}", config.storage.backend))
+        .map_err(|e| e.to_string());
+    let mailer = crate::mailer::from_config(&config.mailer)
+        .map(|_| {
+            format!(
+                "{:?}, sending as {}",
+                config.mailer.backend, config.mailer.from
+            )
+        })
+        .map_err(|e| e.to_string());
+
+    vec![
+        ConfigCheck {
+            name: "jwt",
+            result: jwt,
+        },
+        ConfigCheck {
+            name: "database",
+            result: check_database(&config.database).await,
+        },
+        ConfigCheck {
+            name: "storage",
+            result: storage,
+        },
+        ConfigCheck {
+            name: "mailer",
+            result: mailer,
+        },
+    ]
+}
+
+async fn check_database(config: &DatabaseConfig) -> Result<String, String> {
+    let url = config
+        .url()
+        .ok_or("neither DATABASE_URL nor `database.url` is set")?;
+    // A check should not sit through the startup retries.
+    let config = DatabaseConfig {
+        connect_attempts: 1,
+        ..config.clone()
+    };
+    let db_pool = crate::db::connect(&url, &config)
+        .await
+        .map_err(|e| e.to_string())?;
+    let backend = db_pool.backend();
+    let status = crate::migrations::status(&db_pool)
+        .await
+        .map_err(|e| e.to_string());
+    db_pool.close().await;
+
+    let status = status?;
+    let count = |state| status.iter().filter(|m| m.state == state).count();
+    if let Some(modified) = status.iter().find(|m| m.state == MigrationState::Modified) {
+        return Err(format!(
+            "migration {} was changed after it was applied",
+            modified.version
+        ));
+    }
+    Ok(format!(
+        "{}, {} migrations applied, {} pending",
+        backend.as_str(),
+        count(MigrationState::Applied),
+        count(MigrationState::Pending)
+    ))
+}
@@ -0,0 +1,552 @@
+use std::io::Cursor;
+
+use axum::{
+    debug_handler,
+    extract::{multipart::MultipartError, Multipart, Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use common::{AttachmentDto, ContactDto, SharePermission};
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+
+use crate::error::AppError;
+use crate::extractors::AuthUser;
+use crate::sharing::check_access;
+use crate::storage::Storage;
+use crate::web_server::AppState;
+
+/// Room for the multipart boundaries and part headers around an upload, on top of the
+/// configured file size limit.
+pub const UPLOAD_OVERHEAD_BYTES: usize = 64 * 1024;
+
+/// Longest file name kept for an attachment, in characters.
+const MAX_FILE_NAME_CHARS: usize = 255;
+
+/// A file read from the `file` part of a multipart request.
+struct Upload {
+    file_name: String,
+    content_type: String,
+    data: Vec<u8>,
+}
+
+/// Reads the part named `file`, rejecting it as soon as it grows past `max_bytes`.
+async fn read_upload(multipart: &mut Multipart, max_bytes: usize) -> Result<Upload, AppError> {
+    let mut field = loop {
+        match multipart.next_field().await.map_err(upload_error)? {
+            Some(field) if field.name() == Some("file") => break field,
+            Some(_) => continue,
+            None => {
+                return Err(AppError::BadRequest(
+                    "Expected a multipart field named 'file'".to_string(),
+                ))
+            }
+        }
+    };
+
+    let file_name = clean_file_name(field.file_name().unwrap_or_default());
+    let content_type = field
+        .content_type()
+        .and_then(|content_type| content_type.split(';').next())
+        .map(|essence| essence.trim().to_lowercase())
+        .filter(|essence| !essence.is_empty())
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+
+    let mut data = Vec::new();
+    while let Some(chunk) = field.chunk().await.map_err(upload_error)? {
+        if data.len() + chunk.len() > max_bytes {
+            return Err(too_large(max_bytes));
+        }
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(Upload {
+        file_name,
+        content_type,
+        data,
+    })
+}
+
+fn upload_error(e: MultipartError) -> AppError {
+    if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        return AppError::PayloadTooLarge("The upload is too large".to_string());
+    }
+    AppError::BadRequest(format!("Failed to read upload: {}", e.body_text()))
+}
+
+fn too_large(max_bytes: usize) -> AppError {
+    AppError::PayloadTooLarge(format!("Files can be at most {max_bytes} bytes"))
+}
+
+/// Keeps the last path segment of a client-supplied file name, without control characters
+/// or quotes.
+fn clean_file_name(name: &str) -> String {
+    let name: String = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(MAX_FILE_NAME_CHARS)
+        .collect();
+    match name.trim() {
+        "" => "file".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Whether `content_type` matches one of the configured patterns, which are either exact
+/// types or whole families like `image/*`.
+fn content_type_allowed(allowed: &[String], content_type: &str) -> bool {
+    allowed
+        .iter()
+        .any(|pattern| match pattern.strip_suffix("/*") {
+            Some(family) => content_type
+                .split_once('/')
+                .is_some_and(|(kind, _)| kind.eq_ignore_ascii_case(family)),
+            None => pattern.eq_ignore_ascii_case(content_type),
+        })
+}
+
+/// A `Content-Disposition` value that downloads the file under its original name. Names
+/// outside printable ASCII are sent as RFC 5987 `filename*`, with an ASCII fallback.
+fn attachment_disposition(file_name: &str) -> String {
+    let fallback: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_graphic() || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if fallback == file_name {
+        return format!("attachment; filename=\"{file_name}\"");
+    }
+
+    let encoded: String = file_name
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect();
+    format!("attachment; filename=\"{fallback}\"; filename*=UTF-8''{encoded}")
+}
+
+/// Crops the image to a centered square of `size` pixels and encodes it as PNG.
+fn avatar_thumbnail(data: &[u8], format: ImageFormat, size: u32) -> Result<Vec<u8>, AppError> {
+    let image = image::load_from_memory_with_format(data, format).map_err(|_| {
+        AppError::BadRequest("The avatar could not be read as an image".to_string())
+    })?;
+    let thumbnail = DynamicImage::ImageRgba8(
+        image
+            .resize_to_fill(size, size, FilterType::Lanczos3)
+            .to_rgba8(),
+    );
+
+    let mut png = Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut png, ImageFormat::Png)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to encode avatar: {e}")))?;
+    Ok(png.into_inner())
+}
+
+/// Removes an object whose database row is already gone. A failure leaves an orphaned
+/// object behind, which is logged rather than reported to the client.
+async fn delete_object(storage: &dyn Storage, key: &str) {
+    if let Err(e) = storage.delete(key).await {
+        tracing::warn!("Failed to delete stored object {}: {}", key, e);
+    }
+}
+
+// --- API Handlers ---
+
+/// ## List the attachments of a contact
+#[utoipa::path(
+    get,
+    path = "/api/v1/contacts/{id}/attachments",
+    params(
+        ("id" = i64, Path, description = "Contact ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Attachments in upload order", body = Vec<AttachmentDto>),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Contact not found"),
+    )
+)]
+#[debug_handler]
+pub async fn get_attachments(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+) -> Result<Json<Vec<AttachmentDto>>, AppError> {
+    tracing::info!(
+        "Fetching attachments of contact {} for user {}",
+        id,
+        user.id
+    );
+
+    check_access(
+        state.repos.contacts.access(id, &user).await?,
+        SharePermission::Read,
+    )?;
+    let attachments = state.repos.attachments.list(id).await?;
+
+    Ok(Json(attachments))
+}
+
+/// ## Attach a file to a contact
+/// Accepts files up to the configured size whose type is on the configured list.
+#[utoipa::path(
+    post,
+    path = "/api/v1/contacts/{id}/attachments",
+    params(
+        ("id" = i64, Path, description = "Contact ID")
+    ),
+    request_body(content_type = "multipart/form-data", description = "A `file` part with the file to attach"),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 201, description = "File attached", body = AttachmentDto),
+        (status = 400, description = "Missing file part"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user may not change this contact"),
+        (status = 404, description = "Contact not found"),
+        (status = 413, description = "The file is too large"),
+        (status = 415, description = "The file type is not allowed"),
+    )
+)]
+#[debug_handler]
+pub async fn upload_attachment(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<AttachmentDto>), AppError> {
+    tracing::info!(
+        "Uploading attachment to contact {} for user {}",
+        id,
+        user.id
+    );
+
+    let config = &state.app_config.storage;
+    check_access(
+        state.repos.contacts.access(id, &user).await?,
+        SharePermission::Write,
+    )?;
+
+    let upload = read_upload(&mut multipart, config.max_upload_bytes).await?;
+    if !content_type_allowed(&config.allowed_content_types, &upload.content_type) {
+        return Err(AppError::UnsupportedMediaType(format!(
+            "Files of type {} cannot be attached",
+            upload.content_type
+        )));
+    }
+
+    let key = format!("attachments/{}", uuid::Uuid::new_v4());
+    let size = upload.data.len() as i64;
+    state
+        .storage
+        .put(&key, upload.data, &upload.content_type)
+        .await?;
+
+    let result = state
+        .repos
+        .attachments
+        .create(
+            id,
+            user.id,
+            &upload.file_name,
+            &upload.content_type,
+            size,
+            &key,
+        )
+        .await;
+
+    match result {
+        Ok(attachment) => Ok((StatusCode::CREATED, Json(attachment))),
+        Err(e) => {
+            delete_object(state.storage.as_ref(), &key).await;
+            Err(e.into())
+        }
+    }
+}
+
+/// ## Download an attachment
+#[utoipa::path(
+    get,
+    path = "/api/v1/contacts/{id}/attachments/{attachment_id}",
+    params(
+        ("id" = i64, Path, description = "Contact ID"),
+        ("attachment_id" = i64, Path, description = "Attachment ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The file, with the type it was uploaded as", content_type = "application/octet-stream"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Contact or attachment not found"),
+    )
+)]
+#[debug_handler]
+pub async fn download_attachment(
+    State(state): State<AppState>,
+    Path((id, attachment_id)): Path<(i64, i64)>,
+    user: AuthUser,
+) -> Result<Response, AppError> {
+    tracing::info!(
+        "Downloading attachment {} of contact {} for user {}",
+        attachment_id,
+        id,
+        user.id
+    );
+
+    check_access(
+        state.repos.contacts.access(id, &user).await?,
+        SharePermission::Read,
+    )?;
+    let attachment = state
+        .repos
+        .attachments
+        .find(id, attachment_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let Some(data) = state.storage.get(&attachment.storage_key).await? else {
+        tracing::error!(
+            "Attachment {} is missing from storage ({})",
+            attachment_id,
+            attachment.storage_key
+        );
+        return Err(AppError::NotFound);
+    };
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, attachment.content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                attachment_disposition(&attachment.file_name),
+            ),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        data,
+    )
+        .into_response())
+}
+
+/// ## Delete an attachment
+#[utoipa::path(
+    delete,
+    path = "/api/v1/contacts/{id}/attachments/{attachment_id}",
+    params(
+        ("id" = i64, Path, description = "Contact ID"),
+        ("attachment_id" = i64, Path, description = "Attachment ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Attachment deleted"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user may not change this contact"),
+        (status = 404, description = "Contact or attachment not found"),
+    )
+)]
+#[debug_handler]
+pub async fn delete_attachment(
+    State(state): State<AppState>,
+    Path((id, attachment_id)): Path<(i64, i64)>,
+    user: AuthUser,
+) -> Result<StatusCode, AppError> {
+    tracing::info!(
+        "Deleting attachment {} of contact {} for user {}",
+        attachment_id,
+        id,
+        user.id
+    );
+
+    check_access(
+        state.repos.contacts.access(id, &user).await?,
+        SharePermission::Write,
+    )?;
+    let key = state
+        .repos
+        .attachments
+        .delete(id, attachment_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    delete_object(state.storage.as_ref(), &key).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// ## Get the avatar of a contact
+/// A square PNG thumbnail of the uploaded image.
+#[utoipa::path(
+    get,
+    path = "/api/v1/contacts/{id}/avatar",
+    params(
+        ("id" = i64, Path, description = "Contact ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The avatar thumbnail", content_type = "image/png"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Contact not found or without an avatar"),
+    )
+)]
+#[debug_handler]
+pub async fn get_avatar(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+) -> Result<Response, AppError> {
+    tracing::info!("Fetching avatar of contact {} for user {}", id, user.id);
+
+    check_access(
+        state.repos.contacts.access(id, &user).await?,
+        SharePermission::Read,
+    )?;
+    let key = state
+        .repos
+        .attachments
+        .avatar_key(id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let data = state.storage.get(&key).await?.ok_or(AppError::NotFound)?;
+    Ok(([(header::CONTENT_TYPE, "image/png")], data).into_response())
+}
+
+/// ## Set the avatar of a contact
+/// The uploaded PNG, JPEG, GIF or WebP image is cropped to a square thumbnail of the
+/// configured size. The previous avatar is replaced.
+#[utoipa::path(
+    put,
+    path = "/api/v1/contacts/{id}/avatar",
+    params(
+        ("id" = i64, Path, description = "Contact ID")
+    ),
+    request_body(content_type = "multipart/form-data", description = "A `file` part with the image"),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Avatar set", body = ContactDto),
+        (status = 400, description = "Missing file part or unreadable image"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user may not change this contact"),
+        (status = 404, description = "Contact not found"),
+        (status = 413, description = "The image is too large"),
+        (status = 415, description = "The file is not a supported image type"),
+    )
+)]
+#[debug_handler]
+pub async fn set_avatar(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+    mut multipart: Multipart,
+) -> Result<Json<ContactDto>, AppError> {
+    tracing::info!("Setting avatar of contact {} for user {}", id, user.id);
+
+    let config = &state.app_config.storage;
+    check_access(
+        state.repos.contacts.access(id, &user).await?,
+        SharePermission::Write,
+    )?;
+
+    let upload = read_upload(&mut multipart, config.max_upload_bytes).await?;
+    let format = ImageFormat::from_mime_type(&upload.content_type)
+        .filter(|format| {
+            matches!(
+                format,
+                ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP
+            )
+        })
+        .ok_or_else(|| {
+            AppError::UnsupportedMediaType(
+                "Avatars must be PNG, JPEG, GIF or WebP images".to_string(),
+            )
+        })?;
+
+    // Decoding and resizing is CPU-bound, so it stays off the async workers.
+    let size = config.avatar_size;
+    let thumbnail =
+        tokio::task::spawn_blocking(move || avatar_thumbnail(&upload.data, format, size))
+            .await
+            .map_err(|e| AppError::InternalServerError(format!("Avatar task failed: {e}")))??;
+
+    let key = format!("avatars/{}.png", uuid::Uuid::new_v4());
+    state.storage.put(&key, thumbnail, "image/png").await?;
+
+    let result = state.repos.attachments.set_avatar(&user, id, &key).await;
+    match result {
+        Ok(Some((contact, previous))) => {
+            if let Some(previous) = previous {
+                delete_object(state.storage.as_ref(), &previous).await;
+            }
+            Ok(Json(contact))
+        }
+        Ok(None) => {
+            delete_object(state.storage.as_ref(), &key).await;
+            Err(AppError::NotFound)
+        }
+        Err(e) => {
+            delete_object(state.storage.as_ref(), &key).await;
+            Err(e.into())
+        }
+    }
+}
+
+/// ## Remove the avatar of a contact
+#[utoipa::path(
+    delete,
+    path = "/api/v1/contacts/{id}/avatar",
+    params(
+        ("id" = i64, Path, description = "Contact ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Avatar removed"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user may not change this contact"),
+        (status = 404, description = "Contact not found or without an avatar"),
+    )
+)]
+#[debug_handler]
+pub async fn delete_avatar(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+) -> Result<StatusCode, AppError> {
+    tracing::info!("Removing avatar of contact {} for user {}", id, user.id);
+
+    check_access(
+        state.repos.contacts.access(id, &user).await?,
+        SharePermission::Write,
+    )?;
+    let key = state
+        .repos
+        .attachments
+        .remove_avatar(&user, id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    delete_object(state.storage.as_ref(), &key).await;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -17,25 +17,20 @@ use axum_extra::{
 
 use crate::config::JwtConfig;
 use crate::error::AppError;
+use crate::extractors::AuthUser;
+use crate::repository::{RepositoryError, TokenRepository, UserRepository};
 use crate::web_server::AppState;
-use crate::{db::DbPool, extractors::AuthUser};
 use rand::Rng;
 use sha2::{Digest, Sha256};
 use utoipa::ToSchema;
 use validator::Validate;
 
-// --- User & Payload Structs ---
-
-#[derive(sqlx::FromRow, Debug)]
-pub struct User {
-    pub id: i64,
-    pub email: String,
-    pub password_hash: String,
-}
+// --- Payload Structs ---
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,   // Subject (user id)
+    pub org: i64,      // Organization the token is scoped to
     pub exp: usize,    // Expiration time
     pub nonce: String, // Nonce for access token uniqueness
 }
@@ -46,22 +41,25 @@ pub struct RefreshPayload {
     pub refresh_token: String,
 }
 
-// --- Helper struct for reading the token from the database ---
-#[derive(sqlx::FromRow)]
-struct RefreshTokenRecord {
-    user_id: i64,
-    expires_at: chrono::NaiveDateTime,
-}
-
 // --- Token Helper ---
 
-/// Creates a new access token and a new refresh token for a user.
-/// It stores the hashed refresh token in the database, replacing any existing one for the user.
-/// Optionally, if an `old_token_hash` is provided, it will be deleted as part of the transaction,
+/// Returns the organization a user starts a session in: the one they joined first, which is
+/// the personal organization created when they registered.
+async fn default_org(users: &dyn UserRepository, user_id: i64) -> Result<i64, AppError> {
+    users
+        .default_org(user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)
+}
+
+/// Creates a new access token and a new refresh token for a user, scoped to `org_id`.
+/// It stores the hashed refresh token, replacing any existing one for the user.
+/// Optionally, if an `old_token_hash` is provided, it will be deleted in the same step,
 /// ensuring old refresh tokens are invalidated upon use.
-async fn issue_tokens(
+pub(crate) async fn issue_tokens(
     user_id: i64,
-    db_pool: &DbPool,
+    org_id: i64,
+    tokens: &dyn TokenRepository,
     jwt_config: &JwtConfig,
     old_token_hash: Option<&str>,
 ) -> Result<LoginResponse, AppError> {
@@ -77,6 +75,7 @@ async fn issue_tokens(
         .timestamp() as usize;
     let access_claims = Claims {
         sub: user_id.to_string(),
+        org: org_id,
         exp: access_token_exp,
         nonce,
     };
@@ -98,30 +97,17 @@ async fn issue_tokens(
     let new_refresh_token_exp =
         (Utc::now() + Duration::days(jwt_config.refresh_token_expires_days)).naive_utc();
 
-    // --- Database Operations in a Transaction ---
-    let mut tx = db_pool.begin().await?;
-
-    // If an old token was used (in a refresh operation), delete it.
-    if let Some(old_hash) = old_token_hash {
-        sqlx::query!("DELETE FROM refresh_tokens WHERE token_hash = $1", old_hash)
-            .execute(&mut *tx)
-            .await?;
-    }
-
-    // Insert the new refresh token, replacing any existing token for the user.
-    // This invalidates any other sessions if the user logs in again.
-
-    sqlx::query!(
-		"INSERT INTO refresh_tokens (user_id, token_hash, expires_at) VALUES ($1, $2, $3)
-         ON CONFLICT(user_id) DO UPDATE SET token_hash=excluded.token_hash, expires_at=excluded.expires_at",
-		user_id,
-		new_refresh_token_hash,
-		new_refresh_token_exp
-	)
-	.execute(&mut *tx)
-	.await?;
-
-    tx.commit().await?;
+    // Store the new token, deleting the old one (in a refresh operation) and replacing any
+    // existing token for the user. This invalidates any other sessions if the user logs in again.
+    tokens
+        .store(
+            user_id,
+            org_id,
+            &new_refresh_token_hash,
+            new_refresh_token_exp,
+            old_token_hash,
+        )
+        .await?;
 
     // Return the new pair of tokens to the client.
     Ok(LoginResponse {
@@ -133,7 +119,8 @@ async fn issue_tokens(
 // --- API Handlers ---
 
 /// ## Register a new user
-/// Takes email and password, hashes the password, and stores the user in the database.
+/// Takes email and password, hashes the password, and stores the user in the database along
+/// with a personal organization they own.
 #[utoipa::path(
     post,
     path = "/api/v1/register",
@@ -153,14 +140,12 @@ pub async fn register(
 
     tracing::info!("Registering user with email: {}", &payload.email);
     // Check if user already exists
-    let existing_user: Option<User> = sqlx::query_as!(
-        User,
-        "SELECT id as \"id!\", email, password_hash FROM users WHERE email = $1",
-        payload.email
-    )
-    .fetch_optional(&state.db_pool)
-    .await
-    .map_err(|_| (AppError::InternalServerError("Database error".to_string())))?;
+    let existing_user = state
+        .repos
+        .users
+        .find_by_email(&payload.email)
+        .await
+        .map_err(|_| AppError::InternalServerError("Database error".to_string()))?;
 
     if existing_user.is_some() {
         return Err(AppError::Conflict(
@@ -174,18 +159,26 @@ pub async fn register(
         AppError::InternalServerError("Password hashing error".to_string())
     })?;
 
-    // Insert new user into the database
-    sqlx::query!(
-        "INSERT INTO users (email, password_hash) VALUES ($1, $2)",
-        payload.email,
-        password_hash
-    )
-    .execute(&state.db_pool)
-    .await
-    .map_err(|e| {
-        tracing::error!("Failed to create user: {}", e);
-        AppError::InternalServerError("Failed to create user".to_string())
-    })?;
+    // Store the new user
+    match state
+        .repos
+        .users
+        .create(&payload.email, &password_hash)
+        .await
+    {
+        Ok(_) => {}
+        Err(RepositoryError::Duplicate(_)) => {
+            return Err(AppError::Conflict(
+                "User with this email already exists".to_string(),
+            ));
+        }
+        Err(e) => {
+            tracing::error!("Failed to create user: {}", e);
+            return Err(AppError::InternalServerError(
+                "Failed to create user".to_string(),
+            ));
+        }
+    }
 
     Ok(StatusCode::CREATED)
 }
@@ -210,20 +203,26 @@ pub async fn login(
     payload.validate()?;
 
     tracing::info!("Logging in user with email: {}", &payload.email);
-    let user: User = sqlx::query_as!(
-        User,
-        "SELECT id as \"id!\", email, password_hash FROM users WHERE email = $1",
-        payload.email
-    )
-    .fetch_optional(&state.db_pool)
-    .await?
-    .ok_or(AppError::Unauthorized)?;
+    let user = state
+        .repos
+        .users
+        .find_by_email(&payload.email)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
 
     if !verify(&payload.password, &user.password_hash)? {
         return Err(AppError::Unauthorized);
     }
 
-    let tokens = issue_tokens(user.id, &state.db_pool, &state.app_config.jwt, None).await?;
+    let org_id = default_org(&*state.repos.users, user.id).await?;
+    let tokens = issue_tokens(
+        user.id,
+        org_id,
+        &*state.repos.tokens,
+        &state.app_config.jwt,
+        None,
+    )
+    .await?;
 
     Ok(Json(tokens))
 }
@@ -247,33 +246,41 @@ pub async fn refresh(
     hasher.update(payload.refresh_token.as_bytes());
     let incoming_token_hash = hex::encode(hasher.finalize());
 
-    // Find the token in the database by its hash.
-    let record: RefreshTokenRecord = sqlx::query_as!(
-        RefreshTokenRecord,
-        "SELECT user_id, expires_at FROM refresh_tokens WHERE token_hash = $1",
-        incoming_token_hash
-    )
-    .fetch_optional(&state.db_pool)
-    .await?
-    .ok_or(AppError::Unauthorized)?;
+    // Find the token by its hash.
+    let record = state
+        .repos
+        .tokens
+        .find(&incoming_token_hash)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
 
     // Check if the database token has expired.
     if record.expires_at < Utc::now().naive_utc() {
-        // As a cleanup, remove the expired token from the DB
-        sqlx::query!(
-            "DELETE FROM refresh_tokens WHERE token_hash = $1",
-            incoming_token_hash
-        )
-        .execute(&state.db_pool)
-        .await
-        .ok(); // We don't care about the result of the cleanup
+        // As a cleanup, remove the expired token
+        state.repos.tokens.delete(&incoming_token_hash).await.ok(); // We don't care about the result of the cleanup
         return Err(AppError::Unauthorized);
     }
 
+    // Stay in the session's organization, unless the user has left it since.
+    let session_org = match record.org_id {
+        Some(org_id) => state
+            .repos
+            .users
+            .role_in(record.user_id, org_id)
+            .await?
+            .map(|_| org_id),
+        None => None,
+    };
+    let org_id = match session_org {
+        Some(org_id) => org_id,
+        None => default_org(&*state.repos.users, record.user_id).await?,
+    };
+
     // All checks passed. Rotate tokens: issue a new pair and invalidate the old refresh token.
     let tokens = issue_tokens(
         record.user_id,
-        &state.db_pool,
+        org_id,
+        &*state.repos.tokens,
         &state.app_config.jwt,
         Some(&incoming_token_hash), // Pass the old token hash to be deleted
     )
@@ -295,10 +302,8 @@ pub async fn refresh(
     )
 )]
 pub async fn logout(State(state): State<AppState>, user: AuthUser) -> Result<StatusCode, AppError> {
-    // Simply delete the refresh token from the database
-    sqlx::query!("DELETE FROM refresh_tokens WHERE user_id = $1", user.id)
-        .execute(&state.db_pool)
-        .await?;
+    // Simply delete the user's refresh token
+    state.repos.tokens.delete_for_user(user.id).await?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -333,20 +338,29 @@ pub async fn auth_middleware(
         .parse()
         .map_err(|_| AppError::InternalServerError("Invalid user ID in token".to_string()))?;
 
-    // Fetch the user from the database ONCE in the middleware
-    let user = sqlx::query_as!(
-        User,
-        "SELECT id, email, password_hash FROM users WHERE id = $1",
-        user_id
-    )
-    .fetch_optional(&state.db_pool)
-    .await?
-    .ok_or(AppError::Unauthorized)?; // User not found, token is for a deleted user
+    // Fetch the user ONCE in the middleware
+    let user = state
+        .repos
+        .users
+        .find_by_id(user_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?; // User not found, token is for a deleted user
+
+    // The token is only good for as long as the user stays in its organization
+    let org_id = token_data.claims.org;
+    let role = state
+        .repos
+        .users
+        .role_in(user.id, org_id)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
 
     // Add the authenticated user data to the request extensions
     request.extensions_mut().insert(AuthUser {
         id: user.id,
         email: user.email,
+        org_id,
+        role,
     });
 
     Ok(next.run(request).await)
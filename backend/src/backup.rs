@@ -0,0 +1,363 @@
+//! Online backups of a SQLite database. `VACUUM INTO` writes a consistent copy of the database
+//! while the server keeps serving, unlike copying the file, which can catch a write halfway.
+//! Backups are kept in one directory, named after the time they were made, and the oldest are
+//! deleted once there are more than the configured number.
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use axum::{debug_handler, extract::State, http::StatusCode, Json};
+use chrono::{NaiveDateTime, Utc};
+use common::BackupDto;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Connection, Row};
+use thiserror::Error;
+
+use crate::config::BackupConfig;
+use crate::db::{check_url, DbBackend, DbPool, SQLITE_MIGRATOR};
+use crate::error::AppError;
+use crate::extractors::AuthUser;
+use crate::web_server::AppState;
+
+const FILE_PREFIX: &str = "cornerstone-";
+const FILE_SUFFIX: &str = ".db";
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("Backups are only made of SQLite databases; back up PostgreSQL with pg_dump")]
+    Unsupported,
+
+    #[error("The database is in memory, so there is no file to restore into")]
+    InMemory,
+
+    #[error("{path} is not a backup of this application: {reason}")]
+    Invalid { path: String, reason: String },
+
+    #[error(
+        "The backup has migration {0}, which this build does not know; restore it with the \
+         build that made it or a newer one"
+    )]
+    UnknownMigration(i64),
+
+    #[error("Migration {0} of the backup differs from the one this build has")]
+    ModifiedMigration(i64),
+
+    #[error("Failed to access {path}: {source}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+impl From<BackupError> for AppError {
+    fn from(e: BackupError) -> Self {
+        match e {
+            BackupError::Unsupported | BackupError::InMemory => AppError::BadRequest(e.to_string()),
+            BackupError::Database(e) => AppError::DatabaseError(e),
+            e => AppError::InternalServerError(e.to_string()),
+        }
+    }
+}
+
+fn io_error(path: &Path) -> impl FnOnce(std::io::Error) -> BackupError + '_ {
+    move |source| BackupError::Io {
+        path: path.display().to_string(),
+        source,
+    }
+}
+
+/// Writes a backup of the database to the backup directory, then deletes the backups beyond
+/// the configured number, oldest first.
+pub async fn create(db_pool: &DbPool, config: &BackupConfig) -> Result<BackupDto, BackupError> {
+    let DbPool::Sqlite(db_pool) = db_pool else {
+        return Err(BackupError::Unsupported);
+    };
+    tokio::fs::create_dir_all(&config.dir)
+        .await
+        .map_err(io_error(&config.dir))?;
+
+    let now = Utc::now().naive_utc();
+    let name = format!("{FILE_PREFIX}{}{FILE_SUFFIX}", now.format(TIMESTAMP_FORMAT));
+    let path = config.dir.join(&name);
+    sqlx::query("VACUUM INTO $1")
+        .bind(file_uri(&path))
+        .execute(db_pool)
+        .await?;
+    let size_bytes = tokio::fs::metadata(&path)
+        .await
+        .map_err(io_error(&path))?
+        .len();
+    tracing::info!("Backed up the database to {}", path.display());
+
+    prune(config).await?;
+    Ok(BackupDto {
+        name,
+        size_bytes,
+        created_at: now,
+    })
+}
+
+/// The backups in the backup directory, newest first.
+pub async fn list(config: &BackupConfig) -> Result<Vec<BackupDto>, BackupError> {
+    let mut entries = match tokio::fs::read_dir(&config.dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(io_error(&config.dir)(e)),
+    };
+    let mut backups = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(io_error(&config.dir))? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(created_at) = backup_time(&name) else {
+            continue;
+        };
+        let size_bytes = entry
+            .metadata()
+            .await
+            .map_err(io_error(&entry.path()))?
+            .len();
+        backups.push(BackupDto {
+            name,
+            size_bytes,
+            created_at,
+        });
+    }
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    Ok(backups)
+}
+
+/// When a backup was made, read from its file name, or `None` if the file is not a backup.
+fn backup_time(name: &str) -> Option<NaiveDateTime> {
+    let timestamp = name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX)?;
+    NaiveDateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT).ok()
+}
+
+/// Deletes the backups beyond the newest `config.keep`; none if it is 0.
+async fn prune(config: &BackupConfig) -> Result<(), BackupError> {
+    if config.keep == 0 {
+        return Ok(());
+    }
+    for backup in list(config).await?.into_iter().skip(config.keep) {
+        let path = config.dir.join(&backup.name);
+        tokio::fs::remove_file(&path)
+            .await
+            .map_err(io_error(&path))?;
+        tracing::info!("Deleted the old backup {}", path.display());
+    }
+    Ok(())
+}
+
+/// What a restore replaced the database with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Restored {
+    /// The latest migration of the backup.
+    pub version: i64,
+    /// How many migrations the server applies when it starts on the restored database.
+    pub pending: usize,
+    /// Where the replaced database was moved, if there was one.
+    pub previous: Option<PathBuf>,
+}
+
+/// Replaces the database at `database_url` with a backup, after checking that the backup is
+/// intact and that its migrations are ones this build has. The replaced database is moved
+/// next to it with a `.before-restore` suffix. The server must not be running meanwhile.
+pub async fn restore(database_url: &str, backup: &Path) -> Result<Restored, BackupError> {
+    if check_url(database_url).map_err(|_| BackupError::Unsupported)? != DbBackend::Sqlite {
+        return Err(BackupError::Unsupported);
+    }
+    let target = sqlite_path(database_url).ok_or(BackupError::InMemory)?;
+    let (version, pending) = check_backup(backup).await?;
+
+    // The copy is made next to the database first, so the database is swapped in one rename.
+    let staged = with_suffix(&target, ".restoring");
+    tokio::fs::copy(backup, &staged)
+        .await
+        .map_err(io_error(&staged))?;
+
+    let previous = if tokio::fs::try_exists(&target)
+        .await
+        .map_err(io_error(&target))?
+    {
+        let previous = with_suffix(&target, ".before-restore");
+        remove_if_exists(&with_suffix(&previous, "-wal")).await?;
+        tokio::fs::rename(&target, &previous)
+            .await
+            .map_err(io_error(&target))?;
+        // The write-ahead log holds writes not yet copied into the database file, so it goes
+        // with it, under the name SQLite looks for next to the moved file.
+        let wal = with_suffix(&target, "-wal");
+        if tokio::fs::try_exists(&wal).await.map_err(io_error(&wal))? {
+            tokio::fs::rename(&wal, with_suffix(&previous, "-wal"))
+                .await
+                .map_err(io_error(&wal))?;
+        }
+        Some(previous)
+    } else {
+        None
+    };
+    remove_if_exists(&with_suffix(&target, "-shm")).await?;
+    tokio::fs::rename(&staged, &target)
+        .await
+        .map_err(io_error(&target))?;
+
+    Ok(Restored {
+        version,
+        pending,
+        previous,
+    })
+}
+
+/// Checks the integrity of a backup and that every migration it has is one of this build.
+/// Returns its latest migration and how many of the build's are newer.
+async fn check_backup(backup: &Path) -> Result<(i64, usize), BackupError> {
+    let invalid = |reason: String| BackupError::Invalid {
+        path: backup.display().to_string(),
+        reason,
+    };
+    if !tokio::fs::try_exists(backup)
+        .await
+        .map_err(io_error(backup))?
+    {
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "there is no such file");
+        return Err(io_error(backup)(missing));
+    }
+
+    let mut conn = SqliteConnectOptions::from_str(&format!("sqlite:{}?mode=ro", backup.display()))?
+        .connect()
+        .await?;
+    let integrity: String = sqlx::query_scalar("PRAGMA integrity_check")
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|e| invalid(e.to_string()))?;
+    if integrity != "ok" {
+        return Err(invalid(format!("the integrity check found {integrity}")));
+    }
+    let applied = sqlx::query(
+        "SELECT version, checksum FROM _sqlx_migrations WHERE success ORDER BY version",
+    )
+    .fetch_all(&mut conn)
+    .await
+    .map_err(|_| invalid("it has no migrations table".to_string()))?;
+    conn.close().await?;
+
+    let mut version = None;
+    for row in &applied {
+        let applied_version: i64 = row.try_get("version")?;
+        let checksum: Vec<u8> = row.try_get("checksum")?;
+        let migration = SQLITE_MIGRATOR
+            .iter()
+            .find(|m| m.version == applied_version && m.migration_type.is_up_migration())
+            .ok_or(BackupError::UnknownMigration(applied_version))?;
+        if *migration.checksum != *checksum {
+            return Err(BackupError::ModifiedMigration(applied_version));
+        }
+        version = Some(applied_version);
+    }
+    let version = version.ok_or_else(|| invalid("it has no migrations applied".to_string()))?;
+    let pending = SQLITE_MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration() && m.version > version)
+        .count();
+    Ok((version, pending))
+}
+
+/// The file of a SQLite database URL, or `None` for an in-memory database. It is read the
+/// way sqlx reads it: what follows `sqlite://` or `sqlite:`, up to the query.
+fn sqlite_path(url: &str) -> Option<PathBuf> {
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))?;
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    (path != ":memory:" && !path.is_empty()).then(|| PathBuf::from(path))
+}
+
+/// `path` as a URI with an explicit mode. Given a plain path, `VACUUM INTO` opens the copy
+/// the way the database was opened, and an in-memory database would be copied into memory.
+fn file_uri(path: &Path) -> String {
+    let path = path
+        .display()
+        .to_string()
+        .replace('%', "%25")
+        .replace('?', "%3f")
+        .replace('#', "%23");
+    format!("file:{path}?mode=rwc")
+}
+
+async fn remove_if_exists(path: &Path) -> Result<(), BackupError> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(io_error(path)(e)),
+        _ => Ok(()),
+    }
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Fails with a 403 unless the user is a server administrator. The flag is read on every
+/// request, so taking it away takes effect at once.
+async fn require_server_admin(state: &AppState, user: &AuthUser) -> Result<(), AppError> {
+    if state.repos.users.is_admin(user.id).await? {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden)
+    }
+}
+
+// --- API Handlers ---
+
+/// ## Back up the database
+/// Writes a backup of the SQLite database to the server's backup directory while it keeps
+/// serving, and deletes the oldest backups beyond the configured number. Only server
+/// administrators may make backups.
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/backups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 201, description = "Backup written", body = BackupDto),
+        (status = 400, description = "The database is not SQLite"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user is not a server administrator"),
+    )
+)]
+#[debug_handler]
+pub async fn create_backup(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<(StatusCode, Json<BackupDto>), AppError> {
+    require_server_admin(&state, &user).await?;
+    tracing::info!("User {} is backing up the database", user.id);
+    let backup = create(&state.db_pools.primary, &state.app_config.backup).await?;
+    Ok((StatusCode::CREATED, Json(backup)))
+}
+
+/// ## List backups
+/// The backups in the server's backup directory, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/backups",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Backups", body = Vec<BackupDto>),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user is not a server administrator"),
+    )
+)]
+#[debug_handler]
+pub async fn get_backups(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<BackupDto>>, AppError> {
+    require_server_admin(&state, &user).await?;
+    Ok(Json(list(&state.app_config.backup).await?))
+}
@@ -5,6 +5,7 @@ use figment::{
 };
 use serde::Deserialize;
 use std::fmt;
+use std::path::PathBuf;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct WebConfig {
@@ -37,17 +38,209 @@ impl fmt::Debug for JwtConfig {
     }
 }
 
+#[derive(Deserialize, Clone)]
+pub struct DatabaseConfig {
+    /// Connection string. `DATABASE_URL`, which sqlx-cli reads as well, is used when unset.
+    pub url: Option<String>,
+    pub min_connections: u32,
+    pub max_connections: u32,
+    /// How long a request waits for a free connection before failing.
+    pub acquire_timeout_seconds: u64,
+    /// Connections idle for longer than this are closed; unset keeps them open.
+    pub idle_timeout_seconds: Option<u64>,
+    /// Postgres aborts statements that run longer than this; unset means no limit. SQLite
+    /// and MySQL ignore it.
+    pub statement_timeout_seconds: Option<u64>,
+    /// Read replicas for list and search traffic. Unset sends every query to the primary.
+    #[serde(default)]
+    pub replica_urls: Vec<String>,
+    /// How often replicas are checked; one that doesn't answer is skipped until it does.
+    pub replica_check_interval_seconds: u64,
+    /// How often startup tries to connect before giving up, so that the server can start
+    /// before the database is ready.
+    pub connect_attempts: u32,
+    /// Wait before the second attempt, doubled after each further failure.
+    pub connect_backoff_ms: u64,
+    pub connect_backoff_max_ms: u64,
+    pub sqlite: SqliteConfig,
+}
+
+impl DatabaseConfig {
+    /// The configured connection string, falling back to `DATABASE_URL`.
+    pub fn url(&self) -> Option<String> {
+        self.url
+            .clone()
+            .or_else(|| std::env::var("DATABASE_URL").ok())
+    }
+}
+
+// Custom Debug that redacts the URL, which may hold a password
+impl fmt::Debug for DatabaseConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DatabaseConfig")
+            .field("url", &self.url.as_ref().map(|_| "<redacted>"))
+            .field("min_connections", &self.min_connections)
+            .field("max_connections", &self.max_connections)
+            .field("acquire_timeout_seconds", &self.acquire_timeout_seconds)
+            .field("idle_timeout_seconds", &self.idle_timeout_seconds)
+            .field("statement_timeout_seconds", &self.statement_timeout_seconds)
+            .field(
+                "replica_urls",
+                &format!("<{} redacted>", self.replica_urls.len()),
+            )
+            .field(
+                "replica_check_interval_seconds",
+                &self.replica_check_interval_seconds,
+            )
+            .field("connect_attempts", &self.connect_attempts)
+            .field("connect_backoff_ms", &self.connect_backoff_ms)
+            .field("connect_backoff_max_ms", &self.connect_backoff_max_ms)
+            .field("sqlite", &self.sqlite)
+            .finish()
+    }
+}
+
+/// Pragmas set on every SQLite connection. Postgres ignores them.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SqliteConfig {
+    /// Use the write-ahead log, so that readers don't block the writer, with
+    /// `synchronous=NORMAL`, which is safe from corruption in that mode and only syncs at
+    /// checkpoints.
+    pub wal: bool,
+    /// How long a connection waits for a lock held by another one.
+    pub busy_timeout_ms: u64,
+    /// Enforce foreign keys, including their `ON DELETE CASCADE`, which SQLite ignores unless
+    /// each connection turns them on.
+    pub foreign_keys: bool,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct RateLimitConfig {
     pub per_second: u64,
     pub burst_size: u32,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct TrashConfig {
+    /// Days a deleted contact stays restorable before it is purged for good.
+    pub retention_days: i64,
+    pub purge_interval_minutes: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    Local,
+    S3,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+    /// Directory the `local` backend keeps files in.
+    pub local_path: PathBuf,
+    /// Largest accepted upload, in bytes.
+    pub max_upload_bytes: usize,
+    /// MIME types accepted for attachments, either exact (`application/pdf`) or a whole
+    /// family (`image/*`).
+    pub allowed_content_types: Vec<String>,
+    /// Width and height of avatar thumbnails, in pixels.
+    pub avatar_size: u32,
+    /// Connection to the bucket used by the `s3` backend.
+    pub s3: Option<S3Config>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct S3Config {
+    /// Base URL of the service, e.g. `https://s3.eu-west-1.amazonaws.com` or
+    /// `http://localhost:9000` for MinIO.
+    pub endpoint: String,
+    pub bucket: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+// Custom Debug that redacts the secret key
+impl fmt::Debug for S3Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"<redacted>")
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MailerBackend {
+    /// Write emails to the log instead of sending them, for development.
+    Log,
+    Smtp,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MailerConfig {
+    pub backend: MailerBackend,
+    /// Sender of outgoing emails, e.g. `Cornerstone <no-reply@example.com>`.
+    pub from: String,
+    /// Connection to the relay used by the `smtp` backend.
+    pub smtp: Option<SmtpConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    /// Upgrade the connection with STARTTLS. Only local test relays should go without.
+    pub starttls: bool,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+// Custom Debug that redacts the password
+impl fmt::Debug for SmtpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmtpConfig")
+            .field("host", &self.host)
+            .field("port", &self.port)
+            .field("starttls", &self.starttls)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct NewsletterConfig {
+    /// Base URL of this server as seen by contacts, used for the links in emails.
+    pub public_url: String,
+    /// How long a double opt-in confirmation link stays valid.
+    pub confirmation_expires_hours: i64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct BackupConfig {
+    /// Directory the SQLite backups are written to.
+    pub dir: PathBuf,
+    /// How many backups to keep; older ones are deleted after each new one. 0 keeps them all.
+    pub keep: usize,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct AppConfig {
     pub web: WebConfig,
+    pub database: DatabaseConfig,
     pub jwt: JwtConfig,
     pub ratelimit: RateLimitConfig,
+    pub trash: TrashConfig,
+    pub storage: StorageConfig,
+    pub mailer: MailerConfig,
+    pub newsletter: NewsletterConfig,
+    pub backup: BackupConfig,
 }
 
 impl AppConfig {
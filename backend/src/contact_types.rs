@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+
+use axum::{debug_handler, extract::State, Json};
+use common::{ContactType, ContactTypeDto};
+use validator::{ValidationError, ValidationErrors};
+
+use crate::error::AppError;
+use crate::extractors::AuthUser;
+use crate::web_server::AppState;
+
+/// Rejects a contact type that is disabled in this deployment, in the same shape as the
+/// `ContactDto` validation errors.
+pub(crate) fn check_contact_type(
+    enabled: &HashSet<ContactType>,
+    contact_type: ContactType,
+) -> Result<(), ValidationErrors> {
+    if enabled.contains(&contact_type) {
+        return Ok(());
+    }
+    let mut errors = ValidationErrors::new();
+    errors.add(
+        "contact_type",
+        ValidationError::new("contact_type")
+            .with_message(format!("Contact type '{contact_type}' is not available").into()),
+    );
+    Err(errors)
+}
+
+// --- API Handlers ---
+
+/// ## List contact types
+/// The contact types offered by this deployment, in display order.
+#[utoipa::path(
+    get,
+    path = "/api/v1/contact-types",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The enabled contact types", body = Vec<ContactTypeDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn get_contact_types(
+    State(state): State<AppState>,
+    _user: AuthUser,
+) -> Result<Json<Vec<ContactTypeDto>>, AppError> {
+    let result = state.repos.contacts.contact_types().await;
+
+    match result {
+        Ok(types) => Ok(Json(types)),
+        Err(e) => {
+            tracing::error!("Failed to fetch contact types: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch contact types".to_string(),
+            ))
+        }
+    }
+}
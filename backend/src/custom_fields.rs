@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use chrono::NaiveDate;
+use common::{CustomFieldDto, CustomFieldType};
+use serde_json::Value;
+use sqlx::types::Json as SqlJson;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+use crate::error::AppError;
+use crate::extractors::AuthUser;
+use crate::repository::RepositoryError;
+use crate::web_server::AppState;
+
+/// Checks the custom field values of a contact against the organization's definitions, in the same
+/// shape as the `ContactDto` validation errors.
+pub(crate) fn check_custom_fields(
+    definitions: &[CustomFieldDto],
+    values: &BTreeMap<String, Value>,
+) -> Result<(), ValidationErrors> {
+    let mut messages = Vec::new();
+
+    for (name, value) in values.iter().filter(|(_, value)| !value.is_null()) {
+        match definitions.iter().find(|field| field.name == *name) {
+            Some(field) => messages.extend(check_custom_field_value(field, value).err()),
+            None => messages.push(format!("Unknown custom field '{name}'")),
+        }
+    }
+    for field in definitions.iter().filter(|field| field.required) {
+        let missing = match values.get(&field.name) {
+            None | Some(Value::Null) => true,
+            Some(Value::String(value)) => value.trim().is_empty(),
+            Some(_) => false,
+        };
+        if missing {
+            messages.push(format!("'{}' is required", field.name));
+        }
+    }
+
+    if messages.is_empty() {
+        return Ok(());
+    }
+    let mut errors = ValidationErrors::new();
+    for message in messages {
+        errors.add(
+            "custom_fields",
+            ValidationError::new("custom_fields").with_message(message.into()),
+        );
+    }
+    Err(errors)
+}
+
+fn check_custom_field_value(field: &CustomFieldDto, value: &Value) -> Result<(), String> {
+    let (valid, expected) = match field.field_type {
+        CustomFieldType::Text => (value.is_string(), "text".to_string()),
+        CustomFieldType::Number => (value.is_number(), "a number".to_string()),
+        CustomFieldType::Date => (
+            value
+                .as_str()
+                .is_some_and(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").is_ok()),
+            "a date in YYYY-MM-DD format".to_string(),
+        ),
+        CustomFieldType::Bool => (value.is_boolean(), "true or false".to_string()),
+        CustomFieldType::Select => (
+            value
+                .as_str()
+                .is_some_and(|choice| field.options.iter().any(|option| option == choice)),
+            format!("one of {}", field.options.join(", ")),
+        ),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("'{}' must be {expected}", field.name))
+    }
+}
+
+/// The custom field values to store for a contact: `null` values are dropped.
+pub(crate) fn stored_custom_fields(
+    values: &BTreeMap<String, Value>,
+) -> SqlJson<BTreeMap<&String, &Value>> {
+    SqlJson(
+        values
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .collect(),
+    )
+}
+
+/// Maps a failed custom field write to a 409 for a taken name, or a logged 500.
+fn custom_field_write_error(e: RepositoryError, message: &str) -> AppError {
+    if let RepositoryError::Duplicate(_) = e {
+        return AppError::Conflict("A custom field with this name already exists".to_string());
+    }
+    tracing::error!("{}: {}", message, e);
+    AppError::InternalServerError(message.to_string())
+}
+
+// --- API Handlers ---
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/custom-fields",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The organization's custom fields, in creation order", body = Vec<CustomFieldDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn get_custom_fields(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<CustomFieldDto>>, AppError> {
+    tracing::info!("Fetching custom fields for organization {}", user.org_id);
+
+    let result = state.repos.custom_fields.list(user.org_id).await;
+
+    match result {
+        Ok(fields) => Ok(Json(fields)),
+        Err(e) => {
+            tracing::error!("Failed to fetch custom fields: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch custom fields".to_string(),
+            ))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/custom-fields",
+    request_body = CustomFieldDto,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 201, description = "Custom field created successfully", body = CustomFieldDto),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only owners and admins can change custom fields"),
+        (status = 409, description = "A custom field with this name already exists"),
+        (status = 422, description = "Validation error"),
+    )
+)]
+#[debug_handler]
+pub async fn create_custom_field(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(field): Json<CustomFieldDto>,
+) -> Result<(StatusCode, Json<CustomFieldDto>), AppError> {
+    tracing::info!(
+        "Creating custom field {:?} for organization {}",
+        field.name,
+        user.org_id
+    );
+
+    user.require_manage()?;
+    field.validate()?;
+    let field = trimmed(field);
+    let result = state.repos.custom_fields.create(user.org_id, &field).await;
+
+    match result {
+        Ok(id) => Ok((
+            StatusCode::CREATED,
+            Json(CustomFieldDto {
+                id: Some(id),
+                ..field
+            }),
+        )),
+        Err(e) => Err(custom_field_write_error(e, "Failed to create custom field")),
+    }
+}
+
+/// ## Update a custom field
+/// Changes the name, the required flag or the options of a field; its type is fixed. A new
+/// name is carried over to every contact that has a value for the field. Existing values are
+/// not re-checked, so a contact only has to satisfy new rules the next time it is updated.
+#[utoipa::path(
+    put,
+    path = "/api/v1/custom-fields/{id}",
+    request_body = CustomFieldDto,
+    params(
+        ("id" = i64, Path, description = "Custom field ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Custom field updated successfully", body = CustomFieldDto),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only owners and admins can change custom fields"),
+        (status = 404, description = "Custom field not found"),
+        (status = 409, description = "A custom field with this name already exists"),
+        (status = 422, description = "Validation error, or an attempt to change the type"),
+    )
+)]
+#[debug_handler]
+pub async fn update_custom_field(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+    Json(field): Json<CustomFieldDto>,
+) -> Result<Json<CustomFieldDto>, AppError> {
+    tracing::info!(
+        "Updating custom field {} for organization {}",
+        id,
+        user.org_id
+    );
+
+    user.require_manage()?;
+    field.validate()?;
+    let field = trimmed(field);
+
+    let result = state
+        .repos
+        .custom_fields
+        .update(user.org_id, id, &field)
+        .await;
+    let field_type = match result {
+        Ok(field_type) => field_type.ok_or(AppError::NotFound)?,
+        Err(e) => return Err(custom_field_write_error(e, "Failed to update custom field")),
+    };
+    if field_type != field.field_type {
+        let mut errors = ValidationErrors::new();
+        errors.add(
+            "field_type",
+            ValidationError::new("field_type")
+                .with_message("The type of a custom field cannot be changed".into()),
+        );
+        return Err(errors.into());
+    }
+
+    Ok(Json(CustomFieldDto {
+        id: Some(id),
+        ..field
+    }))
+}
+
+/// ## Delete a custom field
+/// The field's value is removed from every contact; the contacts are kept.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/custom-fields/{id}",
+    params(
+        ("id" = i64, Path, description = "Custom field ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Custom field deleted successfully"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "Only owners and admins can change custom fields"),
+        (status = 404, description = "Custom field not found"),
+    )
+)]
+#[debug_handler]
+pub async fn delete_custom_field(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+) -> Result<StatusCode, AppError> {
+    tracing::info!(
+        "Deleting custom field {} for organization {}",
+        id,
+        user.org_id
+    );
+
+    user.require_manage()?;
+    let deleted = state.repos.custom_fields.delete(user.org_id, id).await?;
+    if !deleted {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Trims the name and options of a field before it is stored.
+fn trimmed(field: CustomFieldDto) -> CustomFieldDto {
+    CustomFieldDto {
+        name: field.name.trim().to_string(),
+        options: field
+            .options
+            .iter()
+            .map(|option| option.trim().to_string())
+            .collect(),
+        ..field
+    }
+}
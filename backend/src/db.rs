@@ -1,11 +1,410 @@
-#[cfg(not(any(feature = "db-sqlite", feature = "db-postgres")))]
-compile_error!("Either the `db-sqlite` or `db-postgres` feature must be enabled.");
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
-#[cfg(all(feature = "db-sqlite", feature = "db-postgres"))]
-compile_error!("Only one of `db-sqlite` or `db-postgres` can be enabled.");
+use sqlx::migrate::Migrator;
+#[cfg(feature = "db-mysql")]
+use sqlx::mysql::{MySql, MySqlConnectOptions, MySqlPool};
+use sqlx::pool::PoolOptions;
+use sqlx::postgres::{PgConnectOptions, PgPool};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqliteSynchronous};
+use sqlx::Database;
+use tokio::task::JoinHandle;
 
-#[cfg(feature = "db-postgres")]
-pub use sqlx::postgres::{PgPool as DbPool, PgPoolOptions as DbPoolOptions, Postgres as Db};
+use crate::config::DatabaseConfig;
 
-#[cfg(feature = "db-sqlite")]
-pub use sqlx::sqlite::{Sqlite as Db, SqlitePool as DbPool, SqlitePoolOptions as DbPoolOptions};
+/// The database servers the backend can talk to, told apart by the scheme of a database URL.
+///
+/// One build serves all three: the server connects to whichever `DATABASE_URL` points at and
+/// runs that database's migrations. MySQL needs the `db-mysql` feature, which is on by
+/// default; builds without it refuse MySQL URLs.
+///
+/// Queries are checked at compile time against SQLite only. The sqlx macros check a query
+/// against the one database they are built for, so on Postgres and MySQL the same SQL runs
+/// unchecked; that is the price of serving every backend from one build. Their CI jobs run
+/// the whole test suite on those databases in place of the check, and have to pass before
+/// a change is merged. See `repository::postgres` and `repository::mysql` for the details.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DbBackend {
+    Sqlite,
+    Postgres,
+    MySql,
+}
+
+impl DbBackend {
+    /// The backend a database URL points at, or `None` for an unknown scheme.
+    pub fn from_url(url: &str) -> Option<Self> {
+        let (scheme, _) = url.split_once(':')?;
+        match scheme {
+            "sqlite" => Some(DbBackend::Sqlite),
+            "postgres" | "postgresql" => Some(DbBackend::Postgres),
+            "mysql" | "mariadb" => Some(DbBackend::MySql),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DbBackend::Sqlite => "SQLite",
+            DbBackend::Postgres => "Postgres",
+            DbBackend::MySql => "MySQL",
+        }
+    }
+
+    /// The migrations of this backend, embedded at compile time.
+    pub fn migrator(self) -> &'static Migrator {
+        match self {
+            DbBackend::Sqlite => &SQLITE_MIGRATOR,
+            DbBackend::Postgres => &POSTGRES_MIGRATOR,
+            DbBackend::MySql => &MYSQL_MIGRATOR,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum DbError {
+    #[error("DATABASE_URL has an unsupported scheme; expected sqlite:, postgres:, postgresql:, mysql: or mariadb:")]
+    UnknownScheme,
+
+    #[error("DATABASE_URL points at {}, which this build leaves out; build with the db-mysql feature", .0.as_str())]
+    NotBuiltIn(DbBackend),
+
+    #[error(
+        "A read replica is a {replica} database, but the primary is {primary}",
+        replica = .replica.as_str(),
+        primary = .primary.as_str()
+    )]
+    MixedBackends {
+        primary: DbBackend,
+        replica: DbBackend,
+    },
+
+    #[error("DATABASE_URL is invalid: {0}")]
+    InvalidUrl(sqlx::Error),
+
+    #[error("Failed to connect to the database: {0}")]
+    Connect(#[from] sqlx::Error),
+}
+
+/// The backend `url` points at, if this build supports it.
+pub fn check_url(url: &str) -> Result<DbBackend, DbError> {
+    match DbBackend::from_url(url) {
+        #[cfg(not(feature = "db-mysql"))]
+        Some(DbBackend::MySql) => Err(DbError::NotBuiltIn(DbBackend::MySql)),
+        Some(backend) => Ok(backend),
+        None => Err(DbError::UnknownScheme),
+    }
+}
+
+/// A pool of connections to one of the backends.
+#[derive(Clone, Debug)]
+pub enum DbPool {
+    Sqlite(SqlitePool),
+    Postgres(PgPool),
+    #[cfg(feature = "db-mysql")]
+    MySql(MySqlPool),
+}
+
+/// Evaluates `$body` with `$pool` bound to the typed pool of a `DbPool`, once for each
+/// backend, for code that works the same on all of them, such as runtime queries in SQL every
+/// database understands. Every arm has to evaluate to the same type. The MySQL arm follows
+/// the `db-mysql` feature of the calling crate, which is this package's for its tests.
+#[macro_export]
+macro_rules! with_pool {
+    ($db_pool:expr, |$pool:ident| $body:expr) => {
+        match $db_pool {
+            $crate::db::DbPool::Sqlite($pool) => $body,
+            $crate::db::DbPool::Postgres($pool) => $body,
+            #[cfg(feature = "db-mysql")]
+            $crate::db::DbPool::MySql($pool) => $body,
+        }
+    };
+}
+
+impl DbPool {
+    pub fn backend(&self) -> DbBackend {
+        match self {
+            DbPool::Sqlite(_) => DbBackend::Sqlite,
+            DbPool::Postgres(_) => DbBackend::Postgres,
+            #[cfg(feature = "db-mysql")]
+            DbPool::MySql(_) => DbBackend::MySql,
+        }
+    }
+
+    /// The migrations of the pool's backend.
+    pub fn migrator(&self) -> &'static Migrator {
+        self.backend().migrator()
+    }
+
+    pub async fn close(&self) {
+        with_pool!(self, |pool| pool.close().await)
+    }
+}
+
+/// The options of SQLite connections to `url`, with the pragmas of `config`.
+pub fn sqlite_connect_options(
+    url: &str,
+    config: &DatabaseConfig,
+) -> Result<SqliteConnectOptions, DbError> {
+    let options = SqliteConnectOptions::from_str(url)
+        .map_err(DbError::InvalidUrl)?
+        .foreign_keys(config.sqlite.foreign_keys)
+        .busy_timeout(Duration::from_millis(config.sqlite.busy_timeout_ms));
+    Ok(if config.sqlite.wal {
+        options
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+    } else {
+        options
+    })
+}
+
+/// The options of Postgres connections to `url`, with the statement timeout of `config`.
+pub fn postgres_connect_options(
+    url: &str,
+    config: &DatabaseConfig,
+) -> Result<PgConnectOptions, DbError> {
+    let options = PgConnectOptions::from_str(url).map_err(DbError::InvalidUrl)?;
+    Ok(match config.statement_timeout_seconds {
+        Some(seconds) => options.options([("statement_timeout", format!("{seconds}s"))]),
+        None => options,
+    })
+}
+
+/// The options of MySQL connections to `url`.
+#[cfg(feature = "db-mysql")]
+pub fn mysql_connect_options(url: &str) -> Result<MySqlConnectOptions, DbError> {
+    MySqlConnectOptions::from_str(url).map_err(DbError::InvalidUrl)
+}
+
+/// The pool sizes and timeouts of `config`.
+pub fn pool_options<DB: Database>(config: &DatabaseConfig) -> PoolOptions<DB> {
+    PoolOptions::new()
+        .min_connections(config.min_connections)
+        .max_connections(config.max_connections)
+        .acquire_timeout(Duration::from_secs(config.acquire_timeout_seconds))
+        .idle_timeout(config.idle_timeout_seconds.map(Duration::from_secs))
+}
+
+/// The pool options of `config` for MySQL. `GROUP_CONCAT`, which lists the tags of a contact,
+/// cuts its result at 1024 bytes by default; each connection raises that limit.
+#[cfg(feature = "db-mysql")]
+fn mysql_pool_options(config: &DatabaseConfig) -> PoolOptions<MySql> {
+    use sqlx::Executor;
+
+    pool_options(config).after_connect(|conn: &mut sqlx::MySqlConnection, _| {
+        Box::pin(async move {
+            conn.execute("SET SESSION group_concat_max_len = 1048576")
+                .await?;
+            Ok(())
+        })
+    })
+}
+
+/// Connects to the database at `url`, on the backend its scheme names. Failed attempts are
+/// retried `config.connect_attempts` times in all, with exponential backoff, since the
+/// database may still be starting up, e.g. next to the server in docker-compose.
+pub async fn connect(url: &str, config: &DatabaseConfig) -> Result<DbPool, DbError> {
+    Ok(match check_url(url)? {
+        DbBackend::Sqlite => DbPool::Sqlite(
+            connect_with(
+                pool_options(config),
+                sqlite_connect_options(url, config)?,
+                config,
+            )
+            .await?,
+        ),
+        DbBackend::Postgres => DbPool::Postgres(
+            connect_with(
+                pool_options(config),
+                postgres_connect_options(url, config)?,
+                config,
+            )
+            .await?,
+        ),
+        #[cfg(feature = "db-mysql")]
+        DbBackend::MySql => DbPool::MySql(
+            connect_with(
+                mysql_pool_options(config),
+                mysql_connect_options(url)?,
+                config,
+            )
+            .await?,
+        ),
+        #[cfg(not(feature = "db-mysql"))]
+        DbBackend::MySql => unreachable!("check_url refuses MySQL URLs without db-mysql"),
+    })
+}
+
+async fn connect_with<DB: Database>(
+    pool_options: PoolOptions<DB>,
+    options: <DB::Connection as sqlx::Connection>::Options,
+    config: &DatabaseConfig,
+) -> Result<sqlx::Pool<DB>, DbError> {
+    let mut backoff = Duration::from_millis(config.connect_backoff_ms);
+    let mut attempt = 1;
+    loop {
+        match pool_options.clone().connect_with(options.clone()).await {
+            Ok(pool) => return Ok(pool),
+            Err(e) if attempt < config.connect_attempts => {
+                tracing::warn!(
+                    "Database connection attempt {}/{} failed: {}; retrying in {:?}",
+                    attempt,
+                    config.connect_attempts,
+                    e,
+                    backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(Duration::from_millis(config.connect_backoff_max_ms));
+                attempt += 1;
+            }
+            Err(e) => return Err(DbError::Connect(e)),
+        }
+    }
+}
+
+/// A pool for the database at `url` that only connects once it is used.
+pub fn connect_lazy(url: &str, config: &DatabaseConfig) -> Result<DbPool, DbError> {
+    Ok(match check_url(url)? {
+        DbBackend::Sqlite => DbPool::Sqlite(
+            pool_options(config).connect_lazy_with(sqlite_connect_options(url, config)?),
+        ),
+        DbBackend::Postgres => DbPool::Postgres(
+            pool_options(config).connect_lazy_with(postgres_connect_options(url, config)?),
+        ),
+        #[cfg(feature = "db-mysql")]
+        DbBackend::MySql => {
+            DbPool::MySql(mysql_pool_options(config).connect_lazy_with(mysql_connect_options(url)?))
+        }
+        #[cfg(not(feature = "db-mysql"))]
+        DbBackend::MySql => unreachable!("check_url refuses MySQL URLs without db-mysql"),
+    })
+}
+
+/// The primary database, which takes every write, and its read replicas.
+#[derive(Clone)]
+pub struct DbPools {
+    pub primary: DbPool,
+    replicas: Arc<[Replica]>,
+    next: Arc<AtomicUsize>,
+}
+
+struct Replica {
+    pool: DbPool,
+    /// Whether the replica answered its last health check.
+    healthy: AtomicBool,
+}
+
+impl DbPools {
+    /// Pools without replicas, which send reads to the primary.
+    pub fn new(primary: DbPool) -> Self {
+        Self::with_replicas(primary, Vec::new())
+    }
+
+    /// The backend of the primary and the replicas.
+    pub fn backend(&self) -> DbBackend {
+        self.primary.backend()
+    }
+
+    /// Replicas are only used once `check_replicas` has found them healthy. They have to be
+    /// on the primary's backend.
+    pub fn with_replicas(primary: DbPool, replicas: Vec<DbPool>) -> Self {
+        assert!(
+            replicas.iter().all(|r| r.backend() == primary.backend()),
+            "The read replicas are on another backend than the primary"
+        );
+        Self {
+            primary,
+            replicas: replicas
+                .into_iter()
+                .map(|pool| Replica {
+                    pool,
+                    healthy: AtomicBool::new(false),
+                })
+                .collect(),
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Connects to the primary at `url`, retrying like `connect`, and lazily to the replicas of
+    /// `config`, which may be down at startup without keeping the server from starting.
+    pub async fn connect(url: &str, config: &DatabaseConfig) -> Result<Self, DbError> {
+        let primary = connect(url, config).await?;
+        let replicas = config
+            .replica_urls
+            .iter()
+            .map(|url| connect_lazy(url, config))
+            .collect::<Result<Vec<_>, DbError>>()?;
+        if let Some(replica) = replicas.iter().find(|r| r.backend() != primary.backend()) {
+            return Err(DbError::MixedBackends {
+                primary: primary.backend(),
+                replica: replica.backend(),
+            });
+        }
+        let pools = Self::with_replicas(primary, replicas);
+        pools.check_replicas().await;
+        Ok(pools)
+    }
+
+    /// The pool for a read that may lag behind the latest writes: the healthy replicas in
+    /// turn, or the primary if there are none.
+    pub fn read(&self) -> &DbPool {
+        let healthy = || {
+            self.replicas
+                .iter()
+                .filter(|replica| replica.healthy.load(Ordering::Relaxed))
+        };
+        let count = healthy().count();
+        if count == 0 {
+            return &self.primary;
+        }
+        let turn = self.next.fetch_add(1, Ordering::Relaxed) % count;
+        healthy()
+            .nth(turn)
+            .map_or(&self.primary, |replica| &replica.pool)
+    }
+
+    /// Marks each replica healthy or not by whether it answers a trivial query in time.
+    pub async fn check_replicas(&self) {
+        for (index, replica) in self.replicas.iter().enumerate() {
+            let check = async {
+                with_pool!(&replica.pool, |pool| sqlx::query("SELECT 1")
+                    .execute(pool)
+                    .await
+                    .map(|_| ()))
+            };
+            let healthy = matches!(
+                tokio::time::timeout(Duration::from_secs(5), check).await,
+                Ok(Ok(()))
+            );
+            if replica.healthy.swap(healthy, Ordering::Relaxed) != healthy {
+                if healthy {
+                    tracing::info!("Read replica {} is available", index);
+                } else {
+                    tracing::warn!("Read replica {} is unavailable, skipping it", index);
+                }
+            }
+        }
+    }
+}
+
+/// Spawns the background task that checks the replicas every `interval_seconds`. Does
+/// nothing without replicas.
+pub fn spawn_replica_checks(pools: DbPools, interval_seconds: u64) -> Option<JoinHandle<()>> {
+    if pools.replicas.is_empty() {
+        return None;
+    }
+    Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_seconds.max(1)));
+        loop {
+            interval.tick().await;
+            pools.check_replicas().await;
+        }
+    }))
+}
+
+/// The migrations of each backend, embedded at compile time.
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+pub static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+pub static MYSQL_MIGRATOR: Migrator = sqlx::migrate!("./migrations/mysql");
@@ -0,0 +1,383 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    debug_handler,
+    extract::{Query, State},
+    Json,
+};
+use common::{
+    ContactDto, ContactEmailDto, DuplicateCandidateDto, DuplicateContactDto, DuplicateReason,
+    MergeContactsRequest, MergeStrategy,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+use crate::error::AppError;
+use crate::extractors::{AuthUser, RequestId};
+use crate::history::Actor;
+use crate::import::DEFAULT_LABEL;
+use crate::web_server::{
+    confirm_subscription_request, contact_repository_error, AppState, Pagination,
+};
+
+/// Name similarity from which two contacts are reported when no threshold is given.
+const DEFAULT_MIN_SIMILARITY: f64 = 0.85;
+
+/// Number of leading letters of a name's words that contacts must share to be compared.
+const NAME_KEY_CHARS: usize = 3;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DuplicateParams {
+    /// Lowest name similarity, from 0 to 1, reported as a likely duplicate. Defaults to 0.85.
+    pub min_similarity: Option<f64>,
+}
+
+/// Lowercases an email address and drops the `+suffix` of its local part, so that
+/// `Ada+News@Example.com` and `ada@example.com` compare equal.
+fn normalize_email(email: &str) -> String {
+    let email = email.trim().to_lowercase();
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            let local = local.split('+').next().unwrap_or_default();
+            format!("{local}@{domain}")
+        }
+        None => email,
+    }
+}
+
+/// The lowercased words of a name, sorted, so that "Lovelace, Ada" and "ada lovelace"
+/// have the same ones.
+fn name_words(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = name
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect();
+    words.sort_unstable();
+    words
+}
+
+/// One minus the Levenshtein distance of two names relative to the longer one: 1 for equal
+/// names, 0 for names without anything in common.
+fn name_similarity(a: &[char], b: &[char]) -> f64 {
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    1.0 - previous[b.len()] as f64 / longest as f64
+}
+
+/// Combines two contacts, taking the values of `preferred` unless they are blank. Tags,
+/// emails, phone numbers, addresses and custom fields of both are kept, and differing notes
+/// are joined.
+fn merge_fields(preferred: &ContactDto, other: &ContactDto) -> ContactDto {
+    let email = if preferred.email.trim().is_empty() {
+        &other.email
+    } else {
+        &preferred.email
+    };
+
+    // The primary email of the other contact is kept as an additional one.
+    let mut seen_emails = HashSet::from([email.to_lowercase()]);
+    let other_primary = ContactEmailDto {
+        label: DEFAULT_LABEL.to_string(),
+        email: other.email.clone(),
+    };
+    let emails = preferred
+        .emails
+        .iter()
+        .chain([&other_primary])
+        .chain(&other.emails)
+        .filter(|extra| seen_emails.insert(extra.email.to_lowercase()))
+        .cloned()
+        .collect();
+
+    let mut seen_numbers = HashSet::new();
+    let phones = preferred
+        .phones
+        .iter()
+        .chain(&other.phones)
+        .filter(|phone| seen_numbers.insert(phone.number.clone()))
+        .cloned()
+        .collect();
+
+    let mut addresses = preferred.addresses.clone();
+    for address in &other.addresses {
+        let known = addresses.iter().any(|known| {
+            (
+                &known.street,
+                &known.city,
+                &known.region,
+                &known.postal_code,
+                &known.country,
+            ) == (
+                &address.street,
+                &address.city,
+                &address.region,
+                &address.postal_code,
+                &address.country,
+            )
+        });
+        if !known {
+            addresses.push(address.clone());
+        }
+    }
+
+    let non_blank = |notes: &Option<String>| notes.clone().filter(|n| !n.trim().is_empty());
+    let notes = match (non_blank(&preferred.notes), non_blank(&other.notes)) {
+        (Some(first), Some(second)) if first != second => Some(format!("{first}\n\n{second}")),
+        (first, second) => first.or(second),
+    };
+
+    let mut custom_fields = other.custom_fields.clone();
+    custom_fields.extend(preferred.custom_fields.clone());
+
+    ContactDto {
+        id: preferred.id,
+        name: if preferred.name.trim().is_empty() {
+            other.name.clone()
+        } else {
+            preferred.name.clone()
+        },
+        email: email.clone(),
+        birthday: preferred.birthday.or(other.birthday),
+        subscribed: preferred.subscribed,
+        contact_type: preferred.contact_type,
+        tags: preferred.tags.iter().chain(&other.tags).cloned().collect(),
+        emails,
+        phones,
+        addresses,
+        notes,
+        custom_fields,
+        // Avatars are not merged; the primary contact keeps its own.
+        avatar_url: None,
+    }
+}
+
+/// The keys of a contact in the index that narrows down which contacts are compared.
+/// Contacts are only compared when they share one: a normalized email, or the first
+/// letters of a word of their names. Initials are left out, since they are shared by too
+/// many names to narrow anything down.
+fn block_keys(emails: &HashSet<String>, words: &[String]) -> HashSet<String> {
+    let email_keys = emails.iter().map(|email| format!("@{email}"));
+    let word_keys = words
+        .iter()
+        .filter(|word| word.chars().count() > 1)
+        .map(|word| word.chars().take(NAME_KEY_CHARS).collect());
+    email_keys.chain(word_keys).collect()
+}
+
+/// Finds the likely duplicate pairs among contacts, given with their emails as returned by
+/// `ContactRepository::identities`, in the order the endpoint reports them.
+fn duplicate_candidates(
+    identities: Vec<(DuplicateContactDto, Vec<String>)>,
+    min_similarity: f64,
+) -> Vec<DuplicateCandidateDto> {
+    let (contacts, emails): (Vec<DuplicateContactDto>, Vec<HashSet<String>>) = identities
+        .into_iter()
+        .map(|(contact, emails)| {
+            let emails = emails.iter().map(|email| normalize_email(email)).collect();
+            (contact, emails)
+        })
+        .unzip();
+    let words: Vec<Vec<String>> = contacts
+        .iter()
+        .map(|contact| name_words(&contact.name))
+        .collect();
+    let names: Vec<Vec<char>> = words
+        .iter()
+        .map(|words| words.join(" ").chars().collect())
+        .collect();
+
+    let mut blocks: HashMap<String, Vec<usize>> = HashMap::new();
+    for (position, (emails, words)) in emails.iter().zip(&words).enumerate() {
+        for key in block_keys(emails, words) {
+            blocks.entry(key).or_default().push(position);
+        }
+    }
+
+    // Positions follow the IDs, so `i < j` keeps the older contact first.
+    let mut compared = HashSet::new();
+    let mut candidates = Vec::new();
+    for block in blocks.values() {
+        for (k, &i) in block.iter().enumerate() {
+            for &j in &block[k + 1..] {
+                if !compared.insert((i, j)) {
+                    continue;
+                }
+                let similarity = name_similarity(&names[i], &names[j]);
+                let reason = if !emails[i].is_disjoint(&emails[j]) {
+                    DuplicateReason::SameEmail
+                } else if similarity >= min_similarity {
+                    DuplicateReason::SimilarName
+                } else {
+                    continue;
+                };
+                candidates.push(DuplicateCandidateDto {
+                    first: contacts[i].clone(),
+                    second: contacts[j].clone(),
+                    reason,
+                    similarity,
+                });
+            }
+        }
+    }
+    candidates.sort_by(|a, b| {
+        (b.reason == DuplicateReason::SameEmail)
+            .cmp(&(a.reason == DuplicateReason::SameEmail))
+            .then(b.similarity.total_cmp(&a.similarity))
+            .then((a.first.id, a.second.id).cmp(&(b.first.id, b.second.id)))
+    });
+    candidates
+}
+
+// --- API Handlers ---
+
+/// ## Find likely duplicate contacts
+/// Pairs of contacts outside the trash that share an email address (ignoring case and
+/// `+suffixes`, and including additional emails) or whose names are spelled alike. Pairs
+/// with a shared email come first, then by descending name similarity. Names are only
+/// compared when they have a word starting alike. The pairs are paginated like the contact
+/// list and carry the IDs and names of the contacts.
+#[utoipa::path(
+    get,
+    path = "/api/v1/contacts/duplicates",
+    params(DuplicateParams, Pagination),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Likely duplicate pairs", body = Vec<DuplicateCandidateDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn find_duplicates(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(params): Query<DuplicateParams>,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Vec<DuplicateCandidateDto>>, AppError> {
+    tracing::info!("Finding duplicate contacts for user {}", user.id);
+
+    let min_similarity = params
+        .min_similarity
+        .unwrap_or(DEFAULT_MIN_SIMILARITY)
+        .clamp(0.0, 1.0);
+    let (per_page, offset) = pagination.limit_and_offset();
+
+    let identities = state.repos.contacts.identities(user.org_id).await?;
+    let candidates = duplicate_candidates(identities, min_similarity);
+
+    Ok(Json(
+        candidates
+            .into_iter()
+            .skip(offset as usize)
+            .take(per_page as usize)
+            .collect(),
+    ))
+}
+
+/// ## Merge two contacts
+/// Combines the duplicate into the primary contact according to the strategy and moves the
+/// duplicate to the trash. Both contacts get a `merged` entry in their history.
+#[utoipa::path(
+    post,
+    path = "/api/v1/contacts/merge",
+    request_body = MergeContactsRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The merged contact", body = ContactDto),
+        (status = 400, description = "A contact cannot be merged with itself"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+        (status = 404, description = "Contact not found"),
+        (status = 422, description = "The merged contact is invalid"),
+    )
+)]
+#[debug_handler]
+pub async fn merge_contacts(
+    State(state): State<AppState>,
+    user: AuthUser,
+    request_id: RequestId,
+    Json(request): Json<MergeContactsRequest>,
+) -> Result<Json<ContactDto>, AppError> {
+    tracing::info!(
+        "Merging contact {} into {} for user {} ({:?})",
+        request.duplicate_id,
+        request.primary_id,
+        user.id,
+        request.strategy
+    );
+
+    if request.primary_id == request.duplicate_id {
+        return Err(AppError::BadRequest(
+            "A contact cannot be merged with itself".to_string(),
+        ));
+    }
+
+    user.require_write()?;
+    let actor = Actor::new(&user, request_id);
+    let contacts = &state.repos.contacts;
+    let primary = contacts
+        .find(user.org_id, request.primary_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let duplicate = contacts
+        .find(user.org_id, request.duplicate_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let prefer_primary = match request.strategy {
+        MergeStrategy::PreferPrimary => true,
+        MergeStrategy::PreferDuplicate => false,
+        MergeStrategy::PreferNewest => {
+            let primary_updated = contacts.updated_at(user.org_id, request.primary_id).await?;
+            let duplicate_updated = contacts
+                .updated_at(user.org_id, request.duplicate_id)
+                .await?;
+            primary_updated >= duplicate_updated
+        }
+    };
+    let merged = if prefer_primary {
+        merge_fields(&primary, &duplicate)
+    } else {
+        merge_fields(&duplicate, &primary)
+    };
+    merged.validate()?;
+
+    // The primary may take over the duplicate's email, which leaves the contact book first.
+    let stored = contacts
+        .merge(
+            &actor,
+            user.org_id,
+            request.primary_id,
+            request.duplicate_id,
+            &merged,
+        )
+        .await
+        .map_err(|e| contact_repository_error(e, "Failed to merge contacts"))?
+        .ok_or(AppError::NotFound)?;
+
+    // Consent stays with the contact that gave it, so a subscription taken from the
+    // duplicate has to be confirmed again.
+    confirm_subscription_request(&state, &actor, &merged, &stored).await;
+
+    Ok(Json(stored))
+}
@@ -7,6 +7,9 @@ use serde_json::json;
 use thiserror::Error;
 use validator::ValidationErrors;
 
+use crate::repository::RepositoryError;
+use crate::storage::StorageError;
+
 // Define a custom error type
 #[derive(Debug, Error)]
 pub enum AppError {
@@ -16,21 +19,36 @@ pub enum AppError {
     #[error("Database error")]
     DatabaseError(sqlx::Error),
 
+    #[error("Storage error")]
+    StorageError(StorageError),
+
     #[error("Authentication error")]
     JwtError(jsonwebtoken::errors::Error),
 
     #[error("Authentication error")]
     PasswordError(bcrypt::BcryptError),
 
+    #[error("{0}")]
+    BadRequest(String),
+
     #[error("{0}")]
     Conflict(String),
 
     #[error("Unauthorized")]
     Unauthorized,
 
+    #[error("Forbidden")]
+    Forbidden,
+
     #[error("Resource not found")]
     NotFound,
 
+    #[error("{0}")]
+    PayloadTooLarge(String),
+
+    #[error("{0}")]
+    UnsupportedMediaType(String),
+
     #[error("Validation error: {0}")]
     ValidationError(ValidationErrors),
 }
@@ -50,6 +68,13 @@ impl IntoResponse for AppError {
                     "Database error".to_string(),
                 )
             }
+            AppError::StorageError(e) => {
+                tracing::error!("Storage error: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Storage error".to_string(),
+                )
+            }
             AppError::JwtError(e) => {
                 tracing::warn!("JWT error: {}", e);
                 (StatusCode::UNAUTHORIZED, "Invalid token".to_string())
@@ -59,9 +84,16 @@ impl IntoResponse for AppError {
                 (StatusCode::UNAUTHORIZED, "Invalid password".to_string())
             }
             // ... other error mappings ...
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()),
+            AppError::Forbidden => (
+                StatusCode::FORBIDDEN,
+                "You do not have permission to do this".to_string(),
+            ),
             AppError::NotFound => (StatusCode::NOT_FOUND, "Resource not found".to_string()),
+            AppError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
+            AppError::UnsupportedMediaType(msg) => (StatusCode::UNSUPPORTED_MEDIA_TYPE, msg),
             AppError::ValidationError(errors) => {
                 // The `errors` object contains detailed information on which fields failed.
                 // We can serialize this to JSON for a rich client-side error message.
@@ -86,6 +118,22 @@ impl From<sqlx::Error> for AppError {
     }
 }
 
+impl From<RepositoryError> for AppError {
+    fn from(e: RepositoryError) -> Self {
+        match e {
+            RepositoryError::Duplicate(_) => AppError::Conflict(e.to_string()),
+            RepositoryError::LastOwner => AppError::BadRequest(e.to_string()),
+            RepositoryError::Database(e) => AppError::DatabaseError(e),
+        }
+    }
+}
+
+impl From<StorageError> for AppError {
+    fn from(e: StorageError) -> Self {
+        AppError::StorageError(e)
+    }
+}
+
 impl From<ValidationErrors> for AppError {
     fn from(errors: ValidationErrors) -> Self {
         AppError::ValidationError(errors)
@@ -103,4 +151,43 @@ impl From<bcrypt::BcryptError> for AppError {
     }
 }
 
+/// Returns the column guarded by the unique constraint that `e` violated, if any.
+/// Scoping columns are skipped, so a violation of `UNIQUE (org_id, email)` yields `email`.
+pub fn unique_violation_field(e: &sqlx::Error) -> Option<String> {
+    let sqlx::Error::Database(db_error) = e else {
+        return None;
+    };
+    if !db_error.is_unique_violation() {
+        return None;
+    }
+
+    // Postgres: `Key (user_id, email)=(1, bob@example.com) already exists.`
+    // SQLite: `UNIQUE constraint failed: contacts.user_id, contacts.email`
+    // MySQL: `Duplicate entry '1-bob@example.com' for key 'contacts.email'`, whose unique keys
+    // are named after the column they guard.
+    let columns =
+        if let Some(pg_error) = db_error.try_downcast_ref::<sqlx::postgres::PgDatabaseError>() {
+            pg_error
+                .detail()
+                .and_then(|detail| detail.strip_prefix("Key ("))
+                .and_then(|detail| detail.split_once(")="))
+                .map(|(columns, _)| columns.to_string())
+        } else if let Some(entry) = db_error.message().strip_prefix("Duplicate entry ") {
+            entry
+                .rsplit_once(" for key ")
+                .map(|(_, key)| key.trim_matches('\'').to_string())
+        } else {
+            db_error
+                .message()
+                .strip_prefix("UNIQUE constraint failed: ")
+                .map(str::to_string)
+        };
+
+    columns?
+        .split(',')
+        .map(|column| column.trim().rsplit('.').next().unwrap_or_default())
+        .rfind(|column| !matches!(*column, "user_id" | "org_id"))
+        .map(str::to_string)
+}
+
 // Add From for other error types...
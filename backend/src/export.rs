@@ -0,0 +1,257 @@
+use axum::{
+    body::Body,
+    debug_handler,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use common::ContactDto;
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::error::AppError;
+use crate::extractors::AuthUser;
+use crate::repository::ContactFilter;
+use crate::web_server::AppState;
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Jsonl,
+    Vcf,
+}
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Jsonl => "application/x-ndjson",
+            ExportFormat::Vcf => "text/vcard; charset=utf-8",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+            ExportFormat::Vcf => "vcf",
+        }
+    }
+
+    fn header(self) -> Option<String> {
+        match self {
+            ExportFormat::Csv => Some(
+                "id,name,email,birthday,subscribed,contact_type,tags,phones,notes\r\n".to_string(),
+            ),
+            ExportFormat::Jsonl | ExportFormat::Vcf => None,
+        }
+    }
+
+    fn render(self, contact: &ContactDto) -> String {
+        match self {
+            ExportFormat::Csv => {
+                let phone_numbers: Vec<&str> =
+                    contact.phones.iter().map(|p| p.number.as_str()).collect();
+                let fields = [
+                    contact.id.map(|id| id.to_string()).unwrap_or_default(),
+                    csv_field(&contact.name),
+                    csv_field(&contact.email),
+                    contact.birthday.map(|d| d.to_string()).unwrap_or_default(),
+                    contact.subscribed.to_string(),
+                    contact.contact_type.to_string(),
+                    csv_field(&contact.tags.join(",")),
+                    csv_field(&phone_numbers.join(",")),
+                    csv_field(contact.notes.as_deref().unwrap_or_default()),
+                ];
+                fields.join(",") + "\r\n"
+            }
+            ExportFormat::Jsonl => {
+                // Serializing a plain struct of strings, numbers and booleans cannot fail.
+                serde_json::to_string(contact).unwrap_or_default() + "\n"
+            }
+            ExportFormat::Vcf => {
+                let mut card = String::from("BEGIN:VCARD\r\nVERSION:4.0\r\n");
+                // The contact type comes first, followed by the tags, so that an import
+                // reads them back into the same fields.
+                let categories = std::iter::once(contact.contact_type.as_str())
+                    .chain(contact.tags.iter().map(String::as_str))
+                    .map(escape_vcard_value)
+                    .collect::<Vec<_>>()
+                    .join(",");
+                let mut properties = vec![
+                    ("FN".to_string(), escape_vcard_value(&contact.name)),
+                    (
+                        "EMAIL;PREF=1".to_string(),
+                        escape_vcard_value(&contact.email),
+                    ),
+                ];
+                for email in &contact.emails {
+                    properties.push((
+                        format!("EMAIL;{}", vcard_type_param(&email.label)),
+                        escape_vcard_value(&email.email),
+                    ));
+                }
+                for phone in &contact.phones {
+                    properties.push((
+                        format!("TEL;{}", vcard_type_param(&phone.label)),
+                        phone.number.clone(),
+                    ));
+                }
+                for address in &contact.addresses {
+                    // ADR is "PO box;extended;street;locality;region;postal code;country".
+                    let components = [
+                        "",
+                        "",
+                        &address.street,
+                        &address.city,
+                        &address.region,
+                        &address.postal_code,
+                        &address.country,
+                    ];
+                    properties.push((
+                        format!("ADR;{}", vcard_type_param(&address.label)),
+                        components.map(escape_vcard_value).join(";"),
+                    ));
+                }
+                if let Some(birthday) = contact.birthday {
+                    properties.push(("BDAY".to_string(), birthday.format("%Y%m%d").to_string()));
+                }
+                if let Some(notes) = contact.notes.as_deref().filter(|n| !n.is_empty()) {
+                    properties.push(("NOTE".to_string(), escape_vcard_value(notes)));
+                }
+                properties.push(("CATEGORIES".to_string(), categories));
+
+                for (property, value) in properties {
+                    card.push_str(&fold_vcard_line(&format!("{property}:{value}")));
+                }
+                card.push_str("END:VCARD\r\n");
+                card
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    /// Output format, `csv` when omitted.
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+// --- API Handlers ---
+
+/// ## Export the contact book
+/// Streams every contact of the current organization that matches the list filters. Rows are
+/// read from a database cursor and written to the response as they arrive, so memory use
+/// does not grow with the size of the contact book.
+#[utoipa::path(
+    get,
+    path = "/api/v1/contacts/export",
+    params(ExportParams, ContactFilter),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The contact book in the requested format", content(
+            (String = "text/csv"),
+            (String = "application/x-ndjson"),
+            (String = "text/vcard"),
+        )),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn export_contacts(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(params): Query<ExportParams>,
+    Query(filter): Query<ContactFilter>,
+) -> Result<Response, AppError> {
+    let format = params.format;
+    tracing::info!(
+        "Exporting contacts for user {} as {:?}, filter: {:?}",
+        user.id,
+        format,
+        filter
+    );
+
+    // The repository only reads ahead of the response by a small buffer, so the client's pace
+    // drives the database cursor.
+    let rows = state
+        .repos
+        .contacts
+        .export(user.org_id, filter)
+        .map_ok(move |contact| format.render(&contact))
+        .inspect_err(|e| tracing::error!("Failed to export contacts: {}", e));
+    let body = Body::from_stream(stream::iter(format.header().map(Ok)).chain(rows));
+
+    let disposition = format!("attachment; filename=\"contacts.{}\"", format.extension());
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// The leading characters that make a spreadsheet read a cell as a formula.
+pub(crate) const CSV_FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Quotes a CSV field if it contains a delimiter, quote or line break. A value that a
+/// spreadsheet would evaluate as a formula is prefixed with `'`, which the importer strips.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(CSV_FORMULA_PREFIXES) {
+        format!("'{value}")
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn escape_vcard_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// A `TYPE` parameter carrying a label, quoted when the label contains structural characters.
+fn vcard_type_param(label: &str) -> String {
+    let label = label.replace('"', "'");
+    if label.contains([',', ';', ':']) {
+        format!("TYPE=\"{label}\"")
+    } else {
+        format!("TYPE={label}")
+    }
+}
+
+/// Folds a content line so that no physical line exceeds 75 octets (RFC 6350 §3.2).
+fn fold_vcard_line(line: &str) -> String {
+    const MAX_OCTETS: usize = 75;
+
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut line_octets = 0;
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > MAX_OCTETS {
+            folded.push_str("\r\n ");
+            // The leading space counts towards the continuation line's length.
+            line_octets = 1;
+        }
+        folded.push(c);
+        line_octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
@@ -1,11 +1,38 @@
+use std::convert::Infallible;
+
 use crate::{error::AppError, web_server::AppState};
 use axum::{extract::FromRequestParts, http::request::Parts};
+use common::OrgRole;
 
 // The struct is the same
 #[derive(Clone, Debug)]
 pub struct AuthUser {
     pub id: i64,
     pub email: String,
+    /// The organization the access token was issued for, which scopes every contact query.
+    pub org_id: i64,
+    /// The user's role in that organization.
+    pub role: OrgRole,
+}
+
+impl AuthUser {
+    /// Fails with a 403 unless the user's role allows changing the organization's contacts.
+    pub fn require_write(&self) -> Result<(), AppError> {
+        if self.role.can_write() {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden)
+        }
+    }
+
+    /// Fails with a 403 unless the user's role allows managing the organization.
+    pub fn require_manage(&self) -> Result<(), AppError> {
+        if self.role.can_manage() {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden)
+        }
+    }
 }
 
 // But the extractor logic changes completely
@@ -28,3 +55,24 @@ impl FromRequestParts<AppState> for AuthUser {
         Ok(user.clone())
     }
 }
+
+/// The `x-request-id` assigned to the request by `SetRequestIdLayer`, if any.
+#[derive(Clone, Debug, Default)]
+pub struct RequestId(pub Option<String>);
+
+impl FromRequestParts<AppState> for RequestId {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let request_id = parts
+            .extensions
+            .get::<tower_http::request_id::RequestId>()
+            .and_then(|id| id.header_value().to_str().ok())
+            .map(str::to_string);
+
+        Ok(RequestId(request_id))
+    }
+}
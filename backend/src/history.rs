@@ -0,0 +1,148 @@
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    Json,
+};
+use chrono::NaiveDateTime;
+use common::{ContactDto, ContactEventAction, ContactEventDto, SharePermission};
+
+use crate::error::AppError;
+use crate::extractors::{AuthUser, RequestId};
+use crate::sharing::check_access;
+use crate::web_server::AppState;
+
+/// The user and request behind a change to a contact.
+#[derive(Clone, Debug)]
+pub struct Actor {
+    pub user_id: i64,
+    pub request_id: Option<String>,
+}
+
+impl Actor {
+    pub fn new(user: &AuthUser, request_id: RequestId) -> Self {
+        Self {
+            user_id: user.id,
+            request_id: request_id.0,
+        }
+    }
+}
+
+pub(crate) fn action_name(action: ContactEventAction) -> &'static str {
+    match action {
+        ContactEventAction::Created => "created",
+        ContactEventAction::Updated => "updated",
+        ContactEventAction::Deleted => "deleted",
+        ContactEventAction::Restored => "restored",
+        ContactEventAction::Merged => "merged",
+    }
+}
+
+fn parse_action(name: &str) -> Option<ContactEventAction> {
+    match name {
+        "created" => Some(ContactEventAction::Created),
+        "updated" => Some(ContactEventAction::Updated),
+        "deleted" => Some(ContactEventAction::Deleted),
+        "restored" => Some(ContactEventAction::Restored),
+        "merged" => Some(ContactEventAction::Merged),
+        _ => None,
+    }
+}
+
+pub(crate) fn snapshot(contact: Option<&ContactDto>) -> Result<Option<String>, sqlx::Error> {
+    contact
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| sqlx::Error::Encode(Box::new(e)))
+}
+
+/// A row of `contact_events`, with the email of the user who made the change.
+#[derive(sqlx::FromRow)]
+pub(crate) struct ContactEventRow {
+    pub(crate) id: i64,
+    pub(crate) contact_id: i64,
+    pub(crate) action: String,
+    pub(crate) user_id: i64,
+    pub(crate) user_email: Option<String>,
+    pub(crate) request_id: Option<String>,
+    pub(crate) before_snapshot: Option<String>,
+    pub(crate) after_snapshot: Option<String>,
+    pub(crate) related_contact_id: Option<i64>,
+    pub(crate) created_at: NaiveDateTime,
+}
+
+impl ContactEventRow {
+    pub(crate) fn into_dto(self) -> Option<ContactEventDto> {
+        let Some(action) = parse_action(&self.action) else {
+            tracing::error!(
+                "Unknown action '{}' in contact event {}",
+                self.action,
+                self.id
+            );
+            return None;
+        };
+        let parse_snapshot = |snapshot: Option<String>| {
+            snapshot.and_then(|json| serde_json::from_str::<ContactDto>(&json).ok())
+        };
+
+        Some(ContactEventDto {
+            id: self.id,
+            contact_id: self.contact_id,
+            action,
+            user_id: self.user_id,
+            user_email: self.user_email,
+            request_id: self.request_id,
+            before: parse_snapshot(self.before_snapshot),
+            after: parse_snapshot(self.after_snapshot),
+            related_contact_id: self.related_contact_id,
+            created_at: self.created_at,
+        })
+    }
+}
+
+// --- API Handlers ---
+
+/// ## Get a contact's history
+/// Every create, update, delete, restore and merge of the contact, oldest first. The history of a
+/// contact in the trash is still available until the contact is purged.
+#[utoipa::path(
+    get,
+    path = "/api/v1/contacts/{id}/history",
+    params(
+        ("id" = i64, Path, description = "Contact ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The contact's change log", body = Vec<ContactEventDto>),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Contact not found or not shared with the user"),
+    )
+)]
+#[debug_handler]
+pub async fn get_contact_history(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+) -> Result<Json<Vec<ContactEventDto>>, AppError> {
+    tracing::info!("Fetching history of contact {} for user {}", id, user.id);
+
+    let owned = state.repos.history.in_org(user.org_id, id).await?;
+    if !owned {
+        // Users outside the organization see the history of contacts shared with them, outside the trash.
+        check_access(
+            state.repos.contacts.access(id, &user).await?,
+            SharePermission::Read,
+        )?;
+    }
+
+    match state.repos.history.list(id).await {
+        Ok(events) => Ok(Json(events)),
+        Err(e) => {
+            tracing::error!("Failed to fetch contact history: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch contact history".to_string(),
+            ))
+        }
+    }
+}
@@ -0,0 +1,985 @@
+use std::collections::{BTreeMap, HashSet};
+
+use axum::{
+    debug_handler,
+    extract::{multipart::Field, Multipart, Query, State},
+    Json,
+};
+use chrono::NaiveDate;
+use common::{
+    ContactAddressDto, ContactDto, ContactEmailDto, ContactPhoneDto, ContactType, CustomFieldDto,
+    CustomFieldType, ImportReport, ImportRowResult, ImportRowStatus, SubscriptionSource,
+};
+use csv_core::ReadRecordResult;
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use validator::Validate;
+
+use crate::contact_types::check_contact_type;
+use crate::custom_fields::check_custom_fields;
+use crate::error::AppError;
+use crate::export::CSV_FORMULA_PREFIXES;
+use crate::extractors::{AuthUser, RequestId};
+use crate::history::Actor;
+use crate::repository::{ContactWrite, RepositoryError, WriteOutcome};
+use crate::web_server::{confirm_subscription_request, AppState};
+
+/// Upper bound on the size of an uploaded import file.
+pub const MAX_IMPORT_BYTES: usize = 50 * 1024 * 1024;
+
+/// Number of rows checked and written in one batch. Writing in batches keeps a large import
+/// from holding a single long transaction, and the database from keeping a savepoint for
+/// every row of it.
+const IMPORT_BATCH_ROWS: usize = 500;
+
+/// Label of imported emails, phone numbers and addresses that don't name their kind.
+pub(crate) const DEFAULT_LABEL: &str = "other";
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    Csv,
+    Vcf,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportParams {
+    /// File format. Detected from the file name or content type when omitted.
+    pub format: Option<ImportFormat>,
+    /// Validate and deduplicate the file without writing anything.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// --- API Handlers ---
+
+/// ## Import contacts from a CSV or vCard file
+/// Expects a `multipart/form-data` body with a single `file` part. The file is parsed while
+/// it is being received, so large uploads are never held in memory. Rows whose email already
+/// exists (case-insensitively) in the contact book or earlier in the file are skipped.
+/// CSV columns named after one of the organization's custom fields fill in that field.
+/// Contacts marked as subscribed are stored unsubscribed and asked to confirm by email, as
+/// when they are added by hand. Rows are committed in batches, so the rows of finished
+/// batches stay imported when the upload breaks off.
+#[utoipa::path(
+    post,
+    path = "/api/v1/contacts/import",
+    params(ImportParams),
+    request_body(content_type = "multipart/form-data", description = "A `file` part containing a .csv or .vcf file"),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Import report", body = ImportReport),
+        (status = 400, description = "Missing file, unknown format or unusable CSV header"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+    )
+)]
+#[debug_handler]
+pub async fn import_contacts(
+    State(state): State<AppState>,
+    user: AuthUser,
+    request_id: RequestId,
+    Query(params): Query<ImportParams>,
+    mut multipart: Multipart,
+) -> Result<Json<ImportReport>, AppError> {
+    user.require_write()?;
+    let mut field = loop {
+        match multipart.next_field().await.map_err(bad_upload)? {
+            Some(field) if field.name() == Some("file") => break field,
+            Some(_) => continue,
+            None => {
+                return Err(AppError::BadRequest(
+                    "Expected a multipart field named 'file'".to_string(),
+                ))
+            }
+        }
+    };
+
+    let format = params
+        .format
+        .or_else(|| detect_format(&field))
+        .ok_or_else(|| {
+            AppError::BadRequest(
+                "Could not detect the file format, pass ?format=csv or ?format=vcf".to_string(),
+            )
+        })?;
+
+    tracing::info!(
+        "Importing {:?} file for user {} (dry_run: {})",
+        format,
+        user.id,
+        params.dry_run
+    );
+
+    let contacts = &state.repos.contacts;
+    let enabled_types = contacts.enabled_contact_types().await?;
+    let custom_fields = contacts.custom_fields(user.org_id).await?;
+    let mut session = ImportSession::new(
+        state.clone(),
+        Actor::new(&user, request_id),
+        user.org_id,
+        enabled_types,
+        custom_fields,
+        params.dry_run,
+    );
+
+    match format {
+        ImportFormat::Csv => {
+            let mut parser = CsvRecordParser::new();
+            let mut columns: Option<Vec<CsvColumn>> = None;
+            let mut records = Vec::new();
+
+            while let Some(chunk) = field.chunk().await.map_err(bad_upload)? {
+                parser.feed(&chunk, &mut records);
+                session
+                    .process_csv_records(&mut columns, records.drain(..))
+                    .await?;
+            }
+            parser.finish(&mut records);
+            session
+                .process_csv_records(&mut columns, records.drain(..))
+                .await?;
+
+            if columns.is_none() {
+                return Err(AppError::BadRequest("The CSV file is empty".to_string()));
+            }
+        }
+        ImportFormat::Vcf => {
+            let mut parser = VcardParser::default();
+            let mut cards = Vec::new();
+
+            while let Some(chunk) = field.chunk().await.map_err(bad_upload)? {
+                parser.feed(&chunk, &mut cards);
+                for card in cards.drain(..) {
+                    session
+                        .process_row(card.and_then(Vcard::into_contact))
+                        .await?;
+                }
+            }
+            parser.finish(&mut cards);
+            for card in cards.drain(..) {
+                session
+                    .process_row(card.and_then(Vcard::into_contact))
+                    .await?;
+            }
+        }
+    }
+
+    session.write_pending().await?;
+    session.report.rows.sort_by_key(|result| result.row);
+
+    Ok(Json(session.report))
+}
+
+fn bad_upload(e: axum::extract::multipart::MultipartError) -> AppError {
+    AppError::BadRequest(format!("Failed to read upload: {}", e.body_text()))
+}
+
+fn detect_format(field: &Field<'_>) -> Option<ImportFormat> {
+    let file_name = field.file_name().unwrap_or_default().to_lowercase();
+    let content_type = field.content_type().unwrap_or_default().to_lowercase();
+
+    if file_name.ends_with(".csv") || content_type.starts_with("text/csv") {
+        Some(ImportFormat::Csv)
+    } else if file_name.ends_with(".vcf")
+        || file_name.ends_with(".vcard")
+        || content_type.starts_with("text/vcard")
+        || content_type.starts_with("text/x-vcard")
+    {
+        Some(ImportFormat::Vcf)
+    } else {
+        None
+    }
+}
+
+// --- Import Session ---
+
+/// Validates, deduplicates and (unless dry-running) inserts rows as they are parsed.
+struct ImportSession {
+    state: AppState,
+    actor: Actor,
+    /// The organization the contacts are imported into.
+    org_id: i64,
+    /// Contact types this deployment accepts.
+    enabled_types: HashSet<ContactType>,
+    /// The organization's custom field definitions.
+    custom_fields: Vec<CustomFieldDto>,
+    dry_run: bool,
+    /// Lowercased emails seen so far in the file.
+    seen_emails: HashSet<String>,
+    /// Valid rows of the current batch, with their row numbers, not yet checked against the
+    /// contact book.
+    pending: Vec<(usize, ContactDto)>,
+    report: ImportReport,
+}
+
+impl ImportSession {
+    fn new(
+        state: AppState,
+        actor: Actor,
+        org_id: i64,
+        enabled_types: HashSet<ContactType>,
+        custom_fields: Vec<CustomFieldDto>,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            state,
+            actor,
+            org_id,
+            enabled_types,
+            custom_fields,
+            dry_run,
+            seen_emails: HashSet::new(),
+            pending: Vec::new(),
+            report: ImportReport {
+                dry_run,
+                total_rows: 0,
+                imported: 0,
+                duplicates: 0,
+                failed: 0,
+                rows: Vec::new(),
+            },
+        }
+    }
+
+    /// The first record is the header; every following record is a data row.
+    async fn process_csv_records(
+        &mut self,
+        columns: &mut Option<Vec<CsvColumn>>,
+        records: impl Iterator<Item = Vec<String>>,
+    ) -> Result<(), AppError> {
+        for record in records {
+            match columns {
+                None => *columns = Some(parse_csv_header(&record, &self.custom_fields)?),
+                Some(columns) => {
+                    let parsed = csv_record_to_contact(columns, &record, &self.custom_fields);
+                    self.process_row(parsed).await?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn process_row(&mut self, parsed: Result<ContactDto, String>) -> Result<(), AppError> {
+        self.report.total_rows += 1;
+        let row = self.report.total_rows;
+
+        let contact = match parsed {
+            Ok(contact) => contact,
+            Err(error) => {
+                self.skip(row, ImportRowStatus::Failed, None, Some(error), None);
+                return Ok(());
+            }
+        };
+
+        let validation = contact
+            .validate()
+            .and_then(|_| check_contact_type(&self.enabled_types, contact.contact_type))
+            .and_then(|_| check_custom_fields(&self.custom_fields, &contact.custom_fields));
+        if let Err(errors) = validation {
+            self.skip(
+                row,
+                ImportRowStatus::Failed,
+                Some(contact.email),
+                Some("Input validation failed".to_string()),
+                serde_json::to_value(&errors).ok(),
+            );
+            return Ok(());
+        }
+
+        if !self.seen_emails.insert(contact.email.to_lowercase()) {
+            self.skip(
+                row,
+                ImportRowStatus::Duplicate,
+                Some(contact.email),
+                None,
+                None,
+            );
+            return Ok(());
+        }
+
+        self.pending.push((row, contact));
+        if self.pending.len() >= IMPORT_BATCH_ROWS {
+            self.write_pending().await?;
+        }
+        Ok(())
+    }
+
+    /// Skips the pending rows whose email is already in the contact book and, unless
+    /// dry-running, writes the others as one batch.
+    async fn write_pending(&mut self) -> Result<(), AppError> {
+        let pending = std::mem::take(&mut self.pending);
+        let emails: Vec<String> = pending.iter().map(|(_, c)| c.email.clone()).collect();
+        let stored = self
+            .state
+            .repos
+            .contacts
+            .existing_emails(self.org_id, &emails)
+            .await?;
+        let (duplicates, new): (Vec<_>, Vec<_>) = pending
+            .into_iter()
+            .partition(|(_, contact)| stored.contains(&contact.email.to_lowercase()));
+        for (row, contact) in duplicates {
+            self.skip(
+                row,
+                ImportRowStatus::Duplicate,
+                Some(contact.email),
+                None,
+                None,
+            );
+        }
+
+        if self.dry_run {
+            self.report.imported += new.len();
+            return Ok(());
+        }
+
+        // An import is a user's word for the contacts' consent, which isn't enough to
+        // subscribe them.
+        let writes: Vec<ContactWrite<'_>> = new
+            .iter()
+            .map(|(_, contact)| ContactWrite::Create {
+                contact,
+                source: SubscriptionSource::Manual,
+            })
+            .collect();
+        let outcomes = self
+            .state
+            .repos
+            .contacts
+            .write_batch(&self.actor, self.org_id, &writes, false)
+            .await?;
+
+        for ((row, contact), outcome) in new.into_iter().zip(outcomes) {
+            match outcome {
+                WriteOutcome::Failed(RepositoryError::Duplicate(_)) => {
+                    // Another request stored the same email since the check above.
+                    self.skip(
+                        row,
+                        ImportRowStatus::Duplicate,
+                        Some(contact.email),
+                        None,
+                        None,
+                    );
+                }
+                WriteOutcome::Failed(e) => {
+                    tracing::error!("Failed to import row {}: {}", row, e);
+                    self.skip(
+                        row,
+                        ImportRowStatus::Failed,
+                        Some(contact.email),
+                        Some("Failed to create contact".to_string()),
+                        None,
+                    );
+                }
+                WriteOutcome::Created(stored) => {
+                    confirm_subscription_request(&self.state, &self.actor, &contact, &stored).await;
+                    self.report.imported += 1;
+                }
+                _ => self.report.imported += 1,
+            }
+        }
+        Ok(())
+    }
+
+    fn skip(
+        &mut self,
+        row: usize,
+        status: ImportRowStatus,
+        email: Option<String>,
+        error: Option<String>,
+        validation_errors: Option<serde_json::Value>,
+    ) {
+        match status {
+            ImportRowStatus::Duplicate => self.report.duplicates += 1,
+            ImportRowStatus::Failed => self.report.failed += 1,
+        }
+        self.report.rows.push(ImportRowResult {
+            row,
+            status,
+            email,
+            error,
+            validation_errors,
+        });
+    }
+}
+
+// --- CSV ---
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum CsvColumn {
+    Name,
+    FirstName,
+    LastName,
+    Email,
+    Birthday,
+    Phone,
+    Subscribed,
+    ContactType,
+    Tags,
+    Notes,
+    /// The custom field at this index of the organization's definitions.
+    CustomField(usize),
+    Ignored,
+}
+
+/// Maps a header cell onto a `ContactDto` field, accepting the spellings used by common
+/// address book exports.
+fn csv_column(header: &str) -> CsvColumn {
+    let normalized: String = header
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    match normalized.as_str() {
+        "name" | "fullname" | "displayname" | "contactname" => CsvColumn::Name,
+        "firstname" | "givenname" => CsvColumn::FirstName,
+        "lastname" | "surname" | "familyname" => CsvColumn::LastName,
+        "email" | "emailaddress" | "primaryemail" | "email1" | "email1value" => CsvColumn::Email,
+        "birthday" | "bday" | "birthdate" | "dateofbirth" | "dob" => CsvColumn::Birthday,
+        "phone" | "phones" | "phonenumber" | "telephone" | "mobile" | "mobilephone" => {
+            CsvColumn::Phone
+        }
+        "subscribed" | "newsletter" | "optin" => CsvColumn::Subscribed,
+        "contacttype" | "type" | "category" | "group" => CsvColumn::ContactType,
+        "tags" | "labels" => CsvColumn::Tags,
+        "notes" | "note" | "comments" => CsvColumn::Notes,
+        _ => CsvColumn::Ignored,
+    }
+}
+
+/// Maps the header row onto columns. Headers that aren't a `ContactDto` field are matched
+/// case-insensitively against the names of the organization's custom fields.
+fn parse_csv_header(
+    record: &[String],
+    custom_fields: &[CustomFieldDto],
+) -> Result<Vec<CsvColumn>, AppError> {
+    let mut columns: Vec<CsvColumn> = record
+        .iter()
+        .map(|header| match csv_column(header) {
+            CsvColumn::Ignored => custom_fields
+                .iter()
+                .position(|field| field.name.eq_ignore_ascii_case(header.trim()))
+                .map_or(CsvColumn::Ignored, CsvColumn::CustomField),
+            column => column,
+        })
+        .collect();
+
+    // Only the first column mapped to a field is used.
+    for i in 0..columns.len() {
+        if columns[..i].contains(&columns[i]) {
+            columns[i] = CsvColumn::Ignored;
+        }
+    }
+
+    if !columns.contains(&CsvColumn::Email) {
+        return Err(AppError::BadRequest(
+            "The CSV header must contain an email column".to_string(),
+        ));
+    }
+    let has_name = columns.contains(&CsvColumn::Name)
+        || columns.contains(&CsvColumn::FirstName)
+        || columns.contains(&CsvColumn::LastName);
+    if !has_name {
+        return Err(AppError::BadRequest(
+            "The CSV header must contain a name column".to_string(),
+        ));
+    }
+
+    Ok(columns)
+}
+
+fn csv_record_to_contact(
+    columns: &[CsvColumn],
+    record: &[String],
+    custom_fields: &[CustomFieldDto],
+) -> Result<ContactDto, String> {
+    let value = |column| csv_value(columns, record, column);
+
+    let name = match value(CsvColumn::Name) {
+        "" => [value(CsvColumn::FirstName), value(CsvColumn::LastName)]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+        name => name.to_string(),
+    };
+
+    let birthday = match value(CsvColumn::Birthday) {
+        "" => None,
+        birthday => {
+            Some(parse_birthday(birthday).ok_or_else(|| format!("Invalid birthday '{birthday}'"))?)
+        }
+    };
+
+    let subscribed = match value(CsvColumn::Subscribed) {
+        "" => false,
+        subscribed => parse_csv_bool(subscribed)
+            .ok_or_else(|| format!("Invalid subscribed value '{subscribed}'"))?,
+    };
+
+    let contact_type = match value(CsvColumn::ContactType) {
+        "" => ContactType::default(),
+        contact_type => contact_type.parse()?,
+    };
+
+    Ok(ContactDto {
+        id: None,
+        name,
+        email: value(CsvColumn::Email).to_string(),
+        birthday,
+        subscribed,
+        contact_type,
+        tags: split_tags(value(CsvColumn::Tags).split(',')),
+        phones: value(CsvColumn::Phone)
+            .split([',', ';'])
+            .filter(|number| !number.trim().is_empty())
+            .map(|number| ContactPhoneDto {
+                label: DEFAULT_LABEL.to_string(),
+                number: normalize_phone_number(number),
+            })
+            .collect(),
+        notes: Some(value(CsvColumn::Notes))
+            .filter(|notes| !notes.is_empty())
+            .map(str::to_string),
+        custom_fields: custom_fields
+            .iter()
+            .enumerate()
+            .filter_map(|(i, field)| match value(CsvColumn::CustomField(i)) {
+                "" => None,
+                text => Some(csv_custom_field_value(field, text).map(|v| (field.name.clone(), v))),
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?,
+        ..Default::default()
+    })
+}
+
+/// Converts a CSV cell into the JSON value of a custom field. Select options are left to
+/// the validation of the whole contact.
+fn csv_custom_field_value(field: &CustomFieldDto, text: &str) -> Result<serde_json::Value, String> {
+    let invalid = || {
+        format!(
+            "Invalid {} value '{text}' for '{}'",
+            field.field_type.as_str(),
+            field.name
+        )
+    };
+    match field.field_type {
+        CustomFieldType::Text | CustomFieldType::Select => Ok(text.into()),
+        CustomFieldType::Number => text
+            .parse::<i64>()
+            .ok()
+            .map(serde_json::Value::from)
+            .or_else(|| {
+                let number = text.parse::<f64>().ok()?;
+                serde_json::Number::from_f64(number).map(serde_json::Value::Number)
+            })
+            .ok_or_else(invalid),
+        CustomFieldType::Date => parse_birthday(text)
+            .map(|date| date.to_string().into())
+            .ok_or_else(invalid),
+        CustomFieldType::Bool => parse_csv_bool(text).map(Into::into).ok_or_else(invalid),
+    }
+}
+
+/// Parses the spellings of yes and no found in spreadsheets.
+fn parse_csv_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "false" | "no" | "n" | "0" => Some(false),
+        "true" | "yes" | "y" | "1" | "x" => Some(true),
+        _ => None,
+    }
+}
+
+/// The trimmed value of a column, without the `'` that an export puts before a value a
+/// spreadsheet would read as a formula.
+fn csv_value<'a>(columns: &[CsvColumn], record: &'a [String], column: CsvColumn) -> &'a str {
+    let value = columns
+        .iter()
+        .position(|c| *c == column)
+        .and_then(|i| record.get(i))
+        .map(|v| v.trim())
+        .unwrap_or_default();
+    match value.strip_prefix('\'') {
+        Some(formula) if formula.starts_with(CSV_FORMULA_PREFIXES) => formula,
+        _ => value,
+    }
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// Incremental CSV parser: bytes are pushed in as they arrive and complete records are
+/// handed back, so the file never has to be fully buffered.
+struct CsvRecordParser {
+    reader: csv_core::Reader,
+    output: Vec<u8>,
+    output_len: usize,
+    ends: Vec<usize>,
+    ends_len: usize,
+    at_start: bool,
+    head: Vec<u8>,
+}
+
+impl CsvRecordParser {
+    fn new() -> Self {
+        Self {
+            reader: csv_core::Reader::new(),
+            output: vec![0; 4096],
+            output_len: 0,
+            ends: vec![0; 32],
+            ends_len: 0,
+            at_start: true,
+            head: Vec::new(),
+        }
+    }
+
+    fn feed(&mut self, input: &[u8], records: &mut Vec<Vec<String>>) {
+        if self.at_start {
+            // Hold back the first bytes until we know whether they are a UTF-8 BOM.
+            self.head.extend_from_slice(input);
+            if self.head.len() < UTF8_BOM.len() && UTF8_BOM.starts_with(&self.head) {
+                return;
+            }
+            self.flush_head(records);
+        } else if !input.is_empty() {
+            // An empty input means EOF to csv_core, so it must only be passed by `finish`.
+            self.read(input, records);
+        }
+    }
+
+    fn finish(&mut self, records: &mut Vec<Vec<String>>) {
+        if self.at_start {
+            self.flush_head(records);
+        }
+        self.read(&[], records);
+    }
+
+    fn flush_head(&mut self, records: &mut Vec<Vec<String>>) {
+        self.at_start = false;
+        let head = std::mem::take(&mut self.head);
+        let head = head.strip_prefix(UTF8_BOM).unwrap_or(&head);
+        if !head.is_empty() {
+            self.read(head, records);
+        }
+    }
+
+    fn read(&mut self, mut input: &[u8], records: &mut Vec<Vec<String>>) {
+        let at_eof = input.is_empty();
+        loop {
+            if input.is_empty() && !at_eof {
+                return;
+            }
+            let (result, read, written, ends_written) = self.reader.read_record(
+                input,
+                &mut self.output[self.output_len..],
+                &mut self.ends[self.ends_len..],
+            );
+            input = &input[read..];
+            self.output_len += written;
+            self.ends_len += ends_written;
+
+            match result {
+                ReadRecordResult::OutputFull => {
+                    let len = self.output.len();
+                    self.output.resize(len * 2, 0);
+                }
+                ReadRecordResult::OutputEndsFull => {
+                    let len = self.ends.len();
+                    self.ends.resize(len * 2, 0);
+                }
+                ReadRecordResult::Record => {
+                    let mut start = 0;
+                    let record = self.ends[..self.ends_len]
+                        .iter()
+                        .map(|&end| {
+                            let field = String::from_utf8_lossy(&self.output[start..end]);
+                            start = end;
+                            field.into_owned()
+                        })
+                        .collect::<Vec<_>>();
+                    self.output_len = 0;
+                    self.ends_len = 0;
+
+                    // Blank lines are not data rows.
+                    if !(record.len() == 1 && record[0].trim().is_empty()) {
+                        records.push(record);
+                    }
+                }
+                ReadRecordResult::InputEmpty | ReadRecordResult::End => return,
+            }
+        }
+    }
+}
+
+// --- vCard ---
+
+/// The properties of a single `BEGIN:VCARD` ... `END:VCARD` block that map onto a contact.
+#[derive(Default, Debug)]
+struct Vcard {
+    formatted_name: Option<String>,
+    structured_name: Option<String>,
+    /// Every EMAIL property, with its label and whether it is marked as preferred.
+    emails: Vec<(ContactEmailDto, bool)>,
+    phones: Vec<ContactPhoneDto>,
+    addresses: Vec<ContactAddressDto>,
+    birthday: Option<String>,
+    note: Option<String>,
+    categories: Option<String>,
+}
+
+impl Vcard {
+    fn into_contact(self) -> Result<ContactDto, String> {
+        let name = self
+            .formatted_name
+            .filter(|name| !name.trim().is_empty())
+            .or_else(|| {
+                // N is "Family;Given;Additional;Prefix;Suffix".
+                self.structured_name.map(|n| {
+                    let parts: Vec<&str> = n.split(';').collect();
+                    [parts.get(1), parts.first()]
+                        .into_iter()
+                        .flatten()
+                        .map(|p| p.trim())
+                        .filter(|p| !p.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+            })
+            .unwrap_or_default();
+
+        let birthday = match self.birthday {
+            Some(birthday) => Some(
+                parse_birthday(&birthday)
+                    .ok_or_else(|| format!("Invalid birthday '{birthday}'"))?,
+            ),
+            None => None,
+        };
+
+        // The first preferred address is the primary email, or else the first one.
+        let mut emails = self.emails;
+        let primary = emails
+            .iter()
+            .position(|(_, preferred)| *preferred)
+            .or((!emails.is_empty()).then_some(0))
+            .map(|index| emails.remove(index).0.email);
+
+        // The first category naming a contact type sets the type, all others become tags.
+        let categories = self.categories.unwrap_or_default();
+        let mut contact_type = None;
+        let tags = split_tags(categories.split(',').filter(|category| {
+            match (contact_type, category.parse::<ContactType>()) {
+                (None, Ok(parsed)) => {
+                    contact_type = Some(parsed);
+                    false
+                }
+                _ => true,
+            }
+        }));
+
+        Ok(ContactDto {
+            id: None,
+            name,
+            email: primary.unwrap_or_default(),
+            birthday,
+            subscribed: false,
+            contact_type: contact_type.unwrap_or_default(),
+            tags,
+            emails: emails.into_iter().map(|(email, _)| email).collect(),
+            phones: self.phones,
+            addresses: self.addresses,
+            notes: self.note.filter(|note| !note.trim().is_empty()),
+            ..Default::default()
+        })
+    }
+}
+
+fn split_tags<'a>(names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    names
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse_birthday(birthday: &str) -> Option<NaiveDate> {
+    // Both the basic (19850412) and extended (1985-04-12) forms may carry a time part.
+    let date = birthday.trim().split('T').next().unwrap_or_default();
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(date, "%Y%m%d"))
+        .ok()
+}
+
+/// Strips the punctuation people put into phone numbers and turns an international `00`
+/// prefix into `+`. Whatever is left still has to pass E.164 validation.
+fn normalize_phone_number(number: &str) -> String {
+    let number = number.trim();
+    let number = number.strip_prefix("tel:").unwrap_or(number);
+    let number: String = number
+        .chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')' | '/'))
+        .collect();
+    match number.strip_prefix("00") {
+        Some(rest) => format!("+{rest}"),
+        None => number,
+    }
+}
+
+/// The label of an EMAIL, TEL or ADR property: its first `TYPE` that isn't in `ignored`,
+/// lowercased. vCard 2.1 lists types as bare parameters, as in `TEL;CELL;VOICE`.
+fn vcard_label(params: &[&str], ignored: &[&str]) -> String {
+    params
+        .iter()
+        .filter_map(|param| match param.split_once('=') {
+            Some((name, value)) if name.eq_ignore_ascii_case("TYPE") => Some(value),
+            Some(_) => None,
+            None => Some(*param),
+        })
+        .flat_map(|value| value.trim_matches('"').split(','))
+        .map(|value| value.trim().to_lowercase())
+        .find(|value| !value.is_empty() && value != "pref" && !ignored.contains(&value.as_str()))
+        .unwrap_or_else(|| DEFAULT_LABEL.to_string())
+}
+
+/// Incremental vCard parser. Lines are unfolded (RFC 6350 §3.2) before being interpreted, and
+/// each completed card is handed back as soon as its `END:VCARD` line arrives.
+#[derive(Default)]
+struct VcardParser {
+    partial_line: Vec<u8>,
+    pending_line: Option<String>,
+    current: Option<Vcard>,
+}
+
+impl VcardParser {
+    fn feed(&mut self, input: &[u8], cards: &mut Vec<Result<Vcard, String>>) {
+        let mut rest = input;
+        while let Some(newline) = rest.iter().position(|&b| b == b'\n') {
+            self.partial_line.extend_from_slice(&rest[..newline]);
+            rest = &rest[newline + 1..];
+            let line = std::mem::take(&mut self.partial_line);
+            self.physical_line(&line, cards);
+        }
+        self.partial_line.extend_from_slice(rest);
+    }
+
+    fn finish(&mut self, cards: &mut Vec<Result<Vcard, String>>) {
+        let line = std::mem::take(&mut self.partial_line);
+        if !line.is_empty() {
+            self.physical_line(&line, cards);
+        }
+        if let Some(line) = self.pending_line.take() {
+            self.logical_line(&line, cards);
+        }
+        if self.current.take().is_some() {
+            cards.push(Err("Missing END:VCARD".to_string()));
+        }
+    }
+
+    fn physical_line(&mut self, line: &[u8], cards: &mut Vec<Result<Vcard, String>>) {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim_end_matches('\r');
+
+        if let Some(continuation) = line.strip_prefix([' ', '\t']) {
+            if let Some(pending) = self.pending_line.as_mut() {
+                pending.push_str(continuation);
+                return;
+            }
+        }
+        if let Some(previous) = self.pending_line.replace(line.to_string()) {
+            self.logical_line(&previous, cards);
+        }
+    }
+
+    fn logical_line(&mut self, line: &str, cards: &mut Vec<Result<Vcard, String>>) {
+        let Some((key, value)) = line.split_once(':') else {
+            return;
+        };
+        let mut params = key.split(';');
+        // Drop an optional group prefix, as in `item1.EMAIL`.
+        let property = params.next().unwrap_or_default();
+        let params: Vec<&str> = params.collect();
+        let property = property
+            .rsplit_once('.')
+            .map_or(property, |(_, name)| name)
+            .to_uppercase();
+        let value = unescape_vcard_value(value.trim());
+
+        match property.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VCARD") => {
+                if self.current.replace(Vcard::default()).is_some() {
+                    cards.push(Err("Missing END:VCARD".to_string()));
+                }
+            }
+            "END" if value.eq_ignore_ascii_case("VCARD") => {
+                if let Some(card) = self.current.take() {
+                    cards.push(Ok(card));
+                }
+            }
+            _ => {
+                let Some(card) = self.current.as_mut() else {
+                    return;
+                };
+                match property.as_str() {
+                    "FN" => card.formatted_name = Some(value),
+                    "N" => card.structured_name = Some(value),
+                    "EMAIL" => {
+                        let preferred = params.iter().any(|p| {
+                            let p = p.to_uppercase();
+                            p == "PREF" || p.starts_with("PREF=") || p.contains("TYPE=PREF")
+                        });
+                        let email = ContactEmailDto {
+                            label: vcard_label(&params, &["internet", "x400"]),
+                            email: value,
+                        };
+                        card.emails.push((email, preferred));
+                    }
+                    "TEL" => card.phones.push(ContactPhoneDto {
+                        label: vcard_label(&params, &["voice", "uri", "text"]),
+                        number: normalize_phone_number(&value),
+                    }),
+                    "ADR" => {
+                        // ADR is "PO box;extended;street;locality;region;postal code;country".
+                        let parts: Vec<&str> = value.split(';').map(str::trim).collect();
+                        let part = |index: usize| parts.get(index).copied().unwrap_or_default();
+                        card.addresses.push(ContactAddressDto {
+                            label: vcard_label(&params, &["postal", "parcel", "dom", "intl"]),
+                            street: [part(0), part(1), part(2)]
+                                .into_iter()
+                                .filter(|p| !p.is_empty())
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                            city: part(3).to_string(),
+                            region: part(4).to_string(),
+                            postal_code: part(5).to_string(),
+                            country: part(6).to_string(),
+                        });
+                    }
+                    "BDAY" => card.birthday = Some(value),
+                    "NOTE" => card.note = Some(value),
+                    "CATEGORIES" => card.categories = Some(value),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn unescape_vcard_value(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => result.push('\n'),
+                Some(escaped) => result.push(escaped),
+                None => result.push('\\'),
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
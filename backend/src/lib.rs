@@ -1,9 +1,28 @@
 // This file acts as the entry point for the `backend` library.
 // By declaring `web_server` as a public module here, we make its
 // contents available to other crates, like our integration test.
+pub mod admin;
+pub mod attachments;
 pub mod auth;
+pub mod backup;
 pub mod config;
+pub mod contact_types;
+pub mod custom_fields;
 pub mod db;
+pub mod duplicates;
 pub mod error;
+pub mod export;
 pub mod extractors;
+pub mod history;
+pub mod import;
+pub mod mailer;
+pub mod migrations;
+pub mod newsletter;
+pub mod orgs;
+pub mod repository;
+pub mod seed;
+pub mod sharing;
+pub mod storage;
+pub mod tags;
+pub mod trash;
 pub mod web_server;
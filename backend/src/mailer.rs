@@ -0,0 +1,113 @@
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::config::{MailerBackend, MailerConfig, SmtpConfig};
+
+#[derive(Debug, Error)]
+pub enum MailerError {
+    #[error("Invalid email address: {0}")]
+    Address(#[from] lettre::address::AddressError),
+
+    #[error("Failed to build email: {0}")]
+    Message(#[from] lettre::error::Error),
+
+    #[error("SMTP error: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+
+    #[error("Mailer is misconfigured: {0}")]
+    Config(String),
+}
+
+/// A plain-text email.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Sends emails on behalf of the server.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), MailerError>;
+}
+
+/// Builds the mailer selected by `config.backend`.
+pub fn from_config(config: &MailerConfig) -> Result<Arc<dyn Mailer>, MailerError> {
+    let from: Mailbox = config.from.parse()?;
+    match config.backend {
+        MailerBackend::Log => Ok(Arc::new(LogMailer { from })),
+        MailerBackend::Smtp => {
+            let smtp = config.smtp.as_ref().ok_or_else(|| {
+                MailerError::Config("`mailer.smtp` must be set for the smtp backend".to_string())
+            })?;
+            Ok(Arc::new(SmtpMailer::new(from, smtp)?))
+        }
+    }
+}
+
+// --- Log ---
+
+/// Writes emails to the log instead of sending them, so that links in them can be followed
+/// during development.
+pub struct LogMailer {
+    from: Mailbox,
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: Email) -> Result<(), MailerError> {
+        tracing::info!(
+            "Email from {} to {}, subject '{}':\n{}",
+            self.from,
+            email.to,
+            email.subject,
+            email.body
+        );
+        Ok(())
+    }
+}
+
+// --- SMTP ---
+
+/// Sends emails through an SMTP relay.
+pub struct SmtpMailer {
+    from: Mailbox,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    pub fn new(from: Mailbox, config: &SmtpConfig) -> Result<Self, MailerError> {
+        let mut builder = if config.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host)
+        }
+        .port(config.port);
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Ok(Self {
+            from,
+            transport: builder.build(),
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), MailerError> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email.to.parse()?)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body)?;
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
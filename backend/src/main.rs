@@ -1,16 +1,137 @@
 // Use the library part of the `backend` crate instead of a local module.
+use backend::repository::Repositories;
 use backend::web_server::AppState;
+use clap::{Parser, Subcommand};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use backend::config::AppConfig;
-use backend::db::DbPoolOptions;
+use backend::db::{DbPool, DbPools};
 
 use tokio::signal;
 
-use std::env;
 use std::net::IpAddr;
 
+#[derive(Parser)]
+#[command(version, about = "The Cornerstone server")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Serve the API and the frontend (the default)
+    Serve {
+        /// Start without applying pending migrations
+        #[arg(long)]
+        no_migrate: bool,
+    },
+    /// Apply, revert or list the database migrations
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Manage user accounts
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Manage sessions
+    #[command(subcommand)]
+    Tokens(TokensCommand),
+    /// Inspect the configuration
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Back up a SQLite database, or restore a backup
+    #[command(subcommand)]
+    Backup(BackupCommand),
+    /// Fill a development database with users and contacts
+    Seed {
+        /// A YAML or JSON file of users to create, with their contacts
+        #[arg(long)]
+        file: Option<PathBuf>,
+        /// How many made-up contacts to add to each user of the file, or to every user
+        /// without one
+        #[arg(long, default_value_t = 0)]
+        contacts: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum MigrateCommand {
+    /// Apply every pending migration
+    Up,
+    /// Revert the latest applied migrations
+    Down {
+        /// How many migrations to revert
+        #[arg(long, default_value_t = 1)]
+        steps: usize,
+    },
+    /// List the migrations and whether they are applied
+    Status,
+    /// Revert the latest applied migration and apply it again
+    Redo,
+}
+
+#[derive(Subcommand)]
+enum UserCommand {
+    /// Create a user with their personal organization
+    Create {
+        email: String,
+        /// The password; a random one is generated and printed if left out
+        #[arg(long)]
+        password: Option<String>,
+        /// Make the user a server administrator
+        #[arg(long)]
+        admin: bool,
+    },
+    /// List every user
+    List,
+    /// Delete a user and the organizations nobody else is a member of
+    Delete { email: String },
+    /// Set a new password and end the user's session
+    ResetPassword {
+        email: String,
+        /// The password; a random one is generated and printed if left out
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Make a user a server administrator
+    SetAdmin {
+        email: String,
+        /// Take the administrator rights away instead
+        #[arg(long)]
+        revoke: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum TokensCommand {
+    /// Delete expired refresh tokens
+    Purge {
+        /// Delete every refresh token, signing everybody out
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum BackupCommand {
+    /// Back up the database into the backup directory, while the server may keep running
+    Create,
+    /// List the backups in the backup directory, newest first
+    List,
+    /// Replace the database with a backup; stop the server first
+    Restore {
+        /// The backup file, or the name of one in the backup directory
+        file: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Check that the server could start with the current configuration
+    Check,
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         signal::ctrl_c()
@@ -39,6 +160,8 @@ async fn shutdown_signal() {
 
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+
     // --- Setup ---
     // 1. Initialize structured logging
     tracing_subscriber::registry()
@@ -48,36 +171,277 @@ async fn main() {
 
     let config = AppConfig::from_env().expect("Failed to load configuration");
 
-    let database_url =
-        env::var("DATABASE_URL").expect("DATABASE_URL environment variable must be set");
+    if let Err(e) = run(cli.command, config).await {
+        tracing::error!("{e:#}");
+        std::process::exit(1);
+    }
+}
 
-    let db_pool = DbPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
-        .await
-        .unwrap();
+async fn run(command: Option<Command>, config: AppConfig) -> anyhow::Result<()> {
+    match command.unwrap_or(Command::Serve { no_migrate: false }) {
+        Command::Serve { no_migrate } => serve(config, no_migrate).await,
+        Command::Migrate(command) => {
+            let db_pool = connect(&config).await?;
+            migrate(command, &db_pool).await?;
+            db_pool.close().await;
+        }
+        Command::User(command) => {
+            let db_pool = connect(&config).await?;
+            user(command, &config, &db_pool).await?;
+            db_pool.close().await;
+        }
+        Command::Tokens(TokensCommand::Purge { all }) => {
+            let db_pool = connect(&config).await?;
+            let purged = backend::admin::purge_tokens(&db_pool, all).await?;
+            println!("Purged {purged} refresh tokens.");
+            db_pool.close().await;
+        }
+        Command::Config(ConfigCommand::Check) => {
+            let checks = backend::admin::check_config(&config).await;
+            for check in &checks {
+                match &check.result {
+                    Ok(found) => println!("ok    {}: {}", check.name, found),
+                    Err(problem) => println!("FAIL  {}: {}", check.name, problem),
+                }
+            }
+            if checks.iter().any(|check| check.result.is_err()) {
+                anyhow::bail!("The configuration has problems");
+            }
+        }
+        Command::Backup(command) => backup(command, &config).await?,
+        Command::Seed { file, contacts } => {
+            if file.is_none() && contacts == 0 {
+                anyhow::bail!("Give a fixture --file, a number of --contacts, or both");
+            }
+            let db_pool = connect(&config).await?;
+            seed(file, contacts, &db_pool).await?;
+            db_pool.close().await;
+        }
+    }
+    Ok(())
+}
 
-    tracing::info!("Running database migrations...");
+fn database_url(config: &AppConfig) -> String {
+    config
+        .database
+        .url()
+        .expect("DATABASE_URL environment variable or `database.url` must be set")
+}
 
-    #[cfg(feature = "db-sqlite")]
-    sqlx::migrate!("./migrations/sqlite")
-        .run(&db_pool)
-        .await
-        .unwrap();
+/// The primary database alone, for the commands that run once and exit.
+async fn connect(config: &AppConfig) -> anyhow::Result<DbPool> {
+    Ok(backend::db::connect(&database_url(config), &config.database).await?)
+}
 
-    #[cfg(feature = "db-postgres")]
-    sqlx::migrate!("./migrations/postgres")
-        .run(&db_pool)
+async fn backup(command: BackupCommand, config: &AppConfig) -> anyhow::Result<()> {
+    match command {
+        BackupCommand::Create => {
+            let db_pool = connect(config).await?;
+            let backup = backend::backup::create(&db_pool, &config.backup).await;
+            db_pool.close().await;
+            let backup = backup?;
+            println!(
+                "Wrote {} ({} bytes).",
+                config.backup.dir.join(&backup.name).display(),
+                backup.size_bytes
+            );
+        }
+        BackupCommand::List => {
+            for backup in backend::backup::list(&config.backup).await? {
+                println!("{:<40}  {:>12} bytes", backup.name, backup.size_bytes);
+            }
+        }
+        BackupCommand::Restore { file } => {
+            // A bare name is looked up in the backup directory, as `backup list` shows it.
+            let file = if file.exists() || file.components().count() > 1 {
+                file
+            } else {
+                config.backup.dir.join(file)
+            };
+            let restored = backend::backup::restore(&database_url(config), &file).await?;
+            println!(
+                "Restored {}, at migration {}.",
+                file.display(),
+                restored.version
+            );
+            if let Some(previous) = restored.previous {
+                println!("The replaced database was moved to {}.", previous.display());
+            }
+            if restored.pending > 0 {
+                println!(
+                    "The server applies the {} newer migrations when it starts.",
+                    restored.pending
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn seed(file: Option<PathBuf>, contacts: usize, db_pool: &DbPool) -> anyhow::Result<()> {
+    // A fresh database is seeded right away, without starting the server first.
+    backend::migrations::up(db_pool).await?;
+
+    let user_ids = match file {
+        Some(path) => {
+            let fixture = backend::seed::Fixture::read(&path)?;
+            let (user_ids, report) = backend::seed::load(db_pool, &fixture).await?;
+            println!(
+                "Created {} users with {} contacts from {}.",
+                report.users,
+                report.contacts,
+                path.display()
+            );
+            user_ids
+        }
+        None => backend::admin::list_users(db_pool)
+            .await?
+            .into_iter()
+            .map(|user| user.id)
+            .collect(),
+    };
+    if contacts > 0 {
+        let added = backend::seed::generate(db_pool, &user_ids, contacts).await?;
+        println!(
+            "Added {added} made-up contacts to {} users.",
+            user_ids.len()
+        );
+    }
+    Ok(())
+}
+
+async fn migrate(command: MigrateCommand, db_pool: &DbPool) -> anyhow::Result<()> {
+    match command {
+        MigrateCommand::Up => {
+            backend::migrations::up(db_pool).await?;
+            println!("All migrations are applied.");
+        }
+        MigrateCommand::Down { steps } => {
+            let reverted = backend::migrations::down(db_pool, steps).await?;
+            if reverted.is_empty() {
+                println!("No migrations to revert.");
+            }
+            for version in reverted {
+                println!("Reverted {version}");
+            }
+        }
+        MigrateCommand::Status => {
+            for migration in backend::migrations::status(db_pool).await? {
+                println!(
+                    "{}  {:<8}  {}",
+                    migration.version,
+                    migration.state.as_str(),
+                    migration.description
+                );
+            }
+        }
+        MigrateCommand::Redo => match backend::migrations::redo(db_pool).await? {
+            Some(version) => println!("Redid {version}"),
+            None => println!("No migrations to redo."),
+        },
+    }
+    Ok(())
+}
+
+async fn user(command: UserCommand, config: &AppConfig, db_pool: &DbPool) -> anyhow::Result<()> {
+    match command {
+        UserCommand::Create {
+            email,
+            password,
+            admin,
+        } => {
+            let (password, generated) = password_or_generated(password);
+            let id = backend::admin::create_user(db_pool, &email, &password, admin).await?;
+            println!("Created user {id}: {email}");
+            if generated {
+                println!("Password: {password}");
+            }
+        }
+        UserCommand::List => {
+            for user in backend::admin::list_users(db_pool).await? {
+                println!(
+                    "{:>6}  {:<40}  {:<5}  {} organizations",
+                    user.id,
+                    user.email,
+                    if user.is_admin { "admin" } else { "" },
+                    user.orgs
+                );
+            }
+        }
+        UserCommand::Delete { email } => {
+            let storage = backend::storage::from_config(&config.storage)?;
+            let orgs = backend::admin::delete_user(db_pool, storage.as_ref(), &email).await?;
+            println!("Deleted {email} and {orgs} organizations.");
+        }
+        UserCommand::ResetPassword { email, password } => {
+            let (password, generated) = password_or_generated(password);
+            backend::admin::reset_password(db_pool, &email, &password).await?;
+            println!("Reset the password of {email} and ended their session.");
+            if generated {
+                println!("Password: {password}");
+            }
+        }
+        UserCommand::SetAdmin { email, revoke } => {
+            backend::admin::set_admin(db_pool, &email, !revoke).await?;
+            if revoke {
+                println!("{email} is no longer an administrator.");
+            } else {
+                println!("{email} is now an administrator.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The given password, or a generated one to print, and whether it was generated.
+fn password_or_generated(password: Option<String>) -> (String, bool) {
+    match password {
+        Some(password) => (password, false),
+        None => (backend::admin::generate_password(), true),
+    }
+}
+
+async fn serve(config: AppConfig, no_migrate: bool) {
+    let database_url = database_url(&config);
+    let db_pools = DbPools::connect(&database_url, &config.database)
         .await
-        .unwrap();
+        .unwrap_or_else(|e| panic!("{e}"));
 
-    tracing::info!("Migrations complete.");
+    if no_migrate {
+        tracing::info!("Skipping database migrations (--no-migrate).");
+    } else {
+        tracing::info!(
+            "Running {} database migrations...",
+            db_pools.backend().as_str()
+        );
+
+        backend::migrations::up(&db_pools.primary).await.unwrap();
+
+        tracing::info!("Migrations complete.");
+    }
+
+    let storage =
+        backend::storage::from_config(&config.storage).expect("Failed to set up file storage");
+    let mailer = backend::mailer::from_config(&config.mailer).expect("Failed to set up the mailer");
 
     let app_state = AppState {
-        db_pool,
+        repos: Repositories::sql(db_pools.clone()),
+        db_pools,
         app_config: config.clone(),
+        storage,
+        mailer,
     };
 
+    backend::trash::spawn_purge_task(
+        app_state.repos.contacts.clone(),
+        app_state.storage.clone(),
+        config.trash.clone(),
+    );
+    backend::db::spawn_replica_checks(
+        app_state.db_pools.clone(),
+        config.database.replica_check_interval_seconds,
+    );
+
     // --- Run Server ---
     // 3. Start the web server and pass it the state
     tracing::info!("Initializing server...");
@@ -103,6 +467,6 @@ async fn main() {
 
     // This code runs after the server has stopped accepting new connections
     tracing::info!("Server shut down gracefully. Closing database connections.");
-    app_state.db_pool.close().await;
+    app_state.db_pools.primary.close().await;
     tracing::info!("Database pool closed.");
 }
@@ -0,0 +1,111 @@
+//! The `backend migrate` commands. They apply, revert and list the migrations embedded in the
+//! binary, so a deployment can roll its schema back without the sqlx CLI.
+
+use std::collections::HashMap;
+
+use sqlx::migrate::{Migrate, MigrateError};
+
+use crate::db::DbPool;
+use crate::with_pool;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Applied, but the migration has been changed since, so `up` refuses to run.
+    Modified,
+}
+
+impl MigrationState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MigrationState::Applied => "applied",
+            MigrationState::Pending => "pending",
+            MigrationState::Modified => "modified",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+/// Every embedded migration, oldest first, with whether the database has it.
+pub async fn status(db_pool: &DbPool) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let applied: HashMap<_, _> = with_pool!(db_pool, |pool| async {
+        let mut conn = pool.acquire().await?;
+        conn.ensure_migrations_table().await?;
+        conn.list_applied_migrations().await
+    }
+    .await)?
+    .into_iter()
+    .map(|m| (m.version, m.checksum))
+    .collect();
+
+    Ok(db_pool
+        .migrator()
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .map(|m| MigrationStatus {
+            version: m.version,
+            description: m.description.to_string(),
+            state: match applied.get(&m.version) {
+                None => MigrationState::Pending,
+                Some(checksum) if *checksum == m.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+            },
+        })
+        .collect())
+}
+
+/// Applies every pending migration, as startup does.
+pub async fn up(db_pool: &DbPool) -> Result<(), MigrateError> {
+    let migrator = db_pool.migrator();
+    with_pool!(db_pool, |pool| migrator.run(pool).await)
+}
+
+/// Reverts the latest `steps` applied migrations, newest first, and returns their versions.
+pub async fn down(db_pool: &DbPool, steps: usize) -> Result<Vec<i64>, MigrateError> {
+    let migrator = db_pool.migrator();
+    with_pool!(db_pool, |pool| async {
+        let mut conn = pool.acquire().await?;
+        conn.ensure_migrations_table().await?;
+        let applied: Vec<i64> = conn
+            .list_applied_migrations()
+            .await?
+            .into_iter()
+            .map(|m| m.version)
+            .collect();
+
+        // `undo` reverts everything newer than the target, the latest migration that stays.
+        let kept = applied.len().saturating_sub(steps);
+        let target = kept.checked_sub(1).map_or(0, |i| applied[i]);
+        migrator.undo(&mut *conn, target).await?;
+
+        Ok(applied[kept..].iter().rev().copied().collect())
+    }
+    .await)
+}
+
+/// Reverts the latest applied migration and applies it again, leaving any pending ones alone.
+/// Returns its version, or `None` if nothing was applied.
+pub async fn redo(db_pool: &DbPool) -> Result<Option<i64>, MigrateError> {
+    let Some(version) = down(db_pool, 1).await?.pop() else {
+        return Ok(None);
+    };
+    let migration = db_pool
+        .migrator()
+        .iter()
+        .find(|m| m.version == version && m.migration_type.is_up_migration())
+        .ok_or(MigrateError::VersionMissing(version))?;
+
+    with_pool!(db_pool, |pool| async {
+        let mut conn = pool.acquire().await?;
+        conn.apply(migration).await
+    }
+    .await)?;
+    Ok(Some(version))
+}
@@ -0,0 +1,430 @@
+use axum::{
+    debug_handler,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Html,
+    Json,
+};
+use base64::engine::{general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{Duration, Utc};
+use common::{SharePermission, SubscriptionDto};
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use utoipa::IntoParams;
+
+use crate::error::AppError;
+use crate::extractors::{AuthUser, RequestId};
+use crate::history::Actor;
+use crate::mailer::Email;
+use crate::sharing::check_access;
+use crate::web_server::AppState;
+
+// --- Signed Links ---
+// Links in emails carry an HMAC of their content, keyed with the JWT secret, so that they
+// work without logging in and can't be forged for other contacts. The messages are prefixed
+// with their purpose, so a signature for one kind of link is useless for another.
+
+fn mac(secret: &str, message: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(message.as_bytes());
+    mac
+}
+
+fn sign(secret: &str, message: &str) -> String {
+    URL_SAFE_NO_PAD.encode(mac(secret, message).finalize().into_bytes())
+}
+
+fn verify(secret: &str, message: &str, signature: &str) -> bool {
+    URL_SAFE_NO_PAD
+        .decode(signature)
+        .is_ok_and(|signature| mac(secret, message).verify_slice(&signature).is_ok())
+}
+
+/// A token confirming the subscription of `email` for the contact, valid until `expires_at`,
+/// a Unix timestamp. Changing the contact's email invalidates it.
+pub fn confirmation_token(secret: &str, contact_id: i64, email: &str, expires_at: i64) -> String {
+    let signature = sign(
+        secret,
+        &format!("confirm:{contact_id}:{email}:{expires_at}"),
+    );
+    format!("{contact_id}.{expires_at}.{signature}")
+}
+
+/// A token unsubscribing the contact. It doesn't expire, since newsletters are kept for a
+/// long time.
+pub fn unsubscribe_token(secret: &str, contact_id: i64) -> String {
+    let signature = sign(secret, &format!("unsubscribe:{contact_id}"));
+    format!("{contact_id}.{signature}")
+}
+
+/// The contact a valid unsubscribe token is for.
+fn check_unsubscribe_token(secret: &str, token: &str) -> Option<i64> {
+    let (contact_id, signature) = token.split_once('.')?;
+    let contact_id = contact_id.parse().ok()?;
+    verify(secret, &format!("unsubscribe:{contact_id}"), signature).then_some(contact_id)
+}
+
+/// Splits a confirmation token into the contact, the expiry and the signature, which can
+/// only be checked once the contact's email is known.
+fn split_confirmation_token(token: &str) -> Option<(i64, i64, &str)> {
+    let mut parts = token.splitn(3, '.');
+    let contact_id = parts.next()?.parse().ok()?;
+    let expires_at = parts.next()?.parse().ok()?;
+    Some((contact_id, expires_at, parts.next()?))
+}
+
+fn unsubscribe_url(state: &AppState, contact_id: i64) -> String {
+    format!(
+        "{}/api/v1/newsletter/unsubscribe?token={}",
+        state.app_config.newsletter.public_url.trim_end_matches('/'),
+        unsubscribe_token(&state.app_config.jwt.secret, contact_id)
+    )
+}
+
+// --- Consent Records ---
+
+/// Emails the contact a link that subscribes them once they follow it, and records the
+/// request as made by the actor. This is the only way users can get a contact subscribed.
+pub(crate) async fn ask_to_subscribe(
+    state: &AppState,
+    actor: &Actor,
+    contact_id: i64,
+    name: &str,
+    email: &str,
+) -> Result<(), AppError> {
+    let config = &state.app_config.newsletter;
+    let expires_at = (Utc::now() + Duration::hours(config.confirmation_expires_hours)).timestamp();
+    let token = confirmation_token(&state.app_config.jwt.secret, contact_id, email, expires_at);
+    let confirm_url = format!(
+        "{}/api/v1/newsletter/confirm?token={token}",
+        config.public_url.trim_end_matches('/')
+    );
+    let message = Email {
+        to: email.to_string(),
+        subject: "Please confirm your subscription".to_string(),
+        body: format!(
+            "Hello {name},\n\n\
+             please confirm that you want to receive our newsletter by opening this link:\n\n\
+             {confirm_url}\n\n\
+             The link is valid for {} hours. If you did not ask for the newsletter, ignore this \
+             email and you will not hear from us again.\n",
+            config.confirmation_expires_hours
+        ),
+    };
+    if let Err(e) = state.mailer.send(message).await {
+        tracing::error!(
+            "Failed to send confirmation email for contact {}: {}",
+            contact_id,
+            e
+        );
+        return Err(AppError::InternalServerError(
+            "Failed to send the confirmation email".to_string(),
+        ));
+    }
+
+    state
+        .repos
+        .subscriptions
+        .record_request(actor, contact_id, email)
+        .await?;
+    Ok(())
+}
+
+// --- API Handlers ---
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TokenQuery {
+    /// The token from the emailed link.
+    pub token: String,
+}
+
+/// ## Get a contact's newsletter subscription
+/// The current consent, the link to unsubscribe the contact, and every subscription change.
+#[utoipa::path(
+    get,
+    path = "/api/v1/contacts/{id}/subscription",
+    params(
+        ("id" = i64, Path, description = "Contact ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The contact's subscription and consent record", body = SubscriptionDto),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Contact not found or not shared with the user"),
+    )
+)]
+#[debug_handler]
+pub async fn get_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+) -> Result<Json<SubscriptionDto>, AppError> {
+    tracing::info!(
+        "Fetching subscription of contact {} for user {}",
+        id,
+        user.id
+    );
+
+    check_access(
+        state.repos.contacts.access(id, &user).await?,
+        SharePermission::Read,
+    )?;
+    let (consent, events) = state.repos.subscriptions.subscription(id).await?;
+
+    Ok(Json(SubscriptionDto {
+        subscribed: consent.subscribed,
+        subscribed_at: consent.subscribed_at,
+        source: consent.source,
+        unsubscribe_url: unsubscribe_url(&state, id),
+        events,
+    }))
+}
+
+/// ## Ask a contact to subscribe
+/// Emails the contact a link that subscribes them to the newsletter once they follow it
+/// (double opt-in). The contact stays unsubscribed until then.
+#[utoipa::path(
+    post,
+    path = "/api/v1/contacts/{id}/subscription",
+    params(
+        ("id" = i64, Path, description = "Contact ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 202, description = "Confirmation email sent"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user may not change the contact"),
+        (status = 404, description = "Contact not found or not shared with the user"),
+        (status = 409, description = "The contact is already subscribed"),
+    )
+)]
+#[debug_handler]
+pub async fn request_subscription(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<StatusCode, AppError> {
+    tracing::info!(
+        "Requesting subscription of contact {} for user {}",
+        id,
+        user.id
+    );
+
+    check_access(
+        state.repos.contacts.access(id, &user).await?,
+        SharePermission::Write,
+    )?;
+
+    let contact = state.repos.subscriptions.recipient(id).await?;
+    if contact.subscribed {
+        return Err(AppError::Conflict(
+            "The contact is already subscribed".to_string(),
+        ));
+    }
+
+    ask_to_subscribe(
+        &state,
+        &Actor::new(&user, request_id),
+        id,
+        &contact.name,
+        &contact.email,
+    )
+    .await?;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// ## Open a confirmation link
+/// Followed by contacts from the double opt-in email; needs no login. Only shows a page with
+/// a button that confirms the subscription, since mail scanners and link previews open
+/// links nobody clicked.
+#[utoipa::path(
+    get,
+    path = "/api/v1/newsletter/confirm",
+    params(TokenQuery),
+    security(()),
+    responses(
+        (status = 200, description = "A page asking to confirm the subscription", body = String, content_type = "text/html"),
+        (status = 400, description = "The link is invalid or has expired"),
+    )
+)]
+#[debug_handler]
+pub async fn confirm_subscription_page(
+    Query(query): Query<TokenQuery>,
+) -> Result<Html<String>, AppError> {
+    // The signature covers the contact's email, so it is only checked on confirming.
+    match split_confirmation_token(&query.token) {
+        Some((_, expires_at, _)) if expires_at >= Utc::now().timestamp() => Ok(page(
+            "Confirm your subscription",
+            "Please confirm that you want to receive our newsletter.",
+            Some((&query.token, "Confirm subscription")),
+        )),
+        _ => Err(invalid_confirmation_link()),
+    }
+}
+
+/// ## Confirm a subscription
+/// Sent by the page of the double opt-in link; needs no login. Confirming twice is fine.
+#[utoipa::path(
+    post,
+    path = "/api/v1/newsletter/confirm",
+    params(TokenQuery),
+    security(()),
+    responses(
+        (status = 200, description = "The contact is subscribed", body = String, content_type = "text/html"),
+        (status = 400, description = "The link is invalid or has expired"),
+    )
+)]
+#[debug_handler]
+pub async fn confirm_subscription(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+    request_id: RequestId,
+) -> Result<Html<String>, AppError> {
+    let (id, expires_at, signature) =
+        split_confirmation_token(&query.token).ok_or_else(invalid_confirmation_link)?;
+    if expires_at < Utc::now().timestamp() {
+        return Err(invalid_confirmation_link());
+    }
+
+    let secret = &state.app_config.jwt.secret;
+    let link_is_valid = |email: &str| {
+        verify(
+            secret,
+            &format!("confirm:{id}:{email}:{expires_at}"),
+            signature,
+        )
+    };
+    let confirmed = state
+        .repos
+        .subscriptions
+        .confirm(id, request_id.0.as_deref(), &link_is_valid)
+        .await?;
+    if !confirmed {
+        return Err(invalid_confirmation_link());
+    }
+
+    Ok(page(
+        "Subscription confirmed",
+        "Thank you, your subscription is confirmed.",
+        None,
+    ))
+}
+
+fn invalid_confirmation_link() -> AppError {
+    AppError::BadRequest("This link is invalid or has expired".to_string())
+}
+
+/// ## Open an unsubscribe link
+/// Followed by contacts from the unsubscribe link of an email; needs no login. Only shows a
+/// page with a button that unsubscribes the contact, since mail scanners and link previews
+/// open links nobody clicked.
+#[utoipa::path(
+    get,
+    path = "/api/v1/newsletter/unsubscribe",
+    params(TokenQuery),
+    security(()),
+    responses(
+        (status = 200, description = "A page asking to unsubscribe", body = String, content_type = "text/html"),
+        (status = 400, description = "The link is invalid"),
+    )
+)]
+#[debug_handler]
+pub async fn unsubscribe_page(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+) -> Result<Html<String>, AppError> {
+    check_unsubscribe_token(&state.app_config.jwt.secret, &query.token)
+        .ok_or_else(invalid_unsubscribe_link)?;
+    Ok(page(
+        "Unsubscribe",
+        "Do you want to stop receiving our newsletter?",
+        Some((&query.token, "Unsubscribe")),
+    ))
+}
+
+/// ## Unsubscribe
+/// Sent by the page of the unsubscribe link, and by mail clients as the one-click unsubscribe
+/// of RFC 8058: newsletters name the link in `List-Unsubscribe` and add
+/// `List-Unsubscribe-Post: List-Unsubscribe=One-Click`. Needs no login, and ignores the
+/// request body. Unsubscribing twice is fine.
+#[utoipa::path(
+    post,
+    path = "/api/v1/newsletter/unsubscribe",
+    params(TokenQuery),
+    security(()),
+    responses(
+        (status = 200, description = "The contact is unsubscribed", body = String, content_type = "text/html"),
+        (status = 400, description = "The link is invalid"),
+    )
+)]
+#[debug_handler]
+pub async fn unsubscribe(
+    State(state): State<AppState>,
+    Query(query): Query<TokenQuery>,
+    request_id: RequestId,
+) -> Result<Html<String>, AppError> {
+    let id = check_unsubscribe_token(&state.app_config.jwt.secret, &query.token)
+        .ok_or_else(invalid_unsubscribe_link)?;
+
+    state
+        .repos
+        .subscriptions
+        .unsubscribe(id, request_id.0.as_deref())
+        .await?;
+
+    Ok(page(
+        "Unsubscribed",
+        "You have been unsubscribed and will not receive our newsletter anymore.",
+        None,
+    ))
+}
+
+fn invalid_unsubscribe_link() -> AppError {
+    AppError::BadRequest("This link is invalid".to_string())
+}
+
+// --- Pages ---
+// Contacts open the links in their emails in a browser, so the public endpoints answer with
+// plain HTML pages.
+
+/// A page saying `text`, with a button labelled `button.1` that posts the token `button.0`
+/// back to the URL the page was opened at.
+fn page(title: &str, text: &str, button: Option<(&str, &str)>) -> Html<String> {
+    let form = button.map_or(String::new(), |(token, label)| {
+        format!(
+            "<form method=\"post\" action=\"?token={}\"><button type=\"submit\">{}</button></form>",
+            escape_html(token),
+            escape_html(label)
+        )
+    });
+    Html(format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{title}</title></head>\
+         <body><h1>{title}</h1><p>{text}</p>{form}</body></html>\n",
+        title = escape_html(title),
+        text = escape_html(text),
+    ))
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,347 @@
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use common::{
+    AddMemberRequest, CreateOrgRequest, LoginResponse, MembershipDto, OrgDto, OrgRole,
+    UpdateMemberRequest,
+};
+use validator::Validate;
+
+use crate::auth::issue_tokens;
+use crate::error::AppError;
+use crate::extractors::AuthUser;
+use crate::repository::RepositoryError;
+use crate::web_server::AppState;
+
+/// ## List the user's organizations
+#[utoipa::path(
+    get,
+    path = "/api/v1/orgs",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Organizations the user is a member of, in the order joined", body = Vec<OrgDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn get_orgs(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<OrgDto>>, AppError> {
+    tracing::info!("Fetching organizations of user {}", user.id);
+
+    let result = state.repos.orgs.list_for_user(user.id).await;
+
+    match result {
+        Ok(orgs) => Ok(Json(orgs)),
+        Err(e) => {
+            tracing::error!("Failed to fetch organizations: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch organizations".to_string(),
+            ))
+        }
+    }
+}
+
+/// ## Create an organization
+/// The caller becomes its owner. Switch to it to work with its contacts.
+#[utoipa::path(
+    post,
+    path = "/api/v1/orgs",
+    request_body = CreateOrgRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 201, description = "Organization created", body = OrgDto),
+        (status = 401, description = "Authentication required"),
+        (status = 422, description = "Validation error"),
+    )
+)]
+#[debug_handler]
+pub async fn create_org(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<CreateOrgRequest>,
+) -> Result<(StatusCode, Json<OrgDto>), AppError> {
+    tracing::info!(
+        "Creating organization {:?} for user {}",
+        request.name,
+        user.id
+    );
+
+    request.validate()?;
+    let name = request.name.trim();
+
+    let id = state.repos.orgs.create(name, user.id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(OrgDto {
+            id,
+            name: name.to_string(),
+            role: OrgRole::Owner,
+        }),
+    ))
+}
+
+/// ## Switch to another organization
+/// Issues a new token pair scoped to the organization, replacing the user's refresh token.
+#[utoipa::path(
+    post,
+    path = "/api/v1/orgs/{id}/switch",
+    params(
+        ("id" = i64, Path, description = "Organization ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Tokens for the organization", body = LoginResponse),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Organization not found or the user is not a member"),
+    )
+)]
+#[debug_handler]
+pub async fn switch_org(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+) -> Result<Json<LoginResponse>, AppError> {
+    tracing::info!("Switching user {} to organization {}", user.id, id);
+
+    state
+        .repos
+        .users
+        .role_in(user.id, id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let tokens = issue_tokens(
+        user.id,
+        id,
+        &*state.repos.tokens,
+        &state.app_config.jwt,
+        None,
+    )
+    .await?;
+
+    Ok(Json(tokens))
+}
+
+/// ## List the members of the current organization
+#[utoipa::path(
+    get,
+    path = "/api/v1/org/members",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Members in the order they joined", body = Vec<MembershipDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn get_members(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<MembershipDto>>, AppError> {
+    tracing::info!("Fetching members of organization {}", user.org_id);
+
+    let result = state.repos.orgs.members(user.org_id).await;
+
+    match result {
+        Ok(members) => Ok(Json(members)),
+        Err(e) => {
+            tracing::error!("Failed to fetch members: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch members".to_string(),
+            ))
+        }
+    }
+}
+
+/// ## Add a member to the current organization
+/// Admins and owners add users by the email they signed up with. Only owners add owners.
+#[utoipa::path(
+    post,
+    path = "/api/v1/org/members",
+    request_body = AddMemberRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 201, description = "Member added", body = MembershipDto),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow this"),
+        (status = 404, description = "User not found"),
+        (status = 409, description = "The user is already a member"),
+        (status = 422, description = "Validation error"),
+    )
+)]
+#[debug_handler]
+pub async fn add_member(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<AddMemberRequest>,
+) -> Result<(StatusCode, Json<MembershipDto>), AppError> {
+    tracing::info!(
+        "Adding {} to organization {} as {:?}",
+        request.email,
+        user.org_id,
+        request.role
+    );
+
+    request.validate()?;
+    user.require_manage()?;
+    if request.role == OrgRole::Owner && user.role != OrgRole::Owner {
+        return Err(AppError::Forbidden);
+    }
+
+    let member_id = state
+        .repos
+        .users
+        .find_by_email(&request.email)
+        .await?
+        .ok_or(AppError::NotFound)?
+        .id;
+
+    let result = state
+        .repos
+        .orgs
+        .add_member(user.org_id, member_id, request.role)
+        .await;
+    let membership = match result {
+        Ok(membership) => membership,
+        Err(RepositoryError::Duplicate(_)) => {
+            return Err(AppError::Conflict(
+                "The user is already a member".to_string(),
+            ))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok((StatusCode::CREATED, Json(membership)))
+}
+
+/// ## Change a member's role
+/// Admins and owners change roles; only owners make or unmake owners. The last owner
+/// stays an owner.
+#[utoipa::path(
+    put,
+    path = "/api/v1/org/members/{user_id}",
+    request_body = UpdateMemberRequest,
+    params(
+        ("user_id" = i64, Path, description = "User ID of the member")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Role changed", body = MembershipDto),
+        (status = 400, description = "The organization would be left without an owner"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow this"),
+        (status = 404, description = "Member not found"),
+    )
+)]
+#[debug_handler]
+pub async fn update_member(
+    State(state): State<AppState>,
+    Path(member_id): Path<i64>,
+    user: AuthUser,
+    Json(request): Json<UpdateMemberRequest>,
+) -> Result<Json<MembershipDto>, AppError> {
+    tracing::info!(
+        "Changing role of user {} in organization {} to {:?}",
+        member_id,
+        user.org_id,
+        request.role
+    );
+
+    user.require_manage()?;
+
+    let current = state
+        .repos
+        .users
+        .role_in(member_id, user.org_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    let involves_owner = current == OrgRole::Owner || request.role == OrgRole::Owner;
+    if involves_owner && user.role != OrgRole::Owner {
+        return Err(AppError::Forbidden);
+    }
+
+    // The repository checks again that an owner remains, as the membership may have changed.
+    let membership = state
+        .repos
+        .orgs
+        .set_role(user.org_id, member_id, request.role)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(membership))
+}
+
+/// ## Remove a member from the current organization
+/// Admins and owners remove members, and anyone may leave. Only owners remove owners, and
+/// the last owner cannot leave. The removed member's tokens stop working for the organization.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/org/members/{user_id}",
+    params(
+        ("user_id" = i64, Path, description = "User ID of the member")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Member removed"),
+        (status = 400, description = "The organization would be left without an owner"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow this"),
+        (status = 404, description = "Member not found"),
+    )
+)]
+#[debug_handler]
+pub async fn remove_member(
+    State(state): State<AppState>,
+    Path(member_id): Path<i64>,
+    user: AuthUser,
+) -> Result<StatusCode, AppError> {
+    tracing::info!(
+        "Removing user {} from organization {}",
+        member_id,
+        user.org_id
+    );
+
+    if member_id != user.id {
+        user.require_manage()?;
+    }
+
+    let current = state
+        .repos
+        .users
+        .role_in(member_id, user.org_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if current == OrgRole::Owner && user.role != OrgRole::Owner {
+        return Err(AppError::Forbidden);
+    }
+
+    let removed = state
+        .repos
+        .orgs
+        .remove_member(user.org_id, member_id)
+        .await?;
+    if !removed {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
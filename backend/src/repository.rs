@@ -0,0 +1,637 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use common::{
+    AttachmentDto, ContactDto, ContactEventDto, ContactType, ContactTypeDto, CustomFieldDto,
+    CustomFieldType, DuplicateContactDto, MembershipDto, OrgDto, OrgRole, ShareDto,
+    SharePermission, SharedContactDto, SubscriptionEventDto, SubscriptionSource, TagDto,
+    TrashedContactDto,
+};
+use futures::stream::BoxStream;
+use serde::Deserialize;
+use thiserror::Error;
+use utoipa::IntoParams;
+
+use crate::admin::UserSummary;
+use crate::db::{DbBackend, DbPools};
+use crate::error::unique_violation_field;
+use crate::extractors::AuthUser;
+use crate::history::Actor;
+use crate::sharing::ContactAccess;
+
+pub mod memory;
+#[cfg(feature = "db-mysql")]
+pub mod mysql;
+pub mod postgres;
+pub mod sqlite;
+
+#[derive(Debug, Error)]
+pub enum RepositoryError {
+    /// A unique field, named by the value, already holds the written value.
+    #[error("A record with this {0} already exists")]
+    Duplicate(String),
+
+    /// The change would leave an organization without an owner.
+    #[error("An organization needs at least one owner")]
+    LastOwner,
+
+    #[error("Database error: {0}")]
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for RepositoryError {
+    fn from(e: sqlx::Error) -> Self {
+        match unique_violation_field(&e) {
+            Some(field) => RepositoryError::Duplicate(field),
+            None => RepositoryError::Database(e),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct User {
+    pub id: i64,
+    pub email: String,
+    pub password_hash: String,
+}
+
+/// A stored refresh token, which is only known by its hash.
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct RefreshToken {
+    pub user_id: i64,
+    /// The organization the session is in.
+    pub org_id: Option<i64>,
+    pub expires_at: NaiveDateTime,
+}
+
+/// What became of deleting a user with `UserRepository::delete`.
+#[derive(Debug)]
+pub enum UserDeletion {
+    UnknownUser,
+    /// The user is the last owner of the named organization, which has other members.
+    LastOwner(String),
+    Deleted {
+        orgs: u64,
+        /// The stored files of the deleted organizations' contacts.
+        storage_keys: Vec<String>,
+    },
+}
+
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, RepositoryError>;
+
+    async fn find_by_id(&self, id: i64) -> Result<Option<User>, RepositoryError>;
+
+    /// Creates a user along with a personal organization they own, named after their email,
+    /// and returns the user's ID.
+    async fn create(&self, email: &str, password_hash: &str) -> Result<i64, RepositoryError>;
+
+    /// The user's role in the organization, or `None` if they aren't a member.
+    async fn role_in(&self, user_id: i64, org_id: i64) -> Result<Option<OrgRole>, RepositoryError>;
+
+    /// The organization a user starts a session in: the one they joined first, which is the
+    /// personal organization created when they registered.
+    async fn default_org(&self, user_id: i64) -> Result<Option<i64>, RepositoryError>;
+
+    /// Whether the user is a server administrator; `false` for an unknown user.
+    async fn is_admin(&self, user_id: i64) -> Result<bool, RepositoryError>;
+
+    /// Every user with the number of organizations they are a member of, ordered by ID.
+    async fn summaries(&self) -> Result<Vec<UserSummary>, RepositoryError>;
+
+    /// Makes the user with the email a server administrator, or takes it away. Returns
+    /// `false` if there is no such user.
+    async fn set_admin(&self, email: &str, is_admin: bool) -> Result<bool, RepositoryError>;
+
+    /// Stores a new password hash and ends the user's session. Returns `false` if there is no
+    /// user with the email.
+    async fn reset_password(
+        &self,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<bool, RepositoryError>;
+
+    /// Deletes the user with the email along with the organizations nobody else is a member
+    /// of. What they created in the organizations that stay is credited to an owner there.
+    /// A user who is the last owner of an organization with other members is kept.
+    async fn delete(&self, email: &str) -> Result<UserDeletion, RepositoryError>;
+}
+
+#[async_trait]
+pub trait TokenRepository: Send + Sync {
+    /// Stores the hash of a new refresh token, replacing any other token of the user, so that
+    /// logging in again ends other sessions. The token hashed as `replaced`, if any, is deleted
+    /// in the same step.
+    async fn store(
+        &self,
+        user_id: i64,
+        org_id: i64,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+        replaced: Option<&str>,
+    ) -> Result<(), RepositoryError>;
+
+    async fn find(&self, token_hash: &str) -> Result<Option<RefreshToken>, RepositoryError>;
+
+    async fn delete(&self, token_hash: &str) -> Result<(), RepositoryError>;
+
+    async fn delete_for_user(&self, user_id: i64) -> Result<(), RepositoryError>;
+
+    /// Deletes the expired tokens, or every one of them with `all`, and returns how many.
+    async fn purge(&self, all: bool) -> Result<u64, RepositoryError>;
+}
+
+/// Filters shared by the contact list, the list of shared contacts and the export endpoint.
+#[derive(Deserialize, IntoParams, Default, Debug, Clone)]
+#[into_params(parameter_in = Query)]
+pub struct ContactFilter {
+    /// Case-insensitive substring match on name or email.
+    pub q: Option<String>,
+    pub contact_type: Option<ContactType>,
+    pub subscribed: Option<bool>,
+    /// Only contacts carrying the tag with this name.
+    pub tag: Option<String>,
+    /// Only contacts with a value for a custom field, given as `name`, or with a given value,
+    /// given as `name:value`.
+    pub custom_field: Option<String>,
+}
+
+/// One write of a batch run by `ContactRepository::write_batch`.
+#[derive(Clone, Copy, Debug)]
+pub enum ContactWrite<'a> {
+    /// Adds a contact. A subscribed one is recorded as having consented through `source`,
+    /// except that users can't give consent for their contacts: one written as
+    /// `SubscriptionSource::Manual` is stored unsubscribed.
+    Create {
+        contact: &'a ContactDto,
+        source: SubscriptionSource,
+    },
+    Update {
+        id: i64,
+        contact: &'a ContactDto,
+    },
+    /// Moves a contact to the trash.
+    Delete {
+        id: i64,
+    },
+}
+
+/// What became of one write of a batch.
+#[derive(Debug)]
+pub enum WriteOutcome {
+    Created(ContactDto),
+    Updated(ContactDto),
+    Deleted,
+    /// There is no such contact to update or delete.
+    NotFound,
+    Failed(RepositoryError),
+}
+
+impl WriteOutcome {
+    pub fn succeeded(&self) -> bool {
+        !matches!(self, WriteOutcome::NotFound | WriteOutcome::Failed(_))
+    }
+}
+
+/// The contacts of organizations. Deleted contacts move to the trash, where they keep their
+/// ID and email until they are restored or purged; every other method only sees contacts
+/// outside the trash. Writes record history and consent as the actor, and can't subscribe a
+/// contact on a user's word.
+#[async_trait]
+pub trait ContactRepository: Send + Sync {
+    /// A page of the organization's contacts matching the filter, ordered by ID.
+    async fn list(
+        &self,
+        org_id: i64,
+        filter: &ContactFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ContactDto>, RepositoryError>;
+
+    async fn find(&self, org_id: i64, id: i64) -> Result<Option<ContactDto>, RepositoryError>;
+
+    /// Adds a contact written by hand, like `ContactWrite::Create` with
+    /// `SubscriptionSource::Manual`.
+    async fn create(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        contact: &ContactDto,
+    ) -> Result<ContactDto, RepositoryError>;
+
+    /// Overwrites a contact and returns the stored result, or `None` if there is no such
+    /// contact.
+    async fn update(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        id: i64,
+        contact: &ContactDto,
+    ) -> Result<Option<ContactDto>, RepositoryError>;
+
+    /// Moves a contact to the trash. Returns `true` if a contact was deleted.
+    async fn delete(&self, actor: &Actor, org_id: i64, id: i64) -> Result<bool, RepositoryError>;
+
+    /// Runs the writes in order as one unit, each on its own, so that a failing write leaves
+    /// the others in place. Returns the outcome of every write that was run. With
+    /// `all_or_nothing`, the first write that doesn't succeed ends the batch and none of it is
+    /// kept; its outcome is the last one returned.
+    async fn write_batch(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        writes: &[ContactWrite<'_>],
+        all_or_nothing: bool,
+    ) -> Result<Vec<WriteOutcome>, RepositoryError>;
+
+    /// Those of `emails` that belong to one of the organization's contacts, compared
+    /// case-insensitively. They are returned lowercased.
+    async fn existing_emails(
+        &self,
+        org_id: i64,
+        emails: &[String],
+    ) -> Result<HashSet<String>, RepositoryError>;
+
+    /// Every contact of the organization matching the filter, ordered by ID. Contacts are
+    /// read as the stream is polled, so memory use does not grow with the size of the book.
+    fn export(
+        &self,
+        org_id: i64,
+        filter: ContactFilter,
+    ) -> BoxStream<'static, Result<ContactDto, RepositoryError>>;
+
+    /// The ID, name and emails, primary ones first, of each of the organization's contacts,
+    /// ordered by ID.
+    async fn identities(
+        &self,
+        org_id: i64,
+    ) -> Result<Vec<(DuplicateContactDto, Vec<String>)>, RepositoryError>;
+
+    /// When a contact was last written, or `None` if there is no such contact.
+    async fn updated_at(
+        &self,
+        org_id: i64,
+        id: i64,
+    ) -> Result<Option<NaiveDateTime>, RepositoryError>;
+
+    /// Moves the duplicate to the trash and overwrites the primary contact with `merged`, as
+    /// one unit, recording a `merged` event for both. The duplicate leaves first, so the
+    /// primary can take over its email. Returns the stored primary contact, or `None` if
+    /// either contact doesn't exist.
+    async fn merge(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        primary_id: i64,
+        duplicate_id: i64,
+        merged: &ContactDto,
+    ) -> Result<Option<ContactDto>, RepositoryError>;
+
+    /// The organization of a contact and the access the user has to it, or `None` if the
+    /// contact doesn't exist or isn't visible to the user.
+    async fn access(
+        &self,
+        id: i64,
+        user: &AuthUser,
+    ) -> Result<Option<(i64, ContactAccess)>, RepositoryError>;
+
+    /// A page of the contacts of other organizations shared with the user matching the
+    /// filter, ordered by ID.
+    async fn list_shared(
+        &self,
+        user_id: i64,
+        filter: &ContactFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SharedContactDto>, RepositoryError>;
+
+    /// A page of the organization's trash, most recently deleted first.
+    async fn list_trash(
+        &self,
+        org_id: i64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TrashedContactDto>, RepositoryError>;
+
+    /// Moves a contact out of the trash and returns it, or `None` if the trash holds no such
+    /// contact. Fails with `Duplicate` if another contact has taken its email.
+    async fn restore(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        id: i64,
+    ) -> Result<Option<ContactDto>, RepositoryError>;
+
+    /// Permanently deletes the contacts deleted before `deleted_before`, with their history
+    /// and attachments. Returns the number of purged contacts and the storage keys of their
+    /// files, which the caller removes.
+    async fn purge_trash(
+        &self,
+        deleted_before: NaiveDateTime,
+    ) -> Result<(u64, Vec<String>), RepositoryError>;
+
+    /// How many contacts the organization has, in the trash or not.
+    async fn count(&self, org_id: i64) -> Result<i64, RepositoryError>;
+
+    /// The contact types contacts may currently be given.
+    async fn enabled_contact_types(&self) -> Result<HashSet<ContactType>, RepositoryError>;
+
+    /// The enabled contact types with their labels, in display order.
+    async fn contact_types(&self) -> Result<Vec<ContactTypeDto>, RepositoryError>;
+
+    /// The organization's custom field definitions, which contact values are checked against.
+    async fn custom_fields(&self, org_id: i64) -> Result<Vec<CustomFieldDto>, RepositoryError>;
+}
+
+/// The tags of organizations, which contacts are labelled with by name.
+#[async_trait]
+pub trait TagRepository: Send + Sync {
+    /// The organization's tags, sorted by name.
+    async fn list(&self, org_id: i64) -> Result<Vec<TagDto>, RepositoryError>;
+
+    async fn create(&self, org_id: i64, name: &str) -> Result<TagDto, RepositoryError>;
+
+    /// Renames a tag on every contact carrying it. Returns the renamed tag, or `None` if there
+    /// is no such tag.
+    async fn rename(
+        &self,
+        org_id: i64,
+        id: i64,
+        name: &str,
+    ) -> Result<Option<TagDto>, RepositoryError>;
+
+    /// Deletes a tag, taking it off its contacts. Returns `true` if a tag was deleted.
+    async fn delete(&self, org_id: i64, id: i64) -> Result<bool, RepositoryError>;
+}
+
+/// Access that organizations grant users outside them, to one contact or the whole book.
+#[async_trait]
+pub trait ShareRepository: Send + Sync {
+    /// The organization's shares, oldest first.
+    async fn list(&self, org_id: i64) -> Result<Vec<ShareDto>, RepositoryError>;
+
+    /// The shares granted to the user, oldest first.
+    async fn received(&self, user_id: i64) -> Result<Vec<ShareDto>, RepositoryError>;
+
+    /// Shares one of the organization's contacts, or all of them when `contact_id` is `None`,
+    /// with the user. Fails with `Duplicate` if the same thing is already shared with them.
+    async fn create(
+        &self,
+        org_id: i64,
+        grantee_id: i64,
+        contact_id: Option<i64>,
+        permission: SharePermission,
+    ) -> Result<ShareDto, RepositoryError>;
+
+    /// Changes the permission of one of the organization's shares. Returns the share, or
+    /// `None` if there is no such share.
+    async fn update(
+        &self,
+        org_id: i64,
+        id: i64,
+        permission: SharePermission,
+    ) -> Result<Option<ShareDto>, RepositoryError>;
+
+    /// Revokes a share, if it is one of the organization's, when `org_id` is given, or was
+    /// granted to `grantee_id`. Returns `true` if a share was revoked.
+    async fn delete(
+        &self,
+        id: i64,
+        org_id: Option<i64>,
+        grantee_id: i64,
+    ) -> Result<bool, RepositoryError>;
+}
+
+/// Organizations and their members. Every organization keeps at least one owner: changes
+/// that would take away the last one fail with `LastOwner`.
+#[async_trait]
+pub trait OrgRepository: Send + Sync {
+    /// The organizations the user is a member of, in the order joined.
+    async fn list_for_user(&self, user_id: i64) -> Result<Vec<OrgDto>, RepositoryError>;
+
+    /// Creates an organization with `owner_id` as its only member and owner, and returns its
+    /// ID.
+    async fn create(&self, name: &str, owner_id: i64) -> Result<i64, RepositoryError>;
+
+    /// The organization's members, in the order they joined.
+    async fn members(&self, org_id: i64) -> Result<Vec<MembershipDto>, RepositoryError>;
+
+    /// Adds the user to the organization. Fails with `Duplicate` if they are a member
+    /// already.
+    async fn add_member(
+        &self,
+        org_id: i64,
+        user_id: i64,
+        role: OrgRole,
+    ) -> Result<MembershipDto, RepositoryError>;
+
+    /// Changes a member's role. Returns the membership, or `None` if the user isn't a member.
+    async fn set_role(
+        &self,
+        org_id: i64,
+        user_id: i64,
+        role: OrgRole,
+    ) -> Result<Option<MembershipDto>, RepositoryError>;
+
+    /// Removes the user from the organization. Returns `true` if they were a member.
+    async fn remove_member(&self, org_id: i64, user_id: i64) -> Result<bool, RepositoryError>;
+}
+
+/// The custom fields organizations define for their contacts, whose values are stored on the
+/// contacts by field name.
+#[async_trait]
+pub trait CustomFieldRepository: Send + Sync {
+    /// The organization's fields, in creation order.
+    async fn list(&self, org_id: i64) -> Result<Vec<CustomFieldDto>, RepositoryError>;
+
+    /// Adds a field to the organization and returns its ID. Fails with `Duplicate` if the
+    /// name is taken.
+    async fn create(&self, org_id: i64, field: &CustomFieldDto) -> Result<i64, RepositoryError>;
+
+    /// Changes the name, the required flag and the options of a field and carries a new name
+    /// over to the contacts' values. Returns the stored type of the field, or `None` if there
+    /// is no such field; the field is only changed if `field` has that type.
+    async fn update(
+        &self,
+        org_id: i64,
+        id: i64,
+        field: &CustomFieldDto,
+    ) -> Result<Option<CustomFieldType>, RepositoryError>;
+
+    /// Deletes a field and removes its value from every contact. Returns `true` if a field
+    /// was deleted.
+    async fn delete(&self, org_id: i64, id: i64) -> Result<bool, RepositoryError>;
+}
+
+/// The change log that contact writes leave behind.
+#[async_trait]
+pub trait HistoryRepository: Send + Sync {
+    /// Whether the contact belongs to the organization, in the trash or not.
+    async fn in_org(&self, org_id: i64, contact_id: i64) -> Result<bool, RepositoryError>;
+
+    /// The contact's events, oldest first.
+    async fn list(&self, contact_id: i64) -> Result<Vec<ContactEventDto>, RepositoryError>;
+}
+
+/// The current newsletter consent of a contact.
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct Consent {
+    pub subscribed: bool,
+    pub subscribed_at: Option<NaiveDateTime>,
+    pub source: Option<SubscriptionSource>,
+}
+
+/// Who a subscription request is sent to.
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct Recipient {
+    pub name: String,
+    pub email: String,
+    pub subscribed: bool,
+}
+
+/// The newsletter consent of contacts and its log. Contacts change it themselves through
+/// signed links, so these methods take no user.
+#[async_trait]
+pub trait SubscriptionRepository: Send + Sync {
+    /// Logs that the actor asked the contact at `email` to confirm a subscription.
+    async fn record_request(
+        &self,
+        actor: &Actor,
+        contact_id: i64,
+        email: &str,
+    ) -> Result<(), RepositoryError>;
+
+    /// The contact's consent and its subscription log, oldest first.
+    async fn subscription(
+        &self,
+        contact_id: i64,
+    ) -> Result<(Consent, Vec<SubscriptionEventDto>), RepositoryError>;
+
+    async fn recipient(&self, contact_id: i64) -> Result<Recipient, RepositoryError>;
+
+    /// Subscribes a contact outside the trash that followed a confirmation link, if
+    /// `link_is_valid` accepts the link for the contact's current email. Returns `false` for
+    /// an unknown contact or an invalid link; confirming twice is fine.
+    async fn confirm(
+        &self,
+        contact_id: i64,
+        request_id: Option<&str>,
+        link_is_valid: &(dyn for<'a> Fn(&'a str) -> bool + Sync),
+    ) -> Result<bool, RepositoryError>;
+
+    /// Unsubscribes a contact that followed an unsubscribe link. Contacts in the trash can
+    /// still unsubscribe; purged ones have nothing left to change.
+    async fn unsubscribe(
+        &self,
+        contact_id: i64,
+        request_id: Option<&str>,
+    ) -> Result<(), RepositoryError>;
+}
+
+/// Where an attachment is stored and how it is served.
+#[derive(sqlx::FromRow, Clone, Debug)]
+pub struct StoredAttachment {
+    pub file_name: String,
+    pub content_type: String,
+    pub storage_key: String,
+}
+
+/// The files attached to contacts and the contacts' avatars. Their content lives in the
+/// storage, under the keys recorded here.
+#[async_trait]
+pub trait AttachmentRepository: Send + Sync {
+    /// The contact's attachments, in upload order.
+    async fn list(&self, contact_id: i64) -> Result<Vec<AttachmentDto>, RepositoryError>;
+
+    /// Records a file the user stored under `storage_key` as an attachment of the contact.
+    async fn create(
+        &self,
+        contact_id: i64,
+        user_id: i64,
+        file_name: &str,
+        content_type: &str,
+        size: i64,
+        storage_key: &str,
+    ) -> Result<AttachmentDto, RepositoryError>;
+
+    async fn find(
+        &self,
+        contact_id: i64,
+        id: i64,
+    ) -> Result<Option<StoredAttachment>, RepositoryError>;
+
+    /// Deletes an attachment of the contact and returns its storage key, or `None` if the
+    /// contact has no such attachment.
+    async fn delete(&self, contact_id: i64, id: i64) -> Result<Option<String>, RepositoryError>;
+
+    /// The storage key of the contact's avatar, if it has one.
+    async fn avatar_key(&self, contact_id: i64) -> Result<Option<String>, RepositoryError>;
+
+    /// Points the contact at a newly stored avatar and returns the updated contact along with
+    /// the key of the avatar it replaced. Write access is checked again in the same step,
+    /// since the contact may have been deleted or unshared while the image was processed;
+    /// `None` if the user can no longer change it.
+    async fn set_avatar(
+        &self,
+        user: &AuthUser,
+        contact_id: i64,
+        key: &str,
+    ) -> Result<Option<(ContactDto, Option<String>)>, RepositoryError>;
+
+    /// Removes the contact's avatar and returns the key it was stored under, or `None` if the
+    /// contact has no avatar or the user can't change it.
+    async fn remove_avatar(
+        &self,
+        user: &AuthUser,
+        contact_id: i64,
+    ) -> Result<Option<String>, RepositoryError>;
+}
+
+/// The repositories the handlers work with.
+#[derive(Clone)]
+pub struct Repositories {
+    pub users: Arc<dyn UserRepository>,
+    pub tokens: Arc<dyn TokenRepository>,
+    pub contacts: Arc<dyn ContactRepository>,
+    pub tags: Arc<dyn TagRepository>,
+    pub shares: Arc<dyn ShareRepository>,
+    pub orgs: Arc<dyn OrgRepository>,
+    pub custom_fields: Arc<dyn CustomFieldRepository>,
+    pub history: Arc<dyn HistoryRepository>,
+    pub subscriptions: Arc<dyn SubscriptionRepository>,
+    pub attachments: Arc<dyn AttachmentRepository>,
+}
+
+impl Repositories {
+    /// Repositories backed by the database, on the backend of `db_pools`.
+    pub fn sql(db_pools: DbPools) -> Self {
+        match db_pools.backend() {
+            DbBackend::Sqlite => sqlite::repositories(db_pools),
+            DbBackend::Postgres => postgres::repositories(db_pools),
+            #[cfg(feature = "db-mysql")]
+            DbBackend::MySql => mysql::repositories(db_pools),
+            #[cfg(not(feature = "db-mysql"))]
+            DbBackend::MySql => unreachable!("MySQL pools need the db-mysql feature"),
+        }
+    }
+
+    /// Repositories that share one in-memory store, for tests that don't need a database.
+    pub fn in_memory() -> Self {
+        let store = Arc::new(memory::MemoryStore::default());
+        Self {
+            users: store.clone(),
+            tokens: store.clone(),
+            contacts: store.clone(),
+            tags: store.clone(),
+            shares: store.clone(),
+            orgs: store.clone(),
+            custom_fields: store.clone(),
+            history: store.clone(),
+            subscriptions: store.clone(),
+            attachments: store,
+        }
+    }
+}
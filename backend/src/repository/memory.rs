@@ -0,0 +1,1513 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use common::{
+    AttachmentDto, ContactDto, ContactEventDto, ContactType, ContactTypeDto, CustomFieldDto,
+    CustomFieldType, DuplicateContactDto, MembershipDto, OrgDto, OrgRole, ShareDto,
+    SharePermission, SharedContactDto, SubscriptionEventDto, SubscriptionSource, TagDto,
+    TrashedContactDto,
+};
+use futures::stream::{self, BoxStream, StreamExt};
+use serde_json::Value;
+
+use super::{
+    AttachmentRepository, Consent, ContactFilter, ContactRepository, ContactWrite,
+    CustomFieldRepository, HistoryRepository, OrgRepository, Recipient, RefreshToken,
+    RepositoryError, ShareRepository, StoredAttachment, SubscriptionRepository, TagRepository,
+    TokenRepository, User, UserDeletion, UserRepository, WriteOutcome,
+};
+use crate::admin::UserSummary;
+use crate::extractors::AuthUser;
+use crate::history::Actor;
+use crate::sharing::ContactAccess;
+
+/// Users, organizations, sessions, contacts, tags, shares, custom fields and attachments kept
+/// in memory, for tests of the handlers that don't need a database. Every contact type is
+/// enabled, labelled with its name. No history or subscription log is recorded, and consent is
+/// only the contact's flag, though as in the database, users can't subscribe a contact.
+/// Deleted contacts go to the trash like in the database.
+#[derive(Default)]
+pub struct MemoryStore {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    users: Vec<User>,
+    next_user_id: i64,
+    /// The IDs of the server administrators.
+    admins: HashSet<i64>,
+    /// Organization names by ID.
+    orgs: BTreeMap<i64, String>,
+    next_org_id: i64,
+    /// In the order the users joined.
+    memberships: Vec<Membership>,
+    /// Refresh tokens by hash.
+    tokens: HashMap<String, RefreshToken>,
+    contacts: BTreeMap<i64, StoredContact>,
+    next_contact_id: i64,
+    tags: BTreeMap<i64, StoredTag>,
+    next_tag_id: i64,
+    shares: BTreeMap<i64, StoredShare>,
+    next_share_id: i64,
+    custom_fields: BTreeMap<i64, StoredField>,
+    next_custom_field_id: i64,
+    attachments: BTreeMap<i64, StoredFile>,
+    next_attachment_id: i64,
+}
+
+struct Membership {
+    user_id: i64,
+    org_id: i64,
+    role: OrgRole,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Clone)]
+struct StoredContact {
+    org_id: i64,
+    contact: ContactDto,
+    updated_at: NaiveDateTime,
+    /// When the contact was moved to the trash.
+    deleted_at: Option<NaiveDateTime>,
+    avatar_key: Option<String>,
+}
+
+#[derive(Clone)]
+struct StoredTag {
+    org_id: i64,
+    name: String,
+}
+
+struct StoredField {
+    org_id: i64,
+    field: CustomFieldDto,
+}
+
+struct StoredFile {
+    attachment: AttachmentDto,
+    storage_key: String,
+}
+
+struct StoredShare {
+    org_id: i64,
+    grantee_id: i64,
+    contact_id: Option<i64>,
+    permission: SharePermission,
+    created_at: NaiveDateTime,
+}
+
+impl MemoryStore {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().expect("The memory store is poisoned")
+    }
+}
+
+impl Inner {
+    /// One of the organization's contacts outside the trash.
+    fn live_contact(&self, org_id: i64, id: i64) -> Option<&StoredContact> {
+        self.contacts
+            .get(&id)
+            .filter(|stored| stored.org_id == org_id && stored.deleted_at.is_none())
+    }
+
+    fn live_contacts(&self, org_id: i64) -> impl Iterator<Item = &StoredContact> {
+        self.contacts
+            .values()
+            .filter(move |stored| stored.org_id == org_id && stored.deleted_at.is_none())
+    }
+
+    /// Fails if another contact of the organization outside the trash already has the email.
+    fn check_unique_email(
+        &self,
+        org_id: i64,
+        id: Option<i64>,
+        email: &str,
+    ) -> Result<(), RepositoryError> {
+        let taken = self
+            .live_contacts(org_id)
+            .any(|stored| stored.contact.id != id && stored.contact.email == email);
+        if taken {
+            return Err(RepositoryError::Duplicate("email".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Creates any of the organization's tags named on the contact that don't exist yet.
+    fn add_tags(&mut self, org_id: i64, contact: &ContactDto) {
+        for name in &contact.tags {
+            let exists = self
+                .tags
+                .values()
+                .any(|tag| tag.org_id == org_id && tag.name == *name);
+            if !exists {
+                self.next_tag_id += 1;
+                let tag = StoredTag {
+                    org_id,
+                    name: name.clone(),
+                };
+                self.tags.insert(self.next_tag_id, tag);
+            }
+        }
+    }
+
+    fn create_contact(
+        &mut self,
+        org_id: i64,
+        contact: &ContactDto,
+        source: SubscriptionSource,
+    ) -> Result<ContactDto, RepositoryError> {
+        self.check_unique_email(org_id, None, &contact.email)?;
+        self.next_contact_id += 1;
+        let id = self.next_contact_id;
+        let contact = ContactDto {
+            subscribed: contact.subscribed && source != SubscriptionSource::Manual,
+            ..stored_contact(id, contact)
+        };
+        self.add_tags(org_id, &contact);
+        self.contacts.insert(
+            id,
+            StoredContact {
+                org_id,
+                contact: contact.clone(),
+                updated_at: Utc::now().naive_utc(),
+                deleted_at: None,
+                avatar_key: None,
+            },
+        );
+        Ok(contact)
+    }
+
+    fn update_contact(
+        &mut self,
+        org_id: i64,
+        id: i64,
+        contact: &ContactDto,
+    ) -> Result<Option<ContactDto>, RepositoryError> {
+        let Some(stored) = self.live_contact(org_id, id) else {
+            return Ok(None);
+        };
+        let was_subscribed = stored.contact.subscribed;
+        let avatar_key = stored.avatar_key.clone();
+        self.check_unique_email(org_id, Some(id), &contact.email)?;
+        let contact = ContactDto {
+            subscribed: contact.subscribed && was_subscribed,
+            avatar_url: avatar_key.as_ref().map(|_| avatar_url(id)),
+            ..stored_contact(id, contact)
+        };
+        self.add_tags(org_id, &contact);
+        self.contacts.insert(
+            id,
+            StoredContact {
+                org_id,
+                contact: contact.clone(),
+                updated_at: Utc::now().naive_utc(),
+                deleted_at: None,
+                avatar_key,
+            },
+        );
+        Ok(Some(contact))
+    }
+
+    fn trash_contact(&mut self, org_id: i64, id: i64) -> bool {
+        if self.live_contact(org_id, id).is_none() {
+            return false;
+        }
+        let now = Utc::now().naive_utc();
+        let stored = self.contacts.get_mut(&id).expect("The contact was found");
+        stored.deleted_at = Some(now);
+        stored.updated_at = now;
+        true
+    }
+
+    fn apply(&mut self, org_id: i64, write: &ContactWrite<'_>) -> WriteOutcome {
+        match *write {
+            ContactWrite::Create { contact, source } => {
+                match self.create_contact(org_id, contact, source) {
+                    Ok(stored) => WriteOutcome::Created(stored),
+                    Err(e) => WriteOutcome::Failed(e),
+                }
+            }
+            ContactWrite::Update { id, contact } => {
+                match self.update_contact(org_id, id, contact) {
+                    Ok(Some(stored)) => WriteOutcome::Updated(stored),
+                    Ok(None) => WriteOutcome::NotFound,
+                    Err(e) => WriteOutcome::Failed(e),
+                }
+            }
+            ContactWrite::Delete { id } => match self.trash_contact(org_id, id) {
+                true => WriteOutcome::Deleted,
+                false => WriteOutcome::NotFound,
+            },
+        }
+    }
+
+    /// The broadest permission the user was granted on a contact of the organization.
+    fn shared_permission(&self, user_id: i64, org_id: i64, id: i64) -> Option<SharePermission> {
+        self.shares
+            .values()
+            .filter(|share| {
+                share.grantee_id == user_id
+                    && share.org_id == org_id
+                    && share.contact_id.is_none_or(|contact_id| contact_id == id)
+            })
+            .map(|share| share.permission)
+            .max_by_key(|permission| *permission == SharePermission::Write)
+    }
+
+    /// The organization of a contact outside the trash and the access the user has to it.
+    fn access(&self, id: i64, user: &AuthUser) -> Option<(i64, ContactAccess)> {
+        let stored = self
+            .contacts
+            .get(&id)
+            .filter(|stored| stored.deleted_at.is_none())?;
+        if stored.org_id == user.org_id {
+            return Some((stored.org_id, ContactAccess::Member(user.role)));
+        }
+        self.shared_permission(user.id, stored.org_id, id)
+            .map(|permission| (stored.org_id, ContactAccess::Shared(permission)))
+    }
+
+    /// Whether the user may change the contact.
+    fn can_write(&self, id: i64, user: &AuthUser) -> bool {
+        self.access(id, user)
+            .is_some_and(|(_, access)| access.can_write())
+    }
+
+    /// Removes the contacts and their attachments, and returns the storage keys of their
+    /// files.
+    fn remove_contacts(&mut self, ids: &[i64]) -> Vec<String> {
+        let mut keys = Vec::new();
+        for id in ids {
+            keys.extend(
+                self.contacts
+                    .remove(id)
+                    .and_then(|stored| stored.avatar_key),
+            );
+        }
+        self.attachments.retain(|_, file| {
+            let purged = ids.contains(&file.attachment.contact_id);
+            if purged {
+                keys.push(file.storage_key.clone());
+            }
+            !purged
+        });
+        self.shares
+            .retain(|_, share| share.contact_id.is_none_or(|id| !ids.contains(&id)));
+        keys
+    }
+
+    /// Fails if another field of the organization already has the name.
+    fn check_unique_field_name(
+        &self,
+        org_id: i64,
+        id: Option<i64>,
+        name: &str,
+    ) -> Result<(), RepositoryError> {
+        let taken = self.custom_fields.iter().any(|(field_id, stored)| {
+            Some(*field_id) != id && stored.org_id == org_id && stored.field.name == name
+        });
+        if taken {
+            return Err(RepositoryError::Duplicate("name".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Applies `change` to the custom field values of every contact of the organization,
+    /// trashed ones included.
+    fn rewrite_custom_field_values(
+        &mut self,
+        org_id: i64,
+        change: impl Fn(&mut BTreeMap<String, Value>),
+    ) {
+        for stored in self.contacts.values_mut() {
+            if stored.org_id == org_id {
+                change(&mut stored.contact.custom_fields);
+            }
+        }
+    }
+
+    fn user_email(&self, user_id: i64) -> String {
+        self.users
+            .iter()
+            .find(|user| user.id == user_id)
+            .map(|user| user.email.clone())
+            .unwrap_or_default()
+    }
+
+    fn create_org(&mut self, name: &str, owner_id: i64) -> i64 {
+        self.next_org_id += 1;
+        let org_id = self.next_org_id;
+        self.orgs.insert(org_id, name.to_string());
+        self.memberships.push(Membership {
+            user_id: owner_id,
+            org_id,
+            role: OrgRole::Owner,
+            created_at: Utc::now().naive_utc(),
+        });
+        org_id
+    }
+
+    fn membership(&self, org_id: i64, user_id: i64) -> Option<&Membership> {
+        self.memberships
+            .iter()
+            .find(|m| m.org_id == org_id && m.user_id == user_id)
+    }
+
+    fn membership_dto(&self, membership: &Membership) -> MembershipDto {
+        MembershipDto {
+            user_id: membership.user_id,
+            email: self.user_email(membership.user_id),
+            role: membership.role,
+            created_at: membership.created_at,
+        }
+    }
+
+    /// Fails unless the organization keeps an owner once `user_id` is no longer one.
+    fn ensure_other_owner(&self, org_id: i64, user_id: i64) -> Result<(), RepositoryError> {
+        let other_owner = self
+            .memberships
+            .iter()
+            .any(|m| m.org_id == org_id && m.role == OrgRole::Owner && m.user_id != user_id);
+        if !other_owner {
+            return Err(RepositoryError::LastOwner);
+        }
+        Ok(())
+    }
+
+    fn share_dto(&self, id: i64, share: &StoredShare) -> ShareDto {
+        ShareDto {
+            id,
+            org_name: self.orgs.get(&share.org_id).cloned().unwrap_or_default(),
+            grantee_email: self.user_email(share.grantee_id),
+            contact_id: share.contact_id,
+            permission: share.permission,
+            created_at: share.created_at,
+        }
+    }
+}
+
+fn avatar_url(id: i64) -> String {
+    format!("/api/v1/contacts/{id}/avatar")
+}
+
+/// The contact as it would be stored: tags trimmed and sorted, `null` custom field values
+/// dropped.
+fn stored_contact(id: i64, contact: &ContactDto) -> ContactDto {
+    let mut tags: Vec<String> = contact
+        .tags
+        .iter()
+        .map(|tag| tag.trim().to_string())
+        .collect();
+    tags.sort();
+    tags.dedup();
+    ContactDto {
+        id: Some(id),
+        tags,
+        custom_fields: contact
+            .custom_fields
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect(),
+        avatar_url: None,
+        ..contact.clone()
+    }
+}
+
+fn matches_filter(contact: &ContactDto, filter: &ContactFilter) -> bool {
+    if let Some(q) = filter.q.as_deref().filter(|q| !q.is_empty()) {
+        let q = q.to_lowercase();
+        if !contact.name.to_lowercase().contains(&q) && !contact.email.to_lowercase().contains(&q) {
+            return false;
+        }
+    }
+    if filter
+        .contact_type
+        .is_some_and(|contact_type| contact.contact_type != contact_type)
+    {
+        return false;
+    }
+    if filter
+        .subscribed
+        .is_some_and(|subscribed| contact.subscribed != subscribed)
+    {
+        return false;
+    }
+    if filter
+        .tag
+        .as_ref()
+        .is_some_and(|tag| !contact.tags.contains(tag))
+    {
+        return false;
+    }
+    if let Some(custom_field) = filter.custom_field.as_deref().filter(|f| !f.is_empty()) {
+        let (name, value) = match custom_field.split_once(':') {
+            Some((name, value)) => (name, Some(value)),
+            None => (custom_field, None),
+        };
+        let Some(stored) = contact.custom_fields.get(name) else {
+            return false;
+        };
+        if let Some(value) = value {
+            let stored = match stored {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            if stored != value {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+#[async_trait]
+impl UserRepository for MemoryStore {
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, RepositoryError> {
+        Ok(self.lock().users.iter().find(|u| u.email == email).cloned())
+    }
+
+    async fn find_by_id(&self, id: i64) -> Result<Option<User>, RepositoryError> {
+        Ok(self.lock().users.iter().find(|u| u.id == id).cloned())
+    }
+
+    async fn create(&self, email: &str, password_hash: &str) -> Result<i64, RepositoryError> {
+        let mut inner = self.lock();
+        if inner.users.iter().any(|u| u.email == email) {
+            return Err(RepositoryError::Duplicate("email".to_string()));
+        }
+        inner.next_user_id += 1;
+        let id = inner.next_user_id;
+        inner.users.push(User {
+            id,
+            email: email.to_string(),
+            password_hash: password_hash.to_string(),
+        });
+        inner.create_org(email, id);
+        Ok(id)
+    }
+
+    async fn role_in(&self, user_id: i64, org_id: i64) -> Result<Option<OrgRole>, RepositoryError> {
+        Ok(self.lock().membership(org_id, user_id).map(|m| m.role))
+    }
+
+    async fn default_org(&self, user_id: i64) -> Result<Option<i64>, RepositoryError> {
+        Ok(self
+            .lock()
+            .memberships
+            .iter()
+            .find(|m| m.user_id == user_id)
+            .map(|m| m.org_id))
+    }
+
+    async fn is_admin(&self, user_id: i64) -> Result<bool, RepositoryError> {
+        Ok(self.lock().admins.contains(&user_id))
+    }
+
+    async fn summaries(&self) -> Result<Vec<UserSummary>, RepositoryError> {
+        let inner = self.lock();
+        Ok(inner
+            .users
+            .iter()
+            .map(|user| UserSummary {
+                id: user.id,
+                email: user.email.clone(),
+                is_admin: inner.admins.contains(&user.id),
+                orgs: inner
+                    .memberships
+                    .iter()
+                    .filter(|m| m.user_id == user.id)
+                    .count() as i64,
+            })
+            .collect())
+    }
+
+    async fn set_admin(&self, email: &str, is_admin: bool) -> Result<bool, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(id) = inner.users.iter().find(|u| u.email == email).map(|u| u.id) else {
+            return Ok(false);
+        };
+        if is_admin {
+            inner.admins.insert(id);
+        } else {
+            inner.admins.remove(&id);
+        }
+        Ok(true)
+    }
+
+    async fn reset_password(
+        &self,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<bool, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(user) = inner.users.iter_mut().find(|u| u.email == email) else {
+            return Ok(false);
+        };
+        user.password_hash = password_hash.to_string();
+        let id = user.id;
+        inner.tokens.retain(|_, token| token.user_id != id);
+        Ok(true)
+    }
+
+    async fn delete(&self, email: &str) -> Result<UserDeletion, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(user_id) = inner.users.iter().find(|u| u.email == email).map(|u| u.id) else {
+            return Ok(UserDeletion::UnknownUser);
+        };
+        let org_ids: Vec<i64> = inner
+            .memberships
+            .iter()
+            .filter(|m| m.user_id == user_id)
+            .map(|m| m.org_id)
+            .collect();
+        let has_others = |org_id: i64| {
+            inner
+                .memberships
+                .iter()
+                .any(|m| m.org_id == org_id && m.user_id != user_id)
+        };
+        let orphaned = org_ids.iter().find(|&&org_id| {
+            inner.membership(org_id, user_id).unwrap().role == OrgRole::Owner
+                && has_others(org_id)
+                && inner.ensure_other_owner(org_id, user_id).is_err()
+        });
+        if let Some(org_id) = orphaned {
+            let org = inner.orgs.get(org_id).cloned().unwrap_or_default();
+            return Ok(UserDeletion::LastOwner(org));
+        }
+
+        let deleted: Vec<i64> = org_ids
+            .into_iter()
+            .filter(|&org_id| !has_others(org_id))
+            .collect();
+        let contacts: Vec<i64> = inner
+            .contacts
+            .iter()
+            .filter(|(_, stored)| deleted.contains(&stored.org_id))
+            .map(|(id, _)| *id)
+            .collect();
+        let storage_keys = inner.remove_contacts(&contacts);
+        for org_id in &deleted {
+            inner.orgs.remove(org_id);
+        }
+        inner.tags.retain(|_, tag| !deleted.contains(&tag.org_id));
+        inner
+            .custom_fields
+            .retain(|_, stored| !deleted.contains(&stored.org_id));
+        inner
+            .shares
+            .retain(|_, share| !deleted.contains(&share.org_id) && share.grantee_id != user_id);
+        inner
+            .memberships
+            .retain(|m| !deleted.contains(&m.org_id) && m.user_id != user_id);
+        inner.tokens.retain(|_, token| token.user_id != user_id);
+        inner.admins.remove(&user_id);
+        inner.users.retain(|u| u.id != user_id);
+        Ok(UserDeletion::Deleted {
+            orgs: deleted.len() as u64,
+            storage_keys,
+        })
+    }
+}
+
+#[async_trait]
+impl TokenRepository for MemoryStore {
+    async fn store(
+        &self,
+        user_id: i64,
+        org_id: i64,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+        replaced: Option<&str>,
+    ) -> Result<(), RepositoryError> {
+        let mut inner = self.lock();
+        if let Some(replaced) = replaced {
+            inner.tokens.remove(replaced);
+        }
+        inner.tokens.retain(|_, token| token.user_id != user_id);
+        inner.tokens.insert(
+            token_hash.to_string(),
+            RefreshToken {
+                user_id,
+                org_id: Some(org_id),
+                expires_at,
+            },
+        );
+        Ok(())
+    }
+
+    async fn find(&self, token_hash: &str) -> Result<Option<RefreshToken>, RepositoryError> {
+        Ok(self.lock().tokens.get(token_hash).cloned())
+    }
+
+    async fn delete(&self, token_hash: &str) -> Result<(), RepositoryError> {
+        self.lock().tokens.remove(token_hash);
+        Ok(())
+    }
+
+    async fn delete_for_user(&self, user_id: i64) -> Result<(), RepositoryError> {
+        self.lock()
+            .tokens
+            .retain(|_, token| token.user_id != user_id);
+        Ok(())
+    }
+
+    async fn purge(&self, all: bool) -> Result<u64, RepositoryError> {
+        let mut inner = self.lock();
+        let before = inner.tokens.len();
+        let now = Utc::now().naive_utc();
+        inner
+            .tokens
+            .retain(|_, token| !all && token.expires_at >= now);
+        Ok((before - inner.tokens.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl ContactRepository for MemoryStore {
+    async fn list(
+        &self,
+        org_id: i64,
+        filter: &ContactFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ContactDto>, RepositoryError> {
+        Ok(self
+            .lock()
+            .live_contacts(org_id)
+            .filter(|stored| matches_filter(&stored.contact, filter))
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .map(|stored| stored.contact.clone())
+            .collect())
+    }
+
+    async fn find(&self, org_id: i64, id: i64) -> Result<Option<ContactDto>, RepositoryError> {
+        Ok(self
+            .lock()
+            .live_contact(org_id, id)
+            .map(|stored| stored.contact.clone()))
+    }
+
+    async fn create(
+        &self,
+        _actor: &Actor,
+        org_id: i64,
+        contact: &ContactDto,
+    ) -> Result<ContactDto, RepositoryError> {
+        self.lock()
+            .create_contact(org_id, contact, SubscriptionSource::Manual)
+    }
+
+    async fn update(
+        &self,
+        _actor: &Actor,
+        org_id: i64,
+        id: i64,
+        contact: &ContactDto,
+    ) -> Result<Option<ContactDto>, RepositoryError> {
+        self.lock().update_contact(org_id, id, contact)
+    }
+
+    async fn delete(&self, _actor: &Actor, org_id: i64, id: i64) -> Result<bool, RepositoryError> {
+        Ok(self.lock().trash_contact(org_id, id))
+    }
+
+    async fn write_batch(
+        &self,
+        _actor: &Actor,
+        org_id: i64,
+        writes: &[ContactWrite<'_>],
+        all_or_nothing: bool,
+    ) -> Result<Vec<WriteOutcome>, RepositoryError> {
+        let mut inner = self.lock();
+        let snapshot = all_or_nothing.then(|| (inner.contacts.clone(), inner.tags.clone()));
+        let mut outcomes = Vec::with_capacity(writes.len());
+        for write in writes {
+            let outcome = inner.apply(org_id, write);
+            let succeeded = outcome.succeeded();
+            outcomes.push(outcome);
+            if !succeeded {
+                if let Some((contacts, tags)) = snapshot {
+                    inner.contacts = contacts;
+                    inner.tags = tags;
+                    break;
+                }
+            }
+        }
+        Ok(outcomes)
+    }
+
+    async fn existing_emails(
+        &self,
+        org_id: i64,
+        emails: &[String],
+    ) -> Result<HashSet<String>, RepositoryError> {
+        let wanted: HashSet<String> = emails.iter().map(|email| email.to_lowercase()).collect();
+        Ok(self
+            .lock()
+            .live_contacts(org_id)
+            .map(|stored| stored.contact.email.to_lowercase())
+            .filter(|email| wanted.contains(email))
+            .collect())
+    }
+
+    fn export(
+        &self,
+        org_id: i64,
+        filter: ContactFilter,
+    ) -> BoxStream<'static, Result<ContactDto, RepositoryError>> {
+        let contacts: Vec<_> = self
+            .lock()
+            .live_contacts(org_id)
+            .filter(|stored| matches_filter(&stored.contact, &filter))
+            .map(|stored| Ok(stored.contact.clone()))
+            .collect();
+        stream::iter(contacts).boxed()
+    }
+
+    async fn identities(
+        &self,
+        org_id: i64,
+    ) -> Result<Vec<(DuplicateContactDto, Vec<String>)>, RepositoryError> {
+        Ok(self
+            .lock()
+            .live_contacts(org_id)
+            .map(|stored| {
+                let contact = &stored.contact;
+                let mut emails = vec![contact.email.clone()];
+                emails.extend(contact.emails.iter().map(|extra| extra.email.clone()));
+                let identity = DuplicateContactDto {
+                    id: contact.id.unwrap_or_default(),
+                    name: contact.name.clone(),
+                    email: contact.email.clone(),
+                };
+                (identity, emails)
+            })
+            .collect())
+    }
+
+    async fn updated_at(
+        &self,
+        org_id: i64,
+        id: i64,
+    ) -> Result<Option<NaiveDateTime>, RepositoryError> {
+        Ok(self
+            .lock()
+            .live_contact(org_id, id)
+            .map(|stored| stored.updated_at))
+    }
+
+    async fn merge(
+        &self,
+        _actor: &Actor,
+        org_id: i64,
+        primary_id: i64,
+        duplicate_id: i64,
+        merged: &ContactDto,
+    ) -> Result<Option<ContactDto>, RepositoryError> {
+        let mut inner = self.lock();
+        if inner.live_contact(org_id, primary_id).is_none() {
+            return Ok(None);
+        }
+        let snapshot = (inner.contacts.clone(), inner.tags.clone());
+        if !inner.trash_contact(org_id, duplicate_id) {
+            return Ok(None);
+        }
+        match inner.update_contact(org_id, primary_id, merged) {
+            Ok(stored) => Ok(stored),
+            Err(e) => {
+                (inner.contacts, inner.tags) = snapshot;
+                Err(e)
+            }
+        }
+    }
+
+    async fn access(
+        &self,
+        id: i64,
+        user: &AuthUser,
+    ) -> Result<Option<(i64, ContactAccess)>, RepositoryError> {
+        Ok(self.lock().access(id, user))
+    }
+
+    async fn list_shared(
+        &self,
+        user_id: i64,
+        filter: &ContactFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SharedContactDto>, RepositoryError> {
+        let inner = self.lock();
+        Ok(inner
+            .contacts
+            .iter()
+            .filter(|(_, stored)| {
+                stored.deleted_at.is_none() && matches_filter(&stored.contact, filter)
+            })
+            .filter_map(|(id, stored)| {
+                let permission = inner.shared_permission(user_id, stored.org_id, *id)?;
+                Some(SharedContactDto {
+                    contact: stored.contact.clone(),
+                    org_name: inner.orgs.get(&stored.org_id).cloned().unwrap_or_default(),
+                    permission,
+                })
+            })
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn list_trash(
+        &self,
+        org_id: i64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TrashedContactDto>, RepositoryError> {
+        let inner = self.lock();
+        let mut trash: Vec<TrashedContactDto> = inner
+            .contacts
+            .values()
+            .filter(|stored| stored.org_id == org_id)
+            .filter_map(|stored| {
+                Some(TrashedContactDto {
+                    contact: stored.contact.clone(),
+                    deleted_at: stored.deleted_at?,
+                })
+            })
+            .collect();
+        trash.sort_by_key(|trashed| std::cmp::Reverse((trashed.deleted_at, trashed.contact.id)));
+        Ok(trash
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn restore(
+        &self,
+        _actor: &Actor,
+        org_id: i64,
+        id: i64,
+    ) -> Result<Option<ContactDto>, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(stored) = inner
+            .contacts
+            .get(&id)
+            .filter(|stored| stored.org_id == org_id && stored.deleted_at.is_some())
+        else {
+            return Ok(None);
+        };
+        let email = stored.contact.email.clone();
+        inner.check_unique_email(org_id, Some(id), &email)?;
+        let stored = inner.contacts.get_mut(&id).expect("The contact was found");
+        stored.deleted_at = None;
+        stored.updated_at = Utc::now().naive_utc();
+        Ok(Some(stored.contact.clone()))
+    }
+
+    async fn purge_trash(
+        &self,
+        deleted_before: NaiveDateTime,
+    ) -> Result<(u64, Vec<String>), RepositoryError> {
+        let mut inner = self.lock();
+        let purged: Vec<i64> = inner
+            .contacts
+            .iter()
+            .filter(|(_, stored)| stored.deleted_at.is_some_and(|at| at < deleted_before))
+            .map(|(id, _)| *id)
+            .collect();
+        let keys = inner.remove_contacts(&purged);
+        Ok((purged.len() as u64, keys))
+    }
+
+    async fn count(&self, org_id: i64) -> Result<i64, RepositoryError> {
+        let inner = self.lock();
+        Ok(inner
+            .contacts
+            .values()
+            .filter(|stored| stored.org_id == org_id)
+            .count() as i64)
+    }
+
+    async fn enabled_contact_types(&self) -> Result<HashSet<ContactType>, RepositoryError> {
+        Ok(ContactType::ALL.into_iter().collect())
+    }
+
+    async fn contact_types(&self) -> Result<Vec<ContactTypeDto>, RepositoryError> {
+        Ok(ContactType::ALL
+            .into_iter()
+            .map(|name| ContactTypeDto {
+                name,
+                label: name.to_string(),
+            })
+            .collect())
+    }
+
+    async fn custom_fields(&self, org_id: i64) -> Result<Vec<CustomFieldDto>, RepositoryError> {
+        CustomFieldRepository::list(self, org_id).await
+    }
+}
+
+#[async_trait]
+impl TagRepository for MemoryStore {
+    async fn list(&self, org_id: i64) -> Result<Vec<TagDto>, RepositoryError> {
+        let mut tags: Vec<TagDto> = self
+            .lock()
+            .tags
+            .iter()
+            .filter(|(_, tag)| tag.org_id == org_id)
+            .map(|(id, tag)| TagDto {
+                id: Some(*id),
+                name: tag.name.clone(),
+            })
+            .collect();
+        tags.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tags)
+    }
+
+    async fn create(&self, org_id: i64, name: &str) -> Result<TagDto, RepositoryError> {
+        let mut inner = self.lock();
+        let taken = inner
+            .tags
+            .values()
+            .any(|tag| tag.org_id == org_id && tag.name == name);
+        if taken {
+            return Err(RepositoryError::Duplicate("name".to_string()));
+        }
+        inner.next_tag_id += 1;
+        let id = inner.next_tag_id;
+        let tag = StoredTag {
+            org_id,
+            name: name.to_string(),
+        };
+        inner.tags.insert(id, tag);
+        Ok(TagDto {
+            id: Some(id),
+            name: name.to_string(),
+        })
+    }
+
+    async fn rename(
+        &self,
+        org_id: i64,
+        id: i64,
+        name: &str,
+    ) -> Result<Option<TagDto>, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(old_name) = inner
+            .tags
+            .get(&id)
+            .filter(|tag| tag.org_id == org_id)
+            .map(|tag| tag.name.clone())
+        else {
+            return Ok(None);
+        };
+        let taken = inner
+            .tags
+            .iter()
+            .any(|(other_id, tag)| *other_id != id && tag.org_id == org_id && tag.name == name);
+        if taken {
+            return Err(RepositoryError::Duplicate("name".to_string()));
+        }
+
+        inner.tags.get_mut(&id).expect("The tag was found").name = name.to_string();
+        for stored in inner.contacts.values_mut() {
+            if stored.org_id == org_id && stored.contact.tags.contains(&old_name) {
+                let tags = &mut stored.contact.tags;
+                tags.retain(|tag| *tag != old_name);
+                tags.push(name.to_string());
+                tags.sort();
+            }
+        }
+        Ok(Some(TagDto {
+            id: Some(id),
+            name: name.to_string(),
+        }))
+    }
+
+    async fn delete(&self, org_id: i64, id: i64) -> Result<bool, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(tag) = inner.tags.get(&id).filter(|tag| tag.org_id == org_id) else {
+            return Ok(false);
+        };
+        let name = tag.name.clone();
+        inner.tags.remove(&id);
+        for stored in inner.contacts.values_mut() {
+            if stored.org_id == org_id {
+                stored.contact.tags.retain(|tag| *tag != name);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl ShareRepository for MemoryStore {
+    async fn list(&self, org_id: i64) -> Result<Vec<ShareDto>, RepositoryError> {
+        let inner = self.lock();
+        Ok(inner
+            .shares
+            .iter()
+            .filter(|(_, share)| share.org_id == org_id)
+            .map(|(id, share)| inner.share_dto(*id, share))
+            .collect())
+    }
+
+    async fn received(&self, user_id: i64) -> Result<Vec<ShareDto>, RepositoryError> {
+        let inner = self.lock();
+        Ok(inner
+            .shares
+            .iter()
+            .filter(|(_, share)| share.grantee_id == user_id)
+            .map(|(id, share)| inner.share_dto(*id, share))
+            .collect())
+    }
+
+    async fn create(
+        &self,
+        org_id: i64,
+        grantee_id: i64,
+        contact_id: Option<i64>,
+        permission: SharePermission,
+    ) -> Result<ShareDto, RepositoryError> {
+        let mut inner = self.lock();
+        let shared = inner.shares.values().any(|share| {
+            share.org_id == org_id
+                && share.grantee_id == grantee_id
+                && share.contact_id == contact_id
+        });
+        if shared {
+            return Err(RepositoryError::Duplicate("share".to_string()));
+        }
+        inner.next_share_id += 1;
+        let id = inner.next_share_id;
+        let share = StoredShare {
+            org_id,
+            grantee_id,
+            contact_id,
+            permission,
+            created_at: Utc::now().naive_utc(),
+        };
+        let dto = inner.share_dto(id, &share);
+        inner.shares.insert(id, share);
+        Ok(dto)
+    }
+
+    async fn update(
+        &self,
+        org_id: i64,
+        id: i64,
+        permission: SharePermission,
+    ) -> Result<Option<ShareDto>, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(share) = inner
+            .shares
+            .get_mut(&id)
+            .filter(|share| share.org_id == org_id)
+        else {
+            return Ok(None);
+        };
+        share.permission = permission;
+        let share = &inner.shares[&id];
+        Ok(Some(inner.share_dto(id, share)))
+    }
+
+    async fn delete(
+        &self,
+        id: i64,
+        org_id: Option<i64>,
+        grantee_id: i64,
+    ) -> Result<bool, RepositoryError> {
+        let mut inner = self.lock();
+        let revocable = inner
+            .shares
+            .get(&id)
+            .is_some_and(|share| Some(share.org_id) == org_id || share.grantee_id == grantee_id);
+        if revocable {
+            inner.shares.remove(&id);
+        }
+        Ok(revocable)
+    }
+}
+
+#[async_trait]
+impl OrgRepository for MemoryStore {
+    async fn list_for_user(&self, user_id: i64) -> Result<Vec<OrgDto>, RepositoryError> {
+        let inner = self.lock();
+        Ok(inner
+            .memberships
+            .iter()
+            .filter(|m| m.user_id == user_id)
+            .map(|m| OrgDto {
+                id: m.org_id,
+                name: inner.orgs.get(&m.org_id).cloned().unwrap_or_default(),
+                role: m.role,
+            })
+            .collect())
+    }
+
+    async fn create(&self, name: &str, owner_id: i64) -> Result<i64, RepositoryError> {
+        Ok(self.lock().create_org(name, owner_id))
+    }
+
+    async fn members(&self, org_id: i64) -> Result<Vec<MembershipDto>, RepositoryError> {
+        let inner = self.lock();
+        Ok(inner
+            .memberships
+            .iter()
+            .filter(|m| m.org_id == org_id)
+            .map(|m| inner.membership_dto(m))
+            .collect())
+    }
+
+    async fn add_member(
+        &self,
+        org_id: i64,
+        user_id: i64,
+        role: OrgRole,
+    ) -> Result<MembershipDto, RepositoryError> {
+        let mut inner = self.lock();
+        if inner.membership(org_id, user_id).is_some() {
+            return Err(RepositoryError::Duplicate("membership".to_string()));
+        }
+        let membership = Membership {
+            user_id,
+            org_id,
+            role,
+            created_at: Utc::now().naive_utc(),
+        };
+        let dto = inner.membership_dto(&membership);
+        inner.memberships.push(membership);
+        Ok(dto)
+    }
+
+    async fn set_role(
+        &self,
+        org_id: i64,
+        user_id: i64,
+        role: OrgRole,
+    ) -> Result<Option<MembershipDto>, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(current) = inner.membership(org_id, user_id).map(|m| m.role) else {
+            return Ok(None);
+        };
+        if current == OrgRole::Owner && role != OrgRole::Owner {
+            inner.ensure_other_owner(org_id, user_id)?;
+        }
+        let membership = inner
+            .memberships
+            .iter_mut()
+            .find(|m| m.org_id == org_id && m.user_id == user_id)
+            .expect("The membership was found");
+        membership.role = role;
+        let membership = inner.membership(org_id, user_id).expect("Still a member");
+        Ok(Some(inner.membership_dto(membership)))
+    }
+
+    async fn remove_member(&self, org_id: i64, user_id: i64) -> Result<bool, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(current) = inner.membership(org_id, user_id).map(|m| m.role) else {
+            return Ok(false);
+        };
+        if current == OrgRole::Owner {
+            inner.ensure_other_owner(org_id, user_id)?;
+        }
+        inner
+            .memberships
+            .retain(|m| m.org_id != org_id || m.user_id != user_id);
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl CustomFieldRepository for MemoryStore {
+    async fn list(&self, org_id: i64) -> Result<Vec<CustomFieldDto>, RepositoryError> {
+        Ok(self
+            .lock()
+            .custom_fields
+            .values()
+            .filter(|stored| stored.org_id == org_id)
+            .map(|stored| stored.field.clone())
+            .collect())
+    }
+
+    async fn create(&self, org_id: i64, field: &CustomFieldDto) -> Result<i64, RepositoryError> {
+        let mut inner = self.lock();
+        inner.check_unique_field_name(org_id, None, &field.name)?;
+        inner.next_custom_field_id += 1;
+        let id = inner.next_custom_field_id;
+        let field = CustomFieldDto {
+            id: Some(id),
+            ..field.clone()
+        };
+        inner
+            .custom_fields
+            .insert(id, StoredField { org_id, field });
+        Ok(id)
+    }
+
+    async fn update(
+        &self,
+        org_id: i64,
+        id: i64,
+        field: &CustomFieldDto,
+    ) -> Result<Option<CustomFieldType>, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(existing) = inner
+            .custom_fields
+            .get(&id)
+            .filter(|stored| stored.org_id == org_id)
+            .map(|stored| stored.field.clone())
+        else {
+            return Ok(None);
+        };
+        if existing.field_type != field.field_type {
+            return Ok(Some(existing.field_type));
+        }
+        inner.check_unique_field_name(org_id, Some(id), &field.name)?;
+        let stored = inner
+            .custom_fields
+            .get_mut(&id)
+            .expect("The field was found");
+        stored.field = CustomFieldDto {
+            id: Some(id),
+            ..field.clone()
+        };
+        if existing.name != field.name {
+            inner.rewrite_custom_field_values(org_id, |values| {
+                if let Some(value) = values.remove(&existing.name) {
+                    values.insert(field.name.clone(), value);
+                }
+            });
+        }
+        Ok(Some(existing.field_type))
+    }
+
+    async fn delete(&self, org_id: i64, id: i64) -> Result<bool, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(name) = inner
+            .custom_fields
+            .get(&id)
+            .filter(|stored| stored.org_id == org_id)
+            .map(|stored| stored.field.name.clone())
+        else {
+            return Ok(false);
+        };
+        inner.custom_fields.remove(&id);
+        inner.rewrite_custom_field_values(org_id, |values| {
+            values.remove(&name);
+        });
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for MemoryStore {
+    async fn in_org(&self, org_id: i64, contact_id: i64) -> Result<bool, RepositoryError> {
+        Ok(self
+            .lock()
+            .contacts
+            .get(&contact_id)
+            .is_some_and(|stored| stored.org_id == org_id))
+    }
+
+    async fn list(&self, _contact_id: i64) -> Result<Vec<ContactEventDto>, RepositoryError> {
+        Ok(Vec::new())
+    }
+}
+
+#[async_trait]
+impl SubscriptionRepository for MemoryStore {
+    async fn record_request(
+        &self,
+        _actor: &Actor,
+        _contact_id: i64,
+        _email: &str,
+    ) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+
+    async fn subscription(
+        &self,
+        contact_id: i64,
+    ) -> Result<(Consent, Vec<SubscriptionEventDto>), RepositoryError> {
+        let inner = self.lock();
+        let stored = inner
+            .contacts
+            .get(&contact_id)
+            .ok_or(RepositoryError::Database(sqlx::Error::RowNotFound))?;
+        let consent = Consent {
+            subscribed: stored.contact.subscribed,
+            subscribed_at: None,
+            source: None,
+        };
+        Ok((consent, Vec::new()))
+    }
+
+    async fn recipient(&self, contact_id: i64) -> Result<Recipient, RepositoryError> {
+        let inner = self.lock();
+        let stored = inner
+            .contacts
+            .get(&contact_id)
+            .ok_or(RepositoryError::Database(sqlx::Error::RowNotFound))?;
+        Ok(Recipient {
+            name: stored.contact.name.clone(),
+            email: stored.contact.email.clone(),
+            subscribed: stored.contact.subscribed,
+        })
+    }
+
+    async fn confirm(
+        &self,
+        contact_id: i64,
+        _request_id: Option<&str>,
+        link_is_valid: &(dyn for<'a> Fn(&'a str) -> bool + Sync),
+    ) -> Result<bool, RepositoryError> {
+        let mut inner = self.lock();
+        let Some(stored) = inner
+            .contacts
+            .get_mut(&contact_id)
+            .filter(|stored| stored.deleted_at.is_none() && link_is_valid(&stored.contact.email))
+        else {
+            return Ok(false);
+        };
+        stored.contact.subscribed = true;
+        Ok(true)
+    }
+
+    async fn unsubscribe(
+        &self,
+        contact_id: i64,
+        _request_id: Option<&str>,
+    ) -> Result<(), RepositoryError> {
+        if let Some(stored) = self.lock().contacts.get_mut(&contact_id) {
+            stored.contact.subscribed = false;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AttachmentRepository for MemoryStore {
+    async fn list(&self, contact_id: i64) -> Result<Vec<AttachmentDto>, RepositoryError> {
+        Ok(self
+            .lock()
+            .attachments
+            .values()
+            .filter(|file| file.attachment.contact_id == contact_id)
+            .map(|file| file.attachment.clone())
+            .collect())
+    }
+
+    async fn create(
+        &self,
+        contact_id: i64,
+        _user_id: i64,
+        file_name: &str,
+        content_type: &str,
+        size: i64,
+        storage_key: &str,
+    ) -> Result<AttachmentDto, RepositoryError> {
+        let mut inner = self.lock();
+        inner.next_attachment_id += 1;
+        let attachment = AttachmentDto {
+            id: inner.next_attachment_id,
+            contact_id,
+            file_name: file_name.to_string(),
+            content_type: content_type.to_string(),
+            size,
+            created_at: Utc::now().naive_utc(),
+        };
+        inner.attachments.insert(
+            attachment.id,
+            StoredFile {
+                attachment: attachment.clone(),
+                storage_key: storage_key.to_string(),
+            },
+        );
+        Ok(attachment)
+    }
+
+    async fn find(
+        &self,
+        contact_id: i64,
+        id: i64,
+    ) -> Result<Option<StoredAttachment>, RepositoryError> {
+        Ok(self
+            .lock()
+            .attachments
+            .get(&id)
+            .filter(|file| file.attachment.contact_id == contact_id)
+            .map(|file| StoredAttachment {
+                file_name: file.attachment.file_name.clone(),
+                content_type: file.attachment.content_type.clone(),
+                storage_key: file.storage_key.clone(),
+            }))
+    }
+
+    async fn delete(&self, contact_id: i64, id: i64) -> Result<Option<String>, RepositoryError> {
+        let mut inner = self.lock();
+        if inner
+            .attachments
+            .get(&id)
+            .is_none_or(|file| file.attachment.contact_id != contact_id)
+        {
+            return Ok(None);
+        }
+        Ok(inner.attachments.remove(&id).map(|file| file.storage_key))
+    }
+
+    async fn avatar_key(&self, contact_id: i64) -> Result<Option<String>, RepositoryError> {
+        Ok(self
+            .lock()
+            .contacts
+            .get(&contact_id)
+            .and_then(|stored| stored.avatar_key.clone()))
+    }
+
+    async fn set_avatar(
+        &self,
+        user: &AuthUser,
+        contact_id: i64,
+        key: &str,
+    ) -> Result<Option<(ContactDto, Option<String>)>, RepositoryError> {
+        let mut inner = self.lock();
+        if !inner.can_write(contact_id, user) {
+            return Ok(None);
+        }
+        let stored = inner
+            .contacts
+            .get_mut(&contact_id)
+            .expect("The contact was found");
+        let previous = stored.avatar_key.replace(key.to_string());
+        stored.contact.avatar_url = Some(avatar_url(contact_id));
+        stored.updated_at = Utc::now().naive_utc();
+        Ok(Some((stored.contact.clone(), previous)))
+    }
+
+    async fn remove_avatar(
+        &self,
+        user: &AuthUser,
+        contact_id: i64,
+    ) -> Result<Option<String>, RepositoryError> {
+        let mut inner = self.lock();
+        if !inner.can_write(contact_id, user) {
+            return Ok(None);
+        }
+        let stored = inner
+            .contacts
+            .get_mut(&contact_id)
+            .expect("The contact was found");
+        let Some(key) = stored.avatar_key.take() else {
+            return Ok(None);
+        };
+        stored.contact.avatar_url = None;
+        stored.updated_at = Utc::now().naive_utc();
+        Ok(Some(key))
+    }
+}
@@ -0,0 +1,168 @@
+//! The SQL repositories on MySQL and MariaDB, compiled from the same source in `sql/` as
+//! `sqlite` and `postgres`.
+//!
+//! As on Postgres, `query!`, `query_as!` and `query_scalar!` run the SQL as runtime queries
+//! that are not checked at compile time; the test suite covers them when `TEST_DATABASE_URL`
+//! points at a MySQL database. MySQL has no numbered placeholders, so each `$N` becomes a `?`
+//! and the arguments are bound in the order the placeholders appear, once per use.
+//!
+//! Where MySQL lacks what the other backends share, the queries in `sql/` branch on
+//! `BACKEND`: new IDs are read with `LAST_INSERT_ID()` instead of `RETURNING`, and upserts use
+//! `ON DUPLICATE KEY UPDATE` instead of `ON CONFLICT`. SQL that only MySQL runs can't go
+//! through the macros above, which check it against SQLite, and is written with `?`
+//! placeholders for `sqlx::query` directly.
+
+use sqlx::{MySql, MySqlConnection};
+
+use crate::db::{DbBackend, DbPool};
+
+type Db = MySql;
+type DbConnection = MySqlConnection;
+type Pool = sqlx::MySqlPool;
+
+const BACKEND: DbBackend = DbBackend::MySql;
+
+/// The MySQL pool of `db_pool`. The repositories of this module are only made for MySQL
+/// pools, so anything else is a bug.
+fn typed(db_pool: &DbPool) -> &Pool {
+    match db_pool {
+        DbPool::MySql(pool) => pool,
+        _ => unreachable!(
+            "MySQL repositories were given a {} pool",
+            db_pool.backend().as_str()
+        ),
+    }
+}
+
+/// `sql` in MySQL's placeholder style, with the order its arguments are bound in. Computed
+/// once per call site; a placeholder without a number fails the build.
+macro_rules! unchecked {
+    ($sql:literal) => {{
+        const _: () = assert!(
+            $crate::repository::mysql::placeholders_are_numbered($sql),
+            "a `$` placeholder outside quotes has no parameter number from 1 up"
+        );
+        static SQL: ::std::sync::OnceLock<(String, Vec<usize>)> = ::std::sync::OnceLock::new();
+        let (sql, order) = SQL.get_or_init(|| $crate::repository::mysql::positional($sql));
+        (sql.as_str(), order.as_slice())
+    }};
+}
+
+macro_rules! query {
+    ($sql:literal $(, $arg:expr)* $(,)?) => {{
+        let (sql, order) = unchecked!($sql);
+        $crate::repository::mysql::bind_in_order(
+            sqlx::query(sql),
+            order,
+            &[$(&|query| query.bind(&$arg)),*],
+        )
+    }};
+}
+
+macro_rules! query_as {
+    ($out:ty, $sql:literal $(, $arg:expr)* $(,)?) => {{
+        let (sql, order) = unchecked!($sql);
+        $crate::repository::mysql::bind_in_order(
+            sqlx::query_as::<_, $out>(sql),
+            order,
+            &[$(&|query| query.bind(&$arg)),*],
+        )
+    }};
+}
+
+macro_rules! query_scalar {
+    ($sql:literal $(, $arg:expr)* $(,)?) => {{
+        let (sql, order) = unchecked!($sql);
+        $crate::repository::mysql::bind_in_order(
+            sqlx::query_scalar(sql),
+            order,
+            &[$(&|query| query.bind(&$arg)),*],
+        )
+    }};
+}
+
+/// Binds the arguments of a query in `order`, the index into `binds` of each placeholder.
+fn bind_in_order<Q>(mut query: Q, order: &[usize], binds: &[&(dyn Fn(Q) -> Q + Sync)]) -> Q {
+    for &index in order {
+        query = binds[index](query);
+    }
+    query
+}
+
+/// `sql` without the type overrides, as on Postgres, with a `?` for each `$N` placeholder
+/// and backticks around quoted identifiers, along with the index of the argument each `?`
+/// takes.
+fn positional(sql: &str) -> (String, Vec<usize>) {
+    let sql = super::postgres::without_type_overrides(sql);
+    let mut out = String::with_capacity(sql.len());
+    let mut order = Vec::new();
+    let mut quote = None;
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match quote {
+            None if c == '$' => {
+                let mut number = 0;
+                while let Some(digit) = chars.peek().and_then(|d| d.to_digit(10)) {
+                    number = number * 10 + digit as usize;
+                    chars.next();
+                }
+                let index = number.checked_sub(1);
+                order.push(index.expect("placeholders are numbered from 1 up"));
+                out.push('?');
+                continue;
+            }
+            None if c == '\'' || c == '"' => quote = Some(c),
+            Some(open) if c == open => quote = None,
+            _ => {}
+        }
+        out.push(if c == '"' && quote != Some('\'') {
+            '`'
+        } else {
+            c
+        });
+    }
+    (out, order)
+}
+
+/// Whether every `$` outside quotes in `sql` starts a placeholder numbered from 1 up, as
+/// `positional` needs.
+const fn placeholders_are_numbered(sql: &str) -> bool {
+    let sql = sql.as_bytes();
+    let mut quote = None;
+    let mut i = 0;
+    while i < sql.len() {
+        let c = sql[i];
+        match quote {
+            None if c == b'$' => {
+                let mut end = i + 1;
+                while end < sql.len() && sql[end].is_ascii_digit() {
+                    end += 1;
+                }
+                // `$` alone, or `$0` and `$00`.
+                let mut nonzero = false;
+                let mut digit = i + 1;
+                while digit < end {
+                    nonzero |= sql[digit] != b'0';
+                    digit += 1;
+                }
+                if !nonzero {
+                    return false;
+                }
+                i = end;
+                continue;
+            }
+            None if c == b'\'' || c == b'"' => quote = Some(c),
+            Some(open) if c == open => quote = None,
+            _ => {}
+        }
+        i += 1;
+    }
+    true
+}
+
+// Compiled once per backend on purpose.
+#[allow(clippy::duplicate_mod)]
+#[path = "sql/mod.rs"]
+mod sql;
+
+pub use sql::*;
@@ -0,0 +1,93 @@
+//! The SQL repositories on Postgres, compiled from the same source in `sql/` as `sqlite`.
+//!
+//! The offline query data in `.sqlx` is recorded against SQLite, so the checked sqlx macros
+//! can't be used here: `query!`, `query_as!` and `query_scalar!` run the same SQL as runtime
+//! queries. None of the queries in `sql/` is checked against the Postgres schema at compile
+//! time; the test suite covers them when `TEST_DATABASE_URL` points at a Postgres database.
+//! What the SQLite build checks still holds for both backends, since the two schemas have
+//! the same tables and columns: table and column names, the number of parameters and the
+//! nullability and Rust types of the columns read.
+//!
+//! Unlike the checked macros, runtime queries can't return an anonymous record type, so the
+//! queries in `sql/` read rows into named `FromRow` types or tuples, and `query_scalar!`
+//! needs its result type to be known from the surrounding code.
+
+use sqlx::{PgConnection, Postgres};
+
+use crate::db::{DbBackend, DbPool};
+
+type Db = Postgres;
+type DbConnection = PgConnection;
+type Pool = sqlx::PgPool;
+
+const BACKEND: DbBackend = DbBackend::Postgres;
+
+/// The Postgres pool of `db_pool`. The repositories of this module are only made for
+/// Postgres pools, so anything else is a bug.
+fn typed(db_pool: &DbPool) -> &Pool {
+    match db_pool {
+        DbPool::Postgres(pool) => pool,
+        _ => unreachable!(
+            "Postgres repositories were given a {} pool",
+            db_pool.backend().as_str()
+        ),
+    }
+}
+
+/// `sql` without the sqlx type overrides in its column aliases, so `id AS "id!"` reads into
+/// a field named `id`. Computed once per call site.
+macro_rules! unchecked {
+    ($sql:literal) => {{
+        static SQL: ::std::sync::OnceLock<String> = ::std::sync::OnceLock::new();
+        SQL.get_or_init(|| $crate::repository::postgres::without_type_overrides($sql))
+            .as_str()
+    }};
+}
+
+macro_rules! query {
+    ($sql:literal $(, $arg:expr)* $(,)?) => {
+        sqlx::query(unchecked!($sql))$(.bind(&$arg))*
+    };
+}
+
+macro_rules! query_as {
+    ($out:ty, $sql:literal $(, $arg:expr)* $(,)?) => {
+        sqlx::query_as::<_, $out>(unchecked!($sql))$(.bind(&$arg))*
+    };
+}
+
+macro_rules! query_scalar {
+    ($sql:literal $(, $arg:expr)* $(,)?) => {
+        sqlx::query_scalar(unchecked!($sql))$(.bind(&$arg))*
+    };
+}
+
+/// Cuts the `!`, `?` and `: Type` suffixes the checked macros read from quoted identifiers,
+/// leaving string literals alone.
+pub(super) fn without_type_overrides(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut quote = None;
+    let mut skipping = false;
+    for c in sql.chars() {
+        match quote {
+            None if c == '"' || c == '\'' => quote = Some(c),
+            Some(open) if c == open => {
+                quote = None;
+                skipping = false;
+            }
+            Some('"') if matches!(c, '!' | '?' | ':') => skipping = true,
+            _ => {}
+        }
+        if !skipping {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// Compiled once per backend on purpose.
+#[allow(clippy::duplicate_mod)]
+#[path = "sql/mod.rs"]
+mod sql;
+
+pub use sql::*;
@@ -0,0 +1,203 @@
+use chrono::Utc;
+
+use super::{Pool, BACKEND};
+use crate::admin::UserSummary;
+use crate::db::DbBackend;
+use crate::repository::UserDeletion;
+
+pub(super) async fn list_users(db_pool: &Pool) -> Result<Vec<UserSummary>, sqlx::Error> {
+    query_as!(
+        UserSummary,
+        r#"
+        SELECT u.id AS "id!", u.email, u.is_admin, COUNT(m.org_id) AS "orgs!: i64"
+        FROM users u LEFT JOIN memberships m ON m.user_id = u.id
+        GROUP BY u.id, u.email, u.is_admin
+        ORDER BY u.id
+        "#
+    )
+    .fetch_all(db_pool)
+    .await
+}
+
+/// Sets the admin flag of a user. Returns `false` if there is no user with the email.
+pub(super) async fn set_admin(
+    db_pool: &Pool,
+    email: &str,
+    is_admin: bool,
+) -> Result<bool, sqlx::Error> {
+    let updated = query!(
+        "UPDATE users SET is_admin = $1 WHERE email = $2",
+        is_admin,
+        email
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(updated.rows_affected() > 0)
+}
+
+/// Stores a new password hash and deletes the user's refresh token. Returns `false` if there
+/// is no user with the email.
+pub(super) async fn reset_password(
+    db_pool: &Pool,
+    email: &str,
+    password_hash: &str,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = db_pool.begin().await?;
+    let user_id: Option<i64> = match BACKEND {
+        DbBackend::MySql => {
+            query!(
+                "UPDATE users SET password_hash = $1 WHERE email = $2",
+                password_hash,
+                email
+            )
+            .execute(&mut *tx)
+            .await?;
+            query_scalar!(r#"SELECT id AS "id!" FROM users WHERE email = $1"#, email)
+                .fetch_optional(&mut *tx)
+                .await?
+        }
+        _ => {
+            query_scalar!(
+                r#"UPDATE users SET password_hash = $1 WHERE email = $2 RETURNING id AS "id!""#,
+                password_hash,
+                email
+            )
+            .fetch_optional(&mut *tx)
+            .await?
+        }
+    };
+    let Some(user_id) = user_id else {
+        return Ok(false);
+    };
+    query!("DELETE FROM refresh_tokens WHERE user_id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Deletes a user along with the organizations nobody else is a member of, crediting what
+/// they created in the other organizations to an owner there.
+pub(super) async fn delete_user(db_pool: &Pool, email: &str) -> Result<UserDeletion, sqlx::Error> {
+    let mut tx = db_pool.begin().await?;
+    let user_id: Option<i64> =
+        query_scalar!(r#"SELECT id AS "id!" FROM users WHERE email = $1"#, email)
+            .fetch_optional(&mut *tx)
+            .await?;
+    let Some(user_id) = user_id else {
+        return Ok(UserDeletion::UnknownUser);
+    };
+
+    let orphaned: Option<String> = query_scalar!(
+        r#"
+        SELECT o.name FROM organizations o
+        JOIN memberships m ON m.org_id = o.id AND m.user_id = $1 AND m.role = 'owner'
+        WHERE NOT EXISTS (
+            SELECT 1 FROM memberships x WHERE x.org_id = o.id AND x.user_id <> $1 AND x.role = 'owner'
+        )
+        AND EXISTS (SELECT 1 FROM memberships x WHERE x.org_id = o.id AND x.user_id <> $1)
+        ORDER BY o.id
+        LIMIT 1
+        "#,
+        user_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    if let Some(org) = orphaned {
+        return Ok(UserDeletion::LastOwner(org));
+    }
+
+    // The stored files go with the contacts of the deleted organizations, so their keys are
+    // read first.
+    let attachment_keys: Vec<String> = query_scalar!(
+        r#"
+        SELECT a.storage_key FROM attachments a
+        JOIN contacts c ON c.id = a.contact_id
+        JOIN memberships m ON m.org_id = c.org_id AND m.user_id = $1
+        WHERE NOT EXISTS (SELECT 1 FROM memberships x WHERE x.org_id = m.org_id AND x.user_id <> $1)
+        "#,
+        user_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    let avatar_keys: Vec<String> = query_scalar!(
+        r#"
+        SELECT c.avatar_key AS "avatar_key!" FROM contacts c
+        JOIN memberships m ON m.org_id = c.org_id AND m.user_id = $1
+        WHERE c.avatar_key IS NOT NULL
+        AND NOT EXISTS (SELECT 1 FROM memberships x WHERE x.org_id = m.org_id AND x.user_id <> $1)
+        "#,
+        user_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let deleted_orgs = query!(
+        r#"
+        DELETE FROM organizations WHERE id IN (
+            SELECT m.org_id FROM memberships m
+            WHERE m.user_id = $1
+            AND NOT EXISTS (SELECT 1 FROM memberships x WHERE x.org_id = m.org_id AND x.user_id <> $1)
+        )
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    query!(
+        r#"
+        UPDATE contacts SET user_id = (
+            SELECT m.user_id FROM memberships m
+            WHERE m.org_id = contacts.org_id AND m.role = 'owner' AND m.user_id <> $1
+            ORDER BY m.created_at, m.user_id
+            LIMIT 1
+        )
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    query!(
+        r#"
+        UPDATE contact_events SET user_id = (
+            SELECT m.user_id FROM contacts c
+            JOIN memberships m ON m.org_id = c.org_id
+            WHERE c.id = contact_events.contact_id AND m.role = 'owner' AND m.user_id <> $1
+            ORDER BY m.created_at, m.user_id
+            LIMIT 1
+        )
+        WHERE user_id = $1
+        "#,
+        user_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    query!("DELETE FROM users WHERE id = $1", user_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(UserDeletion::Deleted {
+        orgs: deleted_orgs,
+        storage_keys: attachment_keys.into_iter().chain(avatar_keys).collect(),
+    })
+}
+
+/// Deletes the expired refresh tokens, or every one with `all`, and returns how many.
+pub(super) async fn purge_tokens(db_pool: &Pool, all: bool) -> Result<u64, sqlx::Error> {
+    let result = if all {
+        query!("DELETE FROM refresh_tokens")
+            .execute(db_pool)
+            .await?
+    } else {
+        let now = Utc::now().naive_utc();
+        query!("DELETE FROM refresh_tokens WHERE expires_at < $1", now)
+            .execute(db_pool)
+            .await?
+    };
+    Ok(result.rows_affected())
+}
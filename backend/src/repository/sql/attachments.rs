@@ -0,0 +1,214 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use common::{AttachmentDto, ContactDto};
+
+use super::{contact_access, fetch_contact_row, last_insert_id, DbConnection, Pool, BACKEND};
+use crate::db::DbBackend;
+use crate::extractors::AuthUser;
+use crate::repository::{AttachmentRepository, RepositoryError, StoredAttachment};
+
+/// The organization of a contact the user may change, or `None` if they can't.
+async fn writable_org(
+    conn: &mut DbConnection,
+    contact_id: i64,
+    user: &AuthUser,
+) -> Result<Option<i64>, sqlx::Error> {
+    let access = contact_access(conn, contact_id, user).await?;
+    Ok(access
+        .filter(|(_, access)| access.can_write())
+        .map(|(org_id, _)| org_id))
+}
+
+pub struct SqlAttachmentRepository {
+    db_pool: Pool,
+}
+
+impl SqlAttachmentRepository {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl AttachmentRepository for SqlAttachmentRepository {
+    async fn list(&self, contact_id: i64) -> Result<Vec<AttachmentDto>, RepositoryError> {
+        Ok(query_as!(
+            AttachmentDto,
+            r#"
+            SELECT id AS "id!", contact_id, file_name, content_type, size, created_at
+            FROM attachments WHERE contact_id = $1 ORDER BY id
+            "#,
+            contact_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?)
+    }
+
+    async fn create(
+        &self,
+        contact_id: i64,
+        user_id: i64,
+        file_name: &str,
+        content_type: &str,
+        size: i64,
+        storage_key: &str,
+    ) -> Result<AttachmentDto, RepositoryError> {
+        let created_at = Utc::now().naive_utc();
+        if BACKEND == DbBackend::MySql {
+            let mut conn = self.db_pool.acquire().await?;
+            query!(
+                r#"
+                INSERT INTO attachments (contact_id, user_id, file_name, content_type, size, storage_key, created_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                "#,
+                contact_id,
+                user_id,
+                file_name,
+                content_type,
+                size,
+                storage_key,
+                created_at
+            )
+            .execute(&mut *conn)
+            .await?;
+            let id = last_insert_id(&mut conn).await?;
+            return Ok(query_as!(
+                AttachmentDto,
+                r#"
+                SELECT id AS "id!", contact_id, file_name, content_type, size, created_at
+                FROM attachments WHERE id = $1
+                "#,
+                id
+            )
+            .fetch_one(&mut *conn)
+            .await?);
+        }
+        Ok(query_as!(
+            AttachmentDto,
+            r#"
+            INSERT INTO attachments (contact_id, user_id, file_name, content_type, size, storage_key, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id AS "id!", contact_id, file_name, content_type, size, created_at
+            "#,
+            contact_id,
+            user_id,
+            file_name,
+            content_type,
+            size,
+            storage_key,
+            created_at
+        )
+        .fetch_one(&self.db_pool)
+        .await?)
+    }
+
+    async fn find(
+        &self,
+        contact_id: i64,
+        id: i64,
+    ) -> Result<Option<StoredAttachment>, RepositoryError> {
+        Ok(query_as!(
+            StoredAttachment,
+            "SELECT file_name, content_type, storage_key FROM attachments WHERE id = $1 AND contact_id = $2",
+            id,
+            contact_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?)
+    }
+
+    async fn delete(&self, contact_id: i64, id: i64) -> Result<Option<String>, RepositoryError> {
+        if BACKEND == DbBackend::MySql {
+            let mut tx = self.db_pool.begin().await?;
+            let key: Option<String> = sqlx::query_scalar(
+                "SELECT storage_key FROM attachments WHERE id = ? AND contact_id = ? FOR UPDATE",
+            )
+            .bind(id)
+            .bind(contact_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            query!(
+                "DELETE FROM attachments WHERE id = $1 AND contact_id = $2",
+                id,
+                contact_id
+            )
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            return Ok(key);
+        }
+        Ok(query_scalar!(
+            "DELETE FROM attachments WHERE id = $1 AND contact_id = $2 RETURNING storage_key",
+            id,
+            contact_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?)
+    }
+
+    async fn avatar_key(&self, contact_id: i64) -> Result<Option<String>, RepositoryError> {
+        Ok(
+            query_scalar!("SELECT avatar_key FROM contacts WHERE id = $1", contact_id)
+                .fetch_one(&self.db_pool)
+                .await?,
+        )
+    }
+
+    async fn set_avatar(
+        &self,
+        user: &AuthUser,
+        contact_id: i64,
+        key: &str,
+    ) -> Result<Option<(ContactDto, Option<String>)>, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let Some(org_id) = writable_org(&mut tx, contact_id, user).await? else {
+            return Ok(None);
+        };
+        let previous: Option<String> =
+            query_scalar!("SELECT avatar_key FROM contacts WHERE id = $1", contact_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let now = Utc::now().naive_utc();
+        query!(
+            "UPDATE contacts SET avatar_key = $1, updated_at = $2 WHERE id = $3",
+            key,
+            now,
+            contact_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        let Some(contact) = fetch_contact_row(&mut tx, contact_id, org_id).await? else {
+            return Ok(None);
+        };
+        tx.commit().await?;
+        Ok(Some((contact, previous)))
+    }
+
+    async fn remove_avatar(
+        &self,
+        user: &AuthUser,
+        contact_id: i64,
+    ) -> Result<Option<String>, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        if writable_org(&mut tx, contact_id, user).await?.is_none() {
+            return Ok(None);
+        }
+        let key: Option<String> =
+            query_scalar!("SELECT avatar_key FROM contacts WHERE id = $1", contact_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let Some(key) = key else {
+            return Ok(None);
+        };
+        let now = Utc::now().naive_utc();
+        query!(
+            "UPDATE contacts SET avatar_key = NULL, updated_at = $1 WHERE id = $2",
+            now,
+            contact_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(key))
+    }
+}
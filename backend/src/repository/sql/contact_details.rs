@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use common::{ContactAddressDto, ContactDto, ContactEmailDto, ContactPhoneDto};
+use sqlx::{FromRow, QueryBuilder};
+
+use super::{Db, DbConnection};
+
+// The emails, phone numbers and addresses of a contact live in child tables, ordered by
+// `position`. They are loaded for a whole page of contacts at once and replaced wholesale
+// whenever the contact is written.
+
+#[derive(FromRow)]
+struct EmailRow {
+    contact_id: i64,
+    #[sqlx(flatten)]
+    email: ContactEmailDto,
+}
+
+#[derive(FromRow)]
+struct PhoneRow {
+    contact_id: i64,
+    #[sqlx(flatten)]
+    phone: ContactPhoneDto,
+}
+
+#[derive(FromRow)]
+struct AddressRow {
+    contact_id: i64,
+    #[sqlx(flatten)]
+    address: ContactAddressDto,
+}
+
+/// Selects `columns` of the child rows belonging to any of `ids`, in display order.
+fn select_children(table: &str, columns: &str, ids: &[i64]) -> QueryBuilder<'static, Db> {
+    let mut query = QueryBuilder::new(format!(
+        "SELECT contact_id, {columns} FROM {table} WHERE contact_id IN ("
+    ));
+    let mut separated = query.separated(", ");
+    for id in ids {
+        separated.push_bind(*id);
+    }
+    query.push(") ORDER BY contact_id, position");
+    query
+}
+
+/// Fills in the emails, phone numbers and addresses of `contacts`, which are not part of
+/// the contact row itself.
+pub(crate) async fn load_contact_details(
+    conn: &mut DbConnection,
+    contacts: Vec<&mut ContactDto>,
+) -> Result<(), sqlx::Error> {
+    let ids: Vec<i64> = contacts.iter().filter_map(|contact| contact.id).collect();
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut emails: HashMap<i64, Vec<ContactEmailDto>> = HashMap::new();
+    let rows = select_children("contact_emails", "label, email", &ids)
+        .build_query_as::<EmailRow>()
+        .fetch_all(&mut *conn)
+        .await?;
+    for row in rows {
+        emails.entry(row.contact_id).or_default().push(row.email);
+    }
+
+    let mut phones: HashMap<i64, Vec<ContactPhoneDto>> = HashMap::new();
+    let rows = select_children("contact_phones", "label, number", &ids)
+        .build_query_as::<PhoneRow>()
+        .fetch_all(&mut *conn)
+        .await?;
+    for row in rows {
+        phones.entry(row.contact_id).or_default().push(row.phone);
+    }
+
+    let mut addresses: HashMap<i64, Vec<ContactAddressDto>> = HashMap::new();
+    let rows = select_children(
+        "contact_addresses",
+        "label, street, city, region, postal_code, country",
+        &ids,
+    )
+    .build_query_as::<AddressRow>()
+    .fetch_all(&mut *conn)
+    .await?;
+    for row in rows {
+        addresses
+            .entry(row.contact_id)
+            .or_default()
+            .push(row.address);
+    }
+
+    for contact in contacts {
+        let Some(id) = contact.id else {
+            continue;
+        };
+        contact.emails = emails.remove(&id).unwrap_or_default();
+        contact.phones = phones.remove(&id).unwrap_or_default();
+        contact.addresses = addresses.remove(&id).unwrap_or_default();
+    }
+
+    Ok(())
+}
+
+/// Replaces the emails, phone numbers and addresses of a contact with those of `contact`.
+/// Labels and values are stored trimmed.
+pub(crate) async fn set_contact_details(
+    conn: &mut DbConnection,
+    contact_id: i64,
+    contact: &ContactDto,
+) -> Result<(), sqlx::Error> {
+    query!(
+        "DELETE FROM contact_emails WHERE contact_id = $1",
+        contact_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    for (position, email) in (0_i64..).zip(&contact.emails) {
+        let (label, address) = (email.label.trim(), email.email.trim());
+        query!(
+            "INSERT INTO contact_emails (contact_id, position, label, email) VALUES ($1, $2, $3, $4)",
+            contact_id,
+            position,
+            label,
+            address
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    query!(
+        "DELETE FROM contact_phones WHERE contact_id = $1",
+        contact_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    for (position, phone) in (0_i64..).zip(&contact.phones) {
+        let label = phone.label.trim();
+        query!(
+            "INSERT INTO contact_phones (contact_id, position, label, number) VALUES ($1, $2, $3, $4)",
+            contact_id,
+            position,
+            label,
+            phone.number
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    query!(
+        "DELETE FROM contact_addresses WHERE contact_id = $1",
+        contact_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    for (position, address) in (0_i64..).zip(&contact.addresses) {
+        let [label, street, city, region, postal_code, country] = [
+            &address.label,
+            &address.street,
+            &address.city,
+            &address.region,
+            &address.postal_code,
+            &address.country,
+        ]
+        .map(|part| part.trim());
+        query!(
+            r#"
+            INSERT INTO contact_addresses (contact_id, position, label, street, city, region, postal_code, country)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+            contact_id,
+            position,
+            label,
+            street,
+            city,
+            region,
+            postal_code,
+            country
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use std::collections::HashSet;
+
+use common::{ContactType, ContactTypeDto};
+use sqlx::Executor;
+
+use super::Db;
+
+/// The contact types this deployment accepts for new and updated contacts.
+pub(crate) async fn enabled_contact_types<'e, E>(
+    executor: E,
+) -> Result<HashSet<ContactType>, sqlx::Error>
+where
+    E: Executor<'e, Database = Db>,
+{
+    let types: Vec<ContactType> = query_scalar!(
+        r#"SELECT name AS "name: ContactType" FROM contact_types WHERE enabled = TRUE"#
+    )
+    .fetch_all(executor)
+    .await?;
+    Ok(types.into_iter().collect())
+}
+
+/// The enabled contact types, in display order.
+pub(crate) async fn contact_types<'e, E>(executor: E) -> Result<Vec<ContactTypeDto>, sqlx::Error>
+where
+    E: Executor<'e, Database = Db>,
+{
+    query_as!(
+        ContactTypeDto,
+        r#"
+        SELECT name AS "name: ContactType", label
+        FROM contact_types
+        WHERE enabled = TRUE
+        ORDER BY position
+        "#
+    )
+    .fetch_all(executor)
+    .await
+}
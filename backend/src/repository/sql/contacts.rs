@@ -0,0 +1,944 @@
+use std::collections::{HashMap, HashSet};
+
+use async_trait::async_trait;
+use chrono::{NaiveDateTime, Utc};
+use common::{
+    ContactDto, ContactEventAction, ContactType, ContactTypeDto, CustomFieldDto,
+    DuplicateContactDto, SharedContactDto, SubscriptionSource, TrashedContactDto,
+};
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use sqlx::{Connection, QueryBuilder};
+use tokio::sync::mpsc;
+
+use super::contact_details::{load_contact_details, set_contact_details};
+use super::contact_types::{contact_types, enabled_contact_types};
+use super::custom_fields::{custom_field_definitions, push_custom_field_filter};
+use super::history::{record_contact_event, record_merge_event};
+use super::subscriptions::record_subscription_change;
+use super::tags::set_contact_tags;
+use super::{last_insert_id, typed, Db, DbConnection, Pool, BACKEND};
+use crate::custom_fields::stored_custom_fields;
+use crate::db::{DbBackend, DbPools};
+use crate::extractors::AuthUser;
+use crate::history::Actor;
+use crate::repository::{
+    ContactFilter, ContactRepository, ContactWrite, RepositoryError, WriteOutcome,
+};
+use crate::sharing::ContactAccess;
+
+/// Number of contacts read from the export cursor before their details are loaded, with one
+/// query per child table for the whole batch. As many contacts wait for the reader of the
+/// stream, so the cursor is only advanced as fast as the client reads.
+const EXPORT_BATCH_ROWS: usize = 64;
+
+/// The columns that make up a `ContactDto`, selected from `contacts`, with `$tags` being the
+/// backend's aggregate of `t.name` into a comma-separated string.
+macro_rules! contact_columns {
+    ($tags:literal) => {
+        concat!(
+            "id, name, email, birthday, subscribed, contact_type, notes, custom_fields, \
+            CASE WHEN avatar_key IS NULL THEN NULL ELSE '/api/v1/contacts/' || id || '/avatar' END AS avatar_url, \
+            (SELECT ",
+            $tags,
+            " FROM contact_tags ct \
+             JOIN tags t ON t.id = ct.tag_id WHERE ct.contact_id = contacts.id) AS tags"
+        )
+    };
+}
+
+/// The columns that make up a `ContactDto`, selected from `contacts`. The tag names are
+/// aggregated into a single comma-separated column, which `common::TagList` splits again.
+/// The avatar URL only exists for contacts with an avatar; MySQL reads its `||` as string
+/// concatenation, as sqlx sets `PIPES_AS_CONCAT`. Emails, phone numbers and addresses are
+/// loaded separately by `load_contact_details`.
+const CONTACT_COLUMNS: &str = match BACKEND {
+    DbBackend::MySql => contact_columns!("GROUP_CONCAT(t.name ORDER BY t.name SEPARATOR ',')"),
+    _ => contact_columns!("string_agg(t.name, ',' ORDER BY t.name)"),
+};
+
+/// The `ESCAPE` clause of a `LIKE` with wildcards escaped by `escape_like`. MySQL escapes with
+/// a backslash by default, and would read the one in `'\'` as escaping the closing quote.
+const LIKE_ESCAPE: &str = match BACKEND {
+    DbBackend::MySql => "",
+    _ => " ESCAPE '\\'",
+};
+
+/// Starts a `SELECT` of the organization's contacts outside the trash with the filter
+/// applied, ordered by ID.
+/// Callers may append `LIMIT`/`OFFSET` before building the query.
+fn select_contacts(filter: &ContactFilter, org_id: i64) -> QueryBuilder<'static, Db> {
+    let mut query = QueryBuilder::new(format!(
+        "SELECT {CONTACT_COLUMNS} FROM contacts WHERE deleted_at IS NULL AND org_id = "
+    ));
+    query.push_bind(org_id);
+    push_conditions(filter, &mut query);
+    query.push(" ORDER BY id");
+    query
+}
+
+/// Appends the filter to a `SELECT ... FROM contacts WHERE ...` as further `AND` clauses.
+fn push_conditions(filter: &ContactFilter, query: &mut QueryBuilder<'static, Db>) {
+    if let Some(q) = filter.q.as_deref().filter(|q| !q.is_empty()) {
+        let pattern = format!("%{}%", escape_like(&q.to_lowercase()));
+        query
+            .push(" AND (LOWER(name) LIKE ")
+            .push_bind(pattern.clone())
+            .push(LIKE_ESCAPE)
+            .push(" OR LOWER(email) LIKE ")
+            .push_bind(pattern)
+            .push(LIKE_ESCAPE)
+            .push(")");
+    }
+    if let Some(contact_type) = filter.contact_type {
+        query.push(" AND contact_type = ").push_bind(contact_type);
+    }
+    if let Some(subscribed) = filter.subscribed {
+        query.push(" AND subscribed = ").push_bind(subscribed);
+    }
+    if let Some(tag) = &filter.tag {
+        query
+            .push(
+                " AND EXISTS (SELECT 1 FROM contact_tags ct JOIN tags t ON t.id = ct.tag_id \
+                 WHERE ct.contact_id = contacts.id AND t.name = ",
+            )
+            .push_bind(tag.clone())
+            .push(")");
+    }
+    if let Some(custom_field) = filter.custom_field.as_deref().filter(|f| !f.is_empty()) {
+        match custom_field.split_once(':') {
+            Some((name, value)) => push_custom_field_filter(query, name, Some(value)),
+            None => push_custom_field_filter(query, custom_field, None),
+        }
+    }
+}
+
+/// Escapes the `LIKE` wildcards in user input, for use with `LIKE_ESCAPE`.
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Appends `FROM shares s WHERE ...`, matching the shares that give `user_id` access to the
+/// current row of `contacts`.
+fn push_matching_shares(query: &mut QueryBuilder<'static, Db>, user_id: i64) {
+    query
+        .push(" FROM shares s WHERE s.grantee_id = ")
+        .push_bind(user_id)
+        .push(
+            " AND s.org_id = contacts.org_id \
+             AND (s.contact_id = contacts.id OR s.contact_id IS NULL)",
+        );
+}
+
+/// A further email of a contact, for `ContactRepository::identities`.
+#[derive(sqlx::FromRow)]
+struct ExtraEmail {
+    contact_id: i64,
+    email: String,
+}
+
+/// A file of a trashed contact, which is deleted from storage if the contact is purged.
+#[derive(sqlx::FromRow)]
+struct PurgedAttachment {
+    contact_id: i64,
+    storage_key: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct PurgedContact {
+    id: i64,
+    avatar_key: Option<String>,
+}
+
+// --- Contact Queries ---
+// Every write takes a connection rather than the pool, so that the change and its history
+// entry commit together.
+
+/// Looks up the organization of a contact outside the trash and the access the user has to
+/// it, as a member of the organization of their token or through a share of the contact or of
+/// the whole book. Returns `None` if the contact doesn't exist or isn't visible to the user.
+pub(crate) async fn contact_access(
+    conn: &mut DbConnection,
+    contact_id: i64,
+    user: &AuthUser,
+) -> Result<Option<(i64, ContactAccess)>, sqlx::Error> {
+    let org_id = query_scalar!(
+        r#"SELECT org_id AS "org_id!" FROM contacts WHERE id = $1 AND deleted_at IS NULL"#,
+        contact_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some(org_id) = org_id else {
+        return Ok(None);
+    };
+    if org_id == user.org_id {
+        return Ok(Some((org_id, ContactAccess::Member(user.role))));
+    }
+
+    // 'write' sorts after 'read', so the broadest of several matching shares wins.
+    let permission: Option<String> = query_scalar!(
+        r#"
+        SELECT MAX(permission) AS "permission?: String" FROM shares
+        WHERE grantee_id = $1 AND org_id = $2 AND (contact_id = $3 OR contact_id IS NULL)
+        "#,
+        user.id,
+        org_id,
+        contact_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(permission
+        .and_then(|permission| permission.parse().ok())
+        .map(|permission| (org_id, ContactAccess::Shared(permission))))
+}
+
+pub(crate) async fn fetch_contact_row(
+    conn: &mut DbConnection,
+    id: i64,
+    org_id: i64,
+) -> Result<Option<ContactDto>, sqlx::Error> {
+    let mut query = QueryBuilder::<Db>::new(format!(
+        "SELECT {CONTACT_COLUMNS} FROM contacts WHERE deleted_at IS NULL AND id = "
+    ));
+    query.push_bind(id).push(" AND org_id = ").push_bind(org_id);
+
+    let mut contact = query
+        .build_query_as::<ContactDto>()
+        .fetch_optional(&mut *conn)
+        .await?;
+    if let Some(contact) = contact.as_mut() {
+        load_contact_details(conn, vec![contact]).await?;
+    }
+    Ok(contact)
+}
+
+/// Adds a contact to the organization's book, created by the actor. A subscribed contact is
+/// recorded as having consented through `source`, except that users can't give consent for
+/// their contacts: one created by hand as subscribed is stored unsubscribed, and the caller
+/// asks it to confirm with `newsletter::ask_to_subscribe`.
+async fn insert_contact(
+    conn: &mut DbConnection,
+    actor: &Actor,
+    org_id: i64,
+    contact: &ContactDto,
+    source: SubscriptionSource,
+) -> Result<ContactDto, sqlx::Error> {
+    let subscribed = contact.subscribed && source != SubscriptionSource::Manual;
+    let contact_type = contact.contact_type.as_str();
+    let custom_fields = stored_custom_fields(&contact.custom_fields);
+    let now = Utc::now().naive_utc();
+    let id = match BACKEND {
+        DbBackend::MySql => {
+            query!(
+                r#"
+                INSERT INTO contacts (org_id, user_id, name, email, birthday, subscribed, contact_type, notes, custom_fields, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10)
+                "#,
+                org_id,
+                actor.user_id,
+                contact.name,
+                contact.email,
+                contact.birthday,
+                subscribed,
+                contact_type,
+                contact.notes,
+                custom_fields,
+                now
+            )
+            .execute(&mut *conn)
+            .await?;
+            last_insert_id(conn).await?
+        }
+        _ => {
+            query_scalar!(
+                r#"
+                INSERT INTO contacts (org_id, user_id, name, email, birthday, subscribed, contact_type, notes, custom_fields, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $10)
+                RETURNING id AS "id!";
+                "#,
+                org_id,
+                actor.user_id,
+                contact.name,
+                contact.email,
+                contact.birthday,
+                subscribed,
+                contact_type,
+                contact.notes,
+                custom_fields,
+                now
+            )
+            .fetch_one(&mut *conn)
+            .await?
+        }
+    };
+
+    set_contact_tags(conn, org_id, id, &contact.tags).await?;
+    set_contact_details(conn, id, contact).await?;
+    if subscribed {
+        record_subscription_change(conn, actor, id, true, source).await?;
+    }
+    let created = fetch_contact_row(&mut *conn, id, org_id)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)?;
+
+    record_contact_event(
+        conn,
+        actor,
+        id,
+        ContactEventAction::Created,
+        None,
+        Some(&created),
+    )
+    .await?;
+
+    Ok(created)
+}
+
+async fn update_contact_row(
+    conn: &mut DbConnection,
+    actor: &Actor,
+    id: i64,
+    org_id: i64,
+    contact: &ContactDto,
+) -> Result<Option<ContactDto>, sqlx::Error> {
+    let Some(before) = fetch_contact_row(&mut *conn, id, org_id).await? else {
+        return Ok(None);
+    };
+
+    let updated = write_contact_row(conn, actor, id, org_id, contact).await?;
+    record_contact_event(
+        conn,
+        actor,
+        id,
+        ContactEventAction::Updated,
+        Some(&before),
+        Some(&updated),
+    )
+    .await?;
+
+    Ok(Some(updated))
+}
+
+/// Overwrites a contact outside the trash with `contact` and returns the stored result,
+/// without recording history. The actor may unsubscribe the contact, which is recorded, but
+/// not subscribe it: only the contact can do that, through `newsletter::ask_to_subscribe`.
+async fn write_contact_row(
+    conn: &mut DbConnection,
+    actor: &Actor,
+    id: i64,
+    org_id: i64,
+    contact: &ContactDto,
+) -> Result<ContactDto, sqlx::Error> {
+    let was_subscribed = query_scalar!(
+        "SELECT subscribed FROM contacts WHERE id = $1 AND org_id = $2 AND deleted_at IS NULL",
+        id,
+        org_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+    let subscribed = contact.subscribed && was_subscribed == Some(true);
+    let contact_type = contact.contact_type.as_str();
+    let custom_fields = stored_custom_fields(&contact.custom_fields);
+    let updated_at = Utc::now().naive_utc();
+    query!(
+        r#"
+        UPDATE contacts
+        SET name = $1, email = $2, birthday = $3, subscribed = $4, contact_type = $5, notes = $6,
+            custom_fields = $7, updated_at = $8
+        WHERE id = $9 AND org_id = $10 AND deleted_at IS NULL
+        "#,
+        contact.name,
+        contact.email,
+        contact.birthday,
+        subscribed,
+        contact_type,
+        contact.notes,
+        custom_fields,
+        updated_at,
+        id,
+        org_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    set_contact_tags(conn, org_id, id, &contact.tags).await?;
+    set_contact_details(conn, id, contact).await?;
+    if was_subscribed == Some(true) && !subscribed {
+        record_subscription_change(conn, actor, id, false, SubscriptionSource::Manual).await?;
+    }
+    fetch_contact_row(&mut *conn, id, org_id)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Moves a contact to the trash. Returns `true` if a contact was deleted.
+async fn delete_contact_row(
+    conn: &mut DbConnection,
+    actor: &Actor,
+    id: i64,
+    org_id: i64,
+) -> Result<bool, sqlx::Error> {
+    let Some(before) = fetch_contact_row(&mut *conn, id, org_id).await? else {
+        return Ok(false);
+    };
+
+    trash_contact_row(conn, id, org_id).await?;
+    record_contact_event(
+        conn,
+        actor,
+        id,
+        ContactEventAction::Deleted,
+        Some(&before),
+        None,
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Moves a contact to the trash without recording history.
+async fn trash_contact_row(
+    conn: &mut DbConnection,
+    id: i64,
+    org_id: i64,
+) -> Result<(), sqlx::Error> {
+    let deleted_at = Utc::now().naive_utc();
+    query!(
+        "UPDATE contacts SET deleted_at = $1 WHERE id = $2 AND org_id = $3 AND deleted_at IS NULL",
+        deleted_at,
+        id,
+        org_id
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Runs one write of a batch.
+async fn apply_write(
+    conn: &mut DbConnection,
+    actor: &Actor,
+    org_id: i64,
+    write: &ContactWrite<'_>,
+) -> Result<WriteOutcome, sqlx::Error> {
+    Ok(match *write {
+        ContactWrite::Create { contact, source } => {
+            WriteOutcome::Created(insert_contact(conn, actor, org_id, contact, source).await?)
+        }
+        ContactWrite::Update { id, contact } => {
+            match update_contact_row(conn, actor, id, org_id, contact).await? {
+                Some(updated) => WriteOutcome::Updated(updated),
+                None => WriteOutcome::NotFound,
+            }
+        }
+        ContactWrite::Delete { id } => match delete_contact_row(conn, actor, id, org_id).await? {
+            true => WriteOutcome::Deleted,
+            false => WriteOutcome::NotFound,
+        },
+    })
+}
+
+/// Reads the contacts matching the filter for `ContactRepository::export` and sends them
+/// down the channel, in batches whose details are loaded together. Stops at the first error,
+/// which is sent as well, or when the receiver goes away.
+async fn send_export(
+    db_pools: DbPools,
+    org_id: i64,
+    filter: ContactFilter,
+    sender: mpsc::Sender<Result<ContactDto, RepositoryError>>,
+) {
+    let db_pool = typed(db_pools.read()).clone();
+    let mut query = select_contacts(&filter, org_id);
+    let mut rows = query.build_query_as::<ContactDto>().fetch(&db_pool);
+
+    let mut finished = false;
+    while !finished {
+        let mut batch = Vec::with_capacity(EXPORT_BATCH_ROWS);
+        let mut result = Ok(());
+        while batch.len() < EXPORT_BATCH_ROWS {
+            match rows.try_next().await {
+                Ok(Some(contact)) => batch.push(contact),
+                Ok(None) => {
+                    finished = true;
+                    break;
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+        if result.is_ok() {
+            result = match db_pool.acquire().await {
+                Ok(mut conn) => load_contact_details(&mut conn, batch.iter_mut().collect()).await,
+                Err(e) => Err(e),
+            };
+        }
+        if let Err(e) = result {
+            let _ = sender.send(Err(e.into())).await;
+            return;
+        }
+
+        for contact in batch {
+            // A send error means the reader went away; stop reading from the database.
+            if sender.send(Ok(contact)).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Runs the contact queries, one transaction per call. Lists and exports go to a read
+/// replica and may lag behind the latest writes. Lookups by ID stay on the primary along with
+/// the writes and the access checks before them, so a contact can be fetched as soon as it
+/// was written.
+pub struct SqlContactRepository {
+    db_pools: DbPools,
+}
+
+impl SqlContactRepository {
+    pub fn new(db_pools: DbPools) -> Self {
+        Self { db_pools }
+    }
+
+    fn primary(&self) -> &Pool {
+        typed(&self.db_pools.primary)
+    }
+
+    /// A healthy read replica, or the primary if there is none.
+    fn replica(&self) -> &Pool {
+        typed(self.db_pools.read())
+    }
+}
+
+#[async_trait]
+impl ContactRepository for SqlContactRepository {
+    async fn list(
+        &self,
+        org_id: i64,
+        filter: &ContactFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<ContactDto>, RepositoryError> {
+        let mut query = select_contacts(filter, org_id);
+        query
+            .push(" LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let mut conn = self.replica().acquire().await?;
+        let mut contacts = query
+            .build_query_as::<ContactDto>()
+            .fetch_all(&mut *conn)
+            .await?;
+        load_contact_details(&mut conn, contacts.iter_mut().collect()).await?;
+        Ok(contacts)
+    }
+
+    async fn find(&self, org_id: i64, id: i64) -> Result<Option<ContactDto>, RepositoryError> {
+        // The ID was just checked on the primary, and a lagging replica may not know it yet.
+        let mut conn = self.primary().acquire().await?;
+        Ok(fetch_contact_row(&mut conn, id, org_id).await?)
+    }
+
+    async fn create(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        contact: &ContactDto,
+    ) -> Result<ContactDto, RepositoryError> {
+        let mut tx = self.primary().begin().await?;
+        let created =
+            insert_contact(&mut tx, actor, org_id, contact, SubscriptionSource::Manual).await?;
+        tx.commit().await?;
+        Ok(created)
+    }
+
+    async fn update(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        id: i64,
+        contact: &ContactDto,
+    ) -> Result<Option<ContactDto>, RepositoryError> {
+        let mut tx = self.primary().begin().await?;
+        let updated = update_contact_row(&mut tx, actor, id, org_id, contact).await?;
+        if updated.is_some() {
+            tx.commit().await?;
+        }
+        Ok(updated)
+    }
+
+    async fn delete(&self, actor: &Actor, org_id: i64, id: i64) -> Result<bool, RepositoryError> {
+        let mut tx = self.primary().begin().await?;
+        let deleted = delete_contact_row(&mut tx, actor, id, org_id).await?;
+        if deleted {
+            tx.commit().await?;
+        }
+        Ok(deleted)
+    }
+
+    async fn write_batch(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        writes: &[ContactWrite<'_>],
+        all_or_nothing: bool,
+    ) -> Result<Vec<WriteOutcome>, RepositoryError> {
+        let mut tx = self.primary().begin().await?;
+        let mut outcomes = Vec::with_capacity(writes.len());
+
+        for write in writes {
+            // Each write runs inside its own savepoint, so a failing statement only discards
+            // that write's work and leaves the outer transaction usable.
+            let mut savepoint = tx.begin().await?;
+            let outcome = match apply_write(&mut savepoint, actor, org_id, write).await {
+                Ok(outcome) => outcome,
+                Err(e) => WriteOutcome::Failed(e.into()),
+            };
+
+            if outcome.succeeded() {
+                savepoint.commit().await?;
+                outcomes.push(outcome);
+            } else {
+                savepoint.rollback().await?;
+                outcomes.push(outcome);
+                if all_or_nothing {
+                    tx.rollback().await?;
+                    return Ok(outcomes);
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(outcomes)
+    }
+
+    async fn existing_emails(
+        &self,
+        org_id: i64,
+        emails: &[String],
+    ) -> Result<HashSet<String>, RepositoryError> {
+        if emails.is_empty() {
+            return Ok(HashSet::new());
+        }
+        // Matches the `(org_id, LOWER(email))` index of contacts outside the trash.
+        let mut query = QueryBuilder::<Db>::new(
+            "SELECT LOWER(email) FROM contacts WHERE deleted_at IS NULL AND org_id = ",
+        );
+        query.push_bind(org_id).push(" AND LOWER(email) IN (");
+        let mut separated = query.separated(", ");
+        for email in emails {
+            separated.push_bind(email.to_lowercase());
+        }
+        query.push(")");
+
+        let existing: Vec<String> = query.build_query_scalar().fetch_all(self.primary()).await?;
+        Ok(existing.into_iter().collect())
+    }
+
+    fn export(
+        &self,
+        org_id: i64,
+        filter: ContactFilter,
+    ) -> BoxStream<'static, Result<ContactDto, RepositoryError>> {
+        let (sender, receiver) = mpsc::channel(EXPORT_BATCH_ROWS);
+        tokio::spawn(send_export(self.db_pools.clone(), org_id, filter, sender));
+
+        futures::stream::unfold(receiver, |mut receiver| async {
+            receiver.recv().await.map(|contact| (contact, receiver))
+        })
+        .boxed()
+    }
+
+    async fn identities(
+        &self,
+        org_id: i64,
+    ) -> Result<Vec<(DuplicateContactDto, Vec<String>)>, RepositoryError> {
+        let mut conn = self.primary().acquire().await?;
+        let contacts = query_as!(
+            DuplicateContactDto,
+            r#"SELECT id as "id!", name, email FROM contacts
+            WHERE org_id = $1 AND deleted_at IS NULL ORDER BY id"#,
+            org_id
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        let extra_emails = query_as!(
+            ExtraEmail,
+            r#"SELECT e.contact_id, e.email FROM contact_emails e
+            JOIN contacts c ON c.id = e.contact_id
+            WHERE c.org_id = $1 AND c.deleted_at IS NULL
+            ORDER BY e.contact_id, e.position"#,
+            org_id
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let mut emails: HashMap<i64, Vec<String>> = HashMap::new();
+        for extra in extra_emails {
+            emails
+                .entry(extra.contact_id)
+                .or_default()
+                .push(extra.email);
+        }
+        Ok(contacts
+            .into_iter()
+            .map(|contact| {
+                let mut all = vec![contact.email.clone()];
+                all.extend(emails.remove(&contact.id).unwrap_or_default());
+                (contact, all)
+            })
+            .collect())
+    }
+
+    async fn updated_at(
+        &self,
+        org_id: i64,
+        id: i64,
+    ) -> Result<Option<NaiveDateTime>, RepositoryError> {
+        Ok(query_scalar!(
+            "SELECT updated_at FROM contacts WHERE id = $1 AND org_id = $2 AND deleted_at IS NULL",
+            id,
+            org_id
+        )
+        .fetch_optional(self.primary())
+        .await?)
+    }
+
+    async fn merge(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        primary_id: i64,
+        duplicate_id: i64,
+        merged: &ContactDto,
+    ) -> Result<Option<ContactDto>, RepositoryError> {
+        let mut tx = self.primary().begin().await?;
+        let Some(primary) = fetch_contact_row(&mut tx, primary_id, org_id).await? else {
+            return Ok(None);
+        };
+        let Some(duplicate) = fetch_contact_row(&mut tx, duplicate_id, org_id).await? else {
+            return Ok(None);
+        };
+
+        trash_contact_row(&mut tx, duplicate_id, org_id).await?;
+        let stored = write_contact_row(&mut tx, actor, primary_id, org_id, merged).await?;
+        record_merge_event(
+            &mut tx,
+            actor,
+            primary_id,
+            duplicate_id,
+            &primary,
+            Some(&stored),
+        )
+        .await?;
+        record_merge_event(&mut tx, actor, duplicate_id, primary_id, &duplicate, None).await?;
+        tx.commit().await?;
+        Ok(Some(stored))
+    }
+
+    async fn access(
+        &self,
+        id: i64,
+        user: &AuthUser,
+    ) -> Result<Option<(i64, ContactAccess)>, RepositoryError> {
+        let mut conn = self.primary().acquire().await?;
+        Ok(contact_access(&mut conn, id, user).await?)
+    }
+
+    async fn list_shared(
+        &self,
+        user_id: i64,
+        filter: &ContactFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<SharedContactDto>, RepositoryError> {
+        let mut query = QueryBuilder::new(format!(
+            "SELECT {CONTACT_COLUMNS}, \
+             (SELECT name FROM organizations o WHERE o.id = contacts.org_id) AS org_name, \
+             (SELECT MAX(s.permission)"
+        ));
+        push_matching_shares(&mut query, user_id);
+        query.push(") AS permission FROM contacts WHERE deleted_at IS NULL AND EXISTS (SELECT 1");
+        push_matching_shares(&mut query, user_id);
+        query.push(")");
+        push_conditions(filter, &mut query);
+        query
+            .push(" ORDER BY id LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+
+        let mut conn = self.replica().acquire().await?;
+        let mut shared = query
+            .build_query_as::<SharedContactDto>()
+            .fetch_all(&mut *conn)
+            .await?;
+        let contacts = shared
+            .iter_mut()
+            .map(|shared| &mut shared.contact)
+            .collect();
+        load_contact_details(&mut conn, contacts).await?;
+        Ok(shared)
+    }
+
+    async fn list_trash(
+        &self,
+        org_id: i64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TrashedContactDto>, RepositoryError> {
+        let mut query = QueryBuilder::<Db>::new(format!(
+            "SELECT {CONTACT_COLUMNS}, deleted_at FROM contacts WHERE deleted_at IS NOT NULL \
+             AND org_id = "
+        ));
+        query
+            .push_bind(org_id)
+            .push(" ORDER BY deleted_at DESC, id DESC LIMIT ")
+            .push_bind(limit)
+            .push(" OFFSET ")
+            .push_bind(offset);
+        let mut conn = self.replica().acquire().await?;
+        let mut trash = query
+            .build_query_as::<TrashedContactDto>()
+            .fetch_all(&mut *conn)
+            .await?;
+        let contacts = trash
+            .iter_mut()
+            .map(|trashed| &mut trashed.contact)
+            .collect();
+        load_contact_details(&mut conn, contacts).await?;
+        Ok(trash)
+    }
+
+    async fn restore(
+        &self,
+        actor: &Actor,
+        org_id: i64,
+        id: i64,
+    ) -> Result<Option<ContactDto>, RepositoryError> {
+        let mut tx = self.primary().begin().await?;
+        let now = Utc::now().naive_utc();
+        let restored = query!(
+            r#"
+            UPDATE contacts
+            SET deleted_at = NULL, updated_at = $1
+            WHERE id = $2 AND org_id = $3 AND deleted_at IS NOT NULL
+            "#,
+            now,
+            id,
+            org_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        if restored.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let contact = fetch_contact_row(&mut tx, id, org_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        record_contact_event(
+            &mut tx,
+            actor,
+            id,
+            ContactEventAction::Restored,
+            None,
+            Some(&contact),
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(Some(contact))
+    }
+
+    async fn purge_trash(
+        &self,
+        deleted_before: NaiveDateTime,
+    ) -> Result<(u64, Vec<String>), RepositoryError> {
+        let mut tx = self.primary().begin().await?;
+        // The attachment rows go with their contacts, so their keys are read first.
+        let attachments = query_as!(
+            PurgedAttachment,
+            r#"
+            SELECT a.contact_id, a.storage_key FROM attachments a
+            JOIN contacts c ON c.id = a.contact_id
+            WHERE c.deleted_at IS NOT NULL AND c.deleted_at < $1
+            "#,
+            deleted_before
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let purged = match BACKEND {
+            DbBackend::MySql => {
+                let purged = sqlx::query_as::<_, PurgedContact>(
+                    "SELECT id, avatar_key FROM contacts \
+                     WHERE deleted_at IS NOT NULL AND deleted_at < ? FOR UPDATE",
+                )
+                .bind(deleted_before)
+                .fetch_all(&mut *tx)
+                .await?;
+                query!(
+                    "DELETE FROM contacts WHERE deleted_at IS NOT NULL AND deleted_at < $1",
+                    deleted_before
+                )
+                .execute(&mut *tx)
+                .await?;
+                purged
+            }
+            _ => {
+                query_as!(
+                    PurgedContact,
+                    r#"
+                    DELETE FROM contacts WHERE deleted_at IS NOT NULL AND deleted_at < $1
+                    RETURNING id AS "id!", avatar_key
+                    "#,
+                    deleted_before
+                )
+                .fetch_all(&mut *tx)
+                .await?
+            }
+        };
+        tx.commit().await?;
+
+        // Only the files of contacts that were really purged are returned; a contact restored
+        // in the meantime keeps its files.
+        let purged_ids: HashSet<i64> = purged.iter().map(|contact| contact.id).collect();
+        let keys = purged
+            .iter()
+            .filter_map(|contact| contact.avatar_key.clone())
+            .chain(
+                attachments
+                    .into_iter()
+                    .filter(|attachment| purged_ids.contains(&attachment.contact_id))
+                    .map(|attachment| attachment.storage_key),
+            )
+            .collect();
+        Ok((purged.len() as u64, keys))
+    }
+
+    async fn count(&self, org_id: i64) -> Result<i64, RepositoryError> {
+        Ok(query_scalar!(
+            r#"SELECT COUNT(*) AS "count!: i64" FROM contacts WHERE org_id = $1"#,
+            org_id
+        )
+        .fetch_one(self.primary())
+        .await?)
+    }
+
+    async fn enabled_contact_types(&self) -> Result<HashSet<ContactType>, RepositoryError> {
+        Ok(enabled_contact_types(self.primary()).await?)
+    }
+
+    async fn contact_types(&self) -> Result<Vec<ContactTypeDto>, RepositoryError> {
+        Ok(contact_types(self.primary()).await?)
+    }
+
+    async fn custom_fields(&self, org_id: i64) -> Result<Vec<CustomFieldDto>, RepositoryError> {
+        Ok(custom_field_definitions(self.primary(), org_id).await?)
+    }
+}
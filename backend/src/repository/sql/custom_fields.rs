@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use common::{CustomFieldDto, CustomFieldType};
+use serde_json::Value;
+use sqlx::{types::Json as SqlJson, Executor, QueryBuilder};
+
+use super::{last_insert_id, Db, DbConnection, Pool, BACKEND};
+use crate::db::DbBackend;
+use crate::repository::{CustomFieldRepository, RepositoryError};
+
+/// The organization's custom field definitions, in creation order.
+pub(crate) async fn custom_field_definitions<'e, E>(
+    executor: E,
+    org_id: i64,
+) -> Result<Vec<CustomFieldDto>, sqlx::Error>
+where
+    E: Executor<'e, Database = Db>,
+{
+    let mut query = QueryBuilder::new(
+        "SELECT id, name, field_type, required, options FROM custom_fields WHERE org_id = ",
+    );
+    query.push_bind(org_id).push(" ORDER BY id");
+    query.build_query_as().fetch_all(executor).await
+}
+
+/// Restricts a `SELECT ... FROM contacts` to contacts with a value for the custom field
+/// `name`, or with exactly `value` when given. Values are compared in their JSON text form,
+/// so `true`, `42` and `2024-01-31` all match as written.
+pub(crate) fn push_custom_field_filter(
+    query: &mut QueryBuilder<'static, Db>,
+    name: &str,
+    value: Option<&str>,
+) {
+    match (BACKEND, value) {
+        (DbBackend::Sqlite, value) => {
+            query
+                .push(" AND EXISTS (SELECT 1 FROM json_each(contacts.custom_fields) WHERE key = ")
+                .push_bind(name.to_string());
+            if let Some(value) = value {
+                query
+                    .push(
+                        " AND CASE type WHEN 'true' THEN 'true' WHEN 'false' THEN 'false' \
+                         ELSE CAST(value AS TEXT) END = ",
+                    )
+                    .push_bind(value.to_string());
+            }
+            query.push(")");
+        }
+        (DbBackend::Postgres, Some(value)) => {
+            query
+                .push(" AND contacts.custom_fields ->> ")
+                .push_bind(name.to_string())
+                .push(" = ")
+                .push_bind(value.to_string());
+        }
+        (DbBackend::Postgres, None) => {
+            query
+                .push(" AND contacts.custom_fields -> ")
+                .push_bind(name.to_string())
+                .push(" IS NOT NULL");
+        }
+        (DbBackend::MySql, Some(value)) => {
+            query
+                .push(" AND JSON_UNQUOTE(JSON_EXTRACT(contacts.custom_fields, ")
+                .push_bind(json_path(name))
+                .push(")) = ")
+                .push_bind(value.to_string());
+        }
+        (DbBackend::MySql, None) => {
+            query
+                .push(" AND JSON_CONTAINS_PATH(contacts.custom_fields, 'one', ")
+                .push_bind(json_path(name))
+                .push(")");
+        }
+    }
+}
+
+/// The MySQL JSON path of the member `name` of an object.
+fn json_path(name: &str) -> String {
+    format!("$.\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Applies `change` to the custom field values of every contact of the organization, trashed ones
+/// included, that has a value for the field `name`.
+async fn rewrite_custom_field_values(
+    conn: &mut DbConnection,
+    org_id: i64,
+    name: &str,
+    change: impl Fn(&mut BTreeMap<String, Value>),
+) -> Result<(), sqlx::Error> {
+    let mut query = QueryBuilder::new("SELECT id, custom_fields FROM contacts WHERE org_id = ");
+    query.push_bind(org_id);
+    push_custom_field_filter(&mut query, name, None);
+
+    let contacts = query
+        .build_query_as::<(i64, SqlJson<BTreeMap<String, Value>>)>()
+        .fetch_all(&mut *conn)
+        .await?;
+    for (id, SqlJson(mut values)) in contacts {
+        change(&mut values);
+        let values = SqlJson(values);
+        query!(
+            "UPDATE contacts SET custom_fields = $1 WHERE id = $2",
+            values,
+            id
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredField {
+    name: String,
+    field_type: CustomFieldType,
+}
+
+pub struct SqlCustomFieldRepository {
+    db_pool: Pool,
+}
+
+impl SqlCustomFieldRepository {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl CustomFieldRepository for SqlCustomFieldRepository {
+    async fn list(&self, org_id: i64) -> Result<Vec<CustomFieldDto>, RepositoryError> {
+        Ok(custom_field_definitions(&self.db_pool, org_id).await?)
+    }
+
+    async fn create(&self, org_id: i64, field: &CustomFieldDto) -> Result<i64, RepositoryError> {
+        let field_type = field.field_type.as_str();
+        let options = SqlJson(&field.options);
+        let mut conn = self.db_pool.acquire().await?;
+        let id = match BACKEND {
+            DbBackend::MySql => {
+                query!(
+                    r#"
+                    INSERT INTO custom_fields (org_id, name, field_type, required, options)
+                    VALUES ($1, $2, $3, $4, $5)
+                    "#,
+                    org_id,
+                    field.name,
+                    field_type,
+                    field.required,
+                    options
+                )
+                .execute(&mut *conn)
+                .await?;
+                last_insert_id(&mut conn).await?
+            }
+            _ => {
+                query_scalar!(
+                    r#"
+                    INSERT INTO custom_fields (org_id, name, field_type, required, options)
+                    VALUES ($1, $2, $3, $4, $5)
+                    RETURNING id AS "id!"
+                    "#,
+                    org_id,
+                    field.name,
+                    field_type,
+                    field.required,
+                    options
+                )
+                .fetch_one(&mut *conn)
+                .await?
+            }
+        };
+        Ok(id)
+    }
+
+    async fn update(
+        &self,
+        org_id: i64,
+        id: i64,
+        field: &CustomFieldDto,
+    ) -> Result<Option<CustomFieldType>, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let existing = query_as!(
+            StoredField,
+            r#"SELECT name, field_type AS "field_type: CustomFieldType" FROM custom_fields WHERE id = $1 AND org_id = $2"#,
+            id,
+            org_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(existing) = existing else {
+            return Ok(None);
+        };
+        if existing.field_type != field.field_type {
+            return Ok(Some(existing.field_type));
+        }
+
+        let options = SqlJson(&field.options);
+        query!(
+            "UPDATE custom_fields SET name = $1, required = $2, options = $3 WHERE id = $4 AND org_id = $5",
+            field.name,
+            field.required,
+            options,
+            id,
+            org_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if existing.name != field.name {
+            rewrite_custom_field_values(&mut tx, org_id, &existing.name, |values| {
+                if let Some(value) = values.remove(&existing.name) {
+                    values.insert(field.name.clone(), value);
+                }
+            })
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(Some(existing.field_type))
+    }
+
+    async fn delete(&self, org_id: i64, id: i64) -> Result<bool, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let name: Option<String> = match BACKEND {
+            DbBackend::MySql => {
+                let name: Option<String> = sqlx::query_scalar(
+                    "SELECT name FROM custom_fields WHERE id = ? AND org_id = ? FOR UPDATE",
+                )
+                .bind(id)
+                .bind(org_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+                query!(
+                    "DELETE FROM custom_fields WHERE id = $1 AND org_id = $2",
+                    id,
+                    org_id
+                )
+                .execute(&mut *tx)
+                .await?;
+                name
+            }
+            _ => {
+                query_scalar!(
+                    "DELETE FROM custom_fields WHERE id = $1 AND org_id = $2 RETURNING name",
+                    id,
+                    org_id
+                )
+                .fetch_optional(&mut *tx)
+                .await?
+            }
+        };
+        let Some(name) = name else {
+            return Ok(false);
+        };
+
+        rewrite_custom_field_values(&mut tx, org_id, &name, |values| {
+            values.remove(&name);
+        })
+        .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+}
@@ -0,0 +1,122 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use common::{ContactDto, ContactEventAction, ContactEventDto};
+
+use super::{DbConnection, Pool};
+use crate::history::{action_name, snapshot, Actor, ContactEventRow};
+use crate::repository::{HistoryRepository, RepositoryError};
+
+/// Appends an event to the contact's history. Run it on the same transaction as the change
+/// itself, so that the log and the contact cannot disagree.
+pub(crate) async fn record_contact_event(
+    conn: &mut DbConnection,
+    actor: &Actor,
+    contact_id: i64,
+    action: ContactEventAction,
+    before: Option<&ContactDto>,
+    after: Option<&ContactDto>,
+) -> Result<(), sqlx::Error> {
+    insert_contact_event(conn, actor, contact_id, action, None, before, after).await
+}
+
+/// Appends a `Merged` event to the history of one of the two merged contacts, pointing at
+/// the other one.
+pub(crate) async fn record_merge_event(
+    conn: &mut DbConnection,
+    actor: &Actor,
+    contact_id: i64,
+    merged_with: i64,
+    before: &ContactDto,
+    after: Option<&ContactDto>,
+) -> Result<(), sqlx::Error> {
+    insert_contact_event(
+        conn,
+        actor,
+        contact_id,
+        ContactEventAction::Merged,
+        Some(merged_with),
+        Some(before),
+        after,
+    )
+    .await
+}
+
+async fn insert_contact_event(
+    conn: &mut DbConnection,
+    actor: &Actor,
+    contact_id: i64,
+    action: ContactEventAction,
+    related_contact_id: Option<i64>,
+    before: Option<&ContactDto>,
+    after: Option<&ContactDto>,
+) -> Result<(), sqlx::Error> {
+    let action = action_name(action);
+    let before = snapshot(before)?;
+    let after = snapshot(after)?;
+    let created_at = Utc::now().naive_utc();
+
+    query!(
+        r#"
+        INSERT INTO contact_events (contact_id, user_id, action, request_id, before_snapshot, after_snapshot, related_contact_id, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+        contact_id,
+        actor.user_id,
+        action,
+        actor.request_id,
+        before,
+        after,
+        related_contact_id,
+        created_at
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+pub struct SqlHistoryRepository {
+    db_pool: Pool,
+}
+
+impl SqlHistoryRepository {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for SqlHistoryRepository {
+    async fn in_org(&self, org_id: i64, contact_id: i64) -> Result<bool, RepositoryError> {
+        let owned: Option<i64> = query_scalar!(
+            "SELECT id FROM contacts WHERE id = $1 AND org_id = $2",
+            contact_id,
+            org_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?;
+        Ok(owned.is_some())
+    }
+
+    async fn list(&self, contact_id: i64) -> Result<Vec<ContactEventDto>, RepositoryError> {
+        let rows = query_as!(
+            ContactEventRow,
+            r#"
+            SELECT e.id AS "id!", e.contact_id, e.action, e.user_id, u.email AS "user_email?",
+                   e.request_id, e.before_snapshot, e.after_snapshot, e.related_contact_id,
+                   e.created_at
+            FROM contact_events e
+            LEFT JOIN users u ON u.id = e.user_id
+            WHERE e.contact_id = $1
+            ORDER BY e.id
+            "#,
+            contact_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .filter_map(ContactEventRow::into_dto)
+            .collect())
+    }
+}
@@ -0,0 +1,265 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use common::OrgRole;
+
+use std::sync::Arc;
+
+use super::{typed, Db, DbConnection, Pool, BACKEND};
+use crate::admin::UserSummary;
+use crate::db::{DbBackend, DbPools};
+use crate::repository::{
+    RefreshToken, Repositories, RepositoryError, TokenRepository, User, UserDeletion,
+    UserRepository,
+};
+
+mod admin;
+mod attachments;
+mod contact_details;
+mod contact_types;
+mod contacts;
+mod custom_fields;
+mod history;
+mod orgs;
+mod shares;
+mod subscriptions;
+mod tags;
+
+pub use attachments::SqlAttachmentRepository;
+pub use contacts::SqlContactRepository;
+pub(crate) use contacts::{contact_access, fetch_contact_row};
+pub use custom_fields::SqlCustomFieldRepository;
+pub use history::SqlHistoryRepository;
+pub use orgs::SqlOrgRepository;
+pub use shares::SqlShareRepository;
+pub use subscriptions::SqlSubscriptionRepository;
+pub use tags::SqlTagRepository;
+
+use orgs::insert_org;
+
+/// The repositories on this backend. Everything but contact lists is only read from the
+/// primary, so that a login right after registering, or a tag right after creating it,
+/// doesn't depend on replication.
+pub fn repositories(db_pools: DbPools) -> Repositories {
+    let primary = typed(&db_pools.primary).clone();
+    Repositories {
+        users: Arc::new(SqlUserRepository::new(primary.clone())),
+        tokens: Arc::new(SqlTokenRepository::new(primary.clone())),
+        contacts: Arc::new(SqlContactRepository::new(db_pools)),
+        tags: Arc::new(SqlTagRepository::new(primary.clone())),
+        shares: Arc::new(SqlShareRepository::new(primary.clone())),
+        orgs: Arc::new(SqlOrgRepository::new(primary.clone())),
+        custom_fields: Arc::new(SqlCustomFieldRepository::new(primary.clone())),
+        history: Arc::new(SqlHistoryRepository::new(primary.clone())),
+        subscriptions: Arc::new(SqlSubscriptionRepository::new(primary.clone())),
+        attachments: Arc::new(SqlAttachmentRepository::new(primary)),
+    }
+}
+
+/// The ID of the row the last `INSERT` on `conn` added. MySQL has no `RETURNING`, so its
+/// branch of the inserts that return their new ID reads it from here instead.
+async fn last_insert_id(conn: &mut DbConnection) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT CAST(LAST_INSERT_ID() AS SIGNED)")
+        .fetch_one(conn)
+        .await
+}
+
+// --- Users ---
+
+pub struct SqlUserRepository {
+    db_pool: Pool,
+}
+
+impl SqlUserRepository {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl UserRepository for SqlUserRepository {
+    async fn find_by_email(&self, email: &str) -> Result<Option<User>, RepositoryError> {
+        Ok(query_as!(
+            User,
+            r#"SELECT id AS "id!", email, password_hash FROM users WHERE email = $1"#,
+            email
+        )
+        .fetch_optional(&self.db_pool)
+        .await?)
+    }
+
+    async fn find_by_id(&self, id: i64) -> Result<Option<User>, RepositoryError> {
+        Ok(query_as!(
+            User,
+            r#"SELECT id AS "id!", email, password_hash FROM users WHERE id = $1"#,
+            id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?)
+    }
+
+    async fn create(&self, email: &str, password_hash: &str) -> Result<i64, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let user_id = match BACKEND {
+            DbBackend::MySql => {
+                query!(
+                    "INSERT INTO users (email, password_hash) VALUES ($1, $2)",
+                    email,
+                    password_hash
+                )
+                .execute(&mut *tx)
+                .await?;
+                last_insert_id(&mut tx).await?
+            }
+            _ => query_scalar!(
+                r#"INSERT INTO users (email, password_hash) VALUES ($1, $2) RETURNING id AS "id!""#,
+                email,
+                password_hash
+            )
+            .fetch_one(&mut *tx)
+            .await?,
+        };
+        insert_org(&mut tx, email, user_id).await?;
+        tx.commit().await?;
+        Ok(user_id)
+    }
+
+    async fn role_in(&self, user_id: i64, org_id: i64) -> Result<Option<OrgRole>, RepositoryError> {
+        Ok(query_scalar!(
+            r#"SELECT role AS "role: OrgRole" FROM memberships WHERE org_id = $1 AND user_id = $2"#,
+            org_id,
+            user_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?)
+    }
+
+    async fn default_org(&self, user_id: i64) -> Result<Option<i64>, RepositoryError> {
+        Ok(query_scalar!(
+            "SELECT org_id FROM memberships WHERE user_id = $1 ORDER BY created_at, org_id LIMIT 1",
+            user_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?)
+    }
+
+    async fn is_admin(&self, user_id: i64) -> Result<bool, RepositoryError> {
+        let is_admin: Option<bool> =
+            query_scalar!("SELECT is_admin FROM users WHERE id = $1", user_id)
+                .fetch_optional(&self.db_pool)
+                .await?;
+        Ok(is_admin == Some(true))
+    }
+
+    async fn summaries(&self) -> Result<Vec<UserSummary>, RepositoryError> {
+        Ok(admin::list_users(&self.db_pool).await?)
+    }
+
+    async fn set_admin(&self, email: &str, is_admin: bool) -> Result<bool, RepositoryError> {
+        Ok(admin::set_admin(&self.db_pool, email, is_admin).await?)
+    }
+
+    async fn reset_password(
+        &self,
+        email: &str,
+        password_hash: &str,
+    ) -> Result<bool, RepositoryError> {
+        Ok(admin::reset_password(&self.db_pool, email, password_hash).await?)
+    }
+
+    async fn delete(&self, email: &str) -> Result<UserDeletion, RepositoryError> {
+        Ok(admin::delete_user(&self.db_pool, email).await?)
+    }
+}
+
+// --- Refresh Tokens ---
+
+pub struct SqlTokenRepository {
+    db_pool: Pool,
+}
+
+impl SqlTokenRepository {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl TokenRepository for SqlTokenRepository {
+    async fn store(
+        &self,
+        user_id: i64,
+        org_id: i64,
+        token_hash: &str,
+        expires_at: NaiveDateTime,
+        replaced: Option<&str>,
+    ) -> Result<(), RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+
+        if let Some(replaced) = replaced {
+            query!("DELETE FROM refresh_tokens WHERE token_hash = $1", replaced)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        match BACKEND {
+            DbBackend::MySql => {
+                sqlx::query(
+                    "INSERT INTO refresh_tokens (user_id, token_hash, expires_at, org_id) VALUES (?, ?, ?, ?)
+                     ON DUPLICATE KEY UPDATE token_hash=VALUES(token_hash), expires_at=VALUES(expires_at), org_id=VALUES(org_id)",
+                )
+                .bind(user_id)
+                .bind(token_hash)
+                .bind(expires_at)
+                .bind(org_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+            _ => {
+                query!(
+                    "INSERT INTO refresh_tokens (user_id, token_hash, expires_at, org_id) VALUES ($1, $2, $3, $4)
+                     ON CONFLICT(user_id) DO UPDATE SET token_hash=excluded.token_hash, expires_at=excluded.expires_at, org_id=excluded.org_id",
+                    user_id,
+                    token_hash,
+                    expires_at,
+                    org_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn find(&self, token_hash: &str) -> Result<Option<RefreshToken>, RepositoryError> {
+        Ok(query_as!(
+            RefreshToken,
+            "SELECT user_id, org_id, expires_at FROM refresh_tokens WHERE token_hash = $1",
+            token_hash
+        )
+        .fetch_optional(&self.db_pool)
+        .await?)
+    }
+
+    async fn delete(&self, token_hash: &str) -> Result<(), RepositoryError> {
+        query!(
+            "DELETE FROM refresh_tokens WHERE token_hash = $1",
+            token_hash
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_for_user(&self, user_id: i64) -> Result<(), RepositoryError> {
+        query!("DELETE FROM refresh_tokens WHERE user_id = $1", user_id)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn purge(&self, all: bool) -> Result<u64, RepositoryError> {
+        Ok(admin::purge_tokens(&self.db_pool, all).await?)
+    }
+}
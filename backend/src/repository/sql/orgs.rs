@@ -0,0 +1,236 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use common::{MembershipDto, OrgDto, OrgRole};
+
+use super::{last_insert_id, DbConnection, Pool, BACKEND};
+use crate::db::DbBackend;
+use crate::repository::{OrgRepository, RepositoryError};
+
+/// Creates an organization with `owner_id` as its only member and owner.
+pub(super) async fn insert_org(
+    conn: &mut DbConnection,
+    name: &str,
+    owner_id: i64,
+) -> Result<i64, sqlx::Error> {
+    let created_at = Utc::now().naive_utc();
+    let org_id = match BACKEND {
+        DbBackend::MySql => {
+            query!(
+                "INSERT INTO organizations (name, created_at) VALUES ($1, $2)",
+                name,
+                created_at
+            )
+            .execute(&mut *conn)
+            .await?;
+            last_insert_id(conn).await?
+        }
+        _ => query_scalar!(
+            r#"INSERT INTO organizations (name, created_at) VALUES ($1, $2) RETURNING id AS "id!""#,
+            name,
+            created_at
+        )
+        .fetch_one(&mut *conn)
+        .await?,
+    };
+
+    let role = OrgRole::Owner.as_str();
+    query!(
+        "INSERT INTO memberships (org_id, user_id, role, created_at) VALUES ($1, $2, $3, $4)",
+        org_id,
+        owner_id,
+        role,
+        created_at
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(org_id)
+}
+
+async fn member_role(
+    conn: &mut DbConnection,
+    org_id: i64,
+    user_id: i64,
+) -> Result<Option<OrgRole>, sqlx::Error> {
+    query_scalar!(
+        r#"SELECT role AS "role: OrgRole" FROM memberships WHERE org_id = $1 AND user_id = $2"#,
+        org_id,
+        user_id
+    )
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Fails unless the organization keeps an owner once `user_id` is no longer one.
+async fn ensure_other_owner(
+    conn: &mut DbConnection,
+    org_id: i64,
+    user_id: i64,
+) -> Result<(), RepositoryError> {
+    let owner = OrgRole::Owner.as_str();
+    let other_owners: i64 = query_scalar!(
+        r#"
+        SELECT COUNT(*) AS "count!: i64" FROM memberships
+        WHERE org_id = $1 AND role = $2 AND user_id <> $3
+        "#,
+        org_id,
+        owner,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    if other_owners == 0 {
+        return Err(RepositoryError::LastOwner);
+    }
+    Ok(())
+}
+
+async fn fetch_membership(
+    conn: &mut DbConnection,
+    org_id: i64,
+    user_id: i64,
+) -> Result<MembershipDto, sqlx::Error> {
+    query_as!(
+        MembershipDto,
+        r#"
+        SELECT m.user_id, u.email, m.role AS "role: OrgRole", m.created_at
+        FROM memberships m JOIN users u ON u.id = m.user_id
+        WHERE m.org_id = $1 AND m.user_id = $2
+        "#,
+        org_id,
+        user_id
+    )
+    .fetch_one(&mut *conn)
+    .await
+}
+
+pub struct SqlOrgRepository {
+    db_pool: Pool,
+}
+
+impl SqlOrgRepository {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl OrgRepository for SqlOrgRepository {
+    async fn list_for_user(&self, user_id: i64) -> Result<Vec<OrgDto>, RepositoryError> {
+        Ok(query_as!(
+            OrgDto,
+            r#"
+            SELECT o.id AS "id!", o.name, m.role AS "role: OrgRole"
+            FROM memberships m JOIN organizations o ON o.id = m.org_id
+            WHERE m.user_id = $1
+            ORDER BY m.created_at, o.id
+            "#,
+            user_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?)
+    }
+
+    async fn create(&self, name: &str, owner_id: i64) -> Result<i64, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let id = insert_org(&mut tx, name, owner_id).await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    async fn members(&self, org_id: i64) -> Result<Vec<MembershipDto>, RepositoryError> {
+        Ok(query_as!(
+            MembershipDto,
+            r#"
+            SELECT m.user_id, u.email, m.role AS "role: OrgRole", m.created_at
+            FROM memberships m JOIN users u ON u.id = m.user_id
+            WHERE m.org_id = $1
+            ORDER BY m.created_at, m.user_id
+            "#,
+            org_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?)
+    }
+
+    async fn add_member(
+        &self,
+        org_id: i64,
+        user_id: i64,
+        role: OrgRole,
+    ) -> Result<MembershipDto, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let role = role.as_str();
+        let created_at = Utc::now().naive_utc();
+        let result = query!(
+            "INSERT INTO memberships (org_id, user_id, role, created_at) VALUES ($1, $2, $3, $4)",
+            org_id,
+            user_id,
+            role,
+            created_at
+        )
+        .execute(&mut *tx)
+        .await;
+        match result {
+            Ok(_) => {}
+            // The membership key has no column besides the scoping ones to report.
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+                return Err(RepositoryError::Duplicate("membership".to_string()))
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        let membership = fetch_membership(&mut tx, org_id, user_id).await?;
+        tx.commit().await?;
+        Ok(membership)
+    }
+
+    async fn set_role(
+        &self,
+        org_id: i64,
+        user_id: i64,
+        role: OrgRole,
+    ) -> Result<Option<MembershipDto>, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let Some(current) = member_role(&mut tx, org_id, user_id).await? else {
+            return Ok(None);
+        };
+        if current == OrgRole::Owner && role != OrgRole::Owner {
+            ensure_other_owner(&mut tx, org_id, user_id).await?;
+        }
+
+        let role = role.as_str();
+        query!(
+            "UPDATE memberships SET role = $1 WHERE org_id = $2 AND user_id = $3",
+            role,
+            org_id,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let membership = fetch_membership(&mut tx, org_id, user_id).await?;
+        tx.commit().await?;
+        Ok(Some(membership))
+    }
+
+    async fn remove_member(&self, org_id: i64, user_id: i64) -> Result<bool, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let Some(current) = member_role(&mut tx, org_id, user_id).await? else {
+            return Ok(false);
+        };
+        if current == OrgRole::Owner {
+            ensure_other_owner(&mut tx, org_id, user_id).await?;
+        }
+
+        query!(
+            "DELETE FROM memberships WHERE org_id = $1 AND user_id = $2",
+            org_id,
+            user_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
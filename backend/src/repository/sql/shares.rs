@@ -0,0 +1,144 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use common::{ShareDto, SharePermission};
+use sqlx::{Executor, QueryBuilder};
+
+use super::{last_insert_id, Db, Pool, BACKEND};
+use crate::db::DbBackend;
+use crate::repository::{RepositoryError, ShareRepository};
+
+/// The columns of a `ShareDto`, selected from `shares s` joined with its organization `o`
+/// and grantee `g`.
+const SHARE_SELECT: &str = "SELECT s.id, o.name AS org_name, g.email AS grantee_email, \
+    s.contact_id, s.permission, s.created_at FROM shares s \
+    JOIN organizations o ON o.id = s.org_id JOIN users g ON g.id = s.grantee_id";
+
+/// Starts a `SELECT` of shares with the column `column` of `shares s` equal to `value`.
+fn select_shares(column: &str, value: i64) -> QueryBuilder<'static, Db> {
+    let mut query = QueryBuilder::new(format!("{SHARE_SELECT} WHERE s.{column} = "));
+    query.push_bind(value);
+    query
+}
+
+async fn fetch_share<'e, E>(executor: E, id: i64) -> Result<ShareDto, sqlx::Error>
+where
+    E: Executor<'e, Database = Db>,
+{
+    select_shares("id", id)
+        .build_query_as()
+        .fetch_one(executor)
+        .await
+}
+
+pub struct SqlShareRepository {
+    db_pool: Pool,
+}
+
+impl SqlShareRepository {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl ShareRepository for SqlShareRepository {
+    async fn list(&self, org_id: i64) -> Result<Vec<ShareDto>, RepositoryError> {
+        let mut query = select_shares("org_id", org_id);
+        query.push(" ORDER BY s.id");
+        Ok(query.build_query_as().fetch_all(&self.db_pool).await?)
+    }
+
+    async fn received(&self, user_id: i64) -> Result<Vec<ShareDto>, RepositoryError> {
+        let mut query = select_shares("grantee_id", user_id);
+        query.push(" ORDER BY s.id");
+        Ok(query.build_query_as().fetch_all(&self.db_pool).await?)
+    }
+
+    async fn create(
+        &self,
+        org_id: i64,
+        grantee_id: i64,
+        contact_id: Option<i64>,
+        permission: SharePermission,
+    ) -> Result<ShareDto, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let permission = permission.as_str();
+        let created_at = Utc::now().naive_utc();
+        let id = match BACKEND {
+            DbBackend::MySql => {
+                query!(
+                    r#"
+                    INSERT INTO shares (org_id, grantee_id, contact_id, permission, created_at)
+                    VALUES ($1, $2, $3, $4, $5)
+                    "#,
+                    org_id,
+                    grantee_id,
+                    contact_id,
+                    permission,
+                    created_at
+                )
+                .execute(&mut *tx)
+                .await?;
+                last_insert_id(&mut tx).await?
+            }
+            _ => {
+                query_scalar!(
+                    r#"
+                    INSERT INTO shares (org_id, grantee_id, contact_id, permission, created_at)
+                    VALUES ($1, $2, $3, $4, $5)
+                    RETURNING id AS "id!"
+                    "#,
+                    org_id,
+                    grantee_id,
+                    contact_id,
+                    permission,
+                    created_at
+                )
+                .fetch_one(&mut *tx)
+                .await?
+            }
+        };
+
+        let share = fetch_share(&mut *tx, id).await?;
+        tx.commit().await?;
+        Ok(share)
+    }
+
+    async fn update(
+        &self,
+        org_id: i64,
+        id: i64,
+        permission: SharePermission,
+    ) -> Result<Option<ShareDto>, RepositoryError> {
+        let permission = permission.as_str();
+        let updated = query!(
+            "UPDATE shares SET permission = $1 WHERE id = $2 AND org_id = $3",
+            permission,
+            id,
+            org_id
+        )
+        .execute(&self.db_pool)
+        .await?;
+        if updated.rows_affected() == 0 {
+            return Ok(None);
+        }
+        Ok(Some(fetch_share(&self.db_pool, id).await?))
+    }
+
+    async fn delete(
+        &self,
+        id: i64,
+        org_id: Option<i64>,
+        grantee_id: i64,
+    ) -> Result<bool, RepositoryError> {
+        let deleted = query!(
+            "DELETE FROM shares WHERE id = $1 AND (org_id = $2 OR grantee_id = $3)",
+            id,
+            org_id,
+            grantee_id
+        )
+        .execute(&self.db_pool)
+        .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+}
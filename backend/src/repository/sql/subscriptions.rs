@@ -0,0 +1,258 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use common::{SubscriptionAction, SubscriptionEventDto, SubscriptionSource};
+
+use super::{DbConnection, Pool, BACKEND};
+use crate::db::DbBackend;
+use crate::history::Actor;
+use crate::repository::{Consent, Recipient, RepositoryError, SubscriptionRepository};
+
+/// Sets the contact's subscription, records when and how consent was given, and appends the
+/// change to the subscription log. Run it on the same transaction as any other change to the
+/// contact.
+pub(crate) async fn record_subscription_change(
+    conn: &mut DbConnection,
+    actor: &Actor,
+    contact_id: i64,
+    subscribed: bool,
+    source: SubscriptionSource,
+) -> Result<(), sqlx::Error> {
+    set_subscription(
+        conn,
+        contact_id,
+        subscribed,
+        source,
+        Some(actor.user_id),
+        actor.request_id.as_deref(),
+    )
+    .await
+}
+
+async fn set_subscription(
+    conn: &mut DbConnection,
+    contact_id: i64,
+    subscribed: bool,
+    source: SubscriptionSource,
+    user_id: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now().naive_utc();
+    let subscribed_at = subscribed.then_some(now);
+    let stored_source = subscribed.then_some(source.as_str());
+    let email: String = match BACKEND {
+        DbBackend::MySql => {
+            query!(
+                r#"
+                UPDATE contacts SET subscribed = $1, subscribed_at = $2, subscription_source = $3
+                WHERE id = $4
+                "#,
+                subscribed,
+                subscribed_at,
+                stored_source,
+                contact_id
+            )
+            .execute(&mut *conn)
+            .await?;
+            query_scalar!("SELECT email FROM contacts WHERE id = $1", contact_id)
+                .fetch_one(&mut *conn)
+                .await?
+        }
+        _ => {
+            query_scalar!(
+                r#"
+                UPDATE contacts SET subscribed = $1, subscribed_at = $2, subscription_source = $3
+                WHERE id = $4
+                RETURNING email
+                "#,
+                subscribed,
+                subscribed_at,
+                stored_source,
+                contact_id
+            )
+            .fetch_one(&mut *conn)
+            .await?
+        }
+    };
+
+    let action = if subscribed {
+        SubscriptionAction::Subscribed
+    } else {
+        SubscriptionAction::Unsubscribed
+    };
+    insert_subscription_event(
+        conn, contact_id, action, source, &email, user_id, request_id,
+    )
+    .await
+}
+
+async fn insert_subscription_event(
+    conn: &mut DbConnection,
+    contact_id: i64,
+    action: SubscriptionAction,
+    source: SubscriptionSource,
+    email: &str,
+    user_id: Option<i64>,
+    request_id: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    let action = action.as_str();
+    let source = source.as_str();
+    let created_at = Utc::now().naive_utc();
+    query!(
+        r#"
+        INSERT INTO subscription_events (contact_id, user_id, action, source, email, request_id, created_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        "#,
+        contact_id,
+        user_id,
+        action,
+        source,
+        email,
+        request_id,
+        created_at
+    )
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+#[derive(sqlx::FromRow)]
+struct Confirming {
+    email: String,
+    subscribed: bool,
+}
+
+pub struct SqlSubscriptionRepository {
+    db_pool: Pool,
+}
+
+impl SqlSubscriptionRepository {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl SubscriptionRepository for SqlSubscriptionRepository {
+    async fn record_request(
+        &self,
+        actor: &Actor,
+        contact_id: i64,
+        email: &str,
+    ) -> Result<(), RepositoryError> {
+        let mut conn = self.db_pool.acquire().await?;
+        insert_subscription_event(
+            &mut conn,
+            contact_id,
+            SubscriptionAction::Requested,
+            SubscriptionSource::DoubleOptIn,
+            email,
+            Some(actor.user_id),
+            actor.request_id.as_deref(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn subscription(
+        &self,
+        contact_id: i64,
+    ) -> Result<(Consent, Vec<SubscriptionEventDto>), RepositoryError> {
+        let mut conn = self.db_pool.acquire().await?;
+        let consent = query_as!(
+            Consent,
+            r#"
+            SELECT subscribed, subscribed_at, subscription_source AS "source: SubscriptionSource"
+            FROM contacts WHERE id = $1
+            "#,
+            contact_id
+        )
+        .fetch_one(&mut *conn)
+        .await?;
+        let events = query_as!(
+            SubscriptionEventDto,
+            r#"
+            SELECT e.id AS "id!", e.action AS "action: SubscriptionAction",
+                   e.source AS "source: SubscriptionSource", e.email, e.user_id,
+                   u.email AS "user_email?", e.request_id, e.created_at
+            FROM subscription_events e
+            LEFT JOIN users u ON u.id = e.user_id
+            WHERE e.contact_id = $1
+            ORDER BY e.id
+            "#,
+            contact_id
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        Ok((consent, events))
+    }
+
+    async fn recipient(&self, contact_id: i64) -> Result<Recipient, RepositoryError> {
+        Ok(query_as!(
+            Recipient,
+            "SELECT name, email, subscribed FROM contacts WHERE id = $1",
+            contact_id
+        )
+        .fetch_one(&self.db_pool)
+        .await?)
+    }
+
+    async fn confirm(
+        &self,
+        contact_id: i64,
+        request_id: Option<&str>,
+        link_is_valid: &(dyn for<'a> Fn(&'a str) -> bool + Sync),
+    ) -> Result<bool, RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let contact = query_as!(
+            Confirming,
+            "SELECT email, subscribed FROM contacts WHERE id = $1 AND deleted_at IS NULL",
+            contact_id
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(contact) = contact.filter(|contact| link_is_valid(&contact.email)) else {
+            return Ok(false);
+        };
+
+        if !contact.subscribed {
+            tracing::info!("Contact {} confirmed their subscription", contact_id);
+            set_subscription(
+                &mut tx,
+                contact_id,
+                true,
+                SubscriptionSource::DoubleOptIn,
+                None,
+                request_id,
+            )
+            .await?;
+            tx.commit().await?;
+        }
+        Ok(true)
+    }
+
+    async fn unsubscribe(
+        &self,
+        contact_id: i64,
+        request_id: Option<&str>,
+    ) -> Result<(), RepositoryError> {
+        let mut tx = self.db_pool.begin().await?;
+        let subscribed: Option<bool> =
+            query_scalar!("SELECT subscribed FROM contacts WHERE id = $1", contact_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if subscribed == Some(true) {
+            tracing::info!("Contact {} unsubscribed", contact_id);
+            set_subscription(
+                &mut tx,
+                contact_id,
+                false,
+                SubscriptionSource::UnsubscribeLink,
+                None,
+                request_id,
+            )
+            .await?;
+            tx.commit().await?;
+        }
+        Ok(())
+    }
+}
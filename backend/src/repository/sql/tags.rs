@@ -0,0 +1,146 @@
+use std::collections::BTreeSet;
+
+use async_trait::async_trait;
+use common::TagDto;
+
+use super::{last_insert_id, DbConnection, Pool, BACKEND};
+use crate::db::DbBackend;
+use crate::repository::{RepositoryError, TagRepository};
+
+/// Replaces the tags of a contact with `names`, creating any of the organization's tags that don't
+/// exist yet. Names are trimmed and duplicates ignored.
+pub(super) async fn set_contact_tags(
+    conn: &mut DbConnection,
+    org_id: i64,
+    contact_id: i64,
+    names: &[String],
+) -> Result<(), sqlx::Error> {
+    query!("DELETE FROM contact_tags WHERE contact_id = $1", contact_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let names: BTreeSet<&str> = names.iter().map(|name| name.trim()).collect();
+    for name in names {
+        match BACKEND {
+            DbBackend::MySql => {
+                sqlx::query(
+                    "INSERT INTO tags (org_id, name) VALUES (?, ?) ON DUPLICATE KEY UPDATE id = id",
+                )
+                .bind(org_id)
+                .bind(name)
+                .execute(&mut *conn)
+                .await?;
+            }
+            _ => {
+                query!(
+                    "INSERT INTO tags (org_id, name) VALUES ($1, $2) ON CONFLICT (org_id, name) DO NOTHING",
+                    org_id,
+                    name
+                )
+                .execute(&mut *conn)
+                .await?;
+            }
+        }
+
+        query!(
+            r#"
+            INSERT INTO contact_tags (contact_id, tag_id)
+            SELECT $1, id FROM tags WHERE org_id = $2 AND name = $3
+            "#,
+            contact_id,
+            org_id,
+            name
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub struct SqlTagRepository {
+    db_pool: Pool,
+}
+
+impl SqlTagRepository {
+    pub fn new(db_pool: Pool) -> Self {
+        Self { db_pool }
+    }
+}
+
+#[async_trait]
+impl TagRepository for SqlTagRepository {
+    async fn list(&self, org_id: i64) -> Result<Vec<TagDto>, RepositoryError> {
+        Ok(query_as!(
+            TagDto,
+            "SELECT id, name FROM tags WHERE org_id = $1 ORDER BY name",
+            org_id
+        )
+        .fetch_all(&self.db_pool)
+        .await?)
+    }
+
+    async fn create(&self, org_id: i64, name: &str) -> Result<TagDto, RepositoryError> {
+        if BACKEND == DbBackend::MySql {
+            let mut conn = self.db_pool.acquire().await?;
+            query!(
+                "INSERT INTO tags (org_id, name) VALUES ($1, $2)",
+                org_id,
+                name
+            )
+            .execute(&mut *conn)
+            .await?;
+            let id = last_insert_id(&mut conn).await?;
+            return Ok(TagDto {
+                id: Some(id),
+                name: name.to_string(),
+            });
+        }
+        Ok(query_as!(
+            TagDto,
+            "INSERT INTO tags (org_id, name) VALUES ($1, $2) RETURNING id, name",
+            org_id,
+            name
+        )
+        .fetch_one(&self.db_pool)
+        .await?)
+    }
+
+    async fn rename(
+        &self,
+        org_id: i64,
+        id: i64,
+        name: &str,
+    ) -> Result<Option<TagDto>, RepositoryError> {
+        if BACKEND == DbBackend::MySql {
+            let renamed = query!(
+                "UPDATE tags SET name = $1 WHERE id = $2 AND org_id = $3",
+                name,
+                id,
+                org_id
+            )
+            .execute(&self.db_pool)
+            .await?;
+            return Ok((renamed.rows_affected() > 0).then(|| TagDto {
+                id: Some(id),
+                name: name.to_string(),
+            }));
+        }
+        Ok(query_as!(
+            TagDto,
+            "UPDATE tags SET name = $1 WHERE id = $2 AND org_id = $3 RETURNING id, name",
+            name,
+            id,
+            org_id
+        )
+        .fetch_optional(&self.db_pool)
+        .await?)
+    }
+
+    async fn delete(&self, org_id: i64, id: i64) -> Result<bool, RepositoryError> {
+        let deleted = query!("DELETE FROM tags WHERE id = $1 AND org_id = $2", id, org_id)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+}
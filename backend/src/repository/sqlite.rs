@@ -0,0 +1,50 @@
+//! The SQL repositories on SQLite. The source in `sql/` is compiled once here and once in
+//! `postgres`; on SQLite its `query!`, `query_as!` and `query_scalar!` are the sqlx macros,
+//! checked at compile time against the schema recorded in `.sqlx`.
+
+use sqlx::{Sqlite, SqliteConnection};
+
+use crate::db::{DbBackend, DbPool};
+
+type Db = Sqlite;
+type DbConnection = SqliteConnection;
+type Pool = sqlx::SqlitePool;
+
+const BACKEND: DbBackend = DbBackend::Sqlite;
+
+/// The SQLite pool of `db_pool`. The repositories of this module are only made for SQLite
+/// pools, so anything else is a bug.
+fn typed(db_pool: &DbPool) -> &Pool {
+    match db_pool {
+        DbPool::Sqlite(pool) => pool,
+        _ => unreachable!(
+            "SQLite repositories were given a {} pool",
+            db_pool.backend().as_str()
+        ),
+    }
+}
+
+macro_rules! query {
+    ($($args:tt)*) => {
+        sqlx::query!($($args)*)
+    };
+}
+
+macro_rules! query_as {
+    ($($args:tt)*) => {
+        sqlx::query_as!($($args)*)
+    };
+}
+
+macro_rules! query_scalar {
+    ($($args:tt)*) => {
+        sqlx::query_scalar!($($args)*)
+    };
+}
+
+// Compiled once per backend on purpose.
+#[allow(clippy::duplicate_mod)]
+#[path = "sql/mod.rs"]
+mod sql;
+
+pub use sql::*;
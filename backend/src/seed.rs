@@ -0,0 +1,299 @@
+//! The `backend seed` command. It fills a development database with the users and contacts of
+//! a fixture file, or with made-up contacts, so there is something to look at right away.
+
+use std::path::Path;
+
+use chrono::NaiveDate;
+use common::{
+    ContactAddressDto, ContactDto, ContactEmailDto, ContactPhoneDto, ContactType,
+    SubscriptionSource,
+};
+use fake::faker::address::en::{CityName, StateAbbr, StreetName, ZipCode};
+use fake::faker::lorem::en::Sentence;
+use fake::faker::name::en::{FirstName, LastName};
+use fake::Fake;
+use rand::seq::IndexedRandom;
+use rand::Rng;
+use serde::Deserialize;
+use thiserror::Error;
+use validator::{Validate, ValidationErrors};
+
+use crate::admin::{self, AdminError};
+use crate::contact_types::check_contact_type;
+use crate::custom_fields::check_custom_fields;
+use crate::db::{DbPool, DbPools};
+use crate::history::Actor;
+use crate::repository::{ContactWrite, Repositories, RepositoryError, WriteOutcome};
+
+/// Tags given to made-up contacts, so filtering by tag has something to show.
+const FAKE_TAGS: [&str; 4] = ["family", "newsletter", "vip", "work"];
+
+/// Users to create, each with the contacts of their personal organization. Contacts take the
+/// shape the API accepts.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Fixture {
+    #[serde(default)]
+    pub users: Vec<FixtureUser>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FixtureUser {
+    pub email: String,
+    pub password: String,
+    /// Whether the user is a server administrator.
+    #[serde(default)]
+    pub admin: bool,
+    #[serde(default)]
+    pub contacts: Vec<ContactDto>,
+}
+
+#[derive(Debug, Error)]
+pub enum SeedError {
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid fixture: {0}")]
+    Parse(#[from] serde_yaml::Error),
+
+    #[error(transparent)]
+    Admin(#[from] AdminError),
+
+    #[error("{email} has no organization to add contacts to")]
+    NoOrganization { email: String },
+
+    #[error("The contact {contact} of {email} is invalid: {errors}")]
+    InvalidContact {
+        email: String,
+        contact: String,
+        errors: ValidationErrors,
+    },
+
+    #[error("{email} already has a contact with the email {contact}")]
+    DuplicateContact { email: String, contact: String },
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    Repository(#[from] RepositoryError),
+}
+
+impl Fixture {
+    /// Parses a fixture written in YAML, or in JSON, which YAML reads as well.
+    pub fn parse(text: &str) -> Result<Self, SeedError> {
+        Ok(serde_yaml::from_str(text)?)
+    }
+
+    pub fn read(path: &Path) -> Result<Self, SeedError> {
+        let text = std::fs::read_to_string(path).map_err(|source| SeedError::Read {
+            path: path.display().to_string(),
+            source,
+        })?;
+        Self::parse(&text)
+    }
+}
+
+/// What a seed run added.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SeedReport {
+    pub users: usize,
+    pub contacts: usize,
+}
+
+/// Creates the users of the fixture with their contacts. Returns the IDs of the new users, in
+/// fixture order, along with the counts. Stops at the first user or contact that can't be
+/// stored, keeping what was added before it.
+pub async fn load(
+    db_pool: &DbPool,
+    fixture: &Fixture,
+) -> Result<(Vec<i64>, SeedReport), SeedError> {
+    let repos = Repositories::sql(DbPools::new(db_pool.clone()));
+    let mut user_ids = Vec::with_capacity(fixture.users.len());
+    let mut report = SeedReport::default();
+    for user in &fixture.users {
+        let user_id = admin::create_user(db_pool, &user.email, &user.password, user.admin).await?;
+        report.users += 1;
+        report.contacts += add_contacts(&repos, user_id, &user.contacts).await?;
+        user_ids.push(user_id);
+    }
+    Ok((user_ids, report))
+}
+
+/// Adds `count` made-up contacts to the personal organization of each of the users. Returns
+/// the number of contacts added.
+pub async fn generate(
+    db_pool: &DbPool,
+    user_ids: &[i64],
+    count: usize,
+) -> Result<usize, SeedError> {
+    let repos = Repositories::sql(DbPools::new(db_pool.clone()));
+    let contact_types: Vec<ContactType> = repos
+        .contacts
+        .enabled_contact_types()
+        .await?
+        .into_iter()
+        .collect();
+    let mut added = 0;
+    for &user_id in user_ids {
+        let org_id = personal_org(&repos, user_id).await?;
+        // Numbering the emails after the contacts already stored keeps repeated runs from
+        // making the same email twice.
+        let stored = repos.contacts.count(org_id).await?;
+        let contacts: Vec<ContactDto> = (0..count)
+            .map(|i| fake_contact(&contact_types, stored as usize + i + 1))
+            .collect();
+        added += add_contacts(&repos, user_id, &contacts).await?;
+    }
+    Ok(added)
+}
+
+/// The organization made with the user, which is their oldest membership.
+async fn personal_org(repos: &Repositories, user_id: i64) -> Result<i64, SeedError> {
+    match repos.users.default_org(user_id).await? {
+        Some(org_id) => Ok(org_id),
+        None => Err(SeedError::NoOrganization {
+            email: user_email(repos, user_id).await?,
+        }),
+    }
+}
+
+async fn user_email(repos: &Repositories, user_id: i64) -> Result<String, SeedError> {
+    let user = repos.users.find_by_id(user_id).await?;
+    Ok(user.ok_or(sqlx::Error::RowNotFound)?.email)
+}
+
+/// Adds contacts to the user's personal organization as created by them, checked like the API
+/// checks them. Either all of them are added or none.
+async fn add_contacts(
+    repos: &Repositories,
+    user_id: i64,
+    contacts: &[ContactDto],
+) -> Result<usize, SeedError> {
+    if contacts.is_empty() {
+        return Ok(0);
+    }
+    let org_id = personal_org(repos, user_id).await?;
+    let enabled_types = repos.contacts.enabled_contact_types().await?;
+    let custom_fields = repos.contacts.custom_fields(org_id).await?;
+    let actor = Actor {
+        user_id,
+        request_id: None,
+    };
+
+    for contact in contacts {
+        let validation = contact
+            .validate()
+            .and_then(|_| check_contact_type(&enabled_types, contact.contact_type))
+            .and_then(|_| check_custom_fields(&custom_fields, &contact.custom_fields));
+        if let Err(errors) = validation {
+            return Err(SeedError::InvalidContact {
+                email: user_email(repos, user_id).await?,
+                contact: contact.email.clone(),
+                errors,
+            });
+        }
+    }
+
+    let writes: Vec<ContactWrite<'_>> = contacts
+        .iter()
+        .map(|contact| ContactWrite::Create {
+            contact,
+            source: SubscriptionSource::Import,
+        })
+        .collect();
+    let mut outcomes = repos
+        .contacts
+        .write_batch(&actor, org_id, &writes, true)
+        .await?;
+    // All or nothing: only the last write can have failed.
+    match outcomes.pop() {
+        Some(WriteOutcome::Failed(RepositoryError::Duplicate(_))) => {
+            Err(SeedError::DuplicateContact {
+                email: user_email(repos, user_id).await?,
+                contact: contacts[outcomes.len()].email.clone(),
+            })
+        }
+        Some(WriteOutcome::Failed(e)) => Err(e.into()),
+        _ => Ok(contacts.len()),
+    }
+}
+
+/// A made-up contact whose email is made unique by `n`.
+fn fake_contact(contact_types: &[ContactType], n: usize) -> ContactDto {
+    let mut rng = rand::rng();
+    let first: String = FirstName().fake_with_rng(&mut rng);
+    let last: String = LastName().fake_with_rng(&mut rng);
+    let handle = format!("{first}.{last}.{n}")
+        .to_lowercase()
+        .replace(|c: char| !c.is_ascii_alphanumeric() && c != '.', "");
+
+    let birthday = rng
+        .random_bool(0.6)
+        .then(|| {
+            NaiveDate::from_ymd_opt(
+                rng.random_range(1950..=2005),
+                rng.random_range(1..=12),
+                rng.random_range(1..=28),
+            )
+        })
+        .flatten();
+    let phones = if rng.random_bool(0.7) {
+        vec![ContactPhoneDto {
+            label: "mobile".to_string(),
+            number: format!("+1{}", rng.random_range(2_000_000_000u64..=9_999_999_999)),
+        }]
+    } else {
+        Vec::new()
+    };
+    let addresses = if rng.random_bool(0.5) {
+        vec![ContactAddressDto {
+            label: "home".to_string(),
+            street: format!(
+                "{} {}",
+                rng.random_range(1..=9999),
+                StreetName().fake_with_rng::<String, _>(&mut rng)
+            ),
+            city: CityName().fake_with_rng(&mut rng),
+            region: StateAbbr().fake_with_rng(&mut rng),
+            postal_code: ZipCode().fake_with_rng(&mut rng),
+            country: "United States".to_string(),
+        }]
+    } else {
+        Vec::new()
+    };
+    let emails = if rng.random_bool(0.2) {
+        vec![ContactEmailDto {
+            label: "work".to_string(),
+            email: format!("{handle}@example.org"),
+        }]
+    } else {
+        Vec::new()
+    };
+    let mut tags: Vec<String> = FAKE_TAGS
+        .iter()
+        .filter(|_| rng.random_bool(0.25))
+        .map(|tag| tag.to_string())
+        .collect();
+    tags.sort();
+
+    ContactDto {
+        name: format!("{first} {last}"),
+        email: format!("{handle}@example.com"),
+        birthday,
+        subscribed: rng.random_bool(0.5),
+        contact_type: contact_types.choose(&mut rng).copied().unwrap_or_default(),
+        tags,
+        emails,
+        phones,
+        addresses,
+        notes: rng
+            .random_bool(0.3)
+            .then(|| Sentence(4..10).fake_with_rng(&mut rng)),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,338 @@
+use axum::{
+    debug_handler,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use common::{
+    CreateShareRequest, OrgRole, ShareDto, SharePermission, SharedContactDto, UpdateShareRequest,
+};
+use validator::Validate;
+
+use crate::error::AppError;
+use crate::extractors::AuthUser;
+use crate::repository::{ContactFilter, RepositoryError};
+use crate::web_server::{AppState, Pagination};
+
+/// How a user may reach a contact outside the trash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContactAccess {
+    /// The contact belongs to the organization the user is working in.
+    Member(OrgRole),
+    /// The contact belongs to another organization, which shared it with the user.
+    Shared(SharePermission),
+}
+
+impl ContactAccess {
+    pub fn can_write(self) -> bool {
+        match self {
+            ContactAccess::Member(role) => role.can_write(),
+            ContactAccess::Shared(permission) => permission == SharePermission::Write,
+        }
+    }
+}
+
+/// Checks the access a user has to a contact, as looked up by `ContactRepository::access`,
+/// against the `needed` permission and returns the contact's organization, whose ID scopes the
+/// queries on it. Contacts the user can't see at all are reported as missing, so that their
+/// existence isn't leaked; read-only access that is used to write is forbidden.
+pub(crate) fn check_access(
+    access: Option<(i64, ContactAccess)>,
+    needed: SharePermission,
+) -> Result<i64, AppError> {
+    match access {
+        None => Err(AppError::NotFound),
+        Some((_, access)) if needed == SharePermission::Write && !access.can_write() => {
+            Err(AppError::Forbidden)
+        }
+        Some((org_id, _)) => Ok(org_id),
+    }
+}
+
+// --- API Handlers ---
+
+/// ## List the shares of the current organization
+#[utoipa::path(
+    get,
+    path = "/api/v1/shares",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Shares of the organization's contacts, oldest first", body = Vec<ShareDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn get_shares(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<ShareDto>>, AppError> {
+    tracing::info!("Fetching shares of organization {}", user.org_id);
+
+    let result = state.repos.shares.list(user.org_id).await;
+
+    match result {
+        Ok(shares) => Ok(Json(shares)),
+        Err(e) => {
+            tracing::error!("Failed to fetch shares: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch shares".to_string(),
+            ))
+        }
+    }
+}
+
+/// ## List the shares granted to the user
+#[utoipa::path(
+    get,
+    path = "/api/v1/shares/received",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Shares granted to the user, oldest first", body = Vec<ShareDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn get_received_shares(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<ShareDto>>, AppError> {
+    tracing::info!("Fetching shares granted to user {}", user.id);
+
+    let result = state.repos.shares.received(user.id).await;
+
+    match result {
+        Ok(shares) => Ok(Json(shares)),
+        Err(e) => {
+            tracing::error!("Failed to fetch received shares: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch shares".to_string(),
+            ))
+        }
+    }
+}
+
+/// ## Share a contact or the whole contact book
+/// Grants the user registered with `email` access to one of the current organization's
+/// contacts, or to all of them when no `contactId` is given. A contact shared both ways gets
+/// the broader permission. Members of the organization already have access and can't be
+/// granted more.
+#[utoipa::path(
+    post,
+    path = "/api/v1/shares",
+    request_body = CreateShareRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 201, description = "Share granted", body = ShareDto),
+        (status = 400, description = "The user is a member of the organization"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+        (status = 404, description = "Contact or user not found"),
+        (status = 409, description = "This is already shared with the user"),
+        (status = 422, description = "Validation error"),
+    )
+)]
+#[debug_handler]
+pub async fn create_share(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(request): Json<CreateShareRequest>,
+) -> Result<(StatusCode, Json<ShareDto>), AppError> {
+    tracing::info!(
+        "Sharing {:?} of organization {} with {} ({:?})",
+        request.contact_id,
+        user.org_id,
+        request.email,
+        request.permission
+    );
+
+    request.validate()?;
+    user.require_write()?;
+
+    let grantee_id = state
+        .repos
+        .users
+        .find_by_email(&request.email)
+        .await?
+        .ok_or(AppError::NotFound)?
+        .id;
+    let is_member = state
+        .repos
+        .users
+        .role_in(grantee_id, user.org_id)
+        .await?
+        .is_some();
+    if is_member {
+        return Err(AppError::BadRequest(
+            "Contacts cannot be shared with members of the organization".to_string(),
+        ));
+    }
+    if let Some(contact_id) = request.contact_id {
+        state
+            .repos
+            .contacts
+            .find(user.org_id, contact_id)
+            .await?
+            .ok_or(AppError::NotFound)?;
+    }
+
+    let result = state
+        .repos
+        .shares
+        .create(
+            user.org_id,
+            grantee_id,
+            request.contact_id,
+            request.permission,
+        )
+        .await;
+    let share = match result {
+        Ok(share) => share,
+        Err(RepositoryError::Duplicate(_)) => {
+            return Err(AppError::Conflict(
+                "This is already shared with the user".to_string(),
+            ))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok((StatusCode::CREATED, Json(share)))
+}
+
+/// ## Change the permission of a share
+#[utoipa::path(
+    put,
+    path = "/api/v1/shares/{id}",
+    request_body = UpdateShareRequest,
+    params(
+        ("id" = i64, Path, description = "Share ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Share updated", body = ShareDto),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+        (status = 404, description = "Share not found"),
+    )
+)]
+#[debug_handler]
+pub async fn update_share(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+    Json(request): Json<UpdateShareRequest>,
+) -> Result<Json<ShareDto>, AppError> {
+    tracing::info!(
+        "Setting permission of share {} of organization {} to {:?}",
+        id,
+        user.org_id,
+        request.permission
+    );
+
+    user.require_write()?;
+    let share = state
+        .repos
+        .shares
+        .update(user.org_id, id, request.permission)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(share))
+}
+
+/// ## Revoke a share
+/// Members who may change the organization's contacts revoke its shares, and grantees give up
+/// the shares they received.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/shares/{id}",
+    params(
+        ("id" = i64, Path, description = "Share ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Share revoked"),
+        (status = 401, description = "Authentication required"),
+        (status = 404, description = "Share not found"),
+    )
+)]
+#[debug_handler]
+pub async fn delete_share(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+) -> Result<StatusCode, AppError> {
+    tracing::info!("Revoking share {} for user {}", id, user.id);
+
+    let revoking_org = user.role.can_write().then_some(user.org_id);
+    let result = state.repos.shares.delete(id, revoking_org, user.id).await;
+
+    match result {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(AppError::NotFound),
+        Err(e) => {
+            tracing::error!("Failed to revoke share: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to revoke share".to_string(),
+            ))
+        }
+    }
+}
+
+/// ## List contacts shared with the user
+/// Contacts of other organizations outside the trash that were shared with the caller, one by
+/// one or with a whole contact book, ordered by ID. Accepts the filters of the contact list.
+#[utoipa::path(
+    get,
+    path = "/api/v1/contacts/shared",
+    params(Pagination, ContactFilter),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Contacts shared with the user", body = Vec<SharedContactDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn get_shared_contacts(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(pagination): Query<Pagination>,
+    Query(filter): Query<ContactFilter>,
+) -> Result<Json<Vec<SharedContactDto>>, AppError> {
+    let (per_page, offset) = pagination.limit_and_offset();
+
+    tracing::info!(
+        "Fetching contacts shared with user {}, per_page: {}, offset: {}, filter: {:?}",
+        user.id,
+        per_page,
+        offset,
+        filter
+    );
+
+    let result = state
+        .repos
+        .contacts
+        .list_shared(user.id, &filter, per_page, offset)
+        .await;
+
+    match result {
+        Ok(shared) => Ok(Json(shared)),
+        Err(e) => {
+            tracing::error!("Failed to fetch shared contacts: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch shared contacts".to_string(),
+            ))
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::config::{S3Config, StorageBackend, StorageConfig};
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Unexpected response status: {0}")]
+    Status(reqwest::StatusCode),
+
+    #[error("Invalid storage key: {0}")]
+    InvalidKey(String),
+
+    #[error("Storage is misconfigured: {0}")]
+    Config(String),
+}
+
+/// Where uploaded files live. Keys are generated by the server and made of URL-safe path
+/// segments, like `attachments/<uuid>`.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Stores `data` under `key`, replacing any object already there.
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<(), StorageError>;
+
+    /// The object stored under `key`, or `None` if there is none.
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError>;
+
+    /// Removes the object stored under `key`. Removing a missing object is not an error.
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Builds the storage selected by `config.backend`.
+pub fn from_config(config: &StorageConfig) -> Result<Arc<dyn Storage>, StorageError> {
+    match config.backend {
+        StorageBackend::Local => Ok(Arc::new(LocalStorage::new(&config.local_path))),
+        StorageBackend::S3 => {
+            let s3 = config.s3.clone().ok_or_else(|| {
+                StorageError::Config("`storage.s3` must be set for the s3 backend".to_string())
+            })?;
+            Ok(Arc::new(S3Storage::new(s3)))
+        }
+    }
+}
+
+fn check_key(key: &str) -> Result<(), StorageError> {
+    let valid = !key.is_empty()
+        && key.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment != "."
+                && segment != ".."
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(StorageError::InvalidKey(key.to_string()))
+    }
+}
+
+// --- Local Filesystem ---
+
+/// Keeps objects as files below a root directory, one file per key.
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl AsRef<Path>) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        check_key(key)?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: Vec<u8>, _content_type: &str) -> Result<(), StorageError> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Readers never see a half-written file.
+        let partial = path.with_extension("partial");
+        tokio::fs::write(&partial, data).await?;
+        tokio::fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match tokio::fs::read(self.path(key)?).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// --- S3-Compatible Object Storage ---
+
+/// Keeps objects in a bucket of an S3-compatible service, such as AWS S3 or MinIO. Requests
+/// use path-style URLs (`<endpoint>/<bucket>/<key>`) and are signed with AWS Signature V4.
+pub struct S3Storage {
+    client: reqwest::Client,
+    config: S3Config,
+}
+
+impl S3Storage {
+    pub fn new(config: S3Config) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    /// Starts a request for the object under `key`, signed for the given payload.
+    fn request(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        payload: &[u8],
+    ) -> Result<reqwest::RequestBuilder, StorageError> {
+        check_key(key)?;
+        let endpoint = self.config.endpoint.trim_end_matches('/');
+        let url = reqwest::Url::parse(&format!("{endpoint}/{}/{key}", self.config.bucket))
+            .map_err(|_| StorageError::InvalidKey(key.to_string()))?;
+        let host = match url.port() {
+            Some(port) => format!("{}:{port}", url.host_str().unwrap_or_default()),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(payload));
+        let scope = format!("{date}/{}/s3/aws4_request", self.config.region);
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+
+        let canonical_request = format!(
+            "{method}\n{}\n\nhost:{host}\nx-amz-content-sha256:{payload_hash}\nx-amz-date:{amz_date}\n\n{signed_headers}\n{payload_hash}",
+            url.path()
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let secret = format!("AWS4{}", self.config.secret_access_key);
+        let signing_key = [
+            date.as_str(),
+            self.config.region.as_str(),
+            "s3",
+            "aws4_request",
+        ]
+        .iter()
+        .fold(secret.into_bytes(), |key, part| {
+            hmac_sha256(&key, part.as_bytes())
+        });
+        let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.config.access_key_id
+        );
+        Ok(self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(reqwest::header::AUTHORIZATION, authorization))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[async_trait]
+impl Storage for S3Storage {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> Result<(), StorageError> {
+        let response = self
+            .request(reqwest::Method::PUT, key, &data)?
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(data)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(StorageError::Status(response.status()));
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        let response = self.request(reqwest::Method::GET, key, &[])?.send().await?;
+        match response.status() {
+            status if status.is_success() => Ok(Some(response.bytes().await?.to_vec())),
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            status => Err(StorageError::Status(status)),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let response = self
+            .request(reqwest::Method::DELETE, key, &[])?
+            .send()
+            .await?;
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            reqwest::StatusCode::NOT_FOUND => Ok(()),
+            status => Err(StorageError::Status(status)),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use common::TagDto;
+use validator::Validate;
+
+use crate::error::AppError;
+use crate::extractors::AuthUser;
+use crate::repository::RepositoryError;
+use crate::web_server::AppState;
+
+/// Maps a failed tag write to a 409 for a taken name, or a logged 500.
+fn tag_write_error(e: RepositoryError, message: &str) -> AppError {
+    if let RepositoryError::Duplicate(_) = e {
+        return AppError::Conflict("A tag with this name already exists".to_string());
+    }
+    tracing::error!("{}: {}", message, e);
+    AppError::InternalServerError(message.to_string())
+}
+
+// --- API Handlers ---
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/tags",
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "The organization's tags, sorted by name", body = Vec<TagDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn get_tags(
+    State(state): State<AppState>,
+    user: AuthUser,
+) -> Result<Json<Vec<TagDto>>, AppError> {
+    tracing::info!("Fetching tags for organization {}", user.org_id);
+
+    let result = state.repos.tags.list(user.org_id).await;
+
+    match result {
+        Ok(tags) => Ok(Json(tags)),
+        Err(e) => {
+            tracing::error!("Failed to fetch tags: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch tags".to_string(),
+            ))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/tags",
+    request_body = TagDto,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 201, description = "Tag created successfully", body = TagDto),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+        (status = 409, description = "A tag with this name already exists"),
+        (status = 422, description = "Validation error"),
+    )
+)]
+#[debug_handler]
+pub async fn create_tag(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Json(tag): Json<TagDto>,
+) -> Result<(StatusCode, Json<TagDto>), AppError> {
+    tracing::info!(
+        "Creating tag {:?} for organization {}",
+        tag.name,
+        user.org_id
+    );
+
+    user.require_write()?;
+    tag.validate()?;
+    let name = tag.name.trim();
+
+    let result = state.repos.tags.create(user.org_id, name).await;
+
+    match result {
+        Ok(created) => Ok((StatusCode::CREATED, Json(created))),
+        Err(e) => Err(tag_write_error(e, "Failed to create tag")),
+    }
+}
+
+/// ## Rename a tag
+/// The new name shows up on every contact the tag is assigned to.
+#[utoipa::path(
+    put,
+    path = "/api/v1/tags/{id}",
+    request_body = TagDto,
+    params(
+        ("id" = i64, Path, description = "Tag ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Tag renamed successfully", body = TagDto),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+        (status = 404, description = "Tag not found"),
+        (status = 409, description = "A tag with this name already exists"),
+        (status = 422, description = "Validation error"),
+    )
+)]
+#[debug_handler]
+pub async fn update_tag(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+    Json(tag): Json<TagDto>,
+) -> Result<Json<TagDto>, AppError> {
+    tracing::info!("Renaming tag {} for organization {}", id, user.org_id);
+
+    user.require_write()?;
+    tag.validate()?;
+    let name = tag.name.trim();
+
+    let result = state.repos.tags.rename(user.org_id, id, name).await;
+
+    match result {
+        Ok(Some(updated)) => Ok(Json(updated)),
+        Ok(None) => Err(AppError::NotFound),
+        Err(e) => Err(tag_write_error(e, "Failed to update tag")),
+    }
+}
+
+/// ## Delete a tag
+/// The tag is removed from every contact it was assigned to; the contacts are kept.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/tags/{id}",
+    params(
+        ("id" = i64, Path, description = "Tag ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 204, description = "Tag deleted successfully"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+        (status = 404, description = "Tag not found"),
+    )
+)]
+#[debug_handler]
+pub async fn delete_tag(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+) -> Result<StatusCode, AppError> {
+    tracing::info!("Deleting tag {} for organization {}", id, user.org_id);
+
+    user.require_write()?;
+    let result = state.repos.tags.delete(user.org_id, id).await;
+
+    match result {
+        Ok(true) => Ok(StatusCode::NO_CONTENT),
+        Ok(false) => Err(AppError::NotFound),
+        Err(e) => {
+            tracing::error!("Failed to delete tag: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to delete tag".to_string(),
+            ))
+        }
+    }
+}
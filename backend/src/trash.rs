@@ -0,0 +1,152 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::{
+    debug_handler,
+    extract::{Path, Query, State},
+    Json,
+};
+use chrono::Utc;
+use common::{ContactDto, TrashedContactDto};
+use tokio::task::JoinHandle;
+
+use crate::config::TrashConfig;
+use crate::error::AppError;
+use crate::extractors::{AuthUser, RequestId};
+use crate::history::Actor;
+use crate::repository::{ContactRepository, RepositoryError};
+use crate::storage::Storage;
+use crate::web_server::{contact_repository_error, AppState, Pagination};
+
+// --- API Handlers ---
+
+/// ## List the trash
+/// Deleted contacts of the current organization, most recently deleted first. They can be
+/// restored until the retention period runs out and the purge task removes them for good.
+#[utoipa::path(
+    get,
+    path = "/api/v1/contacts/trash",
+    params(Pagination),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Deleted contacts", body = Vec<TrashedContactDto>),
+        (status = 401, description = "Authentication required"),
+    )
+)]
+#[debug_handler]
+pub async fn list_trash(
+    State(state): State<AppState>,
+    user: AuthUser,
+    Query(pagination): Query<Pagination>,
+) -> Result<Json<Vec<TrashedContactDto>>, AppError> {
+    let (limit, offset) = pagination.limit_and_offset();
+    tracing::info!(
+        "Fetching trash for user {}, limit: {}, offset: {}",
+        user.id,
+        limit,
+        offset
+    );
+
+    let result = state
+        .repos
+        .contacts
+        .list_trash(user.org_id, limit, offset)
+        .await;
+
+    match result {
+        Ok(contacts) => Ok(Json(contacts)),
+        Err(e) => {
+            tracing::error!("Failed to fetch trash: {}", e);
+            Err(AppError::InternalServerError(
+                "Failed to fetch trash".to_string(),
+            ))
+        }
+    }
+}
+
+/// ## Restore a deleted contact
+/// Moves a contact out of the trash. Fails with 409 if another contact has taken its email
+/// in the meantime.
+#[utoipa::path(
+    post,
+    path = "/api/v1/contacts/{id}/restore",
+    params(
+        ("id" = i64, Path, description = "Contact ID")
+    ),
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Contact restored", body = ContactDto),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+        (status = 404, description = "Contact not found in the trash"),
+        (status = 409, description = "A contact with this email already exists"),
+    )
+)]
+#[debug_handler]
+pub async fn restore_contact(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    user: AuthUser,
+    request_id: RequestId,
+) -> Result<Json<ContactDto>, AppError> {
+    tracing::info!("Restoring contact with id: {} for user {}", id, user.id);
+
+    user.require_write()?;
+    let actor = Actor::new(&user, request_id);
+    let result = state.repos.contacts.restore(&actor, user.org_id, id).await;
+
+    match result {
+        Ok(Some(contact)) => Ok(Json(contact)),
+        Ok(None) => Err(AppError::NotFound),
+        Err(e) => Err(contact_repository_error(e, "Failed to restore contact")),
+    }
+}
+
+// --- Purging ---
+
+/// Permanently deletes contacts that have been in the trash for longer than
+/// `retention_days`, along with their attachments and avatars. Returns the number of purged
+/// contacts.
+pub async fn purge_expired_contacts(
+    contacts: &dyn ContactRepository,
+    storage: &dyn Storage,
+    retention_days: i64,
+) -> Result<u64, RepositoryError> {
+    let cutoff = (Utc::now() - chrono::Duration::days(retention_days)).naive_utc();
+    let (purged, keys) = contacts.purge_trash(cutoff).await?;
+    for key in keys {
+        if let Err(e) = storage.delete(&key).await {
+            tracing::warn!("Failed to delete stored object {}: {}", key, e);
+        }
+    }
+
+    Ok(purged)
+}
+
+/// Spawns the background task that purges expired contacts from the trash every
+/// `purge_interval_minutes`, starting right away.
+pub fn spawn_purge_task(
+    contacts: Arc<dyn ContactRepository>,
+    storage: Arc<dyn Storage>,
+    config: TrashConfig,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let period = Duration::from_secs(config.purge_interval_minutes.max(1) * 60);
+        let mut interval = tokio::time::interval(period);
+
+        loop {
+            interval.tick().await;
+            let purged =
+                purge_expired_contacts(contacts.as_ref(), storage.as_ref(), config.retention_days);
+            match purged.await {
+                Ok(0) => {}
+                Ok(purged) => tracing::info!("Purged {} contacts from the trash", purged),
+                Err(e) => tracing::error!("Failed to purge the trash: {}", e),
+            }
+        }
+    })
+}
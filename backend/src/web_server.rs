@@ -2,14 +2,14 @@ use std::{sync::Arc, time::Duration};
 
 use axum::{
     debug_handler,
-    extract::{Path, State},
+    extract::{DefaultBodyLimit, Path, State},
     http::{header, HeaderValue, Method, StatusCode},
     middleware,
-    routing::{get, get_service, post},
+    routing::{get, get_service, post, put},
     Json, Router,
 };
 
-use crate::db::DbPool;
+use crate::db::DbPools;
 use serde::Deserialize;
 use tower_http::{
     cors::CorsLayer,
@@ -21,16 +21,35 @@ use tower_http::{
 use tracing;
 use validator::Validate;
 
+use crate::contact_types::check_contact_type;
+use crate::custom_fields::check_custom_fields;
 use crate::error::AppError;
-use crate::extractors::AuthUser;
-use crate::{auth, config::AppConfig};
-use common::ContactDto;
+use crate::extractors::{AuthUser, RequestId};
+use crate::history::Actor;
+use crate::mailer::Mailer;
+use crate::repository::{ContactFilter, ContactWrite, Repositories, RepositoryError, WriteOutcome};
+use crate::sharing::check_access;
+use crate::storage::Storage;
+use crate::{
+    attachments, auth, backup, config::AppConfig, contact_types, custom_fields, duplicates, export,
+    history, import, newsletter, orgs, sharing, tags, trash,
+};
+use common::{
+    AddMemberRequest, AttachmentDto, BackupDto, BulkContactOperation, BulkContactRequest,
+    BulkContactResponse, BulkItemResult, BulkItemStatus, ContactAddressDto, ContactDto,
+    ContactEmailDto, ContactEventAction, ContactEventDto, ContactPhoneDto, ContactType,
+    ContactTypeDto, CreateOrgRequest, CreateShareRequest, CustomFieldDto, CustomFieldType,
+    DuplicateCandidateDto, DuplicateContactDto, DuplicateReason, ImportReport, ImportRowResult,
+    ImportRowStatus, MembershipDto, MergeContactsRequest, MergeStrategy, OrgDto, OrgRole, ShareDto,
+    SharePermission, SharedContactDto, SubscriptionAction, SubscriptionDto, SubscriptionEventDto,
+    SubscriptionSource, TagDto, TrashedContactDto, UpdateMemberRequest, UpdateShareRequest,
+};
 
 use tower_governor::{governor::GovernorConfigBuilder, GovernorLayer};
 
 use common::Credentials;
 use common::LoginResponse;
-use utoipa::OpenApi;
+use utoipa::{IntoParams, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
 #[derive(OpenApi)]
@@ -44,11 +63,103 @@ use utoipa_swagger_ui::SwaggerUi;
         create_contact,
         get_contact,
         update_contact,
-        delete_contact
+        delete_contact,
+        bulk_contacts,
+        import::import_contacts,
+        export::export_contacts,
+        trash::list_trash,
+        trash::restore_contact,
+        attachments::get_attachments,
+        attachments::upload_attachment,
+        attachments::download_attachment,
+        attachments::delete_attachment,
+        attachments::get_avatar,
+        attachments::set_avatar,
+        attachments::delete_avatar,
+        duplicates::find_duplicates,
+        duplicates::merge_contacts,
+        history::get_contact_history,
+        newsletter::get_subscription,
+        newsletter::request_subscription,
+        newsletter::confirm_subscription_page,
+        newsletter::confirm_subscription,
+        newsletter::unsubscribe_page,
+        newsletter::unsubscribe,
+        orgs::get_orgs,
+        orgs::create_org,
+        orgs::switch_org,
+        orgs::get_members,
+        orgs::add_member,
+        orgs::update_member,
+        orgs::remove_member,
+        sharing::get_shares,
+        sharing::get_received_shares,
+        sharing::create_share,
+        sharing::update_share,
+        sharing::delete_share,
+        sharing::get_shared_contacts,
+        tags::get_tags,
+        tags::create_tag,
+        tags::update_tag,
+        tags::delete_tag,
+        contact_types::get_contact_types,
+        custom_fields::get_custom_fields,
+        custom_fields::create_custom_field,
+        custom_fields::update_custom_field,
+        custom_fields::delete_custom_field,
+        backup::create_backup,
+        backup::get_backups
     ),
     // 👇 All components are now in a single block
     components(
-        schemas(ContactDto, Credentials, LoginResponse),
+        schemas(
+            ContactDto,
+            ContactEmailDto,
+            ContactPhoneDto,
+            ContactAddressDto,
+            ContactType,
+            ContactTypeDto,
+            CustomFieldDto,
+            CustomFieldType,
+            TrashedContactDto,
+            AttachmentDto,
+            TagDto,
+            ContactEventAction,
+            ContactEventDto,
+            SubscriptionSource,
+            SubscriptionAction,
+            SubscriptionEventDto,
+            SubscriptionDto,
+            Credentials,
+            LoginResponse,
+            BulkContactOperation,
+            BulkContactRequest,
+            BulkContactResponse,
+            BulkItemResult,
+            BulkItemStatus,
+            ImportReport,
+            ImportRowResult,
+            ImportRowStatus,
+            DuplicateReason,
+            DuplicateContactDto,
+            DuplicateCandidateDto,
+            MergeStrategy,
+            MergeContactsRequest,
+            OrgRole,
+            OrgDto,
+            CreateOrgRequest,
+            MembershipDto,
+            AddMemberRequest,
+            UpdateMemberRequest,
+            SharePermission,
+            ShareDto,
+            CreateShareRequest,
+            UpdateShareRequest,
+            SharedContactDto,
+            BackupDto,
+            import::ImportFormat,
+            export::ExportFormat
+        ),
     ),
     tags(
         (name = "Cornerstone API", description = "Full-stack Rust template API")
@@ -62,8 +173,11 @@ struct ApiDoc;
 
 #[derive(Clone)]
 pub struct AppState {
-    pub db_pool: DbPool,
+    pub db_pools: DbPools,
     pub app_config: AppConfig,
+    pub storage: Arc<dyn Storage>,
+    pub mailer: Arc<dyn Mailer>,
+    pub repos: Repositories,
 }
 
 fn create_static_router() -> Router {
@@ -131,19 +245,98 @@ pub fn create_router(app_state: AppState) -> Router {
         .route("/register", post(auth::register))
         .route("/login", post(auth::login))
         .route("/refresh", post(auth::refresh))
+        .route(
+            "/newsletter/confirm",
+            get(newsletter::confirm_subscription_page).post(newsletter::confirm_subscription),
+        )
+        .route(
+            "/newsletter/unsubscribe",
+            get(newsletter::unsubscribe_page).post(newsletter::unsubscribe),
+        )
         // Apply the rate-limiting layer to public routes
         .layer(GovernorLayer {
             config: governor_conf,
         });
 
+    // Uploads larger than the configured limit are rejected while they are read.
+    let upload_limit =
+        app_state.app_config.storage.max_upload_bytes + attachments::UPLOAD_OVERHEAD_BYTES;
+
     // Protected routes that require authentication
     let protected_routes = Router::new()
         .route("/logout", post(auth::logout))
         .route("/contacts", get(get_contacts).post(create_contact))
+        .route("/contacts/bulk", post(bulk_contacts))
+        .route("/contacts/export", get(export::export_contacts))
+        .route("/contacts/trash", get(trash::list_trash))
+        .route("/contacts/duplicates", get(duplicates::find_duplicates))
+        .route("/contacts/merge", post(duplicates::merge_contacts))
+        .route("/contacts/shared", get(sharing::get_shared_contacts))
+        .route(
+            "/contacts/import",
+            post(import::import_contacts).layer(DefaultBodyLimit::max(import::MAX_IMPORT_BYTES)),
+        )
         .route(
             "/contacts/{id}",
             get(get_contact).put(update_contact).delete(delete_contact),
         )
+        .route("/contacts/{id}/restore", post(trash::restore_contact))
+        .route("/contacts/{id}/history", get(history::get_contact_history))
+        .route(
+            "/contacts/{id}/subscription",
+            get(newsletter::get_subscription).post(newsletter::request_subscription),
+        )
+        .route(
+            "/contacts/{id}/attachments",
+            get(attachments::get_attachments)
+                .post(attachments::upload_attachment)
+                .layer(DefaultBodyLimit::max(upload_limit)),
+        )
+        .route(
+            "/contacts/{id}/attachments/{attachment_id}",
+            get(attachments::download_attachment).delete(attachments::delete_attachment),
+        )
+        .route(
+            "/contacts/{id}/avatar",
+            get(attachments::get_avatar)
+                .put(attachments::set_avatar)
+                .delete(attachments::delete_avatar)
+                .layer(DefaultBodyLimit::max(upload_limit)),
+        )
+        .route("/orgs", get(orgs::get_orgs).post(orgs::create_org))
+        .route("/orgs/{id}/switch", post(orgs::switch_org))
+        .route(
+            "/org/members",
+            get(orgs::get_members).post(orgs::add_member),
+        )
+        .route(
+            "/org/members/{user_id}",
+            put(orgs::update_member).delete(orgs::remove_member),
+        )
+        .route(
+            "/shares",
+            get(sharing::get_shares).post(sharing::create_share),
+        )
+        .route("/shares/received", get(sharing::get_received_shares))
+        .route(
+            "/shares/{id}",
+            put(sharing::update_share).delete(sharing::delete_share),
+        )
+        .route("/tags", get(tags::get_tags).post(tags::create_tag))
+        .route("/tags/{id}", put(tags::update_tag).delete(tags::delete_tag))
+        .route("/contact-types", get(contact_types::get_contact_types))
+        .route(
+            "/custom-fields",
+            get(custom_fields::get_custom_fields).post(custom_fields::create_custom_field),
+        )
+        .route(
+            "/custom-fields/{id}",
+            put(custom_fields::update_custom_field).delete(custom_fields::delete_custom_field),
+        )
+        .route(
+            "/admin/backups",
+            get(backup::get_backups).post(backup::create_backup),
+        )
         .route_layer(middleware::from_fn_with_state(
             app_state.clone(),
             auth::auth_middleware,
@@ -221,6 +414,8 @@ async fn health_check() -> StatusCode {
     responses(
         (status = 201, description = "Contact created successfully", body = ContactDto),
         (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+        (status = 409, description = "A contact with this email already exists"),
         (status = 422, description = "Validation error"),
     )
 )]
@@ -228,42 +423,33 @@ async fn health_check() -> StatusCode {
 async fn create_contact(
     State(state): State<AppState>,
     user: AuthUser,
+    request_id: RequestId,
     Json(new_contact_dto): Json<ContactDto>,
 ) -> Result<(StatusCode, Json<ContactDto>), AppError> {
     tracing::info!(
-        "Creating contact: {:?}, assigned to user {}",
+        "Creating contact: {:?}, assigned to organization {}",
         new_contact_dto,
-        user.id
+        user.org_id
     );
 
+    user.require_write()?;
     // Validate the new contact DTO
     new_contact_dto.validate()?;
+    let contacts = &state.repos.contacts;
+    let enabled_types = contacts.enabled_contact_types().await?;
+    check_contact_type(&enabled_types, new_contact_dto.contact_type)?;
+    let custom_fields = contacts.custom_fields(user.org_id).await?;
+    check_custom_fields(&custom_fields, &new_contact_dto.custom_fields)?;
 
-    let result = sqlx::query_as!(
-        ContactDto,
-        r#"
-        INSERT INTO contacts (user_id, name, email, age, subscribed, contact_type)
-        VALUES ($1, $2, $3, $4, $5, $6)
-        RETURNING id, name, email, age, subscribed, contact_type;
-        "#,
-        user.id, // Add the user_id here
-        new_contact_dto.name,
-        new_contact_dto.email,
-        new_contact_dto.age,
-        new_contact_dto.subscribed,
-        new_contact_dto.contact_type
-    )
-    .fetch_one(&state.db_pool)
-    .await;
+    let actor = Actor::new(&user, request_id);
+    let result = contacts.create(&actor, user.org_id, &new_contact_dto).await;
 
     match result {
-        Ok(created_contact) => Ok((StatusCode::CREATED, Json(created_contact))),
-        Err(e) => {
-            tracing::error!("Failed to create contact: {}", e);
-            Err(AppError::InternalServerError(
-                "Failed to create contact".to_string(),
-            ))
+        Ok(created_contact) => {
+            confirm_subscription_request(&state, &actor, &new_contact_dto, &created_contact).await;
+            Ok((StatusCode::CREATED, Json(created_contact)))
         }
+        Err(e) => Err(contact_repository_error(e, "Failed to create contact")),
     }
 }
 
@@ -278,7 +464,7 @@ async fn create_contact(
     ),
     responses(
         (status = 200, body = ContactDto),
-        (status = 404, description = "Contact not found"),
+        (status = 404, description = "Contact not found or not shared with the user"),
         (status = 401, description = "Authentication required")
     )
 )]
@@ -294,14 +480,9 @@ async fn get_contact(
         user.id
     );
 
-    let result = sqlx::query_as!(
-        ContactDto,
-        "SELECT id, name, email, age, subscribed, contact_type FROM contacts WHERE id = $1 AND user_id = $2",
-        id,
-        user.id
-    )
-    .fetch_optional(&state.db_pool)
-    .await;
+    let contacts = &state.repos.contacts;
+    let org_id = check_access(contacts.access(id, &user).await?, SharePermission::Read)?;
+    let result = contacts.find(org_id, id).await;
 
     match result {
         Ok(Some(contact)) => Ok(Json(contact)),
@@ -315,15 +496,26 @@ async fn get_contact(
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct Pagination {
     pub page: Option<u32>,
     pub per_page: Option<u32>,
 }
 
+impl Pagination {
+    /// Returns the `LIMIT` and `OFFSET` for the requested page, 20 contacts per page by default.
+    pub(crate) fn limit_and_offset(&self) -> (i64, i64) {
+        let page = self.page.unwrap_or(1).max(1) as i64;
+        let per_page = self.per_page.unwrap_or(20) as i64;
+        (per_page, (page - 1) * per_page)
+    }
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/contacts",
+    params(Pagination, ContactFilter),
     security(
         ("bearer_auth" = [])
     ),
@@ -337,33 +529,24 @@ async fn get_contacts(
     State(state): State<AppState>,
     user: AuthUser,
     axum::extract::Query(pagination): axum::extract::Query<Pagination>, // <-- Add this
+    axum::extract::Query(filter): axum::extract::Query<ContactFilter>,
 ) -> Result<Json<Vec<ContactDto>>, AppError> {
-    // Set default values for pagination
-    let page = pagination.page.unwrap_or(1) as i64;
-    let per_page = pagination.per_page.unwrap_or(20) as i64;
-    let offset = (page - 1) * per_page;
+    let (per_page, offset) = pagination.limit_and_offset();
 
     tracing::info!(
-        "Fetching contacts for user {}, page: {}, per_page: {}",
-        user.id,
-        page,
-        per_page
+        "Fetching contacts for organization {}, per_page: {}, offset: {}, filter: {:?}",
+        user.org_id,
+        per_page,
+        offset,
+        filter
     );
 
-    let result = sqlx::query_as!(
-        ContactDto,
-        "SELECT id, name, email, age, subscribed, contact_type
-         FROM contacts
-         WHERE user_id = $1
-         LIMIT $2 OFFSET $3",
-        user.id,
-        per_page,
-        offset
-    )
-    .fetch_all(&state.db_pool)
-    .await;
+    let result = state
+        .repos
+        .contacts
+        .list(user.org_id, &filter, per_page, offset)
+        .await;
 
-    // ... rest of the handler remains the same
     match result {
         Ok(contacts) => Ok(Json(contacts)),
         Err(e) => {
@@ -387,8 +570,10 @@ async fn get_contacts(
     ),
     responses(
         (status = 200, description = "Contact updated successfully", body = ContactDto),
-        (status = 404, description = "Contact not found"),
+        (status = 403, description = "The contact is read-only for the user"),
+        (status = 404, description = "Contact not found or not shared with the user"),
         (status = 401, description = "Authentication required"),
+        (status = 409, description = "A contact with this email already exists"),
         (status = 422, description = "Validation error"),
     )
 )]
@@ -397,43 +582,37 @@ async fn update_contact(
     State(state): State<AppState>,
     Path(id): Path<i64>,
     user: AuthUser,
+    request_id: RequestId,
     Json(updated_contact): Json<ContactDto>,
 ) -> Result<Json<ContactDto>, AppError> {
     tracing::info!("Updating contact with id: {} for user {}", id, user.id);
 
     updated_contact.validate()?;
-
-    let result = sqlx::query_as!(
-        ContactDto,
-        r#"
-        UPDATE contacts
-        SET name = $1, email = $2, age = $3, subscribed = $4, contact_type = $5
-        WHERE id = $6 AND user_id = $7
-        RETURNING id, name, email, age, subscribed, contact_type
-        "#,
-        updated_contact.name,
-        updated_contact.email,
-        updated_contact.age,
-        updated_contact.subscribed,
-        updated_contact.contact_type,
-        id,
-        user.id
-    )
-    .fetch_optional(&state.db_pool)
-    .await;
+    let contacts = &state.repos.contacts;
+    let enabled_types = contacts.enabled_contact_types().await?;
+    check_contact_type(&enabled_types, updated_contact.contact_type)?;
+
+    let actor = Actor::new(&user, request_id);
+    // A shared contact stays in its organization, with that organization's tags and custom
+    // fields.
+    let org_id = check_access(contacts.access(id, &user).await?, SharePermission::Write)?;
+    let custom_fields = contacts.custom_fields(org_id).await?;
+    check_custom_fields(&custom_fields, &updated_contact.custom_fields)?;
+    let result = contacts.update(&actor, org_id, id, &updated_contact).await;
 
     match result {
-        Ok(Some(contact)) => Ok(Json(contact)),
-        Ok(None) => Err(AppError::NotFound),
-        Err(e) => {
-            tracing::error!("Failed to update contact: {}", e);
-            Err(AppError::InternalServerError(
-                "Failed to update contact".to_string(),
-            ))
+        Ok(Some(contact)) => {
+            confirm_subscription_request(&state, &actor, &updated_contact, &contact).await;
+            Ok(Json(contact))
         }
+        Ok(None) => Err(AppError::NotFound),
+        Err(e) => Err(contact_repository_error(e, "Failed to update contact")),
     }
 }
 
+/// ## Delete a contact
+/// Moves the contact to the trash, from where it can be restored until it is purged. A contact
+/// shared with write permission goes to the trash of its organization.
 #[utoipa::path(
     delete,
     path = "/api/v1/contacts/{id}",
@@ -444,8 +623,9 @@ async fn update_contact(
         ("id" = i64, Path, description = "Contact ID")
     ),
     responses(
-        (status = 204, description = "Contact deleted successfully"),
-        (status = 404, description = "Contact not found"),
+        (status = 204, description = "Contact moved to the trash"),
+        (status = 403, description = "The contact is read-only for the user"),
+        (status = 404, description = "Contact not found or not shared with the user"),
     )
 )]
 #[debug_handler]
@@ -453,18 +633,18 @@ async fn delete_contact(
     State(state): State<AppState>,
     Path(id): Path<i64>,
     user: AuthUser,
+    request_id: RequestId,
 ) -> Result<StatusCode, AppError> {
     tracing::info!("Deleting contact with id: {} for user {}", id, user.id);
 
-    let result = sqlx::query("DELETE FROM contacts WHERE id = $1 AND user_id = $2")
-        .bind(id)
-        .bind(user.id)
-        .execute(&state.db_pool)
-        .await;
+    let actor = Actor::new(&user, request_id);
+    let contacts = &state.repos.contacts;
+    let org_id = check_access(contacts.access(id, &user).await?, SharePermission::Write)?;
+    let result = contacts.delete(&actor, org_id, id).await;
 
     match result {
-        Ok(execution_result) => {
-            if execution_result.rows_affected() > 0 {
+        Ok(deleted) => {
+            if deleted {
                 Ok(StatusCode::NO_CONTENT)
             } else {
                 // Use NotFound to prevent leaking information about which contacts exist
@@ -479,3 +659,230 @@ async fn delete_contact(
         }
     }
 }
+
+/// Emails a contact that a user wrote as subscribed, but which was stored unsubscribed
+/// because only the contact can give consent, the link to confirm the subscription. The
+/// contact is written either way, so a failure is only logged; the user can ask again through
+/// `POST /api/v1/contacts/{id}/subscription`.
+pub(crate) async fn confirm_subscription_request(
+    state: &AppState,
+    actor: &Actor,
+    written: &ContactDto,
+    stored: &ContactDto,
+) {
+    let Some(id) = stored.id else {
+        return;
+    };
+    if !written.subscribed || stored.subscribed {
+        return;
+    }
+    if let Err(e) =
+        newsletter::ask_to_subscribe(state, actor, id, &stored.name, &stored.email).await
+    {
+        tracing::warn!("Failed to ask contact {} to subscribe: {}", id, e);
+    }
+}
+
+/// Upper bound on the number of operations accepted by a single bulk request.
+const MAX_BULK_OPERATIONS: usize = 1000;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/contacts/bulk",
+    request_body = BulkContactRequest,
+    security(
+        ("bearer_auth" = [])
+    ),
+    responses(
+        (status = 200, description = "Per-item results of the batch", body = BulkContactResponse),
+        (status = 400, description = "Too many operations in one request"),
+        (status = 401, description = "Authentication required"),
+        (status = 403, description = "The user's role does not allow changes"),
+    )
+)]
+#[debug_handler]
+async fn bulk_contacts(
+    State(state): State<AppState>,
+    user: AuthUser,
+    request_id: RequestId,
+    Json(request): Json<BulkContactRequest>,
+) -> Result<Json<BulkContactResponse>, AppError> {
+    tracing::info!(
+        "Running {} bulk contact operations for organization {} (all_or_nothing: {})",
+        request.operations.len(),
+        user.org_id,
+        request.all_or_nothing
+    );
+
+    user.require_write()?;
+    if request.operations.len() > MAX_BULK_OPERATIONS {
+        return Err(AppError::BadRequest(format!(
+            "A bulk request may contain at most {MAX_BULK_OPERATIONS} operations"
+        )));
+    }
+
+    // Validate every item up front so that an all-or-nothing batch with invalid
+    // input never touches the database.
+    let contacts = &state.repos.contacts;
+    let enabled_types = contacts.enabled_contact_types().await?;
+    let custom_fields = contacts.custom_fields(user.org_id).await?;
+    let mut results: Vec<BulkItemResult> = request
+        .operations
+        .iter()
+        .enumerate()
+        .map(|(index, operation)| {
+            let contact = match operation {
+                BulkContactOperation::Create { contact }
+                | BulkContactOperation::Update { contact, .. } => Some(contact),
+                BulkContactOperation::Delete { .. } => None,
+            };
+            let validation = contact.map(|contact| {
+                contact.validate()?;
+                check_contact_type(&enabled_types, contact.contact_type)?;
+                check_custom_fields(&custom_fields, &contact.custom_fields)
+            });
+            match validation {
+                Some(Err(errors)) => BulkItemResult {
+                    validation_errors: serde_json::to_value(&errors).ok(),
+                    ..bulk_failure(index, "Input validation failed")
+                },
+                _ => bulk_result(index, BulkItemStatus::Skipped, None),
+            }
+        })
+        .collect();
+
+    let has_invalid_items = results
+        .iter()
+        .any(|result| result.status == BulkItemStatus::Failed);
+    if request.all_or_nothing && has_invalid_items {
+        return Ok(Json(BulkContactResponse {
+            committed: false,
+            results,
+        }));
+    }
+
+    let actor = Actor::new(&user, request_id);
+    let (indexes, writes): (Vec<usize>, Vec<ContactWrite>) = request
+        .operations
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| results[*index].status != BulkItemStatus::Failed)
+        .map(|(index, operation)| (index, contact_write(operation)))
+        .unzip();
+    let outcomes = contacts
+        .write_batch(&actor, user.org_id, &writes, request.all_or_nothing)
+        .await?;
+
+    let committed = !request.all_or_nothing || outcomes.iter().all(WriteOutcome::succeeded);
+    for (index, outcome) in indexes.into_iter().zip(outcomes) {
+        results[index] = bulk_outcome(index, &request.operations[index], outcome);
+    }
+    if !committed {
+        for result in results
+            .iter_mut()
+            .filter(|result| result.status != BulkItemStatus::Failed)
+        {
+            *result = bulk_result(result.index, BulkItemStatus::Skipped, None);
+        }
+        return Ok(Json(BulkContactResponse {
+            committed: false,
+            results,
+        }));
+    }
+
+    for (operation, result) in request.operations.iter().zip(&results) {
+        if let (
+            BulkContactOperation::Create { contact } | BulkContactOperation::Update { contact, .. },
+            Some(stored),
+        ) = (operation, &result.contact)
+        {
+            confirm_subscription_request(&state, &actor, contact, stored).await;
+        }
+    }
+
+    Ok(Json(BulkContactResponse {
+        committed: true,
+        results,
+    }))
+}
+
+/// The write that runs a bulk operation.
+fn contact_write(operation: &BulkContactOperation) -> ContactWrite<'_> {
+    match operation {
+        BulkContactOperation::Create { contact } => ContactWrite::Create {
+            contact,
+            source: SubscriptionSource::Manual,
+        },
+        BulkContactOperation::Update { id, contact } => ContactWrite::Update { id: *id, contact },
+        BulkContactOperation::Delete { id } => ContactWrite::Delete { id: *id },
+    }
+}
+
+/// Converts the outcome of a bulk operation's write into its result entry.
+fn bulk_outcome(
+    index: usize,
+    operation: &BulkContactOperation,
+    outcome: WriteOutcome,
+) -> BulkItemResult {
+    match outcome {
+        WriteOutcome::Created(created) => {
+            bulk_result(index, BulkItemStatus::Created, Some(created))
+        }
+        WriteOutcome::Updated(updated) => {
+            bulk_result(index, BulkItemStatus::Updated, Some(updated))
+        }
+        WriteOutcome::Deleted => bulk_result(index, BulkItemStatus::Deleted, None),
+        WriteOutcome::NotFound => bulk_failure(index, "Resource not found"),
+        WriteOutcome::Failed(e) => {
+            let message = match operation {
+                BulkContactOperation::Create { .. } => "Failed to create contact",
+                BulkContactOperation::Update { .. } => "Failed to update contact",
+                BulkContactOperation::Delete { .. } => "Failed to delete contact",
+            };
+            bulk_error(index, contact_repository_error(e, message))
+        }
+    }
+}
+
+fn bulk_result(
+    index: usize,
+    status: BulkItemStatus,
+    contact: Option<ContactDto>,
+) -> BulkItemResult {
+    BulkItemResult {
+        index,
+        status,
+        contact,
+        error: None,
+        validation_errors: None,
+    }
+}
+
+fn bulk_error(index: usize, error: AppError) -> BulkItemResult {
+    match error {
+        AppError::Conflict(message) | AppError::InternalServerError(message) => {
+            bulk_failure(index, &message)
+        }
+        other => bulk_failure(index, &other.to_string()),
+    }
+}
+
+fn bulk_failure(index: usize, error: &str) -> BulkItemResult {
+    BulkItemResult {
+        error: Some(error.to_string()),
+        ..bulk_result(index, BulkItemStatus::Failed, None)
+    }
+}
+
+/// Maps a failed contact write to a 409 naming the conflicting field, or a logged 500.
+pub(crate) fn contact_repository_error(e: RepositoryError, message: &str) -> AppError {
+    match e {
+        RepositoryError::Duplicate(field) => {
+            AppError::Conflict(format!("A contact with this {field} already exists"))
+        }
+        e => {
+            tracing::error!("{}: {}", message, e);
+            AppError::InternalServerError(message.to_string())
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use backend::admin::{self, AdminError};
+use backend::with_pool;
+use common::{AddMemberRequest, ContactDto, ContactType, Credentials, OrgDto, OrgRole};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::net::SocketAddr;
+mod helpers;
+
+async fn login(
+    addr: &SocketAddr,
+    client: &reqwest::Client,
+    email: &str,
+    password: &str,
+) -> reqwest::Response {
+    client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&Credentials {
+            email: email.to_string(),
+            password: password.to_string(),
+        })
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_create_list_and_reset_password() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let _token = helpers::get_auth_token(&addr, &client).await;
+
+    let ops_id = admin::create_user(&db_pool, "ops@example.com", "password123", true)
+        .await
+        .unwrap();
+    let response = login(&addr, &client, "ops@example.com", "password123").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    assert!(matches!(
+        admin::create_user(&db_pool, "ops@example.com", "password123", false).await,
+        Err(AdminError::UserExists(_))
+    ));
+    assert!(matches!(
+        admin::create_user(&db_pool, "not-an-email", "password123", false).await,
+        Err(AdminError::Invalid(_))
+    ));
+    assert!(matches!(
+        admin::create_user(&db_pool, "new@example.com", "short", false).await,
+        Err(AdminError::Invalid(_))
+    ));
+    assert_eq!(admin::generate_password().len(), 20);
+
+    let users = admin::list_users(&db_pool).await.unwrap();
+    let summary: Vec<(&str, bool, i64)> = users
+        .iter()
+        .map(|u| (u.email.as_str(), u.is_admin, u.orgs))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![("test@example.com", false, 1), ("ops@example.com", true, 1)]
+    );
+    assert_eq!(users[1].id, ops_id);
+
+    admin::set_admin(&db_pool, "ops@example.com", false)
+        .await
+        .unwrap();
+    assert!(!admin::list_users(&db_pool).await.unwrap()[1].is_admin);
+    assert!(matches!(
+        admin::set_admin(&db_pool, "nobody@example.com", true).await,
+        Err(AdminError::UnknownUser(_))
+    ));
+
+    // Resetting the password ends the session the old one started.
+    admin::reset_password(&db_pool, "ops@example.com", "rotated456")
+        .await
+        .unwrap();
+    let sessions: i64 = with_pool!(&db_pool, |pool| sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM refresh_tokens WHERE user_id = {ops_id}"
+    ))
+    .fetch_one(pool)
+    .await)
+    .unwrap();
+    assert_eq!(sessions, 0);
+    let response = login(&addr, &client, "ops@example.com", "password123").await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = login(&addr, &client, "ops@example.com", "rotated456").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    assert!(matches!(
+        admin::reset_password(&db_pool, "nobody@example.com", "rotated456").await,
+        Err(AdminError::UnknownUser(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_delete_user() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let storage = backend::storage::from_config(&helpers::storage_config()).unwrap();
+    let bob_id = admin::create_user(&db_pool, "bob@example.com", "password123", false)
+        .await
+        .unwrap();
+
+    // The test user owns an organization shared with Bob, and adds a contact to it.
+    let org: OrgDto = client
+        .post(format!("http://{addr}/api/v1/orgs"))
+        .bearer_auth(&token)
+        .json(&json!({ "name": "Acme" }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let org_token = client
+        .post(format!("http://{addr}/api/v1/orgs/{}/switch", org.id))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json::<common::LoginResponse>()
+        .await
+        .unwrap()
+        .access_token;
+    let response = client
+        .post(format!("http://{addr}/api/v1/org/members"))
+        .bearer_auth(&org_token)
+        .json(&AddMemberRequest {
+            email: "bob@example.com".to_string(),
+            role: OrgRole::Member,
+        })
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    let response = client
+        .post(format!("http://{addr}/api/v1/contacts"))
+        .bearer_auth(&org_token)
+        .json(&ContactDto {
+            name: "Jane".to_string(),
+            email: "jane@example.com".to_string(),
+            contact_type: ContactType::Customer,
+            ..Default::default()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let error = admin::delete_user(&db_pool, storage.as_ref(), "test@example.com")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AdminError::LastOwner { ref org, .. } if org == "Acme"));
+
+    with_pool!(&db_pool, |pool| sqlx::query(&format!(
+        "UPDATE memberships SET role = 'owner' WHERE user_id = {bob_id}"
+    ))
+    .execute(pool)
+    .await
+    .map(|_| ()))
+    .unwrap();
+
+    // Only the personal organization goes; the contact in Acme is now Bob's.
+    let deleted = admin::delete_user(&db_pool, storage.as_ref(), "test@example.com")
+        .await
+        .unwrap();
+    assert_eq!(deleted, 1);
+    let emails: Vec<String> = admin::list_users(&db_pool)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|u| u.email)
+        .collect();
+    assert_eq!(emails, vec!["bob@example.com"]);
+    let owner: i64 = with_pool!(&db_pool, |pool| sqlx::query_scalar(&format!(
+        "SELECT user_id FROM contacts WHERE org_id = {}",
+        org.id
+    ))
+    .fetch_one(pool)
+    .await)
+    .unwrap();
+    assert_eq!(owner, bob_id);
+
+    assert!(matches!(
+        admin::delete_user(&db_pool, storage.as_ref(), "test@example.com").await,
+        Err(AdminError::UnknownUser(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_purge_tokens() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let _token = helpers::get_auth_token(&addr, &client).await;
+    let response = login(&addr, &client, "test@example.com", "password123").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    assert_eq!(admin::purge_tokens(&db_pool, false).await.unwrap(), 0);
+
+    with_pool!(&db_pool, |pool| sqlx::query(
+        "UPDATE refresh_tokens SET expires_at = '2000-01-01 00:00:00'"
+    )
+    .execute(pool)
+    .await
+    .map(|_| ()))
+    .unwrap();
+    assert_eq!(admin::purge_tokens(&db_pool, false).await.unwrap(), 1);
+
+    let response = login(&addr, &client, "test@example.com", "password123").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(admin::purge_tokens(&db_pool, true).await.unwrap(), 1);
+}
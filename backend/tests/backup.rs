@@ -0,0 +1,157 @@
+use std::path::{Path, PathBuf};
+
+use backend::backup::{self, BackupError};
+use backend::db::DbBackend;
+use common::BackupDto;
+use reqwest::StatusCode;
+use sqlx::SqlitePool;
+mod helpers;
+
+async fn open(path: &Path) -> SqlitePool {
+    let options = backend::db::sqlite_connect_options(
+        &format!("sqlite:{}", path.display()),
+        &helpers::database_config(),
+    )
+    .unwrap();
+    SqlitePool::connect_with(options).await.unwrap()
+}
+
+/// A copy of a backup, changed by `sql`, to restore from.
+async fn tampered(backup: &Path, name: &str, sql: &str) -> PathBuf {
+    let copy = backup.with_file_name(name);
+    std::fs::copy(backup, &copy).unwrap();
+    let db_pool = open(&copy).await;
+    sqlx::query(sql).execute(&db_pool).await.unwrap();
+    db_pool.close().await;
+    copy
+}
+
+#[tokio::test]
+async fn test_backups_are_for_server_admins() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let url = format!("http://{addr}/api/v1/admin/backups");
+
+    let response = client.post(&url).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client.get(&url).bearer_auth(&token).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    backend::admin::set_admin(&db_pool, "test@example.com", true)
+        .await
+        .unwrap();
+    if db_pool.backend() != DbBackend::Sqlite {
+        let response = client.post(&url).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        return;
+    }
+
+    let mut made = Vec::new();
+    for _ in 0..3 {
+        let response = client.post(&url).bearer_auth(&token).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let backup: BackupDto = response.json().await.unwrap();
+        assert!(backup.size_bytes > 0);
+        made.push(backup.name);
+    }
+
+    // The test configuration keeps two backups, so the first one is gone.
+    let listed: Vec<BackupDto> = client
+        .get(&url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let names: Vec<&str> = listed.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, vec![made[2].as_str(), made[1].as_str()]);
+}
+
+#[tokio::test]
+async fn test_restore_checks_the_backup() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let _token = helpers::get_auth_token(&addr, &client).await;
+    let config = helpers::backup_config();
+
+    if db_pool.backend() != DbBackend::Sqlite {
+        assert!(matches!(
+            backup::create(&db_pool, &config).await,
+            Err(BackupError::Unsupported)
+        ));
+        return;
+    }
+
+    let made = backup::create(&db_pool, &config).await.unwrap();
+    let file = config.dir.join(&made.name);
+    let target = config.dir.join("restored.db");
+    let target_url = format!("sqlite:{}", target.display());
+
+    // The first restore creates the database, the second moves the one it replaces aside.
+    let restored = backup::restore(&target_url, &file).await.unwrap();
+    assert_eq!(restored.pending, 0);
+    assert_eq!(restored.previous, None);
+    let restored = backup::restore(&target_url, &file).await.unwrap();
+    assert!(restored.previous.unwrap().exists());
+
+    let restored_pool = open(&target).await;
+    let users: Vec<String> = sqlx::query_scalar("SELECT email FROM users")
+        .fetch_all(&restored_pool)
+        .await
+        .unwrap();
+    assert_eq!(users, vec!["test@example.com"]);
+    restored_pool.close().await;
+
+    // A backup made before the latest migration restores, and the server migrates it later.
+    let older = tampered(
+        &file,
+        "older.db",
+        "DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)",
+    )
+    .await;
+    let restored = backup::restore(&target_url, &older).await.unwrap();
+    assert_eq!(restored.pending, 1);
+
+    let newer = tampered(
+        &file,
+        "newer.db",
+        "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) \
+         VALUES (99990101000000, 'from the future', TRUE, x'00', 0)",
+    )
+    .await;
+    assert!(matches!(
+        backup::restore(&target_url, &newer).await,
+        Err(BackupError::UnknownMigration(99990101000000))
+    ));
+
+    let modified = tampered(
+        &file,
+        "modified.db",
+        "UPDATE _sqlx_migrations SET checksum = x'00' WHERE version = (SELECT MIN(version) FROM _sqlx_migrations)",
+    )
+    .await;
+    assert!(matches!(
+        backup::restore(&target_url, &modified).await,
+        Err(BackupError::ModifiedMigration(_))
+    ));
+
+    let junk = config.dir.join("junk.db");
+    std::fs::write(&junk, "not a database").unwrap();
+    assert!(matches!(
+        backup::restore(&target_url, &junk).await,
+        Err(BackupError::Invalid { .. })
+    ));
+    assert!(matches!(
+        backup::restore("sqlite::memory:", &file).await,
+        Err(BackupError::InMemory)
+    ));
+
+    // None of the refused backups replaced the database.
+    let restored_pool = open(&target).await;
+    let version: i64 = sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations")
+        .fetch_one(&restored_pool)
+        .await
+        .unwrap();
+    assert_eq!(version, restored.version);
+}
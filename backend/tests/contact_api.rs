@@ -1,9 +1,11 @@
-use common::{ContactDto, Credentials, LoginResponse};
+use common::{
+    BulkContactResponse, BulkItemStatus, ContactDto, ContactType, Credentials, LoginResponse,
+};
 use reqwest::StatusCode;
 mod helpers;
 use crate::helpers::TEST_JWT_SECRET;
 use backend::auth::Claims;
-use chrono::{Duration, Utc};
+use chrono::{Duration, NaiveDate, Utc};
 use jsonwebtoken::{encode, EncodingKey, Header};
 use once_cell::sync::Lazy;
 use serde_json::json;
@@ -226,9 +228,11 @@ async fn test_contacts_crud_flow() {
         id: None, // ID is generated by the DB
         name: "John Doe".to_string(),
         email: "john.doe@test.com".to_string(),
-        age: 30,
+        birthday: None,
         subscribed: true,
-        contact_type: "Friend".to_string(),
+        contact_type: ContactType::Friend,
+        tags: Vec::new(),
+        ..Default::default()
     };
 
     let response = client
@@ -265,9 +269,11 @@ async fn test_contacts_crud_flow() {
         id: Some(contact_id),
         name: "John Smith".to_string(),           // Name changed
         email: "john.smith@test.com".to_string(), // Email changed
-        age: 31,
+        birthday: NaiveDate::from_ymd_opt(1990, 4, 12),
         subscribed: false,
-        contact_type: "Work".to_string(),
+        contact_type: ContactType::Work,
+        tags: Vec::new(),
+        ..Default::default()
     };
 
     let response = client
@@ -281,7 +287,10 @@ async fn test_contacts_crud_flow() {
     assert_eq!(response.status(), StatusCode::OK);
     let updated_contact_response: ContactDto = response.json().await.unwrap();
     assert_eq!(updated_contact_response.name, "John Smith");
-    assert_eq!(updated_contact_response.age, 31);
+    assert_eq!(
+        updated_contact_response.birthday,
+        NaiveDate::from_ymd_opt(1990, 4, 12)
+    );
 
     // 5. DELETE the contact
     let response = client
@@ -365,9 +374,11 @@ async fn test_contacts_authorization() {
         id: None,
         name: "User A's Contact".to_string(),
         email: "user_a_contact@test.com".to_string(),
-        age: 40,
+        birthday: None,
         subscribed: false,
-        contact_type: "Private".to_string(),
+        contact_type: ContactType::Personal,
+        tags: Vec::new(),
+        ..Default::default()
     };
     let response = client
         .post(&contacts_url)
@@ -530,6 +541,7 @@ async fn test_invalid_and_expired_tokens() {
 
     let claims = Claims {
         sub: "1".to_string(), // `sub` claim for the user we just created
+        org: 1,               // and the personal organization created with them
         exp: expiration as usize,
         nonce: "test-nonce".to_string(),
     };
@@ -599,7 +611,6 @@ async fn test_validation_errors() {
     let invalid_contact = json!({
         "name": "",
         "email": "some.contact@test.com",
-        "age": 25,
         "subscribed": false,
         "contact_type": "Work"
     });
@@ -630,9 +641,11 @@ async fn test_contacts_pagination() {
             id: None,
             name: format!("Contact {i}"),
             email: format!("contact{i}@test.com"),
-            age: 30 + i,
+            birthday: None,
             subscribed: i % 2 == 0,
-            contact_type: "Test".to_string(),
+            contact_type: ContactType::Other,
+            tags: Vec::new(),
+            ..Default::default()
         };
         let response = client
             .post(&contacts_url)
@@ -681,3 +694,345 @@ async fn test_contacts_pagination() {
     let page3_contacts: Vec<ContactDto> = response.json().await.unwrap();
     assert!(page3_contacts.is_empty(), "Page 3 should be empty");
 }
+
+#[tokio::test]
+async fn test_bulk_contacts_partial_success() {
+    Lazy::force(&TRACING);
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let bulk_url = format!("{contacts_url}/bulk");
+
+    // Seed one contact that the batch will update and another it will delete.
+    let mut seeded_ids = Vec::new();
+    for i in 0..2 {
+        let contact = ContactDto {
+            id: None,
+            name: format!("Seed {i}"),
+            email: format!("seed{i}@test.com"),
+            birthday: None,
+            subscribed: false,
+            contact_type: ContactType::Customer,
+            tags: Vec::new(),
+            ..Default::default()
+        };
+        let response = client
+            .post(&contacts_url)
+            .bearer_auth(&token)
+            .json(&contact)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created: ContactDto = response.json().await.unwrap();
+        seeded_ids.push(created.id.unwrap());
+    }
+
+    let batch = json!({
+        "operations": [
+            { "op": "create", "contact": { "name": "Bulk One", "email": "bulk1@test.com", "subscribed": true, "contactType": "Lead" } },
+            { "op": "create", "contact": { "name": "", "email": "not-an-email", "subscribed": true, "contactType": "Lead" } },
+            { "op": "update", "id": seeded_ids[0], "contact": { "name": "Seed Renamed", "email": "seed0@test.com", "subscribed": true, "contactType": "Partner" } },
+            { "op": "delete", "id": seeded_ids[1] },
+            { "op": "delete", "id": 999_999 }
+        ]
+    });
+
+    let response = client
+        .post(&bulk_url)
+        .bearer_auth(&token)
+        .json(&batch)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: BulkContactResponse = response.json().await.unwrap();
+
+    assert!(body.committed, "A partial batch should still be committed");
+    let statuses: Vec<BulkItemStatus> = body.results.iter().map(|r| r.status).collect();
+    assert_eq!(
+        statuses,
+        vec![
+            BulkItemStatus::Created,
+            BulkItemStatus::Failed,
+            BulkItemStatus::Updated,
+            BulkItemStatus::Deleted,
+            BulkItemStatus::Failed,
+        ]
+    );
+
+    // Validation errors are reported against the offending item.
+    let invalid = &body.results[1];
+    assert_eq!(invalid.index, 1);
+    let errors = invalid
+        .validation_errors
+        .as_ref()
+        .expect("Invalid item should carry validation errors");
+    assert!(errors.get("name").is_some());
+    assert!(errors.get("email").is_some());
+
+    assert_eq!(
+        body.results[2].contact.as_ref().unwrap().name,
+        "Seed Renamed"
+    );
+
+    // The committed changes are visible through the regular endpoints.
+    let response = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    let contacts: Vec<ContactDto> = response.json().await.unwrap();
+    let mut names: Vec<String> = contacts.into_iter().map(|c| c.name).collect();
+    names.sort();
+    assert_eq!(names, vec!["Bulk One", "Seed Renamed"]);
+}
+
+#[tokio::test]
+async fn test_bulk_contacts_all_or_nothing() {
+    Lazy::force(&TRACING);
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let bulk_url = format!("{contacts_url}/bulk");
+
+    // 1. A failing operation rolls back the items that ran before it.
+    let batch = json!({
+        "allOrNothing": true,
+        "operations": [
+            { "op": "create", "contact": { "name": "Atomic One", "email": "atomic1@test.com", "subscribed": false, "contactType": "Lead" } },
+            { "op": "update", "id": 999_999, "contact": { "name": "Missing", "email": "missing@test.com", "subscribed": false, "contactType": "Lead" } }
+        ]
+    });
+    let response = client
+        .post(&bulk_url)
+        .bearer_auth(&token)
+        .json(&batch)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: BulkContactResponse = response.json().await.unwrap();
+    assert!(!body.committed);
+    assert_eq!(body.results[0].status, BulkItemStatus::Skipped);
+    assert_eq!(body.results[1].status, BulkItemStatus::Failed);
+
+    let response = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    let contacts: Vec<ContactDto> = response.json().await.unwrap();
+    assert!(contacts.is_empty(), "Rolled back batch must not persist");
+
+    // 2. Invalid input aborts the batch before anything is written.
+    let batch = json!({
+        "allOrNothing": true,
+        "operations": [
+            { "op": "create", "contact": { "name": "Atomic Two", "email": "atomic2@test.com", "subscribed": false, "contactType": "Lead" } },
+            { "op": "create", "contact": { "name": "Bad", "email": "bad", "subscribed": false, "contactType": "Lead" } }
+        ]
+    });
+    let response = client
+        .post(&bulk_url)
+        .bearer_auth(&token)
+        .json(&batch)
+        .send()
+        .await
+        .unwrap();
+    let body: BulkContactResponse = response.json().await.unwrap();
+    assert!(!body.committed);
+    assert_eq!(body.results[0].status, BulkItemStatus::Skipped);
+    assert_eq!(body.results[1].status, BulkItemStatus::Failed);
+    assert!(body.results[1].validation_errors.is_some());
+
+    // 3. A fully valid batch is committed.
+    let batch = json!({
+        "allOrNothing": true,
+        "operations": [
+            { "op": "create", "contact": { "name": "Atomic Three", "email": "atomic3@test.com", "subscribed": false, "contactType": "Lead" } },
+            { "op": "create", "contact": { "name": "Atomic Four", "email": "atomic4@test.com", "subscribed": false, "contactType": "Lead" } }
+        ]
+    });
+    let response = client
+        .post(&bulk_url)
+        .bearer_auth(&token)
+        .json(&batch)
+        .send()
+        .await
+        .unwrap();
+    let body: BulkContactResponse = response.json().await.unwrap();
+    assert!(body.committed);
+    assert!(body
+        .results
+        .iter()
+        .all(|r| r.status == BulkItemStatus::Created));
+}
+
+#[tokio::test]
+async fn test_contacts_filtering() {
+    Lazy::force(&TRACING);
+    let (addr, client, _db_pool, mailer) = helpers::spawn_app_with_mailer().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let contacts = [
+        (
+            "Ada Lovelace",
+            "ada@engines.test",
+            true,
+            ContactType::Partner,
+        ),
+        (
+            "Alan Turing",
+            "alan@bletchley.test",
+            false,
+            ContactType::Customer,
+        ),
+        ("100% Grace", "grace@navy.test", true, ContactType::Customer),
+    ];
+    for (name, email, subscribed, contact_type) in contacts {
+        let contact = ContactDto {
+            id: None,
+            name: name.to_string(),
+            email: email.to_string(),
+            birthday: None,
+            subscribed,
+            contact_type,
+            tags: Vec::new(),
+            ..Default::default()
+        };
+        let response = client
+            .post(&contacts_url)
+            .bearer_auth(&token)
+            .json(&contact)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    helpers::confirm_subscriptions(&client, &mailer).await;
+
+    let cases = [
+        ("q=LOVELACE", vec!["Ada Lovelace"]),
+        ("q=bletchley", vec!["Alan Turing"]),
+        ("q=100%25", vec!["100% Grace"]),
+        ("q=%25", vec!["100% Grace"]),
+        ("contact_type=Customer", vec!["Alan Turing", "100% Grace"]),
+        ("subscribed=true", vec!["Ada Lovelace", "100% Grace"]),
+        ("subscribed=true&contact_type=Customer", vec!["100% Grace"]),
+        ("q=a&per_page=1&page=2", vec!["Alan Turing"]),
+    ];
+    for (query, expected) in cases {
+        let response = client
+            .get(format!("{contacts_url}?{query}"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let names: Vec<String> = response
+            .json::<Vec<ContactDto>>()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, expected, "Unexpected result for ?{query}");
+    }
+}
+
+#[tokio::test]
+async fn test_contact_email_unique_per_user() {
+    Lazy::force(&TRACING);
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    // User A (from the helper) and user B both add the same person.
+    let token_a = helpers::get_auth_token(&addr, &client).await;
+    let credentials_b = Credentials {
+        email: "user_b@example.com".to_string(),
+        password: "password123".to_string(),
+    };
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials_b)
+        .send()
+        .await
+        .unwrap();
+    let login: LoginResponse = client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials_b)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let token_b = login.access_token;
+
+    let bob = ContactDto {
+        id: None,
+        name: "Bob".to_string(),
+        email: "bob@example.com".to_string(),
+        birthday: None,
+        subscribed: false,
+        contact_type: ContactType::Friend,
+        tags: Vec::new(),
+        ..Default::default()
+    };
+    for token in [&token_a, &token_b] {
+        let response = client
+            .post(&contacts_url)
+            .bearer_auth(token)
+            .json(&bob)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::CREATED,
+            "Each user may store the same email once"
+        );
+    }
+
+    // Adding the same email twice to one contact book is a conflict naming the field.
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token_a)
+        .json(&bob)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(body["error"], "A contact with this email already exists");
+
+    // So is updating another contact to an email that is already taken.
+    let alice = ContactDto {
+        name: "Alice".to_string(),
+        email: "alice@example.com".to_string(),
+        ..bob.clone()
+    };
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token_a)
+        .json(&alice)
+        .send()
+        .await
+        .unwrap();
+    let alice: ContactDto = response.json().await.unwrap();
+    let response = client
+        .put(format!("{contacts_url}/{}", alice.id.unwrap()))
+        .bearer_auth(&token_a)
+        .json(&ContactDto {
+            email: "bob@example.com".to_string(),
+            ..alice.clone()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
@@ -0,0 +1,225 @@
+use chrono::NaiveDate;
+use common::{
+    ContactAddressDto, ContactDto, ContactEmailDto, ContactPhoneDto, ContactType, ImportReport,
+};
+use reqwest::{multipart, StatusCode};
+use serde_json::json;
+mod helpers;
+
+fn ada() -> ContactDto {
+    ContactDto {
+        id: None,
+        name: "Ada Lovelace".to_string(),
+        email: "ada@test.com".to_string(),
+        birthday: NaiveDate::from_ymd_opt(1815, 12, 10),
+        subscribed: false,
+        contact_type: ContactType::Partner,
+        tags: Vec::new(),
+        emails: vec![
+            ContactEmailDto {
+                label: "work".to_string(),
+                email: "ada@engines.test".to_string(),
+            },
+            ContactEmailDto {
+                label: "old".to_string(),
+                email: "ada@byron.test".to_string(),
+            },
+        ],
+        phones: vec![ContactPhoneDto {
+            label: "mobile".to_string(),
+            number: "+442079460958".to_string(),
+        }],
+        addresses: vec![ContactAddressDto {
+            label: "home".to_string(),
+            street: "12 St James's Square".to_string(),
+            city: "London".to_string(),
+            postal_code: "SW1Y 4JH".to_string(),
+            country: "United Kingdom".to_string(),
+            ..Default::default()
+        }],
+        notes: Some("Wrote the first program;\nfor the Analytical Engine".to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_contact_details_round_trip() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    // 1. Nested collections are stored in order and returned on every read.
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&ada())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: ContactDto = response.json().await.unwrap();
+    assert_eq!(
+        created,
+        ContactDto {
+            id: created.id,
+            ..ada()
+        }
+    );
+    let contact_url = format!("{contacts_url}/{}", created.id.unwrap());
+
+    let listed: Vec<ContactDto> = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(listed, vec![created.clone()]);
+
+    // 2. An update replaces the collections wholesale.
+    let response = client
+        .put(&contact_url)
+        .bearer_auth(&token)
+        .json(&ContactDto {
+            emails: vec![created.emails[1].clone()],
+            phones: Vec::new(),
+            birthday: None,
+            notes: None,
+            ..created.clone()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let fetched: ContactDto = client
+        .get(&contact_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(fetched.emails, vec![created.emails[1].clone()]);
+    assert!(fetched.phones.is_empty());
+    assert_eq!(fetched.addresses, created.addresses);
+    assert_eq!(fetched.birthday, None);
+    assert_eq!(fetched.notes, None);
+
+    // 3. Nested entries are validated.
+    for (field, value) in [
+        (
+            "phones",
+            json!([{ "label": "mobile", "number": "020 7946 0958" }]),
+        ),
+        (
+            "phones",
+            json!([{ "label": "mobile", "number": "+0207946" }]),
+        ),
+        (
+            "emails",
+            json!([{ "label": "work", "email": "not-an-email" }]),
+        ),
+        ("addresses", json!([{ "label": "", "city": "London" }])),
+    ] {
+        let mut contact = serde_json::to_value(ada()).unwrap();
+        contact["email"] = json!("invalid@test.com");
+        contact[field] = value;
+        let response = client
+            .post(&contacts_url)
+            .bearer_auth(&token)
+            .json(&contact)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{field} should be rejected"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_contact_details_survive_vcard_export_and_import() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&ada())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let vcf = client
+        .get(format!("{contacts_url}/export?format=vcf"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(vcf.contains("BDAY:18151210\r\n"));
+    assert!(vcf.contains("TEL;TYPE=mobile:+442079460958\r\n"));
+
+    // Another user imports the card and ends up with the same contact.
+    let credentials = json!({ "email": "other@example.com", "password": "password123" });
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    let login: serde_json::Value = client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let other_token = login["access_token"].as_str().unwrap();
+
+    let form = multipart::Form::new().part(
+        "file",
+        multipart::Part::bytes(vcf.into_bytes()).file_name("contacts.vcf"),
+    );
+    let report: ImportReport = client
+        .post(format!("{contacts_url}/import"))
+        .bearer_auth(other_token)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report.imported, 1);
+
+    let imported: Vec<ContactDto> = client
+        .get(&contacts_url)
+        .bearer_auth(other_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        imported[0],
+        ContactDto {
+            id: imported[0].id,
+            // The newsletter subscription is not part of a vCard.
+            subscribed: false,
+            ..ada()
+        }
+    );
+}
@@ -0,0 +1,141 @@
+use backend::with_pool;
+use common::{ContactDto, ContactType, ContactTypeDto, ImportReport};
+use reqwest::{multipart, StatusCode};
+use serde_json::json;
+mod helpers;
+
+#[tokio::test]
+async fn test_contact_types_are_validated_against_the_lookup_table() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let types_url = format!("http://{addr}/api/v1/contact-types");
+
+    // 1. Every type is offered by default, in display order.
+    let response = client
+        .get(&types_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let types: Vec<ContactTypeDto> = response.json().await.unwrap();
+    let names: Vec<ContactType> = types.iter().map(|t| t.name).collect();
+    assert_eq!(names, ContactType::ALL);
+
+    // 2. Only the canonical spelling is accepted.
+    let contact = |email: &str, contact_type: &str| {
+        json!({
+            "name": "Ada", "email": email,
+            "subscribed": false, "contactType": contact_type
+        })
+    };
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact("ada@test.com", "friend"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact("ada@test.com", "Friend"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let ada: ContactDto = response.json().await.unwrap();
+    assert_eq!(ada.contact_type, ContactType::Friend);
+
+    // 3. A deployment can withdraw a type and relabel another.
+    for sql in [
+        "UPDATE contact_types SET enabled = FALSE WHERE name = 'Lead'",
+        "UPDATE contact_types SET label = 'Client' WHERE name = 'Customer'",
+    ] {
+        with_pool!(&db_pool, |pool| sqlx::query(sql)
+            .execute(pool)
+            .await
+            .map(|_| ()))
+        .unwrap();
+    }
+
+    let types: Vec<ContactTypeDto> = client
+        .get(&types_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(types.iter().all(|t| t.name != ContactType::Lead));
+    let customer = types
+        .iter()
+        .find(|t| t.name == ContactType::Customer)
+        .unwrap();
+    assert_eq!(customer.label, "Client");
+
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact("alan@test.com", "Lead"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = response.json().await.unwrap();
+    assert!(body["details"]["contact_type"].is_array());
+
+    let response = client
+        .put(format!("{contacts_url}/{}", ada.id.unwrap()))
+        .bearer_auth(&token)
+        .json(&ContactDto {
+            contact_type: ContactType::Lead,
+            ..ada.clone()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // 4. Imports match types case-insensitively and reject unknown or withdrawn ones.
+    let csv = "name,email,type\n\
+               Grace,grace@test.com,CUSTOMER\n\
+               Linus,linus@test.com,Hacker\n\
+               Ken,ken@test.com,lead\n";
+    let form = multipart::Form::new().part(
+        "file",
+        multipart::Part::bytes(csv.as_bytes().to_vec()).file_name("contacts.csv"),
+    );
+    let report: ImportReport = client
+        .post(format!("{contacts_url}/import"))
+        .bearer_auth(&token)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report.imported, 1);
+    assert_eq!(report.failed, 2);
+    assert_eq!(
+        report.rows[0].error.as_deref(),
+        Some("Unknown contact type 'Hacker'")
+    );
+
+    let customers: Vec<ContactDto> = client
+        .get(format!("{contacts_url}?contact_type=Customer"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(customers.len(), 1);
+    assert_eq!(customers[0].email, "grace@test.com");
+}
@@ -0,0 +1,403 @@
+use common::{ContactDto, ContactType, CustomFieldDto, CustomFieldType, ImportReport};
+use reqwest::{multipart, StatusCode};
+use serde_json::{json, Value};
+mod helpers;
+
+fn contact(name: &str, email: &str, custom_fields: Value) -> Value {
+    json!({
+        "name": name, "email": email, "subscribed": false, "contactType": "Customer",
+        "customFields": custom_fields
+    })
+}
+
+#[tokio::test]
+async fn test_custom_fields_are_validated_and_filterable() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let fields_url = format!("http://{addr}/api/v1/custom-fields");
+
+    // 1. Define one field of every type.
+    for field in [
+        json!({ "name": "Account number", "fieldType": "text", "required": true }),
+        json!({ "name": "Seats", "fieldType": "number" }),
+        json!({ "name": "Renewal", "fieldType": "date" }),
+        json!({ "name": "Vip", "fieldType": "bool" }),
+        json!({ "name": " Region ", "fieldType": "select", "options": ["EMEA", "APAC"] }),
+    ] {
+        let response = client
+            .post(&fields_url)
+            .bearer_auth(&token)
+            .json(&field)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    let fields: Vec<CustomFieldDto> = client
+        .get(&fields_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["Account number", "Seats", "Renewal", "Vip", "Region"]
+    );
+    assert_eq!(fields[4].field_type, CustomFieldType::Select);
+    assert_eq!(fields[4].options, vec!["EMEA", "APAC"]);
+
+    // 2. Values are checked against the definitions.
+    for (custom_fields, message) in [
+        (json!({}), "'Account number' is required"),
+        (
+            json!({ "Account number": "  " }),
+            "'Account number' is required",
+        ),
+        (
+            json!({ "Account number": "A-1", "Seats": "ten" }),
+            "'Seats' must be a number",
+        ),
+        (
+            json!({ "Account number": "A-1", "Renewal": "31/01/2026" }),
+            "'Renewal' must be a date in YYYY-MM-DD format",
+        ),
+        (
+            json!({ "Account number": "A-1", "Vip": "yes" }),
+            "'Vip' must be true or false",
+        ),
+        (
+            json!({ "Account number": "A-1", "Region": "LATAM" }),
+            "'Region' must be one of EMEA, APAC",
+        ),
+        (
+            json!({ "Account number": "A-1", "Color": "blue" }),
+            "Unknown custom field 'Color'",
+        ),
+    ] {
+        let response = client
+            .post(&contacts_url)
+            .bearer_auth(&token)
+            .json(&contact("Ada", "ada@test.com", custom_fields))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(
+            body["details"]["custom_fields"][0]["message"], message,
+            "{body}"
+        );
+    }
+
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact(
+            "Ada",
+            "ada@test.com",
+            json!({
+                "Account number": "A-1", "Seats": 25, "Renewal": "2026-01-31",
+                "Vip": true, "Region": "EMEA"
+            }),
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let ada: ContactDto = response.json().await.unwrap();
+    assert_eq!(ada.custom_fields["Seats"], json!(25));
+    assert_eq!(ada.custom_fields["Vip"], json!(true));
+
+    // A null value is dropped rather than stored.
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact(
+            "Alan",
+            "alan@test.com",
+            json!({ "Account number": "A-2", "Seats": 4.5, "Vip": false, "Region": null }),
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let alan: ContactDto = response.json().await.unwrap();
+    assert!(!alan.custom_fields.contains_key("Region"));
+
+    // Updates are validated the same way.
+    let response = client
+        .put(format!("{contacts_url}/{}", alan.id.unwrap()))
+        .bearer_auth(&token)
+        .json(&ContactDto {
+            custom_fields: [("Seats".to_string(), json!("many"))].into(),
+            ..alan.clone()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // 3. The list can be filtered by the presence or the value of a field.
+    let filter = |query: &str| {
+        let request = client
+            .get(format!("{contacts_url}?custom_field={query}"))
+            .bearer_auth(&token);
+        async move {
+            let contacts: Vec<ContactDto> = request.send().await.unwrap().json().await.unwrap();
+            contacts
+                .into_iter()
+                .map(|c| c.name)
+                .collect::<Vec<String>>()
+        }
+    };
+    assert_eq!(filter("Region").await, vec!["Ada"]);
+    assert_eq!(filter("Region:EMEA").await, vec!["Ada"]);
+    assert_eq!(filter("Region:APAC").await, Vec::<String>::new());
+    assert_eq!(filter("Vip:false").await, vec!["Alan"]);
+    assert_eq!(filter("Seats:25").await, vec!["Ada"]);
+    assert_eq!(filter("Seats:4.5").await, vec!["Alan"]);
+    assert_eq!(filter("Renewal:2026-01-31").await, vec!["Ada"]);
+    assert_eq!(filter("Account%20number").await, vec!["Ada", "Alan"]);
+}
+
+#[tokio::test]
+async fn test_custom_field_definitions_crud() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let fields_url = format!("http://{addr}/api/v1/custom-fields");
+
+    // 1. Invalid definitions and duplicate names are rejected.
+    for field in [
+        json!({ "name": "", "fieldType": "text" }),
+        json!({ "name": "Region: EU", "fieldType": "text" }),
+        json!({ "name": "Region", "fieldType": "select" }),
+        json!({ "name": "Region", "fieldType": "text", "options": ["EMEA"] }),
+        json!({ "name": "Region", "fieldType": "color" }),
+    ] {
+        let response = client
+            .post(&fields_url)
+            .bearer_auth(&token)
+            .json(&field)
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_client_error(), "{field}");
+    }
+
+    let region = CustomFieldDto {
+        id: None,
+        name: "Region".to_string(),
+        field_type: CustomFieldType::Select,
+        required: false,
+        options: vec!["EMEA".to_string(), "APAC".to_string()],
+    };
+    let response = client
+        .post(&fields_url)
+        .bearer_auth(&token)
+        .json(&region)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let region: CustomFieldDto = response.json().await.unwrap();
+    let region_url = format!("{fields_url}/{}", region.id.unwrap());
+
+    let response = client
+        .post(&fields_url)
+        .bearer_auth(&token)
+        .json(&region)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact("Ada", "ada@test.com", json!({ "Region": "APAC" })))
+        .send()
+        .await
+        .unwrap();
+    let ada: ContactDto = response.json().await.unwrap();
+    let ada_url = format!("{contacts_url}/{}", ada.id.unwrap());
+
+    // 2. Renaming a field carries the values over; its type is fixed.
+    let response = client
+        .put(&region_url)
+        .bearer_auth(&token)
+        .json(&CustomFieldDto {
+            name: "Sales region".to_string(),
+            ..region.clone()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let fetched: ContactDto = client
+        .get(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        fetched.custom_fields,
+        [("Sales region".to_string(), json!("APAC"))].into()
+    );
+
+    let response = client
+        .put(&region_url)
+        .bearer_auth(&token)
+        .json(&CustomFieldDto {
+            field_type: CustomFieldType::Text,
+            options: Vec::new(),
+            ..region.clone()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // 3. Other users neither see nor can change the field.
+    let credentials = json!({ "email": "other@example.com", "password": "password123" });
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    let login: Value = client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let other_token = login["access_token"].as_str().unwrap();
+
+    let fields: Vec<CustomFieldDto> = client
+        .get(&fields_url)
+        .bearer_auth(other_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(fields.is_empty());
+    let response = client
+        .delete(&region_url)
+        .bearer_auth(other_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(other_token)
+        .json(&contact(
+            "Ada",
+            "ada@test.com",
+            json!({ "Sales region": "APAC" }),
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // 4. Deleting a field removes its values and keeps the contacts.
+    let response = client
+        .delete(&region_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let fetched: ContactDto = client
+        .get(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(fetched.custom_fields.is_empty());
+    assert_eq!(fetched.contact_type, ContactType::Customer);
+}
+
+#[tokio::test]
+async fn test_csv_import_fills_custom_fields() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    for field in [
+        json!({ "name": "Account number", "fieldType": "text", "required": true }),
+        json!({ "name": "Seats", "fieldType": "number" }),
+        json!({ "name": "Vip", "fieldType": "bool" }),
+    ] {
+        client
+            .post(format!("http://{addr}/api/v1/custom-fields"))
+            .bearer_auth(&token)
+            .json(&field)
+            .send()
+            .await
+            .unwrap();
+    }
+
+    let csv = "name,email,account number,SEATS,vip\n\
+               Ada,ada@test.com,A-1,25,yes\n\
+               Alan,alan@test.com,,3,no\n\
+               Grace,grace@test.com,A-3,lots,no\n";
+    let form = multipart::Form::new().part(
+        "file",
+        multipart::Part::bytes(csv.as_bytes().to_vec()).file_name("contacts.csv"),
+    );
+    let report: ImportReport = client
+        .post(format!("{contacts_url}/import"))
+        .bearer_auth(&token)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(report.imported, 1);
+    assert_eq!(report.failed, 2);
+    assert!(report.rows[0].validation_errors.is_some());
+    assert_eq!(
+        report.rows[1].error.as_deref(),
+        Some("Invalid number value 'lots' for 'Seats'")
+    );
+
+    let contacts: Vec<ContactDto> = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        contacts[0].custom_fields,
+        [
+            ("Account number".to_string(), json!("A-1")),
+            ("Seats".to_string(), json!(25)),
+            ("Vip".to_string(), json!(true)),
+        ]
+        .into()
+    );
+}
@@ -0,0 +1,326 @@
+use backend::db::{check_url, connect, DbBackend, DbError, DbPool, DbPools};
+use backend::migrations::MigrationState;
+use backend::with_pool;
+mod helpers;
+
+#[test]
+fn test_backend_from_url() {
+    assert_eq!(
+        DbBackend::from_url("sqlite:data.db?mode=rwc"),
+        Some(DbBackend::Sqlite)
+    );
+    assert_eq!(
+        DbBackend::from_url("postgres://user@localhost/app"),
+        Some(DbBackend::Postgres)
+    );
+    assert_eq!(
+        DbBackend::from_url("postgresql://localhost/app"),
+        Some(DbBackend::Postgres)
+    );
+    assert_eq!(
+        DbBackend::from_url("mysql://root@localhost/app"),
+        Some(DbBackend::MySql)
+    );
+    assert_eq!(
+        DbBackend::from_url("mariadb://localhost/app"),
+        Some(DbBackend::MySql)
+    );
+    assert_eq!(DbBackend::from_url("mssql://localhost/app"), None);
+    assert_eq!(DbBackend::from_url("data.db"), None);
+}
+
+#[tokio::test]
+async fn test_connect_picks_the_backend_of_the_url() {
+    let db_pool = connect("sqlite::memory:", &helpers::database_config())
+        .await
+        .unwrap();
+    assert_eq!(db_pool.backend(), DbBackend::Sqlite);
+
+    // Replicas connect lazily, so one on another backend is refused before it is reached.
+    let mut config = helpers::database_config();
+    config.replica_urls = vec!["postgres://127.0.0.1:1/app".to_string()];
+    let error = DbPools::connect("sqlite::memory:", &config)
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        DbError::MixedBackends {
+            primary: DbBackend::Sqlite,
+            replica: DbBackend::Postgres,
+        }
+    ));
+
+    assert!(matches!(
+        check_url("mssql://localhost/app"),
+        Err(DbError::UnknownScheme)
+    ));
+}
+
+#[tokio::test]
+async fn test_connect_retries_with_backoff() {
+    let unreachable = "sqlite:/nonexistent-dir/cornerstone.db";
+    let mut config = helpers::database_config();
+    config.connect_attempts = 3;
+    config.connect_backoff_ms = 50;
+    config.connect_backoff_max_ms = 60;
+    // sqlx keeps retrying a refused connection until the acquire timeout.
+    config.acquire_timeout_seconds = 1;
+
+    // Two waits, 50ms and then 60ms after doubling hits the cap, before giving up.
+    let started = std::time::Instant::now();
+    let error = connect(unreachable, &config).await.unwrap_err();
+    assert!(matches!(error, DbError::Connect(_)));
+    assert!(started.elapsed() >= std::time::Duration::from_millis(110));
+}
+
+#[tokio::test]
+async fn test_deleting_a_user_cascades() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    helpers::get_auth_token(&addr, &client).await;
+    let user_id: i64 = with_pool!(&db_pool, |pool| sqlx::query_scalar(
+        "SELECT id FROM users WHERE email = 'test@example.com'"
+    )
+    .fetch_one(pool)
+    .await)
+    .unwrap();
+
+    with_pool!(&db_pool, |pool| sqlx::query(&format!(
+        "DELETE FROM users WHERE id = {user_id}"
+    ))
+    .execute(pool)
+    .await
+    .map(|_| ()))
+    .unwrap();
+
+    for table in ["refresh_tokens", "memberships"] {
+        let sql = format!("SELECT COUNT(*) FROM {table} WHERE user_id = {user_id}");
+        let left: i64 = with_pool!(&db_pool, |pool| sqlx::query_scalar(&sql)
+            .fetch_one(pool)
+            .await)
+        .unwrap();
+        assert_eq!(left, 0, "{table} kept rows of the deleted user");
+    }
+}
+
+#[test]
+fn test_every_migration_can_be_reverted() {
+    for backend in [DbBackend::Sqlite, DbBackend::Postgres, DbBackend::MySql] {
+        let migrator = backend.migrator();
+        let downs: Vec<i64> = migrator
+            .iter()
+            .filter(|m| m.migration_type.is_down_migration())
+            .map(|m| m.version)
+            .collect();
+        for migration in migrator
+            .iter()
+            .filter(|m| m.migration_type.is_up_migration())
+        {
+            assert!(
+                downs.contains(&migration.version),
+                "{} migration {} has no .down.sql",
+                backend.as_str(),
+                migration.version
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_migrations_roll_back_and_forward() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    helpers::get_auth_token(&addr, &client).await;
+    let count = db_pool
+        .migrator()
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .count();
+
+    // 1. Stepping back past the organizations keeps the user and their session.
+    let reverted = backend::migrations::down(&db_pool, 3).await.unwrap();
+    assert_eq!(reverted.len(), 3);
+    assert!(reverted.windows(2).all(|pair| pair[0] > pair[1]));
+    let tokens: i64 = with_pool!(&db_pool, |pool| sqlx::query_scalar(
+        "SELECT COUNT(*) FROM refresh_tokens"
+    )
+    .fetch_one(pool)
+    .await)
+    .unwrap();
+    assert_eq!(tokens, 1);
+
+    let status = backend::migrations::status(&db_pool).await.unwrap();
+    let pending: Vec<i64> = status
+        .iter()
+        .filter(|m| m.state == MigrationState::Pending)
+        .map(|m| m.version)
+        .collect();
+    assert_eq!(pending.len(), 3);
+    assert!(pending.iter().all(|version| reverted.contains(version)));
+
+    // 2. Redo only touches the latest applied migration.
+    let latest = status
+        .iter()
+        .rev()
+        .find(|m| m.state == MigrationState::Applied)
+        .unwrap()
+        .version;
+    assert_eq!(
+        backend::migrations::redo(&db_pool).await.unwrap(),
+        Some(latest)
+    );
+
+    // 3. All the way down and back up again.
+    let reverted = backend::migrations::down(&db_pool, count).await.unwrap();
+    assert_eq!(reverted.len(), count - 3);
+    assert!(backend::migrations::down(&db_pool, 1)
+        .await
+        .unwrap()
+        .is_empty());
+    backend::migrations::up(&db_pool).await.unwrap();
+    let status = backend::migrations::status(&db_pool).await.unwrap();
+    assert_eq!(status.len(), count);
+    assert!(status.iter().all(|m| m.state == MigrationState::Applied));
+}
+
+#[tokio::test]
+async fn test_sqlite_pragmas() {
+    let path = std::env::temp_dir().join(format!("cornerstone-{}.db", uuid::Uuid::new_v4()));
+    let url = format!("sqlite:{}?mode=rwc", path.display());
+    let DbPool::Sqlite(db_pool) = connect(&url, &helpers::database_config()).await.unwrap() else {
+        panic!("sqlite: URLs are SQLite databases");
+    };
+
+    let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys")
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+    let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+    // 1 is NORMAL.
+    let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous")
+        .fetch_one(&db_pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        (foreign_keys, journal_mode.as_str(), synchronous),
+        (1, "wal", 1)
+    );
+
+    db_pool.close().await;
+    std::fs::remove_file(&path).ok();
+}
+
+/// A pool on a fresh SQLite database whose `marker` table names it.
+async fn marked_pool(name: &str) -> DbPool {
+    let pool = sqlx::SqlitePool::connect("sqlite::memory:").await.unwrap();
+    sqlx::query("CREATE TABLE marker (name TEXT NOT NULL)")
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO marker (name) VALUES ($1)")
+        .bind(name)
+        .execute(&pool)
+        .await
+        .unwrap();
+    DbPool::Sqlite(pool)
+}
+
+#[tokio::test]
+async fn test_reads_go_to_healthy_replicas() {
+    let primary = marked_pool("primary").await;
+    let replica_a = marked_pool("a").await;
+    let replica_b = marked_pool("b").await;
+    let mut config = helpers::database_config();
+    config.acquire_timeout_seconds = 1;
+    let unreachable =
+        backend::db::connect_lazy("sqlite:/nonexistent-dir/cornerstone.db", &config).unwrap();
+    let pools = DbPools::with_replicas(
+        primary,
+        vec![replica_a.clone(), unreachable, replica_b.clone()],
+    );
+    let read_marker = || async {
+        with_pool!(pools.read(), |pool| sqlx::query_scalar::<_, String>(
+            "SELECT name FROM marker"
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap())
+    };
+
+    // 1. Replicas are only used once checked.
+    assert_eq!(read_marker().await, "primary");
+
+    // 2. Reads take turns on the healthy replicas and skip the unreachable one.
+    pools.check_replicas().await;
+    let mut markers = Vec::new();
+    for _ in 0..6 {
+        markers.push(read_marker().await);
+    }
+    markers.sort();
+    assert_eq!(markers, ["a", "a", "a", "b", "b", "b"]);
+    assert!(with_pool!(&pools.primary, |pool| pool
+        .acquire()
+        .await
+        .is_ok()));
+
+    // 3. Once every replica is down, reads fall back to the primary.
+    replica_a.close().await;
+    replica_b.close().await;
+    pools.check_replicas().await;
+    assert_eq!(read_marker().await, "primary");
+}
+
+#[tokio::test]
+async fn test_contacts_are_fetched_from_the_primary_after_writes() {
+    use backend::history::Actor;
+    use backend::repository::ContactFilter;
+    use backend::repository::Repositories;
+    use common::ContactDto;
+
+    // A replica that hasn't caught up with anything yet.
+    let pools = DbPools::with_replicas(
+        helpers::test_db_pool().await,
+        vec![helpers::test_db_pool().await],
+    );
+    pools.check_replicas().await;
+    let repos = Repositories::sql(pools);
+
+    let user_id = repos
+        .users
+        .create("ada@example.com", "not-a-real-hash")
+        .await
+        .unwrap();
+    let org_id = repos.users.default_org(user_id).await.unwrap().unwrap();
+    let actor = Actor {
+        user_id,
+        request_id: None,
+    };
+    let contact = ContactDto {
+        name: "Ada Lovelace".to_string(),
+        email: "ada@example.com".to_string(),
+        ..Default::default()
+    };
+    let created = repos
+        .contacts
+        .create(&actor, org_id, &contact)
+        .await
+        .unwrap();
+
+    // The new contact can be fetched right away, while the list still comes from the replica.
+    assert_eq!(
+        repos
+            .contacts
+            .find(org_id, created.id.unwrap())
+            .await
+            .unwrap(),
+        Some(created)
+    );
+    let listed = repos
+        .contacts
+        .list(org_id, &ContactFilter::default(), 20, 0)
+        .await
+        .unwrap();
+    assert!(listed.is_empty());
+}
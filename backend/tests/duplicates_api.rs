@@ -0,0 +1,316 @@
+use common::{
+    ContactDto, ContactEmailDto, ContactEventAction, ContactEventDto, ContactPhoneDto, ContactType,
+    DuplicateCandidateDto, DuplicateReason,
+};
+use reqwest::StatusCode;
+use serde_json::json;
+mod helpers;
+
+fn contact(name: &str, email: &str) -> ContactDto {
+    ContactDto {
+        id: None,
+        name: name.to_string(),
+        email: email.to_string(),
+        birthday: None,
+        subscribed: false,
+        contact_type: ContactType::Friend,
+        tags: Vec::new(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_find_duplicates() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let mut ids = Vec::new();
+    for contact in [
+        contact("Ada Lovelace", "ada@test.com"),
+        contact("Countess of Lovelace", "Ada+Newsletter@Test.com"),
+        contact("Lovelace, Ada", "lovelace@engines.test"),
+        contact("Alan Turing", "alan@test.com"),
+        ContactDto {
+            emails: vec![ContactEmailDto {
+                label: "work".to_string(),
+                email: "ALAN@test.com".to_string(),
+            }],
+            ..contact("A. M. Turing", "turing@bletchley.test")
+        },
+        contact("Grace Hopper", "grace@test.com"),
+    ] {
+        let created: ContactDto = client
+            .post(&contacts_url)
+            .bearer_auth(&token)
+            .json(&contact)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        ids.push(created.id.unwrap());
+    }
+
+    let candidates: Vec<DuplicateCandidateDto> = client
+        .get(format!("{contacts_url}/duplicates"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    // Pairs sharing an email come first, the closest names first within each reason.
+    let pairs: Vec<(i64, i64, DuplicateReason)> = candidates
+        .iter()
+        .map(|c| (c.first.id, c.second.id, c.reason))
+        .collect();
+    assert_eq!(
+        pairs,
+        vec![
+            (ids[3], ids[4], DuplicateReason::SameEmail),
+            (ids[0], ids[1], DuplicateReason::SameEmail),
+            (ids[0], ids[2], DuplicateReason::SimilarName),
+        ]
+    );
+    assert_eq!(candidates[2].similarity, 1.0);
+    // The pairs name the contacts; the rest is fetched by ID.
+    assert_eq!(candidates[0].second.name, "A. M. Turing");
+    assert_eq!(candidates[0].second.email, "turing@bletchley.test");
+
+    // The pairs are paginated in the same order.
+    let page: Vec<DuplicateCandidateDto> = client
+        .get(format!("{contacts_url}/duplicates?page=2&per_page=2"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(page, candidates[2..]);
+
+    // A lower threshold reports more loosely similar names.
+    let candidates: Vec<DuplicateCandidateDto> = client
+        .get(format!("{contacts_url}/duplicates?min_similarity=0.4"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(candidates
+        .iter()
+        .any(|c| c.first.id == ids[1] && c.second.id == ids[2]));
+}
+
+#[tokio::test]
+async fn test_merge_contacts() {
+    let (addr, client, _db_pool, mailer) = helpers::spawn_app_with_mailer().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let create = |contact: ContactDto| {
+        let request = client
+            .post(&contacts_url)
+            .bearer_auth(&token)
+            .json(&contact);
+        async move {
+            let created: ContactDto = request.send().await.unwrap().json().await.unwrap();
+            created
+        }
+    };
+    let primary = create(ContactDto {
+        tags: vec!["work".to_string()],
+        phones: vec![ContactPhoneDto {
+            label: "mobile".to_string(),
+            number: "+442079460958".to_string(),
+        }],
+        notes: Some("Met at the Royal Society".to_string()),
+        ..contact("Ada Lovelace", "ada@test.com")
+    })
+    .await;
+    let duplicate = create(ContactDto {
+        subscribed: true,
+        contact_type: ContactType::Partner,
+        birthday: chrono::NaiveDate::from_ymd_opt(1815, 12, 10),
+        tags: vec!["family".to_string(), "work".to_string()],
+        phones: vec![
+            ContactPhoneDto {
+                label: "home".to_string(),
+                number: "+442079460958".to_string(),
+            },
+            ContactPhoneDto {
+                label: "work".to_string(),
+                number: "+442079460000".to_string(),
+            },
+        ],
+        notes: Some("Wrote the first program".to_string()),
+        ..contact("Augusta Ada King", "ada.king@test.com")
+    })
+    .await;
+    let (primary_id, duplicate_id) = (primary.id.unwrap(), duplicate.id.unwrap());
+    helpers::confirm_subscriptions(&client, &mailer).await;
+    let duplicate: ContactDto = client
+        .get(format!("{contacts_url}/{duplicate_id}"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(duplicate.subscribed);
+
+    // 1. A contact can't be merged with itself or with a contact that doesn't exist.
+    let merge = |body: serde_json::Value| {
+        client
+            .post(format!("{contacts_url}/merge"))
+            .bearer_auth(&token)
+            .json(&body)
+            .send()
+    };
+    let response = merge(json!({ "primaryId": primary_id, "duplicateId": primary_id }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = merge(json!({ "primaryId": primary_id, "duplicateId": 9999 }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // 2. Preferring the duplicate takes its values and combines the lists.
+    let response = merge(json!({
+        "primaryId": primary_id, "duplicateId": duplicate_id, "strategy": "preferDuplicate"
+    }))
+    .await
+    .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let merged: ContactDto = response.json().await.unwrap();
+    assert_eq!(merged.id, Some(primary_id));
+    assert_eq!(merged.name, "Augusta Ada King");
+    assert_eq!(merged.email, "ada.king@test.com");
+    // The duplicate's consent doesn't carry over; the merged contact is asked again.
+    assert!(!merged.subscribed);
+    assert_eq!(mailer.sent.lock().unwrap()[0].to, "ada.king@test.com");
+    assert_eq!(merged.contact_type, ContactType::Partner);
+    assert_eq!(merged.birthday, duplicate.birthday);
+    assert_eq!(merged.tags, vec!["family", "work"]);
+    assert_eq!(
+        merged.emails,
+        vec![ContactEmailDto {
+            label: "other".to_string(),
+            email: "ada@test.com".to_string(),
+        }]
+    );
+    let numbers: Vec<(&str, &str)> = merged
+        .phones
+        .iter()
+        .map(|p| (p.label.as_str(), p.number.as_str()))
+        .collect();
+    assert_eq!(
+        numbers,
+        vec![("home", "+442079460958"), ("work", "+442079460000")]
+    );
+    assert_eq!(
+        merged.notes.as_deref(),
+        Some("Wrote the first program\n\nMet at the Royal Society")
+    );
+
+    // The duplicate is in the trash, and no longer reported.
+    let response = client
+        .get(format!("{contacts_url}/{duplicate_id}"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let trash: Vec<serde_json::Value> = client
+        .get(format!("{contacts_url}/trash"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0]["id"], duplicate_id);
+
+    // 3. Both histories record the merge and point at the other contact.
+    let history = |id: i64| {
+        let request = client
+            .get(format!("{contacts_url}/{id}/history"))
+            .bearer_auth(&token);
+        async move {
+            let events: Vec<ContactEventDto> = request.send().await.unwrap().json().await.unwrap();
+            events
+        }
+    };
+    let events = history(primary_id).await;
+    let last = events.last().unwrap();
+    assert_eq!(last.action, ContactEventAction::Merged);
+    assert_eq!(last.related_contact_id, Some(duplicate_id));
+    assert_eq!(last.before.as_ref().unwrap().name, "Ada Lovelace");
+    assert_eq!(last.after.as_ref(), Some(&merged));
+
+    let events = history(duplicate_id).await;
+    let last = events.last().unwrap();
+    assert_eq!(last.action, ContactEventAction::Merged);
+    assert_eq!(last.related_contact_id, Some(primary_id));
+    assert_eq!(last.before.as_ref(), Some(&duplicate));
+    assert_eq!(last.after, None);
+
+    // 4. Preferring the primary keeps its values where they are set.
+    let other = create(ContactDto {
+        birthday: chrono::NaiveDate::from_ymd_opt(1815, 12, 10),
+        ..contact("Ada", "ada@engines.test")
+    })
+    .await;
+    let response = merge(json!({ "primaryId": other.id, "duplicateId": primary_id }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let merged: ContactDto = response.json().await.unwrap();
+    assert_eq!(merged.name, "Ada");
+    assert_eq!(merged.email, "ada@engines.test");
+    assert!(!merged.subscribed);
+    assert_eq!(merged.contact_type, ContactType::Friend);
+    assert_eq!(merged.tags, vec!["family", "work"]);
+    assert_eq!(merged.emails.len(), 2);
+
+    // Contacts of other users can't be merged.
+    let credentials = json!({ "email": "other@example.com", "password": "password123" });
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    let login: serde_json::Value = client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let stranger: ContactDto = client
+        .post(&contacts_url)
+        .bearer_auth(login["access_token"].as_str().unwrap())
+        .json(&contact("Ada", "ada@test.com"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let response = merge(json!({ "primaryId": other.id, "duplicateId": stranger.id }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,226 @@
+use common::{ContactDto, ContactPhoneDto, ContactType, ImportReport};
+use reqwest::{header, multipart, StatusCode};
+mod helpers;
+
+async fn create_contacts(addr: &std::net::SocketAddr, client: &reqwest::Client, token: &str) {
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let contacts = [
+        ("Ada Lovelace", "ada@test.com", true, ContactType::Partner),
+        (
+            "Smith, \"Agent\"",
+            "smith@test.com",
+            false,
+            ContactType::Customer,
+        ),
+        (
+            "Grace Hopper",
+            "grace@test.com",
+            true,
+            ContactType::Customer,
+        ),
+    ];
+    for (name, email, subscribed, contact_type) in contacts {
+        let contact = ContactDto {
+            id: None,
+            name: name.to_string(),
+            email: email.to_string(),
+            birthday: None,
+            subscribed,
+            contact_type,
+            tags: Vec::new(),
+            ..Default::default()
+        };
+        let response = client
+            .post(&contacts_url)
+            .bearer_auth(token)
+            .json(&contact)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+}
+
+#[tokio::test]
+async fn test_export_formats() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    create_contacts(&addr, &client, &token).await;
+    let export_url = format!("http://{addr}/api/v1/contacts/export");
+
+    // CSV is the default format and quotes fields where needed.
+    let response = client
+        .get(&export_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    assert!(response.headers()[header::CONTENT_DISPOSITION]
+        .to_str()
+        .unwrap()
+        .contains("contacts.csv"));
+    let csv = response.text().await.unwrap();
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(
+        lines[0],
+        "id,name,email,birthday,subscribed,contact_type,tags,phones,notes"
+    );
+    assert!(lines[2].contains(r#""Smith, ""Agent""",smith@test.com,,false,Customer"#));
+
+    // JSON lines round-trip into `ContactDto`.
+    let response = client
+        .get(format!("{export_url}?format=jsonl"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let jsonl = response.text().await.unwrap();
+    let contacts: Vec<ContactDto> = jsonl
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(contacts.len(), 3);
+    assert_eq!(contacts[1].name, "Smith, \"Agent\"");
+
+    // vCard output escapes structured characters.
+    let response = client
+        .get(format!("{export_url}?format=vcf"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let vcf = response.text().await.unwrap();
+    assert_eq!(vcf.matches("BEGIN:VCARD").count(), 3);
+    assert!(vcf.contains("FN:Smith\\, \"Agent\"\r\n"));
+
+    // An unknown format is rejected.
+    let response = client
+        .get(format!("{export_url}?format=xml"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_export_honors_filters_and_round_trips() {
+    let (addr, client, _db_pool, mailer) = helpers::spawn_app_with_mailer().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    create_contacts(&addr, &client, &token).await;
+    helpers::confirm_subscriptions(&client, &mailer).await;
+    let export_url = format!("http://{addr}/api/v1/contacts/export");
+
+    let response = client
+        .get(format!(
+            "{export_url}?format=jsonl&subscribed=true&contact_type=Customer"
+        ))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    let jsonl = response.text().await.unwrap();
+    let emails: Vec<String> = jsonl
+        .lines()
+        .map(|line| serde_json::from_str::<ContactDto>(line).unwrap().email)
+        .collect();
+    assert_eq!(emails, vec!["grace@test.com"]);
+
+    // An exported CSV file can be imported again; everything in it is a duplicate.
+    let response = client
+        .get(&export_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    let csv = response.bytes().await.unwrap();
+    let form = multipart::Form::new().part(
+        "file",
+        multipart::Part::bytes(csv.to_vec()).file_name("contacts.csv"),
+    );
+    let response = client
+        .post(format!("http://{addr}/api/v1/contacts/import?dry_run=true"))
+        .bearer_auth(&token)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: ImportReport = response.json().await.unwrap();
+    assert_eq!(report.total_rows, 3);
+    assert_eq!(report.duplicates, 3);
+}
+
+#[tokio::test]
+async fn test_csv_export_guards_against_formulas() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contact = ContactDto {
+        name: "=HYPERLINK(\"http://evil.test\",\"Click\")".to_string(),
+        email: "formula@test.com".to_string(),
+        phones: vec![ContactPhoneDto {
+            label: "mobile".to_string(),
+            number: "+15550100".to_string(),
+        }],
+        notes: Some("@SUM(A1:A2)".to_string()),
+        ..Default::default()
+    };
+    let response = client
+        .post(format!("http://{addr}/api/v1/contacts"))
+        .bearer_auth(&token)
+        .json(&contact)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Values a spreadsheet would evaluate start with a quote instead.
+    let response = client
+        .get(format!("http://{addr}/api/v1/contacts/export"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    let csv = response.text().await.unwrap();
+    let row = csv.lines().nth(1).unwrap();
+    assert!(row.contains(r#","'=HYPERLINK(""http://evil.test"",""Click"")",formula@test.com,"#));
+    assert!(row.contains(",'+15550100,"));
+    assert!(row.ends_with(",'@SUM(A1:A2)"));
+
+    // Importing the file restores the original values.
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let form = multipart::Form::new().part(
+        "file",
+        multipart::Part::bytes(csv.into_bytes()).file_name("contacts.csv"),
+    );
+    let response = client
+        .post(format!("http://{addr}/api/v1/contacts/import"))
+        .bearer_auth(&token)
+        .multipart(form)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let contacts: Vec<ContactDto> = client
+        .get(format!("http://{addr}/api/v1/contacts"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(contacts[0].name, contact.name);
+    assert_eq!(contacts[0].phones[0].number, "+15550100");
+    assert_eq!(contacts[0].notes, contact.notes);
+}
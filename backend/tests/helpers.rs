@@ -1,123 +1,120 @@
-use backend::config::{JwtConfig, RateLimitConfig, WebConfig};
-use backend::db::DbPool;
-use backend::db::DbPoolOptions;
+use backend::config::{
+    BackupConfig, DatabaseConfig, JwtConfig, MailerBackend, MailerConfig, NewsletterConfig,
+    RateLimitConfig, SqliteConfig, StorageBackend, StorageConfig, TrashConfig, WebConfig,
+};
+use backend::db::{DbBackend, DbPool, DbPools};
+use backend::mailer::{Email, Mailer, MailerError};
+use backend::repository::Repositories;
+use backend::with_pool;
 use backend::{config::AppConfig, web_server::AppState};
 use common::{Credentials, LoginResponse};
 use reqwest::StatusCode;
 use sqlx::Executor;
 use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
 use tokio::net::TcpListener;
 
 pub const TEST_JWT_SECRET: &str = "test_secret";
 
+/// Keeps the emails the server sends instead of sending them, so that tests can follow the
+/// links in them.
+#[derive(Default)]
+pub struct TestMailer {
+    pub sent: Mutex<Vec<Email>>,
+}
+
+#[async_trait::async_trait]
+impl Mailer for TestMailer {
+    async fn send(&self, email: Email) -> Result<(), MailerError> {
+        self.sent.lock().unwrap().push(email);
+        Ok(())
+    }
+}
+
+/// Confirms through the link of every email the server sent, as the contacts that a test
+/// wrote as subscribed would, so that they end up subscribed.
+#[allow(dead_code)] // Not every test binary subscribes contacts.
+pub async fn confirm_subscriptions(client: &reqwest::Client, mailer: &TestMailer) {
+    let links: Vec<String> = mailer
+        .sent
+        .lock()
+        .unwrap()
+        .drain(..)
+        .filter_map(|email| {
+            email
+                .body
+                .lines()
+                .find(|line| line.contains("/newsletter/confirm?"))
+                .map(str::to_string)
+        })
+        .collect();
+    for link in links {
+        let response = client.post(&link).send().await.unwrap();
+        assert_eq!(
+            response.status(),
+            StatusCode::OK,
+            "Failed to confirm {link}"
+        );
+    }
+}
+
 /// Spawn a test server and return the address and a reqwest client.
+#[allow(dead_code)] // Some test binaries only need `spawn_app_with_mailer`.
 pub async fn spawn_app() -> (SocketAddr, reqwest::Client, DbPool) {
+    let (addr, client, db_pool, _mailer) = spawn_app_with_mailer().await;
+    (addr, client, db_pool)
+}
+
+/// Like `spawn_app`, but also returns the mailer holding the emails the server sent.
+pub async fn spawn_app_with_mailer() -> (SocketAddr, reqwest::Client, DbPool, Arc<TestMailer>) {
     // The listener is bound to a random available port.
     let listener = TcpListener::bind("127.0.0.1:0")
         .await
         .expect("Failed to bind random port");
     let addr = listener.local_addr().unwrap();
 
-    // Load .env file for database URLs, especially for local Postgres tests.
+    // Load .env file for TEST_DATABASE_URL, for local Postgres tests.
     dotenvy::dotenv().ok();
 
-    // --- Database and Config Setup based on feature flags ---
-    let (db_pool, config) = if cfg!(feature = "db-postgres") {
-        // --- PostgreSQL Setup ---
-        println!("🧪 Setting up test environment for PostgreSQL...");
-
-        // 1. Get the database URL from environment, panic if not set.
-        let db_url =
-            std::env::var("DATABASE_URL").expect("DATABASE_URL must be set for Postgres tests.");
-
-        // 2. Create a master pool to create a unique test database.
-        let master_pool = DbPoolOptions::new()
-            .connect(&db_url)
-            .await
-            .expect("Failed to connect to Postgres master database.");
-
-        // 3. Generate a unique database name for this test run.
-        let db_name = uuid::Uuid::new_v4().to_string();
-        master_pool
-            .execute(format!(r#"CREATE DATABASE "{db_name}";"#).as_str())
-            .await
-            .unwrap_or_else(|_| panic!("Failed to create test database: {db_name}"));
-
-        // 4. Create the connection URL for the new test database.
-        let test_db_url = format!("{}/{}", db_url.rsplit_once('/').unwrap().0, db_name);
-
-        // 5. Create the actual connection pool for the test database.
-        let db_pool = DbPoolOptions::new()
-            .connect(&test_db_url)
-            .await
-            .expect("Failed to connect to the test database.");
-
-        // 6. Run migrations on the test database.
-        sqlx::migrate!("./migrations/postgres")
-            .run(&db_pool)
-            .await
-            .unwrap();
-
-        // 7. Create the AppConfig for Postgres.
-        let config = AppConfig {
-            web: WebConfig {
-                addr: "127.0.0.1".to_string(),
-                port: addr.port(),
-                cors_origin: "http://localhost:5173".to_string(),
-            },
-            jwt: JwtConfig {
-                secret: TEST_JWT_SECRET.to_string(),
-                access_token_expires_minutes: 1,
-                refresh_token_expires_days: 1,
-            },
-            ratelimit: RateLimitConfig {
-                per_second: 1000,
-                burst_size: 500,
-            },
-        };
-        (db_pool, config)
-    } else if cfg!(feature = "db-sqlite") {
-        // --- SQLite Setup ---
-        println!("🧪 Setting up test environment for SQLite (in-memory)...");
-
-        // 1. Create an in-memory SQLite pool.
-        let db_pool = DbPoolOptions::new()
-            .connect("sqlite::memory:")
-            .await
-            .unwrap();
-
-        // 2. Run migrations.
-        sqlx::migrate!("./migrations/sqlite")
-            .run(&db_pool)
-            .await
-            .unwrap();
-
-        // 3. Create the AppConfig for SQLite.
-        let config = AppConfig {
-            web: WebConfig {
-                addr: "127.0.0.1".to_string(),
-                port: addr.port(),
-                cors_origin: "http://localhost:5173".to_string(),
-            },
-            jwt: JwtConfig {
-                secret: TEST_JWT_SECRET.to_string(),
-                access_token_expires_minutes: 15,
-                refresh_token_expires_days: 7,
-            },
-            ratelimit: RateLimitConfig {
-                per_second: 1000,
-                burst_size: 500,
-            },
-        };
-        (db_pool, config)
-    } else {
-        panic!("A database feature ('db-postgres' or 'db-sqlite') must be enabled for tests.");
+    let db_pool = test_db_pool().await;
+    let config = AppConfig {
+        web: WebConfig {
+            addr: "127.0.0.1".to_string(),
+            port: addr.port(),
+            cors_origin: "http://localhost:5173".to_string(),
+        },
+        database: database_config(),
+        jwt: JwtConfig {
+            secret: TEST_JWT_SECRET.to_string(),
+            access_token_expires_minutes: 15,
+            refresh_token_expires_days: 7,
+        },
+        ratelimit: RateLimitConfig {
+            per_second: 1000,
+            burst_size: 500,
+        },
+        trash: TrashConfig {
+            retention_days: 30,
+            purge_interval_minutes: 60,
+        },
+        storage: storage_config(),
+        mailer: mailer_config(),
+        newsletter: NewsletterConfig {
+            public_url: format!("http://{addr}"),
+            confirmation_expires_hours: 72,
+        },
+        backup: backup_config(),
     };
 
     // --- Common App Setup ---
+    let storage = backend::storage::from_config(&config.storage).unwrap();
+    let mailer = Arc::new(TestMailer::default());
     let app_state = AppState {
-        db_pool: db_pool.clone(),
+        db_pools: DbPools::new(db_pool.clone()),
         app_config: config,
+        storage,
+        mailer: mailer.clone(),
+        repos: Repositories::sql(DbPools::new(db_pool.clone())),
     };
 
     let app = backend::web_server::create_router(app_state);
@@ -136,7 +133,101 @@ pub async fn spawn_app() -> (SocketAddr, reqwest::Client, DbPool) {
         .build()
         .unwrap();
 
-    (addr, client, db_pool)
+    (addr, client, db_pool, mailer)
+}
+
+/// A migrated pool on a fresh database: a new database on the Postgres or MySQL server that
+/// `TEST_DATABASE_URL` points at if it is set, and an in-memory SQLite database otherwise.
+pub async fn test_db_pool() -> DbPool {
+    let db_pool = match std::env::var("TEST_DATABASE_URL") {
+        Ok(url) => {
+            let db_backend = DbBackend::from_url(&url).expect("Unsupported TEST_DATABASE_URL.");
+            println!(
+                "🧪 Setting up test environment for {}...",
+                db_backend.as_str()
+            );
+            // Every test gets a database of its own, made through the one the URL names.
+            let master_pool = backend::db::connect(&url, &database_config())
+                .await
+                .expect("Failed to connect to the master database.");
+            let db_name = uuid::Uuid::new_v4().to_string();
+            let create = match db_backend {
+                DbBackend::MySql => format!("CREATE DATABASE `{db_name}`;"),
+                _ => format!(r#"CREATE DATABASE "{db_name}";"#),
+            };
+            with_pool!(&master_pool, |pool| pool
+                .execute(create.as_str())
+                .await
+                .map(drop))
+            .unwrap_or_else(|_| panic!("Failed to create test database: {db_name}"));
+            master_pool.close().await;
+            let test_db_url = format!("{}/{}", url.rsplit_once('/').unwrap().0, db_name);
+            backend::db::connect(&test_db_url, &database_config())
+                .await
+                .expect("Failed to connect to the test database.")
+        }
+        Err(_) => {
+            println!("🧪 Setting up test environment for SQLite (in-memory)...");
+            backend::db::connect("sqlite::memory:", &database_config())
+                .await
+                .unwrap()
+        }
+    };
+    let migrator = db_pool.migrator();
+    with_pool!(&db_pool, |pool| migrator.run(pool).await).unwrap();
+    db_pool
+}
+
+/// Local storage in a fresh temporary directory, with small limits so that tests of the
+/// limits stay fast.
+pub fn storage_config() -> StorageConfig {
+    StorageConfig {
+        backend: StorageBackend::Local,
+        local_path: std::env::temp_dir().join(format!("cornerstone-test-{}", uuid::Uuid::new_v4())),
+        max_upload_bytes: 64 * 1024,
+        allowed_content_types: vec!["image/*".to_string(), "text/plain".to_string()],
+        avatar_size: 32,
+        s3: None,
+    }
+}
+
+/// A fresh temporary backup directory that keeps two backups, so that tests see old ones go.
+pub fn backup_config() -> BackupConfig {
+    BackupConfig {
+        dir: std::env::temp_dir().join(format!("cornerstone-backups-{}", uuid::Uuid::new_v4())),
+        keep: 2,
+    }
+}
+
+/// The pool settings of `Config.toml`. The test pools are created by the helpers themselves,
+/// so the URL is left unset.
+pub fn database_config() -> DatabaseConfig {
+    DatabaseConfig {
+        url: None,
+        min_connections: 0,
+        max_connections: 5,
+        acquire_timeout_seconds: 30,
+        idle_timeout_seconds: Some(600),
+        statement_timeout_seconds: None,
+        replica_urls: Vec::new(),
+        replica_check_interval_seconds: 10,
+        connect_attempts: 1,
+        connect_backoff_ms: 250,
+        connect_backoff_max_ms: 10_000,
+        sqlite: SqliteConfig {
+            wal: true,
+            busy_timeout_ms: 5000,
+            foreign_keys: true,
+        },
+    }
+}
+
+fn mailer_config() -> MailerConfig {
+    MailerConfig {
+        backend: MailerBackend::Log,
+        from: "Cornerstone <no-reply@example.com>".to_string(),
+        smtp: None,
+    }
 }
 
 /// Helper to register and login a test user, returning their auth token.
@@ -183,3 +274,17 @@ pub async fn get_auth_token(addr: &SocketAddr, client: &reqwest::Client) -> Stri
 
     login_response.access_token
 }
+
+/// Creates the users and contacts of a fixture in `backend/fixtures`, as `backend seed --file`
+/// does, and returns the IDs of the new users.
+#[allow(dead_code)] // Not every test binary seeds its database.
+pub async fn load_fixture(db_pool: &DbPool, name: &str) -> Vec<i64> {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join(name);
+    let fixture = backend::seed::Fixture::read(&path).expect("Failed to read the fixture");
+    let (user_ids, _) = backend::seed::load(db_pool, &fixture)
+        .await
+        .expect("Failed to load the fixture");
+    user_ids
+}
@@ -0,0 +1,208 @@
+use common::{ContactDto, ContactEventAction, ContactEventDto, ContactType};
+use reqwest::StatusCode;
+use serde_json::json;
+mod helpers;
+
+#[tokio::test]
+async fn test_contact_history() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let original = ContactDto {
+        id: None,
+        name: "Ada".to_string(),
+        email: "ada@test.com".to_string(),
+        birthday: None,
+        subscribed: false,
+        contact_type: ContactType::Friend,
+        tags: Vec::new(),
+        ..Default::default()
+    };
+    let created: ContactDto = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&original)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let id = created.id.unwrap();
+    let contact_url = format!("{contacts_url}/{id}");
+
+    // A request ID sent by the client is kept by `SetRequestIdLayer` and logged.
+    let changes = ContactDto {
+        name: "Ada Lovelace".to_string(),
+        subscribed: true,
+        ..created.clone()
+    };
+    let response = client
+        .put(&contact_url)
+        .bearer_auth(&token)
+        .header("x-request-id", "history-test-update")
+        .json(&changes)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: ContactDto = response.json().await.unwrap();
+
+    // A failed update is not logged.
+    let response = client
+        .put(&contact_url)
+        .bearer_auth(&token)
+        .json(&ContactDto {
+            email: "not-an-email".to_string(),
+            ..updated.clone()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = client
+        .delete(&contact_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // History is still available while the contact is in the trash.
+    let response = client
+        .get(format!("{contact_url}/history"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let history: Vec<ContactEventDto> = response.json().await.unwrap();
+    assert_eq!(history.len(), 3);
+
+    let response = client
+        .post(format!("{contact_url}/restore"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let history: Vec<ContactEventDto> = client
+        .get(format!("{contact_url}/history"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let actions: Vec<ContactEventAction> = history.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        vec![
+            ContactEventAction::Created,
+            ContactEventAction::Updated,
+            ContactEventAction::Deleted,
+            ContactEventAction::Restored,
+        ]
+    );
+
+    for event in &history {
+        assert_eq!(event.contact_id, id);
+        assert_eq!(event.user_email.as_deref(), Some("test@example.com"));
+        assert!(event.request_id.is_some(), "Every request gets an ID");
+    }
+
+    let (create, update, delete, restore) = (&history[0], &history[1], &history[2], &history[3]);
+    assert_eq!(create.before, None);
+    assert_eq!(create.after.as_ref(), Some(&created));
+    assert_eq!(update.request_id.as_deref(), Some("history-test-update"));
+    assert_eq!(update.before.as_ref(), Some(&created));
+    assert_eq!(update.after.as_ref(), Some(&updated));
+    assert_eq!(delete.before.as_ref(), Some(&updated));
+    assert_eq!(delete.after, None);
+    assert_eq!(restore.before, None);
+    assert_eq!(restore.after.as_ref(), Some(&updated));
+    assert!(create.created_at <= restore.created_at);
+}
+
+#[tokio::test]
+async fn test_contact_history_covers_bulk_and_is_private() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let response = client
+        .post(format!("{contacts_url}/bulk"))
+        .bearer_auth(&token)
+        .json(&json!({
+            "operations": [
+                { "op": "create", "contact": {
+                    "name": "Grace", "email": "grace@test.com",
+                    "subscribed": false, "contactType": "Friend"
+                } }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let contacts: Vec<ContactDto> = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let id = contacts[0].id.unwrap();
+
+    let history: Vec<ContactEventDto> = client
+        .get(format!("{contacts_url}/{id}/history"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].action, ContactEventAction::Created);
+
+    // Unknown contacts have no history.
+    let response = client
+        .get(format!("{contacts_url}/{}/history", id + 100))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Another user cannot read it.
+    let credentials = json!({ "email": "other@example.com", "password": "password123" });
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    let login: serde_json::Value = client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let response = client
+        .get(format!("{contacts_url}/{id}/history"))
+        .bearer_auth(login["access_token"].as_str().unwrap())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,287 @@
+use backend::with_pool;
+use chrono::NaiveDate;
+use common::{ContactDto, ContactType, ImportReport, ImportRowStatus};
+use reqwest::{multipart, StatusCode};
+mod helpers;
+
+fn upload(file_name: &str, content: &str) -> multipart::Form {
+    let part = multipart::Part::bytes(content.as_bytes().to_vec()).file_name(file_name.to_string());
+    multipart::Form::new().part("file", part)
+}
+
+#[tokio::test]
+async fn test_csv_import_with_dry_run_and_dedupe() {
+    let (addr, client, _db_pool, mailer) = helpers::spawn_app_with_mailer().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let import_url = format!("{contacts_url}/import");
+
+    // An existing contact the import must not duplicate.
+    let existing = ContactDto {
+        id: None,
+        name: "Existing".to_string(),
+        email: "existing@test.com".to_string(),
+        birthday: None,
+        subscribed: false,
+        contact_type: ContactType::Customer,
+        tags: Vec::new(),
+        ..Default::default()
+    };
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&existing)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Header aliases, a BOM, quoted fields and CRLF line endings, as exported by spreadsheets.
+    let csv = "\u{feff}First Name,Last Name,E-mail Address,Birthday,Newsletter,Category,Notes,Mobile\r\n\
+               Ada,Lovelace,ada@test.com,1815-12-10,yes,Partner,\"Analyst, engine\",+44 20 7946 0958\r\n\
+               Alan,Turing,ALAN@test.com,,no,,\"multi\nline\",\r\n\
+               Grace,Hopper,not-an-email,1906-12-09,no,Lead,,\r\n\
+               Ada,Again,Ada@Test.com,1815-12-10,yes,Partner,,\r\n\
+               Old,Friend,EXISTING@test.com,,no,Customer,,\r\n\
+               Bad,Birthday,bad.birthday@test.com,forty,no,Customer,,\r\n";
+
+    // 1. A dry run reports what would happen without writing anything.
+    let response = client
+        .post(format!("{import_url}?dry_run=true"))
+        .bearer_auth(&token)
+        .multipart(upload("contacts.csv", csv))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: ImportReport = response.json().await.unwrap();
+
+    assert!(report.dry_run);
+    assert_eq!(report.total_rows, 6);
+    assert_eq!(report.imported, 2);
+    assert_eq!(report.duplicates, 2);
+    assert_eq!(report.failed, 2);
+    let skipped: Vec<(usize, ImportRowStatus)> =
+        report.rows.iter().map(|r| (r.row, r.status)).collect();
+    assert_eq!(
+        skipped,
+        vec![
+            (3, ImportRowStatus::Failed),
+            (4, ImportRowStatus::Duplicate),
+            (5, ImportRowStatus::Duplicate),
+            (6, ImportRowStatus::Failed),
+        ]
+    );
+    assert!(report.rows[0].validation_errors.is_some());
+
+    let response = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    let contacts: Vec<ContactDto> = response.json().await.unwrap();
+    assert_eq!(contacts.len(), 1, "A dry run must not create contacts");
+
+    // 2. The real import creates the valid rows.
+    let response = client
+        .post(&import_url)
+        .bearer_auth(&token)
+        .multipart(upload("contacts.csv", csv))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: ImportReport = response.json().await.unwrap();
+    assert!(!report.dry_run);
+    assert_eq!(report.imported, 2);
+
+    let response = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    let contacts: Vec<ContactDto> = response.json().await.unwrap();
+    let ada = contacts
+        .iter()
+        .find(|c| c.email == "ada@test.com")
+        .expect("Ada should have been imported");
+    assert_eq!(ada.name, "Ada Lovelace");
+    assert_eq!(ada.birthday, NaiveDate::from_ymd_opt(1815, 12, 10));
+    assert_eq!(ada.notes.as_deref(), Some("Analyst, engine"));
+    assert_eq!(ada.phones[0].number, "+442079460958");
+    // Subscribed rows are only asked to confirm.
+    assert!(!ada.subscribed);
+    let sent: Vec<String> = mailer
+        .sent
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|e| e.to.clone())
+        .collect();
+    assert_eq!(sent, vec!["ada@test.com"]);
+    assert_eq!(ada.contact_type, ContactType::Partner);
+    let alan = contacts
+        .iter()
+        .find(|c| c.email == "ALAN@test.com")
+        .expect("Alan should have been imported");
+    assert_eq!(alan.contact_type, ContactType::Other);
+
+    // 3. Importing the same file again only finds duplicates.
+    let response = client
+        .post(&import_url)
+        .bearer_auth(&token)
+        .multipart(upload("contacts.csv", csv))
+        .send()
+        .await
+        .unwrap();
+    let report: ImportReport = response.json().await.unwrap();
+    assert_eq!(report.imported, 0);
+    assert_eq!(report.duplicates, 4);
+}
+
+#[tokio::test]
+async fn test_large_import_is_committed_in_batches() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let import_url = format!("http://{addr}/api/v1/contacts/import");
+
+    // Enough rows for several batches, ending on a duplicate of the first row.
+    let mut csv = "Name,Email\r\n".to_string();
+    for i in 0..1200 {
+        csv.push_str(&format!("Contact {i},contact{i}@test.com\r\n"));
+    }
+    csv.push_str("Again,CONTACT0@test.com\r\n");
+
+    let response = client
+        .post(&import_url)
+        .bearer_auth(&token)
+        .multipart(upload("contacts.csv", &csv))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: ImportReport = response.json().await.unwrap();
+    assert_eq!(report.imported, 1200);
+    assert_eq!(report.duplicates, 1);
+    assert_eq!(report.rows[0].row, 1201);
+
+    let stored: i64 = with_pool!(&db_pool, |pool| sqlx::query_scalar(
+        "SELECT COUNT(*) FROM contacts"
+    )
+    .fetch_one(pool)
+    .await)
+    .unwrap();
+    assert_eq!(stored, 1200);
+}
+
+#[tokio::test]
+async fn test_vcard_import() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let import_url = format!("{contacts_url}/import");
+
+    let vcf = "BEGIN:VCARD\r\n\
+               VERSION:4.0\r\n\
+               FN:Margaret\r\n  Hamilton\r\n\
+               N:Hamilton;Margaret;;;\r\n\
+               EMAIL;TYPE=work:margaret@work.test\r\n\
+               EMAIL;TYPE=home;PREF=1:margaret@home.test\r\n\
+               TEL;TYPE=cell:+1 (617) 555-0100\r\n\
+               ADR;TYPE=work:;;77 Massachusetts Ave;Cambridge;MA;02139;USA\r\n\
+               NOTE:Apollo guidance\\nsoftware\r\n\
+               CATEGORIES:Partner,Apollo\r\n\
+               END:VCARD\r\n\
+               BEGIN:VCARD\r\n\
+               VERSION:3.0\r\n\
+               N:Knuth;Donald;;;\r\n\
+               item1.EMAIL:don@test.com\r\n\
+               BDAY:1938-01-10\r\n\
+               END:VCARD\r\n\
+               BEGIN:VCARD\r\n\
+               VERSION:3.0\r\n\
+               FN:No Email\r\n\
+               END:VCARD\r\n";
+
+    let response = client
+        .post(&import_url)
+        .bearer_auth(&token)
+        .multipart(upload("contacts.vcf", vcf))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let report: ImportReport = response.json().await.unwrap();
+    assert_eq!(report.total_rows, 3);
+    assert_eq!(report.imported, 2);
+    assert_eq!(report.failed, 1);
+    assert_eq!(report.rows[0].row, 3);
+
+    let response = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    let contacts: Vec<ContactDto> = response.json().await.unwrap();
+
+    let margaret = contacts
+        .iter()
+        .find(|c| c.name == "Margaret Hamilton")
+        .expect("Folded FN line should be unfolded");
+    assert_eq!(margaret.email, "margaret@home.test");
+    assert_eq!(margaret.contact_type, ContactType::Partner);
+    assert_eq!(margaret.emails.len(), 1);
+    assert_eq!(margaret.emails[0].label, "work");
+    assert_eq!(margaret.emails[0].email, "margaret@work.test");
+    assert_eq!(margaret.phones[0].label, "cell");
+    assert_eq!(margaret.phones[0].number, "+16175550100");
+    assert_eq!(margaret.addresses[0].city, "Cambridge");
+    assert_eq!(margaret.addresses[0].postal_code, "02139");
+    assert_eq!(margaret.notes.as_deref(), Some("Apollo guidance\nsoftware"));
+
+    let donald = contacts
+        .iter()
+        .find(|c| c.email == "don@test.com")
+        .expect("Grouped EMAIL property should be read");
+    assert_eq!(donald.name, "Donald Knuth");
+    assert_eq!(donald.birthday, NaiveDate::from_ymd_opt(1938, 1, 10));
+}
+
+#[tokio::test]
+async fn test_import_rejects_unusable_files() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let import_url = format!("http://{addr}/api/v1/contacts/import");
+
+    // Unknown extension and no explicit format.
+    let response = client
+        .post(&import_url)
+        .bearer_auth(&token)
+        .multipart(upload("contacts.txt", "name,email\n"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // The explicit format wins over the file name, but the header has no email column.
+    let response = client
+        .post(format!("{import_url}?format=csv"))
+        .bearer_auth(&token)
+        .multipart(upload("contacts.txt", "name,phone\nAda,123\n"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Import requires authentication.
+    let response = client
+        .post(&import_url)
+        .multipart(upload("contacts.csv", "name,email\n"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,614 @@
+use backend::config::{
+    AppConfig, BackupConfig, DatabaseConfig, JwtConfig, MailerBackend, MailerConfig,
+    NewsletterConfig, RateLimitConfig, SqliteConfig, StorageBackend, StorageConfig, TrashConfig,
+    WebConfig,
+};
+use backend::db::{DbPool, DbPools};
+use backend::repository::Repositories;
+use backend::web_server::AppState;
+use common::{
+    AttachmentDto, ContactDto, ContactEventDto, ContactType, ContactTypeDto, Credentials,
+    CustomFieldDto, CustomFieldType, LoginResponse, MembershipDto, SubscriptionDto, TagDto,
+    TrashedContactDto,
+};
+use image::{ImageFormat, RgbImage};
+use reqwest::{multipart, StatusCode};
+use std::io::Cursor;
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+
+/// Spawns a server whose users, organizations, sessions, contacts, tags, shares, custom
+/// fields and attachments live in memory. Its pool never connects, so any handler that still
+/// reaches for the database fails.
+async fn spawn_app_in_memory() -> (SocketAddr, reqwest::Client) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let db_pools = DbPools::new(DbPool::Sqlite(
+        sqlx::SqlitePool::connect_lazy("sqlite::memory:").unwrap(),
+    ));
+
+    let config = AppConfig {
+        web: WebConfig {
+            addr: "127.0.0.1".to_string(),
+            port: addr.port(),
+            cors_origin: "http://localhost:5173".to_string(),
+        },
+        database: DatabaseConfig {
+            url: None,
+            min_connections: 0,
+            max_connections: 5,
+            acquire_timeout_seconds: 30,
+            idle_timeout_seconds: None,
+            statement_timeout_seconds: None,
+            replica_urls: Vec::new(),
+            replica_check_interval_seconds: 10,
+            connect_attempts: 1,
+            connect_backoff_ms: 250,
+            connect_backoff_max_ms: 10_000,
+            sqlite: SqliteConfig {
+                wal: false,
+                busy_timeout_ms: 5000,
+                foreign_keys: true,
+            },
+        },
+        jwt: JwtConfig {
+            secret: "test_secret".to_string(),
+            access_token_expires_minutes: 15,
+            refresh_token_expires_days: 7,
+        },
+        ratelimit: RateLimitConfig {
+            per_second: 1000,
+            burst_size: 500,
+        },
+        trash: TrashConfig {
+            retention_days: 30,
+            purge_interval_minutes: 60,
+        },
+        storage: StorageConfig {
+            backend: StorageBackend::Local,
+            local_path: std::env::temp_dir()
+                .join(format!("cornerstone-test-{}", uuid::Uuid::new_v4())),
+            max_upload_bytes: 64 * 1024,
+            allowed_content_types: vec!["image/*".to_string()],
+            avatar_size: 32,
+            s3: None,
+        },
+        mailer: MailerConfig {
+            backend: MailerBackend::Log,
+            from: "Cornerstone <no-reply@example.com>".to_string(),
+            smtp: None,
+        },
+        newsletter: NewsletterConfig {
+            public_url: format!("http://{addr}"),
+            confirmation_expires_hours: 72,
+        },
+        backup: BackupConfig {
+            dir: std::env::temp_dir().join(format!("cornerstone-backups-{}", uuid::Uuid::new_v4())),
+            keep: 2,
+        },
+    };
+
+    let app_state = AppState {
+        storage: backend::storage::from_config(&config.storage).unwrap(),
+        mailer: backend::mailer::from_config(&config.mailer).unwrap(),
+        repos: Repositories::in_memory(),
+        db_pools,
+        app_config: config,
+    };
+    let app = backend::web_server::create_router(app_state);
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    (addr, reqwest::Client::new())
+}
+
+fn new_contact(name: &str, email: &str) -> ContactDto {
+    ContactDto {
+        id: None,
+        name: name.to_string(),
+        email: email.to_string(),
+        birthday: None,
+        subscribed: false,
+        contact_type: ContactType::Personal,
+        tags: vec!["family".to_string()],
+        ..Default::default()
+    }
+}
+
+async fn register_and_login(addr: SocketAddr, client: &reqwest::Client) -> LoginResponse {
+    let credentials = Credentials {
+        email: "test@example.com".to_string(),
+        password: "password123".to_string(),
+    };
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_auth_flow_in_memory() {
+    let (addr, client) = spawn_app_in_memory().await;
+    let credentials = Credentials {
+        email: "test@example.com".to_string(),
+        password: "password123".to_string(),
+    };
+
+    // 1. Register once; a second registration with the same email conflicts.
+    for expected in [StatusCode::CREATED, StatusCode::CONFLICT] {
+        let response = client
+            .post(format!("http://{addr}/api/v1/register"))
+            .json(&credentials)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    // 2. Log in, refresh, and check that the used refresh token is spent.
+    let tokens: LoginResponse = client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let refresh_body = serde_json::json!({ "refresh_token": tokens.refresh_token });
+    let response = client
+        .post(format!("http://{addr}/api/v1/refresh"))
+        .json(&refresh_body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let refreshed: LoginResponse = response.json().await.unwrap();
+    let response = client
+        .post(format!("http://{addr}/api/v1/refresh"))
+        .json(&refresh_body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // 3. Logging out ends the session.
+    let response = client
+        .post(format!("http://{addr}/api/v1/logout"))
+        .bearer_auth(&refreshed.access_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = client
+        .post(format!("http://{addr}/api/v1/refresh"))
+        .json(&serde_json::json!({ "refresh_token": refreshed.refresh_token }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_contact_crud_in_memory() {
+    let (addr, client) = spawn_app_in_memory().await;
+    let token = register_and_login(addr, &client).await.access_token;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    // 1. Create two contacts; the same email again conflicts.
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&new_contact("Ada Lovelace", "ada@test.com"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let ada: ContactDto = response.json().await.unwrap();
+    let ada_url = format!("{contacts_url}/{}", ada.id.unwrap());
+    client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&new_contact("Alan Turing", "alan@test.com"))
+        .send()
+        .await
+        .unwrap();
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&new_contact("Ada Again", "ada@test.com"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // 2. List with a filter, and fetch one.
+    let found: Vec<ContactDto> = client
+        .get(format!("{contacts_url}?q=TURING"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].email, "alan@test.com");
+    let fetched: ContactDto = client
+        .get(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(fetched, ada);
+
+    // 3. Update, then delete; the deleted contact is gone.
+    let mut renamed = new_contact("Augusta Ada King", "ada@test.com");
+    renamed.tags = Vec::new();
+    let updated: ContactDto = client
+        .put(&ada_url)
+        .bearer_auth(&token)
+        .json(&renamed)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(updated.name, "Augusta Ada King");
+    assert!(updated.tags.is_empty());
+
+    let response = client
+        .delete(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = client
+        .get(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let remaining: Vec<ContactDto> = client
+        .get(format!("{contacts_url}?tag=family"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(remaining.len(), 1);
+}
+
+#[tokio::test]
+async fn test_trash_tags_and_orgs_in_memory() {
+    let (addr, client) = spawn_app_in_memory().await;
+    let tokens = register_and_login(addr, &client).await;
+    let token = &tokens.access_token;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let ada: ContactDto = client
+        .post(&contacts_url)
+        .bearer_auth(token)
+        .json(&new_contact("Ada Lovelace", "ada@test.com"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let ada_id = ada.id.unwrap();
+
+    // 1. A deleted contact goes to the trash, frees its email, and can't be restored while
+    // another contact has taken it.
+    client
+        .delete(format!("{contacts_url}/{ada_id}"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    let trash: Vec<TrashedContactDto> = client
+        .get(format!("{contacts_url}/trash"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].contact.id, Some(ada_id));
+
+    let taker: ContactDto = client
+        .post(&contacts_url)
+        .bearer_auth(token)
+        .json(&new_contact("Ada Again", "ada@test.com"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let restore_url = format!("{contacts_url}/{ada_id}/restore");
+    let response = client
+        .post(&restore_url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    client
+        .delete(format!("{contacts_url}/{}", taker.id.unwrap()))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    let response = client
+        .post(&restore_url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let restored: ContactDto = client
+        .get(format!("{contacts_url}/{ada_id}"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(restored, ada);
+
+    // 2. Tags written with a contact are listed, and renaming one relabels its contacts.
+    let tags: Vec<TagDto> = client
+        .get(format!("http://{addr}/api/v1/tags"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(tags.len(), 1);
+    assert_eq!(tags[0].name, "family");
+    let response = client
+        .put(format!("http://{addr}/api/v1/tags/{}", tags[0].id.unwrap()))
+        .bearer_auth(token)
+        .json(&serde_json::json!({ "name": "relatives" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let relabelled: Vec<ContactDto> = client
+        .get(format!("{contacts_url}?tag=relatives"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(relabelled.len(), 1);
+    assert_eq!(relabelled[0].id, Some(ada_id));
+
+    // 3. The only owner can't leave their organization.
+    let members: Vec<MembershipDto> = client
+        .get(format!("http://{addr}/api/v1/org/members"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(members.len(), 1);
+    let response = client
+        .delete(format!(
+            "http://{addr}/api/v1/org/members/{}",
+            members[0].user_id
+        ))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+/// A small PNG image in a multipart form, as the upload endpoints take it.
+fn png_upload() -> multipart::Form {
+    let image = RgbImage::from_fn(8, 8, |x, _| image::Rgb([(x * 30) as u8, 80, 160]));
+    let mut file = Cursor::new(Vec::new());
+    image.write_to(&mut file, ImageFormat::Png).unwrap();
+    let part = multipart::Part::bytes(file.into_inner())
+        .file_name("ada.png")
+        .mime_str("image/png")
+        .unwrap();
+    multipart::Form::new().part("file", part)
+}
+
+#[tokio::test]
+async fn test_fields_files_and_consent_in_memory() {
+    let (addr, client) = spawn_app_in_memory().await;
+    let token = &register_and_login(addr, &client).await.access_token;
+    let api = format!("http://{addr}/api/v1");
+
+    // 1. Every contact type is offered, and a custom field can be defined and renamed, taking
+    //    the contacts' values along.
+    let types: Vec<ContactTypeDto> = client
+        .get(format!("{api}/contact-types"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(types.len(), ContactType::ALL.len());
+
+    let region = CustomFieldDto {
+        id: None,
+        name: "Region".to_string(),
+        field_type: CustomFieldType::Text,
+        required: false,
+        options: Vec::new(),
+    };
+    let response = client
+        .post(format!("{api}/custom-fields"))
+        .bearer_auth(token)
+        .json(&region)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let field_id = response.json::<CustomFieldDto>().await.unwrap().id.unwrap();
+    let response = client
+        .post(format!("{api}/custom-fields"))
+        .bearer_auth(token)
+        .json(&region)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let mut ada = new_contact("Ada Lovelace", "ada@test.com");
+    ada.custom_fields
+        .insert("Region".to_string(), serde_json::json!("EMEA"));
+    let ada: ContactDto = client
+        .post(format!("{api}/contacts"))
+        .bearer_auth(token)
+        .json(&ada)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let ada_url = format!("{api}/contacts/{}", ada.id.unwrap());
+
+    let response = client
+        .put(format!("{api}/custom-fields/{field_id}"))
+        .bearer_auth(token)
+        .json(&CustomFieldDto {
+            name: "Area".to_string(),
+            ..region
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let renamed: ContactDto = client
+        .get(&ada_url)
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(renamed.custom_fields["Area"], "EMEA");
+
+    // 2. Files and avatars are recorded with the contact.
+    let response = client
+        .post(format!("{ada_url}/attachments"))
+        .bearer_auth(token)
+        .multipart(png_upload())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let attachment: AttachmentDto = response.json().await.unwrap();
+    let listed: Vec<AttachmentDto> = client
+        .get(format!("{ada_url}/attachments"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(listed, vec![attachment.clone()]);
+    let response = client
+        .get(format!("{ada_url}/attachments/{}", attachment.id))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = client
+        .put(format!("{ada_url}/avatar"))
+        .bearer_auth(token)
+        .multipart(png_upload())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let with_avatar: ContactDto = response.json().await.unwrap();
+    assert!(with_avatar.avatar_url.is_some());
+    for expected in [StatusCode::NO_CONTENT, StatusCode::NOT_FOUND] {
+        let response = client
+            .delete(format!("{ada_url}/avatar"))
+            .bearer_auth(token)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), expected);
+    }
+
+    // 3. A subscription can be requested but not given by the user, and no history is kept.
+    let response = client
+        .post(format!("{ada_url}/subscription"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let subscription: SubscriptionDto = client
+        .get(format!("{ada_url}/subscription"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(!subscription.subscribed);
+    let history: Vec<ContactEventDto> = client
+        .get(format!("{ada_url}/history"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(history.is_empty());
+}
@@ -0,0 +1,364 @@
+use common::{
+    ContactDto, ContactType, Credentials, LoginResponse, SubscriptionAction, SubscriptionDto,
+    SubscriptionSource,
+};
+use reqwest::{multipart, StatusCode};
+mod helpers;
+
+fn new_contact(email: &str, subscribed: bool) -> ContactDto {
+    ContactDto {
+        id: None,
+        name: "Ada Lovelace".to_string(),
+        email: email.to_string(),
+        birthday: None,
+        subscribed,
+        contact_type: ContactType::Partner,
+        tags: Vec::new(),
+        ..Default::default()
+    }
+}
+
+async fn get_subscription(
+    client: &reqwest::Client,
+    token: &str,
+    contact_url: &str,
+) -> SubscriptionDto {
+    client
+        .get(format!("{contact_url}/subscription"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+/// The link in the last email the server sent.
+fn last_link(mailer: &helpers::TestMailer) -> String {
+    let sent = mailer.sent.lock().unwrap();
+    let email = sent.last().expect("No email was sent");
+    email
+        .body
+        .lines()
+        .find(|line| line.starts_with("http://"))
+        .expect("The email contains no link")
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_double_opt_in_and_unsubscribe() {
+    let (addr, client, _db_pool, mailer) = helpers::spawn_app_with_mailer().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contact: ContactDto = client
+        .post(format!("http://{addr}/api/v1/contacts"))
+        .bearer_auth(&token)
+        .json(&new_contact("ada@test.com", false))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let contact_url = format!("http://{addr}/api/v1/contacts/{}", contact.id.unwrap());
+
+    // 1. Asking for a subscription emails a confirmation link but changes nothing yet.
+    let response = client
+        .post(format!("{contact_url}/subscription"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    assert_eq!(mailer.sent.lock().unwrap()[0].to, "ada@test.com");
+    let confirm_link = last_link(&mailer);
+    assert!(confirm_link.starts_with(&format!("http://{addr}/api/v1/newsletter/confirm?token=")));
+    assert!(
+        !get_subscription(&client, &token, &contact_url)
+            .await
+            .subscribed
+    );
+
+    // 2. Opening the link only shows a button, so a mail scanner following it changes
+    //    nothing. A tampered link is refused; the real one subscribes the contact without a
+    //    login.
+    let response = client.get(&confirm_link).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response
+        .text()
+        .await
+        .unwrap()
+        .contains("<form method=\"post\""));
+    assert!(
+        !get_subscription(&client, &token, &contact_url)
+            .await
+            .subscribed
+    );
+    let response = client
+        .post(format!("{confirm_link}x"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client.post(&confirm_link).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let subscription = get_subscription(&client, &token, &contact_url).await;
+    assert!(subscription.subscribed);
+    assert!(subscription.subscribed_at.is_some());
+    assert_eq!(subscription.source, Some(SubscriptionSource::DoubleOptIn));
+    let events: Vec<_> = subscription
+        .events
+        .iter()
+        .map(|event| (event.action, event.source, event.user_email.as_deref()))
+        .collect();
+    assert_eq!(
+        events,
+        vec![
+            (
+                SubscriptionAction::Requested,
+                SubscriptionSource::DoubleOptIn,
+                Some("test@example.com")
+            ),
+            (
+                SubscriptionAction::Subscribed,
+                SubscriptionSource::DoubleOptIn,
+                None
+            ),
+        ]
+    );
+    assert!(subscription
+        .events
+        .iter()
+        .all(|event| event.email == "ada@test.com"));
+
+    // 3. Confirming twice changes nothing, and subscribed contacts can't be asked again.
+    let response = client.post(&confirm_link).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post(format!("{contact_url}/subscription"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // 4. Opening the unsubscribe link only shows a button; the one-click POST of RFC 8058
+    //    unsubscribes, any number of times.
+    let unsubscribe_url = subscription.unsubscribe_url;
+    assert!(unsubscribe_url.starts_with(&format!(
+        "http://{addr}/api/v1/newsletter/unsubscribe?token="
+    )));
+    let response = client.get(&unsubscribe_url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        get_subscription(&client, &token, &contact_url)
+            .await
+            .subscribed
+    );
+    for _ in 0..2 {
+        let response = client
+            .post(&unsubscribe_url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body("List-Unsubscribe=One-Click")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = client
+        .get(format!(
+            "http://{addr}/api/v1/newsletter/unsubscribe?token=2.{}",
+            unsubscribe_url.rsplit_once('.').unwrap().1
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let subscription = get_subscription(&client, &token, &contact_url).await;
+    assert!(!subscription.subscribed);
+    assert_eq!(subscription.subscribed_at, None);
+    assert_eq!(subscription.source, None);
+    assert_eq!(subscription.events.len(), 3);
+    let last = subscription.events.last().unwrap();
+    assert_eq!(last.action, SubscriptionAction::Unsubscribed);
+    assert_eq!(last.source, SubscriptionSource::UnsubscribeLink);
+    assert_eq!(last.user_id, None);
+
+    // 5. A confirmation link stops working once the contact's email changes.
+    client
+        .post(format!("{contact_url}/subscription"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    let confirm_link = last_link(&mailer);
+    let response = client
+        .put(&contact_url)
+        .bearer_auth(&token)
+        .json(&new_contact("ada.lovelace@test.com", false))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client.post(&confirm_link).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(
+        !get_subscription(&client, &token, &contact_url)
+            .await
+            .subscribed
+    );
+}
+
+#[tokio::test]
+async fn test_subscription_changes_are_recorded() {
+    let (addr, client, _db_pool, mailer) = helpers::spawn_app_with_mailer().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    // 1. Contacts written or imported as subscribed are only asked to confirm.
+    let contact: ContactDto = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&new_contact("ada@test.com", true))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(!contact.subscribed);
+    assert_eq!(mailer.sent.lock().unwrap()[0].to, "ada@test.com");
+    let contact_url = format!("{contacts_url}/{}", contact.id.unwrap());
+    let subscription = get_subscription(&client, &token, &contact_url).await;
+    assert!(!subscription.subscribed);
+    assert_eq!(subscription.events.len(), 1);
+    assert_eq!(subscription.events[0].action, SubscriptionAction::Requested);
+    assert_eq!(
+        subscription.events[0].user_email.as_deref(),
+        Some("test@example.com")
+    );
+    helpers::confirm_subscriptions(&client, &mailer).await;
+
+    let csv = "Name,Email,Newsletter\r\nAlan Turing,alan@test.com,yes\r\n";
+    let part = multipart::Part::bytes(csv.as_bytes().to_vec()).file_name("contacts.csv");
+    let response = client
+        .post(format!("{contacts_url}/import"))
+        .bearer_auth(&token)
+        .multipart(multipart::Form::new().part("file", part))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let imported: Vec<ContactDto> = client
+        .get(format!("{contacts_url}?q=alan"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let imported_url = format!("{contacts_url}/{}", imported[0].id.unwrap());
+    let subscription = get_subscription(&client, &token, &imported_url).await;
+    assert!(!subscription.subscribed);
+    assert_eq!(subscription.events[0].action, SubscriptionAction::Requested);
+    assert_eq!(mailer.sent.lock().unwrap()[0].to, "alan@test.com");
+    helpers::confirm_subscriptions(&client, &mailer).await;
+    let subscription = get_subscription(&client, &token, &imported_url).await;
+    assert!(subscription.subscribed);
+    assert_eq!(subscription.source, Some(SubscriptionSource::DoubleOptIn));
+
+    // 2. Only updates that change the subscription are recorded, and an update can't
+    //    subscribe the contact again.
+    let mut renamed = new_contact("ada@test.com", true);
+    renamed.name = "Augusta Ada King".to_string();
+    client
+        .put(&contact_url)
+        .bearer_auth(&token)
+        .json(&renamed)
+        .send()
+        .await
+        .unwrap();
+    client
+        .put(&contact_url)
+        .bearer_auth(&token)
+        .json(&new_contact("ada@test.com", false))
+        .send()
+        .await
+        .unwrap();
+    let resubscribed: ContactDto = client
+        .put(&contact_url)
+        .bearer_auth(&token)
+        .json(&new_contact("ada@test.com", true))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(!resubscribed.subscribed);
+    let subscription = get_subscription(&client, &token, &contact_url).await;
+    assert!(!subscription.subscribed);
+    assert_eq!(subscription.subscribed_at, None);
+    let actions: Vec<_> = subscription
+        .events
+        .iter()
+        .map(|event| (event.action, event.source))
+        .collect();
+    assert_eq!(
+        actions,
+        vec![
+            (
+                SubscriptionAction::Requested,
+                SubscriptionSource::DoubleOptIn
+            ),
+            (
+                SubscriptionAction::Subscribed,
+                SubscriptionSource::DoubleOptIn
+            ),
+            (SubscriptionAction::Unsubscribed, SubscriptionSource::Manual),
+            (
+                SubscriptionAction::Requested,
+                SubscriptionSource::DoubleOptIn
+            ),
+        ]
+    );
+
+    // 3. Other organizations can't see the record or ask the contact to subscribe.
+    let credentials = Credentials {
+        email: "other@example.com".to_string(),
+        password: "password123".to_string(),
+    };
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    let other_token = client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json::<LoginResponse>()
+        .await
+        .unwrap()
+        .access_token;
+    let response = client
+        .get(format!("{contact_url}/subscription"))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .post(format!("{contact_url}/subscription"))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,472 @@
+use common::{
+    AddMemberRequest, BulkContactResponse, BulkItemStatus, ContactDto, ContactType, Credentials,
+    LoginResponse, MembershipDto, OrgDto, OrgRole, TrashedContactDto,
+};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::net::SocketAddr;
+mod helpers;
+
+fn contact(name: &str, email: &str) -> ContactDto {
+    ContactDto {
+        id: None,
+        name: name.to_string(),
+        email: email.to_string(),
+        birthday: None,
+        subscribed: false,
+        contact_type: ContactType::Friend,
+        tags: Vec::new(),
+        ..Default::default()
+    }
+}
+
+/// Registers and logs in a user besides the default test user, returning their token.
+async fn login_as(addr: &SocketAddr, client: &reqwest::Client, email: &str) -> String {
+    let credentials = Credentials {
+        email: email.to_string(),
+        password: "password123".to_string(),
+    };
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json::<LoginResponse>()
+        .await
+        .unwrap()
+        .access_token
+}
+
+/// Creates an organization owned by the caller and returns tokens scoped to it.
+async fn create_org(
+    addr: &SocketAddr,
+    client: &reqwest::Client,
+    token: &str,
+    name: &str,
+) -> (OrgDto, LoginResponse) {
+    let response = client
+        .post(format!("http://{addr}/api/v1/orgs"))
+        .bearer_auth(token)
+        .json(&json!({ "name": name }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let org: OrgDto = response.json().await.unwrap();
+    let tokens = switch_to(addr, client, token, org.id).await;
+    (org, tokens)
+}
+
+async fn switch_to(
+    addr: &SocketAddr,
+    client: &reqwest::Client,
+    token: &str,
+    org_id: i64,
+) -> LoginResponse {
+    let response = client
+        .post(format!("http://{addr}/api/v1/orgs/{org_id}/switch"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+async fn add_member(
+    addr: &SocketAddr,
+    client: &reqwest::Client,
+    token: &str,
+    email: &str,
+    role: OrgRole,
+) -> reqwest::Response {
+    client
+        .post(format!("http://{addr}/api/v1/org/members"))
+        .bearer_auth(token)
+        .json(&AddMemberRequest {
+            email: email.to_string(),
+            role,
+        })
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn list_contacts(
+    addr: &SocketAddr,
+    client: &reqwest::Client,
+    token: &str,
+) -> Vec<ContactDto> {
+    client
+        .get(format!("http://{addr}/api/v1/contacts"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+async fn create_contact(
+    addr: &SocketAddr,
+    client: &reqwest::Client,
+    token: &str,
+    contact: &ContactDto,
+) -> reqwest::Response {
+    client
+        .post(format!("http://{addr}/api/v1/contacts"))
+        .bearer_auth(token)
+        .json(contact)
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_switch_organization() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let colleague_token = login_as(&addr, &client, "colleague@example.com").await;
+
+    // Every user starts in a personal organization named after them.
+    let orgs: Vec<OrgDto> = client
+        .get(format!("http://{addr}/api/v1/orgs"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(orgs.len(), 1);
+    assert_eq!(orgs[0].name, "test@example.com");
+    assert_eq!(orgs[0].role, OrgRole::Owner);
+
+    let response = create_contact(&addr, &client, &token, &contact("Ada", "ada@test.com")).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // A new organization starts out empty and keeps its own contacts.
+    let (acme, acme_tokens) = create_org(&addr, &client, &token, "Acme Ltd").await;
+    assert_eq!(acme.role, OrgRole::Owner);
+    assert!(list_contacts(&addr, &client, &acme_tokens.access_token)
+        .await
+        .is_empty());
+    let response = create_contact(
+        &addr,
+        &client,
+        &acme_tokens.access_token,
+        &contact("Ada at Acme", "ada@test.com"),
+    )
+    .await;
+    assert_eq!(
+        response.status(),
+        StatusCode::CREATED,
+        "Emails are unique per organization"
+    );
+
+    // Refreshed tokens stay in the organization that was switched to.
+    let refreshed: LoginResponse = client
+        .post(format!("http://{addr}/api/v1/refresh"))
+        .json(&json!({ "refresh_token": acme_tokens.refresh_token }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let contacts = list_contacts(&addr, &client, &refreshed.access_token).await;
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(contacts[0].name, "Ada at Acme");
+
+    // Users cannot switch to organizations they do not belong to.
+    let response = client
+        .post(format!("http://{addr}/api/v1/orgs/{}/switch", acme.id))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Once added, they can.
+    let response = add_member(
+        &addr,
+        &client,
+        &refreshed.access_token,
+        "colleague@example.com",
+        OrgRole::Member,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let colleague_acme = switch_to(&addr, &client, &colleague_token, acme.id).await;
+    let contacts = list_contacts(&addr, &client, &colleague_acme.access_token).await;
+    assert_eq!(contacts.len(), 1);
+
+    let members: Vec<MembershipDto> = client
+        .get(format!("http://{addr}/api/v1/org/members"))
+        .bearer_auth(&colleague_acme.access_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let roles: Vec<(&str, OrgRole)> = members.iter().map(|m| (m.email.as_str(), m.role)).collect();
+    assert_eq!(
+        roles,
+        vec![
+            ("test@example.com", OrgRole::Owner),
+            ("colleague@example.com", OrgRole::Member)
+        ]
+    );
+
+    // Adding the same user twice is a conflict.
+    let response = add_member(
+        &addr,
+        &client,
+        &refreshed.access_token,
+        "colleague@example.com",
+        OrgRole::Viewer,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_cross_tenant_isolation() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let other_token = login_as(&addr, &client, "other@example.com").await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let ada: ContactDto = create_contact(&addr, &client, &token, &contact("Ada", "ada@test.com"))
+        .await
+        .json()
+        .await
+        .unwrap();
+    let ada_id = ada.id.unwrap();
+    let trashed: ContactDto =
+        create_contact(&addr, &client, &token, &contact("Bob", "bob@test.com"))
+            .await
+            .json()
+            .await
+            .unwrap();
+    let trashed_id = trashed.id.unwrap();
+    client
+        .delete(format!("{contacts_url}/{trashed_id}"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+
+    // Single contact endpoints hide contacts of other organizations.
+    let ada_url = format!("{contacts_url}/{ada_id}");
+    let responses = [
+        client.get(&ada_url).bearer_auth(&other_token).send().await,
+        client
+            .put(&ada_url)
+            .bearer_auth(&other_token)
+            .json(&contact("Hijacked", "hijacked@test.com"))
+            .send()
+            .await,
+        client
+            .delete(&ada_url)
+            .bearer_auth(&other_token)
+            .send()
+            .await,
+        client
+            .get(format!("{ada_url}/history"))
+            .bearer_auth(&other_token)
+            .send()
+            .await,
+        client
+            .post(format!("{contacts_url}/{trashed_id}/restore"))
+            .bearer_auth(&other_token)
+            .send()
+            .await,
+    ];
+    for response in responses {
+        assert_eq!(response.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    // Bulk operations fail item by item.
+    let batch = json!({
+        "operations": [
+            { "op": "update", "id": ada_id, "contact": { "name": "Hijacked", "email": "hijacked@test.com", "subscribed": false, "contactType": "Friend" } },
+            { "op": "delete", "id": ada_id }
+        ]
+    });
+    let body: BulkContactResponse = client
+        .post(format!("{contacts_url}/bulk"))
+        .bearer_auth(&other_token)
+        .json(&batch)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(body
+        .results
+        .iter()
+        .all(|result| result.status == BulkItemStatus::Failed));
+
+    // Lists, the trash and exports only show the caller's organization.
+    assert!(list_contacts(&addr, &client, &other_token).await.is_empty());
+    let trash: Vec<TrashedContactDto> = client
+        .get(format!("{contacts_url}/trash"))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(trash.is_empty());
+    let export = client
+        .get(format!("{contacts_url}/export"))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(!export.contains("ada@test.com"));
+
+    // Nothing changed for the owning organization.
+    let stored: ContactDto = client
+        .get(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(stored, ada);
+    let trash: Vec<TrashedContactDto> = client
+        .get(format!("{contacts_url}/trash"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(trash.len(), 1);
+}
+
+#[tokio::test]
+async fn test_organization_roles() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let viewer_token = login_as(&addr, &client, "viewer@example.com").await;
+    let member_token = login_as(&addr, &client, "member@example.com").await;
+
+    let (acme, owner) = create_org(&addr, &client, &token, "Acme Ltd").await;
+    let owner = owner.access_token;
+    for (email, role) in [
+        ("viewer@example.com", OrgRole::Viewer),
+        ("member@example.com", OrgRole::Member),
+    ] {
+        let response = add_member(&addr, &client, &owner, email, role).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    let viewer = switch_to(&addr, &client, &viewer_token, acme.id)
+        .await
+        .access_token;
+    let member = switch_to(&addr, &client, &member_token, acme.id)
+        .await
+        .access_token;
+
+    // Viewers read but do not write.
+    let response = create_contact(&addr, &client, &viewer, &contact("Ada", "ada@test.com")).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = create_contact(&addr, &client, &member, &contact("Ada", "ada@test.com")).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert_eq!(list_contacts(&addr, &client, &viewer).await.len(), 1);
+
+    // Members do not manage the organization.
+    let response = add_member(
+        &addr,
+        &client,
+        &member,
+        "another@example.com",
+        OrgRole::Member,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client
+        .post(format!("http://{addr}/api/v1/custom-fields"))
+        .bearer_auth(&member)
+        .json(&json!({ "name": "Region", "fieldType": "text" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // The last owner can be neither demoted nor removed.
+    let members: Vec<MembershipDto> = client
+        .get(format!("http://{addr}/api/v1/org/members"))
+        .bearer_auth(&owner)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let owner_id = members[0].user_id;
+    let viewer_id = members[1].user_id;
+    let member_id = members[2].user_id;
+    let response = client
+        .put(format!("http://{addr}/api/v1/org/members/{owner_id}"))
+        .bearer_auth(&owner)
+        .json(&json!({ "role": "admin" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = client
+        .delete(format!("http://{addr}/api/v1/org/members/{owner_id}"))
+        .bearer_auth(&owner)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Promoting the viewer lets them write.
+    let response = client
+        .put(format!("http://{addr}/api/v1/org/members/{viewer_id}"))
+        .bearer_auth(&owner)
+        .json(&json!({ "role": "member" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = create_contact(&addr, &client, &viewer, &contact("Bob", "bob@test.com")).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Removed members lose access right away, even with a valid token.
+    let response = client
+        .delete(format!("http://{addr}/api/v1/org/members/{member_id}"))
+        .bearer_auth(&owner)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = client
+        .get(format!("http://{addr}/api/v1/contacts"))
+        .bearer_auth(&member)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,126 @@
+use backend::seed::{self, Fixture, SeedError};
+use backend::with_pool;
+use common::{ContactDto, Credentials, LoginResponse};
+use std::collections::HashSet;
+use std::net::SocketAddr;
+mod helpers;
+
+async fn contacts_of(addr: &SocketAddr, client: &reqwest::Client, email: &str) -> Vec<ContactDto> {
+    let token = client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&Credentials {
+            email: email.to_string(),
+            password: "password123".to_string(),
+        })
+        .send()
+        .await
+        .unwrap()
+        .json::<LoginResponse>()
+        .await
+        .unwrap()
+        .access_token;
+    client
+        .get(format!("http://{addr}/api/v1/contacts"))
+        .bearer_auth(token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_load_fixture() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let _token = helpers::get_auth_token(&addr, &client).await;
+
+    let user_ids = helpers::load_fixture(&db_pool, "demo.yaml").await;
+    assert_eq!(user_ids.len(), 3);
+
+    let users = backend::admin::list_users(&db_pool).await.unwrap();
+    let admins: Vec<&str> = users
+        .iter()
+        .filter(|u| u.is_admin)
+        .map(|u| u.email.as_str())
+        .collect();
+    assert_eq!(admins, vec!["admin@example.com"]);
+
+    let contacts = contacts_of(&addr, &client, "alice@example.com").await;
+    let names: Vec<&str> = contacts.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["Bob Miller", "Carol Jones", "Dan Brown"]);
+    assert_eq!(contacts[0].phones[0].number, "+15555550101");
+    assert_eq!(contacts[1].addresses[0].city, "Springfield");
+    assert_eq!(contacts[2].tags, vec!["newsletter", "vip"]);
+    assert_eq!(
+        contacts_of(&addr, &client, "bob@example.com").await.len(),
+        1
+    );
+    assert!(contacts_of(&addr, &client, "admin@example.com")
+        .await
+        .is_empty());
+
+    // Loading it again stops at the first user who exists already.
+    let fixture =
+        Fixture::read(&std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/demo.yaml"))
+            .unwrap();
+    assert!(matches!(
+        seed::load(&db_pool, &fixture).await,
+        Err(SeedError::Admin(backend::admin::AdminError::UserExists(_)))
+    ));
+}
+
+#[tokio::test]
+async fn test_generate_contacts() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let _token = helpers::get_auth_token(&addr, &client).await;
+    let user_id: i64 = with_pool!(&db_pool, |pool| sqlx::query_scalar(
+        "SELECT id FROM users WHERE email = 'test@example.com'"
+    )
+    .fetch_one(pool)
+    .await)
+    .unwrap();
+
+    assert_eq!(seed::generate(&db_pool, &[user_id], 5).await.unwrap(), 5);
+    // A second run adds to the first without repeating an email.
+    assert_eq!(seed::generate(&db_pool, &[user_id], 5).await.unwrap(), 5);
+
+    let contacts = contacts_of(&addr, &client, "test@example.com").await;
+    assert_eq!(contacts.len(), 10);
+    let emails: HashSet<&str> = contacts.iter().map(|c| c.email.as_str()).collect();
+    assert_eq!(emails.len(), 10);
+    assert!(contacts.iter().all(|c| !c.name.is_empty()));
+}
+
+#[tokio::test]
+async fn test_invalid_fixtures_are_refused() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let _token = helpers::get_auth_token(&addr, &client).await;
+
+    assert!(matches!(
+        Fixture::parse(r#"{"users": [{"email": "x@example.com", "pasword": "password123"}]}"#),
+        Err(SeedError::Parse(_))
+    ));
+
+    // JSON fixtures are read too, and their contacts are checked like the API checks them.
+    let fixture = Fixture::parse(
+        r#"{"users": [{
+            "email": "carol@example.com",
+            "password": "password123",
+            "contacts": [
+                {"name": "Dave", "email": "dave@example.com", "subscribed": false, "contactType": "Work"},
+                {"name": "Eve", "email": "not-an-email", "subscribed": false, "contactType": "Work"}
+            ]
+        }]}"#,
+    )
+    .unwrap();
+    let error = seed::load(&db_pool, &fixture).await.unwrap_err();
+    assert!(
+        matches!(error, SeedError::InvalidContact { ref contact, .. } if contact == "not-an-email")
+    );
+
+    // The user stays, but none of their contacts were added.
+    assert!(contacts_of(&addr, &client, "carol@example.com")
+        .await
+        .is_empty());
+}
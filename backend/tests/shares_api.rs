@@ -0,0 +1,432 @@
+use common::{
+    ContactDto, ContactEventDto, ContactType, Credentials, LoginResponse, ShareDto,
+    SharePermission, SharedContactDto,
+};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::net::SocketAddr;
+mod helpers;
+
+fn contact(name: &str, email: &str) -> ContactDto {
+    ContactDto {
+        id: None,
+        name: name.to_string(),
+        email: email.to_string(),
+        birthday: None,
+        subscribed: false,
+        contact_type: ContactType::Friend,
+        tags: Vec::new(),
+        ..Default::default()
+    }
+}
+
+/// Registers and logs in a user besides the default test user, returning their token.
+async fn login_as(addr: &SocketAddr, client: &reqwest::Client, email: &str) -> String {
+    let credentials = Credentials {
+        email: email.to_string(),
+        password: "password123".to_string(),
+    };
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json::<LoginResponse>()
+        .await
+        .unwrap()
+        .access_token
+}
+
+async fn create_contact(
+    client: &reqwest::Client,
+    contacts_url: &str,
+    token: &str,
+    contact: &ContactDto,
+) -> i64 {
+    let created: ContactDto = client
+        .post(contacts_url)
+        .bearer_auth(token)
+        .json(contact)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    created.id.unwrap()
+}
+
+#[tokio::test]
+async fn test_share_contact_book() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let owner_token = helpers::get_auth_token(&addr, &client).await;
+    let colleague_token = login_as(&addr, &client, "colleague@example.com").await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let shares_url = format!("http://{addr}/api/v1/shares");
+
+    let ada = create_contact(
+        &client,
+        &contacts_url,
+        &owner_token,
+        &contact("Ada Lovelace", "ada@test.com"),
+    )
+    .await;
+    create_contact(
+        &client,
+        &contacts_url,
+        &owner_token,
+        &contact("Alan Turing", "alan@test.com"),
+    )
+    .await;
+
+    // 1. Before anything is shared, the owner's contacts don't exist for the colleague.
+    let response = client
+        .get(format!("{contacts_url}/{ada}"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // 2. Share the whole book read-only.
+    let share = |body: serde_json::Value| {
+        client
+            .post(&shares_url)
+            .bearer_auth(&owner_token)
+            .json(&body)
+            .send()
+    };
+    let response = share(json!({ "email": "colleague@example.com" }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let book_share: ShareDto = response.json().await.unwrap();
+    assert_eq!(book_share.org_name, "test@example.com");
+    assert_eq!(book_share.grantee_email, "colleague@example.com");
+    assert_eq!(book_share.contact_id, None);
+    assert_eq!(book_share.permission, SharePermission::Read);
+
+    let response = share(json!({ "email": "colleague@example.com", "permission": "write" }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let response = share(json!({ "email": "test@example.com" })).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = share(json!({ "email": "nobody@example.com" }))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // 3. Read access lets the colleague look, but not change anything.
+    let response = client
+        .get(format!("{contacts_url}/{ada}"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get(format!("{contacts_url}/{ada}/history"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .put(format!("{contacts_url}/{ada}"))
+        .bearer_auth(&colleague_token)
+        .json(&contact("Ada King", "ada@test.com"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = client
+        .delete(format!("{contacts_url}/{ada}"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // The shared contacts are listed apart from the colleague's own.
+    let shared: Vec<SharedContactDto> = client
+        .get(format!("{contacts_url}/shared"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(shared.len(), 2);
+    assert_eq!(shared[0].contact.name, "Ada Lovelace");
+    assert_eq!(shared[0].org_name, "test@example.com");
+    assert_eq!(shared[0].permission, SharePermission::Read);
+    let shared: Vec<SharedContactDto> = client
+        .get(format!("{contacts_url}/shared?q=turing"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(shared.len(), 1);
+    let own: Vec<ContactDto> = client
+        .get(&contacts_url)
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(own.is_empty());
+
+    let received: Vec<ShareDto> = client
+        .get(format!("{shares_url}/received"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(received, vec![book_share.clone()]);
+
+    // 4. With write access the colleague edits the contact, and the history names them.
+    let response = client
+        .put(format!("{shares_url}/{}", book_share.id))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "permission": "write" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .put(format!("{contacts_url}/{ada}"))
+        .bearer_auth(&colleague_token)
+        .json(&ContactDto {
+            tags: vec!["vip".to_string()],
+            ..contact("Ada King", "ada@test.com")
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let events: Vec<ContactEventDto> = client
+        .get(format!("{contacts_url}/{ada}/history"))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let last = events.last().unwrap();
+    assert_eq!(last.user_email.as_deref(), Some("colleague@example.com"));
+    assert_eq!(last.after.as_ref().unwrap().name, "Ada King");
+
+    // The tag was created in the owner's book.
+    let tags: Vec<serde_json::Value> = client
+        .get(format!("http://{addr}/api/v1/tags"))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(tags.len(), 1);
+
+    // Deleting moves the contact to the owner's trash.
+    let response = client
+        .delete(format!("{contacts_url}/{ada}"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let trash: Vec<serde_json::Value> = client
+        .get(format!("{contacts_url}/trash"))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0]["id"], ada);
+
+    // 5. Once revoked, nothing is shared anymore.
+    let response = client
+        .delete(format!("{shares_url}/{}", book_share.id))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let shared: Vec<SharedContactDto> = client
+        .get(format!("{contacts_url}/shared"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(shared.is_empty());
+}
+
+#[tokio::test]
+async fn test_share_single_contact() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let owner_token = helpers::get_auth_token(&addr, &client).await;
+    let colleague_token = login_as(&addr, &client, "colleague@example.com").await;
+    let stranger_token = login_as(&addr, &client, "stranger@example.com").await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let shares_url = format!("http://{addr}/api/v1/shares");
+
+    let ada = create_contact(
+        &client,
+        &contacts_url,
+        &owner_token,
+        &contact("Ada Lovelace", "ada@test.com"),
+    )
+    .await;
+    let alan = create_contact(
+        &client,
+        &contacts_url,
+        &owner_token,
+        &contact("Alan Turing", "alan@test.com"),
+    )
+    .await;
+    let strangers = create_contact(
+        &client,
+        &contacts_url,
+        &stranger_token,
+        &contact("Grace Hopper", "grace@test.com"),
+    )
+    .await;
+
+    // 1. Only the user's own contacts can be shared.
+    let response = client
+        .post(&shares_url)
+        .bearer_auth(&owner_token)
+        .json(&json!({ "email": "colleague@example.com", "contactId": strangers }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // 2. Sharing one contact leaves the rest of the book private.
+    let share: ShareDto = client
+        .post(&shares_url)
+        .bearer_auth(&owner_token)
+        .json(&json!({
+            "email": "colleague@example.com", "contactId": ada, "permission": "write"
+        }))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(share.contact_id, Some(ada));
+
+    let response = client
+        .put(format!("{contacts_url}/{ada}"))
+        .bearer_auth(&colleague_token)
+        .json(&contact("Ada King", "ada@test.com"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .get(format!("{contacts_url}/{alan}"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // 3. A read-only book share doesn't narrow the write access to the shared contact.
+    client
+        .post(&shares_url)
+        .bearer_auth(&owner_token)
+        .json(&json!({ "email": "colleague@example.com" }))
+        .send()
+        .await
+        .unwrap();
+    let shared: Vec<SharedContactDto> = client
+        .get(format!("{contacts_url}/shared"))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let permissions: Vec<(i64, SharePermission)> = shared
+        .iter()
+        .map(|s| (s.contact.id.unwrap(), s.permission))
+        .collect();
+    assert_eq!(
+        permissions,
+        vec![(ada, SharePermission::Write), (alan, SharePermission::Read)]
+    );
+
+    // 4. Only the owner changes a share; the grantee may give it up, others can't touch it.
+    let response = client
+        .put(format!("{shares_url}/{}", share.id))
+        .bearer_auth(&colleague_token)
+        .json(&json!({ "permission": "read" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .delete(format!("{shares_url}/{}", share.id))
+        .bearer_auth(&stranger_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .delete(format!("{shares_url}/{}", share.id))
+        .bearer_auth(&colleague_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = client
+        .put(format!("{contacts_url}/{ada}"))
+        .bearer_auth(&colleague_token)
+        .json(&contact("Ada Lovelace", "ada@test.com"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // The owner still sees the book share they granted.
+    let granted: Vec<ShareDto> = client
+        .get(&shares_url)
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(granted.len(), 1);
+    assert_eq!(granted[0].contact_id, None);
+}
@@ -0,0 +1,270 @@
+use common::{ContactDto, ContactType, TagDto};
+use reqwest::StatusCode;
+use serde_json::json;
+mod helpers;
+
+fn contact(name: &str, email: &str, tags: &[&str]) -> ContactDto {
+    ContactDto {
+        id: None,
+        name: name.to_string(),
+        email: email.to_string(),
+        birthday: None,
+        subscribed: false,
+        contact_type: ContactType::Friend,
+        tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_tag_assignment_and_filtering() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+    let tags_url = format!("http://{addr}/api/v1/tags");
+
+    // 1. Tags named on a contact are created on the fly, trimmed, deduplicated and sorted.
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact(
+            "Ada",
+            "ada@test.com",
+            &["work", " family", "family"],
+        ))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let ada: ContactDto = response.json().await.unwrap();
+    assert_eq!(ada.tags, vec!["family", "work"]);
+
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact("Alan", "alan@test.com", &["work"]))
+        .send()
+        .await
+        .unwrap();
+    let alan: ContactDto = response.json().await.unwrap();
+
+    // Omitting `tags` means no tags.
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&json!({
+            "name": "Grace", "email": "grace@test.com",
+            "subscribed": true, "contactType": "Friend"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let grace: ContactDto = response.json().await.unwrap();
+    assert!(grace.tags.is_empty());
+
+    let tags: Vec<TagDto> = client
+        .get(&tags_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let names: Vec<&str> = tags.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, vec!["family", "work"]);
+
+    // 2. The list can be filtered by tag, and tags come back on every contact.
+    let work: Vec<ContactDto> = client
+        .get(format!("{contacts_url}?tag=work"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let emails: Vec<&str> = work.iter().map(|c| c.email.as_str()).collect();
+    assert_eq!(emails, vec!["ada@test.com", "alan@test.com"]);
+    assert_eq!(work[0].tags, vec!["family", "work"]);
+
+    // 3. An update replaces the contact's tags.
+    let response = client
+        .put(format!("{contacts_url}/{}", alan.id.unwrap()))
+        .bearer_auth(&token)
+        .json(&ContactDto {
+            tags: vec!["mentor".to_string()],
+            ..alan.clone()
+        })
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let alan: ContactDto = response.json().await.unwrap();
+    assert_eq!(alan.tags, vec!["mentor"]);
+
+    let work: Vec<ContactDto> = client
+        .get(format!("{contacts_url}?tag=work"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(work.len(), 1);
+
+    // 4. Renaming a tag shows on its contacts; deleting it detaches it.
+    let family = tags.iter().find(|t| t.name == "family").unwrap();
+    let response = client
+        .put(format!("{tags_url}/{}", family.id.unwrap()))
+        .bearer_auth(&token)
+        .json(&json!({ "name": "relatives" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let ada_url = format!("{contacts_url}/{}", ada.id.unwrap());
+    let ada: ContactDto = client
+        .get(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(ada.tags, vec!["relatives", "work"]);
+
+    let response = client
+        .delete(format!("{tags_url}/{}", family.id.unwrap()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let ada: ContactDto = client
+        .get(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(ada.tags, vec!["work"]);
+
+    // 5. Tag names with commas are rejected.
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact("Bad", "bad@test.com", &["a,b"]))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_tags_crud_and_isolation() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let tags_url = format!("http://{addr}/api/v1/tags");
+
+    let response = client
+        .post(&tags_url)
+        .bearer_auth(&token)
+        .json(&json!({ "name": "vip" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let vip: TagDto = response.json().await.unwrap();
+    assert_eq!(vip.name, "vip");
+
+    // Names are unique per user.
+    let response = client
+        .post(&tags_url)
+        .bearer_auth(&token)
+        .json(&json!({ "name": "vip" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = client
+        .post(&tags_url)
+        .bearer_auth(&token)
+        .json(&json!({ "name": "  " }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Another user has their own set of tags and cannot touch this one.
+    let credentials = json!({ "email": "other@example.com", "password": "password123" });
+    client
+        .post(format!("http://{addr}/api/v1/register"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap();
+    let login: serde_json::Value = client
+        .post(format!("http://{addr}/api/v1/login"))
+        .json(&credentials)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let other_token = login["access_token"].as_str().unwrap();
+
+    let tags: Vec<TagDto> = client
+        .get(&tags_url)
+        .bearer_auth(other_token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(tags.is_empty());
+
+    let response = client
+        .post(&tags_url)
+        .bearer_auth(other_token)
+        .json(&json!({ "name": "vip" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let vip_url = format!("{tags_url}/{}", vip.id.unwrap());
+    let response = client
+        .put(&vip_url)
+        .bearer_auth(other_token)
+        .json(&json!({ "name": "stolen" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .delete(&vip_url)
+        .bearer_auth(other_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = client
+        .delete(&vip_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+}
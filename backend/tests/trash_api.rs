@@ -0,0 +1,267 @@
+use backend::db::DbPools;
+use backend::repository::Repositories;
+use backend::storage::LocalStorage;
+use backend::trash::purge_expired_contacts;
+use backend::with_pool;
+use chrono::{Duration, Utc};
+use common::{ContactDto, ContactType, TrashedContactDto};
+use reqwest::StatusCode;
+mod helpers;
+
+fn contact(name: &str, email: &str) -> ContactDto {
+    ContactDto {
+        id: None,
+        name: name.to_string(),
+        email: email.to_string(),
+        birthday: None,
+        subscribed: true,
+        contact_type: ContactType::Friend,
+        tags: Vec::new(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_soft_delete_and_restore() {
+    let (addr, client, _db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact("Ada", "ada@test.com"))
+        .send()
+        .await
+        .unwrap();
+    let ada: ContactDto = response.json().await.unwrap();
+    let ada_url = format!("{contacts_url}/{}", ada.id.unwrap());
+
+    // 1. Deleting moves the contact out of every regular view.
+    let response = client
+        .delete(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = client
+        .get(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .put(&ada_url)
+        .bearer_auth(&token)
+        .json(&ada)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = client
+        .delete(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let contacts: Vec<ContactDto> = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(contacts.is_empty());
+    let export = client
+        .get(format!("{contacts_url}/export?format=jsonl"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .text()
+        .await
+        .unwrap();
+    assert!(export.is_empty());
+
+    // 2. The trash lists it with the deletion time.
+    let response = client
+        .get(format!("{contacts_url}/trash"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let trash: Vec<TrashedContactDto> = response.json().await.unwrap();
+    assert_eq!(trash.len(), 1);
+    assert_eq!(trash[0].contact, ada);
+    assert!(trash[0].deleted_at <= Utc::now().naive_utc());
+
+    // 3. A trashed contact does not hold on to its email...
+    let response = client
+        .post(&contacts_url)
+        .bearer_auth(&token)
+        .json(&contact("Ada Again", "ada@test.com"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let ada_again: ContactDto = response.json().await.unwrap();
+
+    // ...so restoring it while the email is taken is a conflict.
+    let restore_url = format!("{ada_url}/restore");
+    let response = client
+        .post(&restore_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = client
+        .delete(format!("{contacts_url}/{}", ada_again.id.unwrap()))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    // 4. Restoring brings the contact back unchanged.
+    let response = client
+        .post(&restore_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let restored: ContactDto = response.json().await.unwrap();
+    assert_eq!(restored, ada);
+
+    let response = client
+        .get(&ada_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Only contacts in the trash can be restored.
+    let response = client
+        .post(&restore_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let trash: Vec<TrashedContactDto> = client
+        .get(format!("{contacts_url}/trash"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(trash.len(), 1, "Only the second Ada is left in the trash");
+}
+
+#[tokio::test]
+async fn test_purge_removes_expired_contacts() {
+    let (addr, client, db_pool) = helpers::spawn_app().await;
+    let token = helpers::get_auth_token(&addr, &client).await;
+    let contacts_url = format!("http://{addr}/api/v1/contacts");
+
+    let mut ids = Vec::new();
+    for (name, email) in [
+        ("Old", "old@test.com"),
+        ("Recent", "recent@test.com"),
+        ("Kept", "kept@test.com"),
+    ] {
+        let created: ContactDto = client
+            .post(&contacts_url)
+            .bearer_auth(&token)
+            .json(&contact(name, email))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        ids.push(created.id.unwrap());
+    }
+    for id in &ids[..2] {
+        client
+            .delete(format!("{contacts_url}/{id}"))
+            .bearer_auth(&token)
+            .send()
+            .await
+            .unwrap();
+    }
+
+    // Pretend the first contact was deleted well before the retention period.
+    let long_ago = (Utc::now() - Duration::days(45)).format("%Y-%m-%d %H:%M:%S");
+    with_pool!(&db_pool, |pool| sqlx::query(&format!(
+        "UPDATE contacts SET deleted_at = '{long_ago}' WHERE id = {}",
+        ids[0]
+    ))
+    .execute(pool)
+    .await
+    .map(|_| ()))
+    .unwrap();
+
+    let storage = LocalStorage::new(helpers::storage_config().local_path);
+    let contacts = Repositories::sql(DbPools::new(db_pool.clone())).contacts;
+    let purged = purge_expired_contacts(contacts.as_ref(), &storage, 30)
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+
+    // The consent record outlives the contact it was about.
+    let kept_events: i64 = with_pool!(&db_pool, |pool| sqlx::query_scalar(
+        "SELECT COUNT(*) FROM subscription_events \
+         WHERE contact_id IS NULL AND email = 'old@test.com'",
+    )
+    .fetch_one(pool)
+    .await)
+    .unwrap();
+    assert_eq!(kept_events, 1);
+
+    let trash: Vec<TrashedContactDto> = client
+        .get(format!("{contacts_url}/trash"))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let trashed_ids: Vec<i64> = trash.iter().filter_map(|t| t.contact.id).collect();
+    assert_eq!(trashed_ids, vec![ids[1]]);
+
+    let response = client
+        .post(format!("{contacts_url}/{}/restore", ids[0]))
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let contacts: Vec<ContactDto> = client
+        .get(&contacts_url)
+        .bearer_auth(&token)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(contacts[0].email, "kept@test.com");
+}
@@ -1,4 +1,14 @@
-use common::{ContactDto, Credentials, LoginResponse};
+use common::{
+    AddMemberRequest, AttachmentDto, BackupDto, BulkContactOperation, BulkContactRequest,
+    BulkContactResponse, BulkItemResult, BulkItemStatus, ContactAddressDto, ContactDto,
+    ContactEmailDto, ContactEventAction, ContactEventDto, ContactPhoneDto, ContactType,
+    ContactTypeDto, CreateOrgRequest, CreateShareRequest, Credentials, CustomFieldDto,
+    CustomFieldType, DuplicateCandidateDto, DuplicateContactDto, DuplicateReason, ImportReport,
+    ImportRowResult, ImportRowStatus, LoginResponse, MembershipDto, MergeContactsRequest,
+    MergeStrategy, OrgDto, OrgRole, ShareDto, SharePermission, SharedContactDto,
+    SubscriptionAction, SubscriptionDto, SubscriptionEventDto, SubscriptionSource, TagDto,
+    TrashedContactDto, UpdateMemberRequest, UpdateShareRequest,
+};
 use dprint_plugin_typescript::configuration::ConfigurationBuilder;
 use dprint_plugin_typescript::{format_text, FormatTextOptions};
 use std::fs;
@@ -10,13 +20,55 @@ fn main() {
 
     // 1. Collect all the unformatted TypeScript type definitions
     let types_to_export = [
+        ContactType::export_to_string().unwrap(),
+        ContactTypeDto::export_to_string().unwrap(),
+        ContactEmailDto::export_to_string().unwrap(),
+        ContactPhoneDto::export_to_string().unwrap(),
+        ContactAddressDto::export_to_string().unwrap(),
         ContactDto::export_to_string().unwrap(),
+        TagDto::export_to_string().unwrap(),
+        CustomFieldType::export_to_string().unwrap(),
+        CustomFieldDto::export_to_string().unwrap(),
+        TrashedContactDto::export_to_string().unwrap(),
+        AttachmentDto::export_to_string().unwrap(),
+        ContactEventAction::export_to_string().unwrap(),
+        ContactEventDto::export_to_string().unwrap(),
+        SubscriptionSource::export_to_string().unwrap(),
+        SubscriptionAction::export_to_string().unwrap(),
+        SubscriptionEventDto::export_to_string().unwrap(),
+        SubscriptionDto::export_to_string().unwrap(),
         Credentials::export_to_string().unwrap(),
         LoginResponse::export_to_string().unwrap(),
+        BulkContactOperation::export_to_string().unwrap(),
+        BulkContactRequest::export_to_string().unwrap(),
+        BulkItemStatus::export_to_string().unwrap(),
+        BulkItemResult::export_to_string().unwrap(),
+        BulkContactResponse::export_to_string().unwrap(),
+        ImportRowStatus::export_to_string().unwrap(),
+        ImportRowResult::export_to_string().unwrap(),
+        ImportReport::export_to_string().unwrap(),
+        DuplicateReason::export_to_string().unwrap(),
+        DuplicateContactDto::export_to_string().unwrap(),
+        DuplicateCandidateDto::export_to_string().unwrap(),
+        MergeStrategy::export_to_string().unwrap(),
+        MergeContactsRequest::export_to_string().unwrap(),
+        SharePermission::export_to_string().unwrap(),
+        ShareDto::export_to_string().unwrap(),
+        CreateShareRequest::export_to_string().unwrap(),
+        UpdateShareRequest::export_to_string().unwrap(),
+        SharedContactDto::export_to_string().unwrap(),
+        OrgRole::export_to_string().unwrap(),
+        OrgDto::export_to_string().unwrap(),
+        CreateOrgRequest::export_to_string().unwrap(),
+        MembershipDto::export_to_string().unwrap(),
+        AddMemberRequest::export_to_string().unwrap(),
+        UpdateMemberRequest::export_to_string().unwrap(),
+        BackupDto::export_to_string().unwrap(),
     ];
 
-    // 2. Join them, and clean up the duplicate "generated by" comments
-    let raw_ts = remove_duplicate_comments(&types_to_export.join("\n\n"));
+    // 2. Join them, and clean up the duplicate "generated by" comments and the
+    //    imports between types that now live in the same file
+    let raw_ts = remove_local_imports(&remove_duplicate_comments(&types_to_export.join("\n\n")));
 
     // 3. Format the combined TypeScript string using the correct API
     let formatted_ts = format_typescript(&raw_ts);
@@ -80,3 +132,14 @@ fn remove_duplicate_comments(content: &str) -> String {
 
     lines.join("\n") + "\n"
 }
+
+/// ts-rs emits `import type` lines for every referenced type, assuming one file per type.
+/// Since all types are written to a single file, those imports must be dropped.
+fn remove_local_imports(content: &str) -> String {
+    content
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("import type "))
+        .collect::<Vec<_>>()
+        .join("\n")
+        + "\n"
+}
@@ -21,27 +21,6 @@ fn main() {
             panic!("❌ Could not find '[features].default' array in {cargo_toml_path}")
         });
 
-    // Check which database feature is present in the default set.
-    let has_postgres = default_features
-        .iter()
-        .any(|v| v.as_str() == Some("db-postgres"));
-    let has_sqlite = default_features
-        .iter()
-        .any(|v| v.as_str() == Some("db-sqlite"));
-
-    if has_postgres {
-        print!("postgres");
-    } else if has_sqlite {
-        print!("sqlite");
-    } else {
-        // Fallback if neither is found in the default features.
-        eprintln!("⚠️ Warning: Neither 'db-postgres' nor 'db-sqlite' found in default features of backend/Cargo.toml. Defaulting to sqlite.");
-        print!("sqlite");
-    }
-
-    // This is crucial: it prints the space separator for the Dockerfile script.
-    print!(" ");
-
     // Check which UI feature is present in the default set.
     let has_svelte = default_features
         .iter()
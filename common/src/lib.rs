@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -6,11 +9,11 @@ use ts_rs::TS;
 
 #[cfg(not(target_arch = "wasm32"))]
 use sqlx::FromRow;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 pub mod utils;
 
 #[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Validate, ToSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Validate, ToSchema)]
 #[cfg_attr(feature = "ts_export", derive(TS))] // Conditionally derive TS
 #[serde(rename_all = "camelCase")]
 pub struct ContactDto {
@@ -23,13 +26,723 @@ pub struct ContactDto {
     #[validate(email(message = "Email must be a valid email address"))]
     #[schema(example = "john.doe@example.com")]
     pub email: String,
-    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
-    #[schema(example = 30)]
-    pub age: i64,
+    #[schema(example = "1990-04-12")]
+    pub birthday: Option<NaiveDate>,
+    /// Whether the contact gets the newsletter. Writing `true` for a contact that isn't
+    /// subscribed emails it a link to confirm; it stays unsubscribed until it does.
     pub subscribed: bool,
-    #[validate(length(min = 1, message = "Contact type cannot be empty"))]
+    pub contact_type: ContactType,
+    /// Names of the tags assigned to the contact, sorted by name.
+    #[serde(default)]
+    #[validate(custom(function = "validate_tag_names"))]
+    #[cfg_attr(not(target_arch = "wasm32"), sqlx(try_from = "TagList"))]
+    #[schema(example = json!(["family", "newsletter"]))]
+    pub tags: Vec<String>,
+    /// Email addresses besides the primary `email`.
+    #[serde(default)]
+    #[validate(nested)]
+    #[cfg_attr(not(target_arch = "wasm32"), sqlx(skip))]
+    pub emails: Vec<ContactEmailDto>,
+    #[serde(default)]
+    #[validate(nested)]
+    #[cfg_attr(not(target_arch = "wasm32"), sqlx(skip))]
+    pub phones: Vec<ContactPhoneDto>,
+    #[serde(default)]
+    #[validate(nested)]
+    #[cfg_attr(not(target_arch = "wasm32"), sqlx(skip))]
+    pub addresses: Vec<ContactAddressDto>,
+    #[validate(length(max = 10000, message = "Notes cannot be longer than 10000 characters"))]
+    #[schema(example = "Met at RustConf")]
+    pub notes: Option<String>,
+    /// Values of the user's custom fields, keyed by field name. They are checked against the
+    /// field definitions when the contact is written; a `null` value is the same as none.
+    #[serde(default)]
+    #[cfg_attr(not(target_arch = "wasm32"), sqlx(json))]
+    #[cfg_attr(
+        feature = "ts_export",
+        ts(type = "Record<string, string | number | boolean | null>")
+    )]
+    #[schema(value_type = Object, example = json!({"Account number": "ACME-42", "Region": "EMEA"}))]
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
+    /// Where the contact's avatar thumbnail can be downloaded, if it has one. Ignored on
+    /// writes; avatars are set and removed through `/api/v1/contacts/{id}/avatar`.
+    #[serde(default)]
+    #[schema(read_only, example = "/api/v1/contacts/1/avatar")]
+    pub avatar_url: Option<String>,
+}
+
+/// Longest accepted label of an email, phone number or address, in characters.
+pub const MAX_LABEL_LENGTH: u64 = 50;
+
+/// An additional, labelled email address of a contact.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Validate, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ContactEmailDto {
+    #[validate(length(
+        min = 1,
+        max = "MAX_LABEL_LENGTH",
+        message = "Labels must be between 1 and 50 characters"
+    ))]
+    #[schema(example = "work")]
+    pub label: String,
+    #[validate(email(message = "Email must be a valid email address"))]
+    #[schema(example = "john.doe@work.example.com")]
+    pub email: String,
+}
+
+/// A labelled phone number of a contact, in E.164 format.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Validate, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ContactPhoneDto {
+    #[validate(length(
+        min = 1,
+        max = "MAX_LABEL_LENGTH",
+        message = "Labels must be between 1 and 50 characters"
+    ))]
+    #[schema(example = "mobile")]
+    pub label: String,
+    #[validate(custom(function = "validate_e164"))]
+    #[schema(example = "+14155550123")]
+    pub number: String,
+}
+
+/// A labelled postal address of a contact. Only the label is required, since address formats
+/// differ between countries.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Validate, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase", default)]
+pub struct ContactAddressDto {
+    #[validate(length(
+        min = 1,
+        max = "MAX_LABEL_LENGTH",
+        message = "Labels must be between 1 and 50 characters"
+    ))]
+    #[schema(example = "home")]
+    pub label: String,
+    #[schema(example = "1 Infinite Loop")]
+    pub street: String,
+    #[schema(example = "Cupertino")]
+    pub city: String,
+    #[schema(example = "CA")]
+    pub region: String,
+    #[schema(example = "95014")]
+    pub postal_code: String,
+    #[schema(example = "USA")]
+    pub country: String,
+}
+
+/// Accepts phone numbers in E.164 format: a `+`, then up to 15 digits with no leading zero.
+pub fn validate_e164(number: &str) -> Result<(), ValidationError> {
+    let valid = number.strip_prefix('+').is_some_and(|digits| {
+        (2..=15).contains(&digits.len())
+            && !digits.starts_with('0')
+            && digits.bytes().all(|b| b.is_ascii_digit())
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("e164")
+            .with_message("Phone numbers must be in E.164 format, like +14155550123".into()))
+    }
+}
+
+/// The kind of relationship with a contact. Which of these a deployment offers, and under
+/// which labels, is configured in the `contact_types` table.
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    ToSchema,
+)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+pub enum ContactType {
+    Personal,
+    Work,
+    Family,
+    Friend,
+    Customer,
+    Lead,
+    Partner,
+    #[default]
+    Other,
+}
+
+impl ContactType {
+    pub const ALL: [ContactType; 8] = [
+        ContactType::Personal,
+        ContactType::Work,
+        ContactType::Family,
+        ContactType::Friend,
+        ContactType::Customer,
+        ContactType::Lead,
+        ContactType::Partner,
+        ContactType::Other,
+    ];
+
+    /// The canonical name, as serialized and stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ContactType::Personal => "Personal",
+            ContactType::Work => "Work",
+            ContactType::Family => "Family",
+            ContactType::Friend => "Friend",
+            ContactType::Customer => "Customer",
+            ContactType::Lead => "Lead",
+            ContactType::Partner => "Partner",
+            ContactType::Other => "Other",
+        }
+    }
+}
+
+impl std::fmt::Display for ContactType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Parses a contact type name case-insensitively, ignoring surrounding whitespace.
+impl std::str::FromStr for ContactType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let name = name.trim();
+        ContactType::ALL
+            .into_iter()
+            .find(|contact_type| contact_type.as_str().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown contact type '{name}'"))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for ContactType
+where
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<String as sqlx::Decode<DB>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'q, DB: sqlx::Database> sqlx::Encode<'q, DB> for ContactType
+where
+    String: sqlx::Encode<'q, DB>,
+{
+    fn encode_by_ref(
+        &self,
+        buf: &mut <DB as sqlx::Database>::ArgumentBuffer<'q>,
+    ) -> Result<sqlx::encode::IsNull, sqlx::error::BoxDynError> {
+        <String as sqlx::Encode<'q, DB>>::encode(self.as_str().to_string(), buf)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<DB: sqlx::Database> sqlx::Type<DB> for ContactType
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+/// A contact type offered by this deployment, with the label to show for it.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ContactTypeDto {
+    pub name: ContactType,
     #[schema(example = "Friend")]
-    pub contact_type: String,
+    pub label: String,
+}
+
+/// Longest accepted tag name, in characters.
+pub const MAX_TAG_NAME_LENGTH: usize = 50;
+
+/// Tag names must be non-blank, reasonably short, and free of commas, which separate tags in
+/// CSV files and in the database aggregate read into `ContactDto::tags`.
+pub fn validate_tag_name(name: &str) -> Result<(), ValidationError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_TAG_NAME_LENGTH {
+        return Err(ValidationError::new("length")
+            .with_message("Tag names must be between 1 and 50 characters".into()));
+    }
+    if name.contains(',') {
+        return Err(
+            ValidationError::new("tag_name").with_message("Tag names cannot contain commas".into())
+        );
+    }
+    Ok(())
+}
+
+fn validate_tag_names(names: &[String]) -> Result<(), ValidationError> {
+    names.iter().try_for_each(|name| validate_tag_name(name))
+}
+
+/// The tags of a contact as selected from the database: a comma-separated aggregate of the
+/// tag names, or NULL when the contact has none.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct TagList(Option<String>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for TagList
+where
+    Option<String>: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(TagList(<Option<String> as sqlx::Decode<DB>>::decode(
+            value,
+        )?))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<DB: sqlx::Database> sqlx::Type<DB> for TagList
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<TagList> for Vec<String> {
+    fn from(tags: TagList) -> Self {
+        tags.0
+            .map(|names| names.split(',').map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// A per-user label that can be attached to any number of contacts.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Validate, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TagDto {
+    #[schema(example = 1)]
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub id: Option<i64>,
+    #[validate(custom(function = "validate_tag_name"))]
+    #[schema(example = "family")]
+    pub name: String,
+}
+
+/// The kind of value a custom field holds.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "lowercase")]
+pub enum CustomFieldType {
+    /// Any string.
+    #[default]
+    Text,
+    /// A JSON number.
+    Number,
+    /// A string in `YYYY-MM-DD` format.
+    Date,
+    /// `true` or `false`.
+    Bool,
+    /// One of the field's `options`.
+    Select,
+}
+
+impl CustomFieldType {
+    /// The name as serialized and stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CustomFieldType::Text => "text",
+            CustomFieldType::Number => "number",
+            CustomFieldType::Date => "date",
+            CustomFieldType::Bool => "bool",
+            CustomFieldType::Select => "select",
+        }
+    }
+}
+
+impl std::str::FromStr for CustomFieldType {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "text" => Ok(CustomFieldType::Text),
+            "number" => Ok(CustomFieldType::Number),
+            "date" => Ok(CustomFieldType::Date),
+            "bool" => Ok(CustomFieldType::Bool),
+            "select" => Ok(CustomFieldType::Select),
+            _ => Err(format!("Unknown custom field type '{name}'")),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for CustomFieldType
+where
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<String as sqlx::Decode<DB>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<DB: sqlx::Database> sqlx::Type<DB> for CustomFieldType
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+/// Longest accepted custom field name or select option, in characters.
+pub const MAX_CUSTOM_FIELD_NAME_LENGTH: usize = 50;
+
+/// A per-user definition of an extra attribute that contacts can carry in `custom_fields`.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Validate, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+#[validate(schema(function = "validate_custom_field_options"))]
+pub struct CustomFieldDto {
+    #[schema(example = 1)]
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub id: Option<i64>,
+    #[validate(custom(function = "validate_custom_field_name"))]
+    #[schema(example = "Region")]
+    pub name: String,
+    pub field_type: CustomFieldType,
+    /// Whether every contact must have a value for this field.
+    #[serde(default)]
+    pub required: bool,
+    /// The choices of a `select` field; empty for every other type.
+    #[serde(default)]
+    #[cfg_attr(not(target_arch = "wasm32"), sqlx(json))]
+    #[schema(example = json!(["EMEA", "APAC", "Americas"]))]
+    pub options: Vec<String>,
+}
+
+/// Custom field names must be non-blank, reasonably short, and free of colons, which separate
+/// the name from the value in the `custom_field` filter of the contact list.
+pub fn validate_custom_field_name(name: &str) -> Result<(), ValidationError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_CUSTOM_FIELD_NAME_LENGTH {
+        return Err(ValidationError::new("length")
+            .with_message("Custom field names must be between 1 and 50 characters".into()));
+    }
+    if name.contains(':') {
+        return Err(ValidationError::new("custom_field_name")
+            .with_message("Custom field names cannot contain colons".into()));
+    }
+    Ok(())
+}
+
+/// A `select` field needs at least one option, and every other type none.
+fn validate_custom_field_options(field: &CustomFieldDto) -> Result<(), ValidationError> {
+    if field.field_type != CustomFieldType::Select {
+        return if field.options.is_empty() {
+            Ok(())
+        } else {
+            Err(ValidationError::new("options")
+                .with_message("Only select fields can have options".into()))
+        };
+    }
+    if field.options.is_empty() {
+        return Err(ValidationError::new("options")
+            .with_message("Select fields need at least one option".into()));
+    }
+    let valid = field.options.iter().all(|option| {
+        let option = option.trim();
+        !option.is_empty() && option.chars().count() <= MAX_CUSTOM_FIELD_NAME_LENGTH
+    });
+    if !valid {
+        return Err(ValidationError::new("options")
+            .with_message("Options must be between 1 and 50 characters".into()));
+    }
+    Ok(())
+}
+
+/// A deleted contact waiting in the trash until it is restored or purged.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedContactDto {
+    #[serde(flatten)]
+    #[cfg_attr(not(target_arch = "wasm32"), sqlx(flatten))]
+    #[cfg_attr(feature = "ts_export", ts(flatten))]
+    pub contact: ContactDto,
+    /// When the contact was deleted, in UTC.
+    pub deleted_at: NaiveDateTime,
+}
+
+/// A file attached to a contact. The content is downloaded separately.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentDto {
+    #[schema(example = 1)]
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub id: i64,
+    #[schema(example = 1)]
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub contact_id: i64,
+    #[schema(example = "contract.pdf")]
+    pub file_name: String,
+    #[schema(example = "application/pdf")]
+    pub content_type: String,
+    /// Size of the file in bytes.
+    #[schema(example = 48213)]
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub size: i64,
+    /// When the file was uploaded, in UTC.
+    pub created_at: NaiveDateTime,
+}
+
+/// The kind of change recorded in a contact's history.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub enum ContactEventAction {
+    Created,
+    Updated,
+    Deleted,
+    Restored,
+    /// The contact was merged with `related_contact_id`. The contact that was kept shows the
+    /// combined result as `after`; the one merged away has no `after` and is in the trash.
+    Merged,
+}
+
+/// One entry in a contact's change log.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ContactEventDto {
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub id: i64,
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub contact_id: i64,
+    pub action: ContactEventAction,
+    /// The user who made the change.
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub user_id: i64,
+    pub user_email: Option<String>,
+    /// The `x-request-id` of the request that made the change.
+    pub request_id: Option<String>,
+    /// The contact before the change, absent for creations and restores.
+    pub before: Option<ContactDto>,
+    /// The contact after the change, absent for deletions.
+    pub after: Option<ContactDto>,
+    /// The other contact involved in a merge.
+    #[cfg_attr(feature = "ts_export", ts(type = "number | null"))]
+    pub related_contact_id: Option<i64>,
+    /// When the change happened, in UTC.
+    pub created_at: NaiveDateTime,
+}
+
+/// How newsletter consent was given or withdrawn.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionSource {
+    /// A user unsubscribed the contact by clearing its `subscribed` flag.
+    Manual,
+    /// An operator loaded the contact as subscribed with the `seed` command. Imports through
+    /// the API ask the contacts to confirm instead.
+    Import,
+    /// The contact confirmed a double opt-in email.
+    DoubleOptIn,
+    /// The contact followed the unsubscribe link of an email.
+    UnsubscribeLink,
+}
+
+impl SubscriptionSource {
+    /// The name as serialized and stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SubscriptionSource::Manual => "manual",
+            SubscriptionSource::Import => "import",
+            SubscriptionSource::DoubleOptIn => "double_opt_in",
+            SubscriptionSource::UnsubscribeLink => "unsubscribe_link",
+        }
+    }
+}
+
+impl std::str::FromStr for SubscriptionSource {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "manual" => Ok(SubscriptionSource::Manual),
+            "import" => Ok(SubscriptionSource::Import),
+            "double_opt_in" => Ok(SubscriptionSource::DoubleOptIn),
+            "unsubscribe_link" => Ok(SubscriptionSource::UnsubscribeLink),
+            _ => Err(format!("Unknown subscription source '{name}'")),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for SubscriptionSource
+where
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<String as sqlx::Decode<DB>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<DB: sqlx::Database> sqlx::Type<DB> for SubscriptionSource
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+/// The kind of change recorded in a contact's subscription log.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionAction {
+    /// A double opt-in confirmation email was sent.
+    Requested,
+    Subscribed,
+    Unsubscribed,
+}
+
+impl SubscriptionAction {
+    /// The name as serialized and stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SubscriptionAction::Requested => "requested",
+            SubscriptionAction::Subscribed => "subscribed",
+            SubscriptionAction::Unsubscribed => "unsubscribed",
+        }
+    }
+}
+
+impl std::str::FromStr for SubscriptionAction {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "requested" => Ok(SubscriptionAction::Requested),
+            "subscribed" => Ok(SubscriptionAction::Subscribed),
+            "unsubscribed" => Ok(SubscriptionAction::Unsubscribed),
+            _ => Err(format!("Unknown subscription action '{name}'")),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for SubscriptionAction
+where
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<String as sqlx::Decode<DB>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<DB: sqlx::Database> sqlx::Type<DB> for SubscriptionAction
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+/// One entry in a contact's subscription log, kept as a record of consent.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionEventDto {
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub id: i64,
+    pub action: SubscriptionAction,
+    pub source: SubscriptionSource,
+    /// The address the change applies to.
+    #[schema(example = "john.doe@example.com")]
+    pub email: String,
+    /// The user who made the change, absent for changes made by the contact through an
+    /// emailed link.
+    #[cfg_attr(feature = "ts_export", ts(type = "number | null"))]
+    pub user_id: Option<i64>,
+    pub user_email: Option<String>,
+    /// The `x-request-id` of the request that made the change.
+    pub request_id: Option<String>,
+    /// When the change happened, in UTC.
+    pub created_at: NaiveDateTime,
+}
+
+/// A contact's newsletter subscription, with its record of consent.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionDto {
+    pub subscribed: bool,
+    /// When the contact gave their current consent, in UTC. Absent while unsubscribed, and for
+    /// subscriptions older than consent tracking.
+    pub subscribed_at: Option<NaiveDateTime>,
+    /// How the contact gave their current consent.
+    pub source: Option<SubscriptionSource>,
+    /// A signed link that unsubscribes the contact without logging in, to include in
+    /// newsletters.
+    #[schema(example = "https://contacts.example.com/api/v1/newsletter/unsubscribe?token=1.x9Tq")]
+    pub unsubscribe_url: String,
+    /// Every subscription change, oldest first.
+    pub events: Vec<SubscriptionEventDto>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, Validate, ToSchema)]
@@ -49,3 +762,458 @@ pub struct LoginResponse {
     pub access_token: String,
     pub refresh_token: String,
 }
+
+/// A single operation inside a bulk contacts request.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum BulkContactOperation {
+    Create {
+        contact: ContactDto,
+    },
+    Update {
+        #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+        id: i64,
+        contact: ContactDto,
+    },
+    Delete {
+        #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+        id: i64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct BulkContactRequest {
+    /// When set, the whole batch is rolled back as soon as one operation fails.
+    #[serde(default)]
+    pub all_or_nothing: bool,
+    pub operations: Vec<BulkContactOperation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub enum BulkItemStatus {
+    Created,
+    Updated,
+    Deleted,
+    Failed,
+    /// The operation was not applied because another item of an all-or-nothing batch failed.
+    Skipped,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct BulkItemResult {
+    /// Position of the operation in the request.
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub index: usize,
+    pub status: BulkItemStatus,
+    pub contact: Option<ContactDto>,
+    pub error: Option<String>,
+    /// Field errors reported by `ContactDto::validate`.
+    #[schema(value_type = Option<Object>)]
+    #[cfg_attr(feature = "ts_export", ts(type = "Record<string, unknown> | null"))]
+    pub validation_errors: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct BulkContactResponse {
+    /// Whether the transaction was committed.
+    pub committed: bool,
+    pub results: Vec<BulkItemResult>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub enum ImportRowStatus {
+    /// The email already exists in the contact book or earlier in the file.
+    Duplicate,
+    Failed,
+}
+
+/// A row of an import file that was not imported.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowResult {
+    /// 1-based position of the record in the file, not counting the CSV header.
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub row: usize,
+    pub status: ImportRowStatus,
+    pub email: Option<String>,
+    pub error: Option<String>,
+    /// Field errors reported by `ContactDto::validate`.
+    #[schema(value_type = Option<Object>)]
+    #[cfg_attr(feature = "ts_export", ts(type = "Record<string, unknown> | null"))]
+    pub validation_errors: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    /// When set, nothing was written and `imported` counts the rows that would have been.
+    pub dry_run: bool,
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub total_rows: usize,
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub imported: usize,
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub duplicates: usize,
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub failed: usize,
+    /// Every row that was skipped, in file order.
+    pub rows: Vec<ImportRowResult>,
+}
+
+/// Why two contacts are suspected to be the same person.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub enum DuplicateReason {
+    /// One of the email addresses of each contact is the same once normalized.
+    SameEmail,
+    /// The names are spelled alike.
+    SimilarName,
+}
+
+/// A contact of a duplicate pair, with just enough to tell it apart. The rest is fetched
+/// by ID.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateContactDto {
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub id: i64,
+    #[schema(example = "Ada Lovelace")]
+    pub name: String,
+    #[schema(example = "ada@example.com")]
+    pub email: String,
+}
+
+/// Two contacts that are likely duplicates of each other, `first` being the older one.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCandidateDto {
+    pub first: DuplicateContactDto,
+    pub second: DuplicateContactDto,
+    pub reason: DuplicateReason,
+    /// How alike the names are, from 0 to 1.
+    #[schema(example = 0.92)]
+    pub similarity: f64,
+}
+
+/// Which contact's values win when two contacts are merged. Blank values never win, and
+/// lists such as tags, phone numbers and custom fields are combined.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub enum MergeStrategy {
+    /// The contact that is kept.
+    #[default]
+    PreferPrimary,
+    /// The contact that is merged away.
+    PreferDuplicate,
+    /// Whichever contact was updated last.
+    PreferNewest,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct MergeContactsRequest {
+    /// The contact that is kept and receives the merged values.
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub primary_id: i64,
+    /// The contact that is merged into the primary one and moved to the trash.
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub duplicate_id: i64,
+    #[serde(default)]
+    pub strategy: MergeStrategy,
+}
+
+/// What the grantee of a share may do with the shared contacts.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "lowercase")]
+pub enum SharePermission {
+    /// View the contacts and their history.
+    #[default]
+    Read,
+    /// Also update contacts and move them to the owner's trash.
+    Write,
+}
+
+impl SharePermission {
+    /// The name as serialized and stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SharePermission::Read => "read",
+            SharePermission::Write => "write",
+        }
+    }
+}
+
+impl std::str::FromStr for SharePermission {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "read" => Ok(SharePermission::Read),
+            "write" => Ok(SharePermission::Write),
+            _ => Err(format!("Unknown share permission '{name}'")),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for SharePermission
+where
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<String as sqlx::Decode<DB>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<DB: sqlx::Database> sqlx::Type<DB> for SharePermission
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+/// Access to a single contact, or to an organization's whole contact book, granted to a user.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct ShareDto {
+    #[schema(example = 1)]
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub id: i64,
+    /// The organization whose contacts are shared.
+    #[schema(example = "Acme Ltd")]
+    pub org_name: String,
+    #[schema(example = "colleague@example.com")]
+    pub grantee_email: String,
+    /// The shared contact, or `None` when the whole contact book is shared.
+    #[cfg_attr(feature = "ts_export", ts(type = "number | null"))]
+    pub contact_id: Option<i64>,
+    pub permission: SharePermission,
+    /// When the share was granted, in UTC.
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Validate, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct CreateShareRequest {
+    /// The email the grantee signed up with.
+    #[validate(email(message = "Email must be a valid email address"))]
+    #[schema(example = "colleague@example.com")]
+    pub email: String,
+    /// The contact to share; leave out to share the whole contact book.
+    #[serde(default)]
+    #[cfg_attr(feature = "ts_export", ts(type = "number | null"))]
+    pub contact_id: Option<i64>,
+    #[serde(default)]
+    pub permission: SharePermission,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateShareRequest {
+    pub permission: SharePermission,
+}
+
+/// A contact of another organization that was shared with the caller.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct SharedContactDto {
+    #[serde(flatten)]
+    #[cfg_attr(not(target_arch = "wasm32"), sqlx(flatten))]
+    #[cfg_attr(feature = "ts_export", ts(flatten))]
+    pub contact: ContactDto,
+    /// The organization the contact belongs to.
+    #[schema(example = "Acme Ltd")]
+    pub org_name: String,
+    /// The broadest permission granted on the contact, by its own share or a book share.
+    pub permission: SharePermission,
+}
+
+/// What a member may do in an organization, from most to least privileged.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "lowercase")]
+pub enum OrgRole {
+    /// Everything an admin may do, and managing other owners.
+    Owner,
+    /// Manage members and custom field definitions.
+    Admin,
+    /// Create, update, delete and share contacts.
+    #[default]
+    Member,
+    /// Only look at contacts.
+    Viewer,
+}
+
+impl OrgRole {
+    /// The name as serialized and stored in the database.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrgRole::Owner => "owner",
+            OrgRole::Admin => "admin",
+            OrgRole::Member => "member",
+            OrgRole::Viewer => "viewer",
+        }
+    }
+
+    /// Whether the role allows changing the organization's contacts.
+    pub fn can_write(self) -> bool {
+        self != OrgRole::Viewer
+    }
+
+    /// Whether the role allows managing members and custom field definitions.
+    pub fn can_manage(self) -> bool {
+        matches!(self, OrgRole::Owner | OrgRole::Admin)
+    }
+}
+
+impl std::str::FromStr for OrgRole {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "owner" => Ok(OrgRole::Owner),
+            "admin" => Ok(OrgRole::Admin),
+            "member" => Ok(OrgRole::Member),
+            "viewer" => Ok(OrgRole::Viewer),
+            _ => Err(format!("Unknown organization role '{name}'")),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'r, DB: sqlx::Database> sqlx::Decode<'r, DB> for OrgRole
+where
+    String: sqlx::Decode<'r, DB>,
+{
+    fn decode(
+        value: <DB as sqlx::Database>::ValueRef<'r>,
+    ) -> Result<Self, sqlx::error::BoxDynError> {
+        Ok(<String as sqlx::Decode<DB>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<DB: sqlx::Database> sqlx::Type<DB> for OrgRole
+where
+    String: sqlx::Type<DB>,
+{
+    fn type_info() -> DB::TypeInfo {
+        <String as sqlx::Type<DB>>::type_info()
+    }
+
+    fn compatible(ty: &DB::TypeInfo) -> bool {
+        <String as sqlx::Type<DB>>::compatible(ty)
+    }
+}
+
+/// An organization the caller is a member of.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct OrgDto {
+    #[schema(example = 1)]
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub id: i64,
+    #[schema(example = "Acme Ltd")]
+    pub name: String,
+    /// The caller's role in the organization.
+    pub role: OrgRole,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Validate, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct CreateOrgRequest {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Organization names must be between 1 and 100 characters"
+    ))]
+    #[schema(example = "Acme Ltd")]
+    pub name: String,
+}
+
+/// A backup of the database in the server's backup directory.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct BackupDto {
+    /// The file name, which tells when the backup was made.
+    #[schema(example = "cornerstone-20250726T090000.000Z.db")]
+    pub name: String,
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub size_bytes: u64,
+    /// When the backup was made, in UTC.
+    pub created_at: NaiveDateTime,
+}
+
+/// A user's membership in the caller's current organization.
+#[cfg_attr(not(target_arch = "wasm32"), derive(FromRow))]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct MembershipDto {
+    #[schema(example = 2)]
+    #[cfg_attr(feature = "ts_export", ts(type = "number"))]
+    pub user_id: i64,
+    #[schema(example = "colleague@example.com")]
+    pub email: String,
+    pub role: OrgRole,
+    /// When the user joined the organization, in UTC.
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Validate, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct AddMemberRequest {
+    /// The email the new member signed up with.
+    #[validate(email(message = "Email must be a valid email address"))]
+    #[schema(example = "colleague@example.com")]
+    pub email: String,
+    #[serde(default)]
+    pub role: OrgRole,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[cfg_attr(feature = "ts_export", derive(TS))]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateMemberRequest {
+    pub role: OrgRole,
+}
@@ -5,7 +5,7 @@ slint::include_modules!();
 use common::ContactDto; // Use the DTO for backend communication
 use common::Credentials;
 use common::LoginResponse;
-use slint::VecModel;
+use slint::{Model, ModelRc, SharedString, VecModel};
 use std::rc::Rc;
 use std::sync::Arc;
 
@@ -21,15 +21,19 @@ fn spawn_local<F: std::future::Future<Output = ()> + 'static>(fut: F) {
 
 impl Contact {
     /// Converts the local Slint `Contact` struct to the foreign `ContactDto`.
+    /// The form doesn't edit emails, phone numbers or addresses, so they are left empty.
     pub fn to_dto(&self) -> ContactDto {
         ContactDto {
             // Note: We assume an existing UI contact has a valid ID.
             id: Some(self.id.into()),
             name: self.name.to_string(),
             email: self.email.to_string(),
-            age: self.age.into(),
+            birthday: self.birthday.parse().ok(),
             subscribed: self.subscribed,
-            contact_type: self.contact_type.to_string(),
+            contact_type: self.contact_type.parse().unwrap_or_default(),
+            tags: self.tags.iter().map(|tag| tag.to_string()).collect(),
+            notes: Some(self.notes.to_string()).filter(|notes| !notes.is_empty()),
+            ..Default::default()
         }
     }
 }
@@ -41,9 +45,21 @@ impl From<ContactDto> for Contact {
             id: dto_contact.id.unwrap_or_default() as i32,
             name: dto_contact.name.into(),
             email: dto_contact.email.into(),
-            age: dto_contact.age as i32,
+            birthday: dto_contact
+                .birthday
+                .map(|birthday| birthday.to_string())
+                .unwrap_or_default()
+                .into(),
             subscribed: dto_contact.subscribed,
-            contact_type: dto_contact.contact_type.into(),
+            contact_type: dto_contact.contact_type.as_str().into(),
+            tags: ModelRc::new(VecModel::from(
+                dto_contact
+                    .tags
+                    .into_iter()
+                    .map(SharedString::from)
+                    .collect::<Vec<_>>(),
+            )),
+            notes: dto_contact.notes.unwrap_or_default().into(),
         }
     }
 }
@@ -152,15 +168,14 @@ pub fn run() {
                 Ok(response) => {
                     match response.json::<Vec<ContactDto>>().await {
                         Ok(contacts_dto) => {
-                            // This data is `Send` and can be moved across threads.
-                            let ui_contacts: Vec<Contact> =
-                                contacts_dto.into_iter().map(Into::into).collect();
-
                             // Post a task to the Slint event loop to update the UI.
-                            // The `move` captures `ui_contacts` and `app_weak`.
+                            // The DTOs are `Send` and can be moved across threads, unlike
+                            // the Slint `Contact` structs, whose tags are an Rc-based model.
                             let _ = slint::invoke_from_event_loop(move || {
                                 // This closure runs on the main UI thread.
-                                // It's now safe to create the Rc-based Slint model.
+                                // It's now safe to create the Rc-based Slint models.
+                                let ui_contacts: Vec<Contact> =
+                                    contacts_dto.into_iter().map(Into::into).collect();
                                 let contacts_model = Rc::new(VecModel::from(ui_contacts));
 
                                 // Set the model on the App component.
@@ -185,46 +200,50 @@ pub fn run() {
     let app_weak = app.as_weak();
     let base_url_clone = base_url.to_string();
     let client_clone = client.clone();
-    app.on_add_contact(move |name, email, age, subscribed, contact_type| {
-        let app_weak = app_weak.clone();
-        let client = client_clone.clone();
-        let url = format!("{base_url_clone}/contacts");
+    app.on_add_contact(
+        move |name, email, birthday, subscribed, contact_type, notes| {
+            let app_weak = app_weak.clone();
+            let client = client_clone.clone();
+            let url = format!("{base_url_clone}/contacts");
 
-        // Create the DTO to send to the backend
-        let new_contact = ContactDto {
-            id: None, // The backend will assign the ID
-            name: name.to_string(),
-            email: email.to_string(),
-            age: age.into(),
-            subscribed,
-            contact_type: contact_type.to_string(),
-        };
+            // Create the DTO to send to the backend
+            let new_contact = ContactDto {
+                id: None, // The backend will assign the ID
+                name: name.to_string(),
+                email: email.to_string(),
+                birthday: birthday.parse().ok(),
+                subscribed,
+                contact_type: contact_type.parse().unwrap_or_default(),
+                notes: Some(notes.to_string()).filter(|notes| !notes.is_empty()),
+                ..Default::default()
+            };
 
-        let token = app_weak.unwrap().get_auth_token().to_string();
+            let token = app_weak.unwrap().get_auth_token().to_string();
 
-        spawn_local(async move {
-            println!("Sending new contact to backend...");
-            match client
-                .clone()
-                .post(&url)
-                .bearer_auth(token)
-                .json(&new_contact)
-                .send()
-                .await
-            {
-                Ok(_) => {
-                    println!("Successfully added contact. Refreshing list...");
-                    // After adding, trigger a fetch to refresh the list
-                    let _ = slint::invoke_from_event_loop(move || {
-                        app_weak.unwrap().invoke_fetch_contacts();
-                    });
-                }
-                Err(e) => {
-                    println!("Error adding contact: {e}");
+            spawn_local(async move {
+                println!("Sending new contact to backend...");
+                match client
+                    .clone()
+                    .post(&url)
+                    .bearer_auth(token)
+                    .json(&new_contact)
+                    .send()
+                    .await
+                {
+                    Ok(_) => {
+                        println!("Successfully added contact. Refreshing list...");
+                        // After adding, trigger a fetch to refresh the list
+                        let _ = slint::invoke_from_event_loop(move || {
+                            app_weak.unwrap().invoke_fetch_contacts();
+                        });
+                    }
+                    Err(e) => {
+                        println!("Error adding contact: {e}");
+                    }
                 }
-            }
-        });
-    });
+            });
+        },
+    );
 
     // --- NEW: Callback for updating an existing contact ---
     let app_weak = app.as_weak();
@@ -234,9 +253,25 @@ pub fn run() {
         let app_weak = app_weak.clone();
         let client = client_clone.clone();
         let url = format!("{}/contacts/{}", base_url_clone, contact_to_update.id);
-        let contact_dto: ContactDto = contact_to_update.to_dto();
+        let edited: ContactDto = contact_to_update.to_dto();
         let token = app_weak.unwrap().get_auth_token().to_string();
         spawn_local(async move {
+            // The update replaces the whole contact, so keep what the form can't edit.
+            let current = match client.get(&url).bearer_auth(&token).send().await {
+                Ok(response) => response.json::<ContactDto>().await.ok(),
+                Err(_) => None,
+            };
+            let Some(current) = current else {
+                println!("Error fetching contact {url} before the update.");
+                return;
+            };
+            let contact_dto = ContactDto {
+                emails: current.emails,
+                phones: current.phones,
+                addresses: current.addresses,
+                custom_fields: current.custom_fields,
+                ..edited
+            };
             match client
                 .put(&url)
                 .bearer_auth(token)
@@ -291,11 +326,10 @@ pub fn run() {
                 Ok(response) => {
                     match response.json::<ContactDto>().await {
                         Ok(contact_dto) => {
-                            // Convert DTO to a slint::Contact struct
-                            let ui_contact: Contact = contact_dto.into();
-
                             // Update the UI on the main thread
                             let _ = slint::invoke_from_event_loop(move || {
+                                // Convert DTO to a slint::Contact struct
+                                let ui_contact: Contact = contact_dto.into();
                                 app_weak.unwrap().set_contact_to_edit(ui_contact);
                             });
                         }
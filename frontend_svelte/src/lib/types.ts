@@ -1,14 +1,519 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
+/**
+ * The kind of relationship with a contact. Which of these a deployment offers, and under
+ * which labels, is configured in the `contact_types` table.
+ */
+export type ContactType = "Personal" | "Work" | "Family" | "Friend" | "Customer" | "Lead" | "Partner" | "Other";
+
+/**
+ * A contact type offered by this deployment, with the label to show for it.
+ */
+export type ContactTypeDto = { name: ContactType; label: string };
+
+/**
+ * An additional, labelled email address of a contact.
+ */
+export type ContactEmailDto = { label: string; email: string };
+
+/**
+ * A labelled phone number of a contact, in E.164 format.
+ */
+export type ContactPhoneDto = { label: string; number: string };
+
+/**
+ * A labelled postal address of a contact. Only the label is required, since address formats
+ * differ between countries.
+ */
+export type ContactAddressDto = {
+  label: string;
+  street: string;
+  city: string;
+  region: string;
+  postalCode: string;
+  country: string;
+};
+
 export type ContactDto = {
   id: number;
   name: string;
   email: string;
-  age: number;
+  birthday: string | null;
+  /**
+   * Whether the contact gets the newsletter. Writing `true` for a contact that isn't
+   * subscribed emails it a link to confirm; it stays unsubscribed until it does.
+   */
+  subscribed: boolean;
+  contactType: ContactType;
+  /**
+   * Names of the tags assigned to the contact, sorted by name.
+   */
+  tags: Array<string>;
+  /**
+   * Email addresses besides the primary `email`.
+   */
+  emails: Array<ContactEmailDto>;
+  phones: Array<ContactPhoneDto>;
+  addresses: Array<ContactAddressDto>;
+  notes: string | null;
+  /**
+   * Values of the user's custom fields, keyed by field name. They are checked against the
+   * field definitions when the contact is written; a `null` value is the same as none.
+   */
+  customFields: Record<string, string | number | boolean | null>;
+  /**
+   * Where the contact's avatar thumbnail can be downloaded, if it has one. Ignored on
+   * writes; avatars are set and removed through `/api/v1/contacts/{id}/avatar`.
+   */
+  avatarUrl: string | null;
+};
+
+/**
+ * A per-user label that can be attached to any number of contacts.
+ */
+export type TagDto = { id: number; name: string };
+
+/**
+ * The kind of value a custom field holds.
+ */
+export type CustomFieldType = "text" | "number" | "date" | "bool" | "select";
+
+/**
+ * A per-user definition of an extra attribute that contacts can carry in `custom_fields`.
+ */
+export type CustomFieldDto = {
+  id: number;
+  name: string;
+  fieldType: CustomFieldType;
+  /**
+   * Whether every contact must have a value for this field.
+   */
+  required: boolean;
+  /**
+   * The choices of a `select` field; empty for every other type.
+   */
+  options: Array<string>;
+};
+
+/**
+ * A deleted contact waiting in the trash until it is restored or purged.
+ */
+export type TrashedContactDto = {
+  /**
+   * When the contact was deleted, in UTC.
+   */
+  deletedAt: string;
+  id: number;
+  name: string;
+  email: string;
+  birthday: string | null;
+  /**
+   * Whether the contact gets the newsletter. Writing `true` for a contact that isn't
+   * subscribed emails it a link to confirm; it stays unsubscribed until it does.
+   */
   subscribed: boolean;
-  contactType: string;
+  contactType: ContactType;
+  /**
+   * Names of the tags assigned to the contact, sorted by name.
+   */
+  tags: Array<string>;
+  /**
+   * Email addresses besides the primary `email`.
+   */
+  emails: Array<ContactEmailDto>;
+  phones: Array<ContactPhoneDto>;
+  addresses: Array<ContactAddressDto>;
+  notes: string | null;
+  /**
+   * Values of the user's custom fields, keyed by field name. They are checked against the
+   * field definitions when the contact is written; a `null` value is the same as none.
+   */
+  customFields: Record<string, string | number | boolean | null>;
+  /**
+   * Where the contact's avatar thumbnail can be downloaded, if it has one. Ignored on
+   * writes; avatars are set and removed through `/api/v1/contacts/{id}/avatar`.
+   */
+  avatarUrl: string | null;
+};
+
+/**
+ * A file attached to a contact. The content is downloaded separately.
+ */
+export type AttachmentDto = {
+  id: number;
+  contactId: number;
+  fileName: string;
+  contentType: string;
+  /**
+   * Size of the file in bytes.
+   */
+  size: number;
+  /**
+   * When the file was uploaded, in UTC.
+   */
+  createdAt: string;
+};
+
+/**
+ * The kind of change recorded in a contact's history.
+ */
+export type ContactEventAction = "created" | "updated" | "deleted" | "restored" | "merged";
+
+/**
+ * One entry in a contact's change log.
+ */
+export type ContactEventDto = {
+  id: number;
+  contactId: number;
+  action: ContactEventAction;
+  /**
+   * The user who made the change.
+   */
+  userId: number;
+  userEmail: string | null;
+  /**
+   * The `x-request-id` of the request that made the change.
+   */
+  requestId: string | null;
+  /**
+   * The contact before the change, absent for creations and restores.
+   */
+  before: ContactDto | null;
+  /**
+   * The contact after the change, absent for deletions.
+   */
+  after: ContactDto | null;
+  /**
+   * The other contact involved in a merge.
+   */
+  relatedContactId: number | null;
+  /**
+   * When the change happened, in UTC.
+   */
+  createdAt: string;
+};
+
+/**
+ * How newsletter consent was given or withdrawn.
+ */
+export type SubscriptionSource = "manual" | "import" | "double_opt_in" | "unsubscribe_link";
+
+/**
+ * The kind of change recorded in a contact's subscription log.
+ */
+export type SubscriptionAction = "requested" | "subscribed" | "unsubscribed";
+
+/**
+ * One entry in a contact's subscription log, kept as a record of consent.
+ */
+export type SubscriptionEventDto = {
+  id: number;
+  action: SubscriptionAction;
+  source: SubscriptionSource;
+  /**
+   * The address the change applies to.
+   */
+  email: string;
+  /**
+   * The user who made the change, absent for changes made by the contact through an
+   * emailed link.
+   */
+  userId: number | null;
+  userEmail: string | null;
+  /**
+   * The `x-request-id` of the request that made the change.
+   */
+  requestId: string | null;
+  /**
+   * When the change happened, in UTC.
+   */
+  createdAt: string;
+};
+
+/**
+ * A contact's newsletter subscription, with its record of consent.
+ */
+export type SubscriptionDto = {
+  subscribed: boolean;
+  /**
+   * When the contact gave their current consent, in UTC. Absent while unsubscribed, and for
+   * subscriptions older than consent tracking.
+   */
+  subscribedAt: string | null;
+  /**
+   * How the contact gave their current consent.
+   */
+  source: SubscriptionSource | null;
+  /**
+   * A signed link that unsubscribes the contact without logging in, to include in
+   * newsletters.
+   */
+  unsubscribeUrl: string;
+  /**
+   * Every subscription change, oldest first.
+   */
+  events: Array<SubscriptionEventDto>;
 };
 
 export type Credentials = { email: string; password: string };
 
 export type LoginResponse = { access_token: string; refresh_token: string };
+
+/**
+ * A single operation inside a bulk contacts request.
+ */
+export type BulkContactOperation = { "op": "create"; contact: ContactDto } | {
+  "op": "update";
+  id: number;
+  contact: ContactDto;
+} | { "op": "delete"; id: number };
+
+export type BulkContactRequest = {
+  /**
+   * When set, the whole batch is rolled back as soon as one operation fails.
+   */
+  allOrNothing: boolean;
+  operations: Array<BulkContactOperation>;
+};
+
+export type BulkItemStatus = "created" | "updated" | "deleted" | "failed" | "skipped";
+
+export type BulkItemResult = {
+  /**
+   * Position of the operation in the request.
+   */
+  index: number;
+  status: BulkItemStatus;
+  contact: ContactDto | null;
+  error: string | null;
+  /**
+   * Field errors reported by `ContactDto::validate`.
+   */
+  validationErrors: Record<string, unknown> | null;
+};
+
+export type BulkContactResponse = {
+  /**
+   * Whether the transaction was committed.
+   */
+  committed: boolean;
+  results: Array<BulkItemResult>;
+};
+
+export type ImportRowStatus = "duplicate" | "failed";
+
+/**
+ * A row of an import file that was not imported.
+ */
+export type ImportRowResult = {
+  /**
+   * 1-based position of the record in the file, not counting the CSV header.
+   */
+  row: number;
+  status: ImportRowStatus;
+  email: string | null;
+  error: string | null;
+  /**
+   * Field errors reported by `ContactDto::validate`.
+   */
+  validationErrors: Record<string, unknown> | null;
+};
+
+export type ImportReport = {
+  /**
+   * When set, nothing was written and `imported` counts the rows that would have been.
+   */
+  dryRun: boolean;
+  totalRows: number;
+  imported: number;
+  duplicates: number;
+  failed: number;
+  /**
+   * Every row that was skipped, in file order.
+   */
+  rows: Array<ImportRowResult>;
+};
+
+/**
+ * Why two contacts are suspected to be the same person.
+ */
+export type DuplicateReason = "sameEmail" | "similarName";
+
+/**
+ * A contact of a duplicate pair, with just enough to tell it apart. The rest is fetched
+ * by ID.
+ */
+export type DuplicateContactDto = { id: number; name: string; email: string };
+
+/**
+ * Two contacts that are likely duplicates of each other, `first` being the older one.
+ */
+export type DuplicateCandidateDto = {
+  first: DuplicateContactDto;
+  second: DuplicateContactDto;
+  reason: DuplicateReason;
+  /**
+   * How alike the names are, from 0 to 1.
+   */
+  similarity: number;
+};
+
+/**
+ * Which contact's values win when two contacts are merged. Blank values never win, and
+ * lists such as tags, phone numbers and custom fields are combined.
+ */
+export type MergeStrategy = "preferPrimary" | "preferDuplicate" | "preferNewest";
+
+export type MergeContactsRequest = {
+  /**
+   * The contact that is kept and receives the merged values.
+   */
+  primaryId: number;
+  /**
+   * The contact that is merged into the primary one and moved to the trash.
+   */
+  duplicateId: number;
+  strategy: MergeStrategy;
+};
+
+/**
+ * What the grantee of a share may do with the shared contacts.
+ */
+export type SharePermission = "read" | "write";
+
+/**
+ * Access to a single contact, or to an organization's whole contact book, granted to a user.
+ */
+export type ShareDto = {
+  id: number;
+  /**
+   * The organization whose contacts are shared.
+   */
+  orgName: string;
+  granteeEmail: string;
+  /**
+   * The shared contact, or `None` when the whole contact book is shared.
+   */
+  contactId: number | null;
+  permission: SharePermission;
+  /**
+   * When the share was granted, in UTC.
+   */
+  createdAt: string;
+};
+
+export type CreateShareRequest = {
+  /**
+   * The email the grantee signed up with.
+   */
+  email: string;
+  /**
+   * The contact to share; leave out to share the whole contact book.
+   */
+  contactId: number | null;
+  permission: SharePermission;
+};
+
+export type UpdateShareRequest = { permission: SharePermission };
+
+/**
+ * A contact of another organization that was shared with the caller.
+ */
+export type SharedContactDto = {
+  /**
+   * The organization the contact belongs to.
+   */
+  orgName: string;
+  /**
+   * The broadest permission granted on the contact, by its own share or a book share.
+   */
+  permission: SharePermission;
+  id: number;
+  name: string;
+  email: string;
+  birthday: string | null;
+  /**
+   * Whether the contact gets the newsletter. Writing `true` for a contact that isn't
+   * subscribed emails it a link to confirm; it stays unsubscribed until it does.
+   */
+  subscribed: boolean;
+  contactType: ContactType;
+  /**
+   * Names of the tags assigned to the contact, sorted by name.
+   */
+  tags: Array<string>;
+  /**
+   * Email addresses besides the primary `email`.
+   */
+  emails: Array<ContactEmailDto>;
+  phones: Array<ContactPhoneDto>;
+  addresses: Array<ContactAddressDto>;
+  notes: string | null;
+  /**
+   * Values of the user's custom fields, keyed by field name. They are checked against the
+   * field definitions when the contact is written; a `null` value is the same as none.
+   */
+  customFields: Record<string, string | number | boolean | null>;
+  /**
+   * Where the contact's avatar thumbnail can be downloaded, if it has one. Ignored on
+   * writes; avatars are set and removed through `/api/v1/contacts/{id}/avatar`.
+   */
+  avatarUrl: string | null;
+};
+
+/**
+ * What a member may do in an organization, from most to least privileged.
+ */
+export type OrgRole = "owner" | "admin" | "member" | "viewer";
+
+/**
+ * An organization the caller is a member of.
+ */
+export type OrgDto = {
+  id: number;
+  name: string;
+  /**
+   * The caller's role in the organization.
+   */
+  role: OrgRole;
+};
+
+export type CreateOrgRequest = { name: string };
+
+/**
+ * A user's membership in the caller's current organization.
+ */
+export type MembershipDto = {
+  userId: number;
+  email: string;
+  role: OrgRole;
+  /**
+   * When the user joined the organization, in UTC.
+   */
+  createdAt: string;
+};
+
+export type AddMemberRequest = {
+  /**
+   * The email the new member signed up with.
+   */
+  email: string;
+  role: OrgRole;
+};
+
+export type UpdateMemberRequest = { role: OrgRole };
+
+/**
+ * A backup of the database in the server's backup directory.
+ */
+export type BackupDto = {
+  /**
+   * The file name, which tells when the backup was made.
+   */
+  name: string;
+  sizeBytes: number;
+  /**
+   * When the backup was made, in UTC.
+   */
+  createdAt: string;
+};